@@ -0,0 +1,125 @@
+//! Job arrays: recognizing array tasks by their ID and collapsing them into one queue row.
+//!
+//! Slurm names tasks `1234_7` and lists still-pending tasks compressed as `1234_[8-100%10]`;
+//! Torque and PBS use `1234[7].server`, with `1234[].server` for the array itself.
+
+use crate::models::{Job, JobState};
+
+/// Array job ID and task part (`7`, `[8-100%10]`, `[]`) of an array task's ID.
+pub fn split_id(id: &str) -> Option<(&str, &str)> {
+    // Slurm: 1234_7 (heterogeneous jobs use `+` instead)
+    if let Some((base, task)) = id.split_once('_') {
+        if !base.is_empty() && base.bytes().all(|b| b.is_ascii_digit()) {
+            return Some((base, task));
+        }
+    }
+    // Torque/PBS: 1234[7].server
+    let (base, rest) = id.split_once('[')?;
+    let end = rest.find(']')?;
+    Some((base, &id[base.len()..base.len() + end + 2]))
+}
+
+/// ID of the whole array an array task belongs to, for acting on all of its tasks at once:
+/// `1234` (Slurm) or `1234[].server` (Torque/PBS).
+pub fn array_id(task_id: &str) -> Option<String> {
+    let (base, task) = split_id(task_id)?;
+    let rest = &task_id[base.len()..];
+    if !rest.starts_with('[') {
+        return Some(base.to_string());
+    }
+    Some(format!("{}[]{}", base, &rest[task.len()..]))
+}
+
+/// Number of tasks an ID stands for: 1 for a single task, the size of the range for a
+/// compressed `[8-100%10]` entry (`%10` being the concurrency limit).
+pub fn task_count(id: &str) -> u32 {
+    let Some((_, task)) = split_id(id) else {
+        return 1;
+    };
+    let Some(ranges) = task.strip_prefix('[').and_then(|t| t.strip_suffix(']')) else {
+        return 1;
+    };
+    let ranges = ranges.split('%').next().unwrap_or(ranges);
+    let count: u32 = ranges
+        .split(',')
+        .filter_map(|range| {
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+            let (first, last) = (first.parse::<u32>().ok()?, last.parse::<u32>().ok()?);
+            Some(last.saturating_sub(first) + 1)
+        })
+        .sum();
+    count.max(1)
+}
+
+/// One entry of the queue: a plain pending job, or an array with pending tasks.
+pub enum QueueEntry<'a> {
+    Job(&'a Job),
+    Array(ArrayJob<'a>),
+}
+
+/// An array job with at least one pending task, and its tasks in the running and pending lists.
+pub struct ArrayJob<'a> {
+    pub id: String,
+    /// Member cluster, in a multi-cluster view
+    pub cluster: Option<String>,
+    /// Running tasks first, then pending ones in queue order
+    pub tasks: Vec<&'a Job>,
+    pub running: u32,
+    pub pending: u32,
+}
+
+impl<'a> ArrayJob<'a> {
+    /// The first pending task, which stands for the array in the queue.
+    pub fn first_pending(&self) -> &'a Job {
+        self.tasks
+            .iter()
+            .find(|job| job.state == JobState::Pending)
+            .or(self.tasks.first())
+            .copied()
+            .expect("arrays are grouped from at least one pending task")
+    }
+
+    /// `37/100 running, 12 pending`; finished tasks aren't known, so they don't count.
+    pub fn summary(&self) -> String {
+        format!("{}/{} running, {} pending", self.running, self.running + self.pending, self.pending)
+    }
+}
+
+/// Collapse the tasks of each array in `pending` into one entry, keeping the queue order
+/// (an array sits where its first pending task does). Running tasks of those arrays are
+/// taken from `running`; arrays with nothing pending don't belong in the queue.
+pub fn group<'a>(pending: &'a [Job], running: &'a [Job]) -> Vec<QueueEntry<'a>> {
+    let mut entries: Vec<QueueEntry<'a>> = Vec::new();
+
+    for job in pending {
+        let Some((base, _)) = split_id(&job.id) else {
+            entries.push(QueueEntry::Job(job));
+            continue;
+        };
+
+        let index = entries.iter().position(|entry| {
+            matches!(entry, QueueEntry::Array(array) if array.id == base && array.cluster == job.cluster)
+        });
+        let index = index.unwrap_or_else(|| {
+            let tasks: Vec<&Job> = running
+                .iter()
+                .filter(|task| task.cluster == job.cluster && split_id(&task.id).is_some_and(|(b, _)| b == base))
+                .collect();
+            entries.push(QueueEntry::Array(ArrayJob {
+                id: base.to_string(),
+                cluster: job.cluster.clone(),
+                running: tasks.iter().map(|task| task_count(&task.id)).sum(),
+                tasks,
+                pending: 0,
+            }));
+            entries.len() - 1
+        });
+        let QueueEntry::Array(ref mut array) = entries[index] else {
+            unreachable!()
+        };
+        array.pending += task_count(&job.id);
+        array.tasks.push(job);
+    }
+
+    entries
+}
@@ -0,0 +1,99 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Skew below this is treated as noise from command latency.
+const SKEW_THRESHOLD_SECS: i64 = 60;
+
+/// Timezone the cluster's scheduler reports its timestamps in.
+#[derive(Debug, Clone, Copy)]
+pub enum ClusterTz {
+    Local,
+    Named(Tz),
+}
+
+impl ClusterTz {
+    pub fn parse(name: &str) -> Result<Self> {
+        if name.eq_ignore_ascii_case("local") {
+            return Ok(ClusterTz::Local);
+        }
+        name.parse::<Tz>()
+            .map(ClusterTz::Named)
+            .map_err(|_| anyhow!("Unknown timezone '{}' (expected an IANA name like America/New_York)", name))
+    }
+
+    /// Interpret a timestamp without offset (as printed by sacct/qstat) in the cluster timezone.
+    pub fn localize(&self, naive: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            ClusterTz::Local => Local.from_local_datetime(naive).earliest().map(|t| t.with_timezone(&Utc)),
+            ClusterTz::Named(tz) => tz.from_local_datetime(naive).earliest().map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    pub fn format(&self, time: &DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            ClusterTz::Local => time.with_timezone(&Local).format(fmt).to_string(),
+            ClusterTz::Named(tz) => time.with_timezone(tz).format(fmt).to_string(),
+        }
+    }
+}
+
+/// Estimate how far the scheduler's clock is ahead of ours.
+///
+/// Prefers the controller's own timestamp; otherwise jobs submitted "in the future"
+/// give a lower bound. Returns `None` when the difference is within the noise threshold.
+pub fn detect_skew(server_time: Option<DateTime<Utc>>, submit_times: &[DateTime<Utc>], now: DateTime<Utc>) -> Option<Duration> {
+    let skew = match server_time {
+        Some(t) => t - now,
+        // Only submissions in the future say anything about skew
+        None => submit_times.iter().map(|t| *t - now).filter(|d| *d > Duration::zero()).max()?,
+    };
+
+    if skew.num_seconds().abs() >= SKEW_THRESHOLD_SECS {
+        Some(skew)
+    } else {
+        None
+    }
+}
+
+/// Compact signed duration for the header, e.g. `+2m05s` or `-45s`.
+pub fn format_skew(skew: &Duration) -> String {
+    let sign = if skew.num_seconds() < 0 { '-' } else { '+' };
+    let secs = skew.num_seconds().abs();
+    if secs >= 3600 {
+        format!("{}{}h{:02}m", sign, secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}{}m{:02}s", sign, secs / 60, secs % 60)
+    } else {
+        format!("{}{}s", sign, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    #[test]
+    fn old_submit_times_are_not_skew() {
+        assert_eq!(detect_skew(None, &[at(-86400), at(-3600), at(-61)], at(0)), None);
+        assert_eq!(detect_skew(None, &[], at(0)), None);
+    }
+
+    #[test]
+    fn future_submit_times_are_skew() {
+        assert_eq!(detect_skew(None, &[at(-3600), at(90)], at(0)), Some(Duration::seconds(90)));
+        // Within the noise threshold
+        assert_eq!(detect_skew(None, &[at(30)], at(0)), None);
+    }
+
+    #[test]
+    fn server_time_wins() {
+        assert_eq!(detect_skew(Some(at(-120)), &[at(600)], at(0)), Some(Duration::seconds(-120)));
+        assert_eq!(format_skew(&Duration::seconds(-120)), "-2m00s");
+        assert_eq!(format_skew(&Duration::seconds(3725)), "+1h02m");
+    }
+}
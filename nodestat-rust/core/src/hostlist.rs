@@ -0,0 +1,97 @@
+/// Expand a compressed hostlist like `node[001-032,040],gpu[01-04],login1` into individual
+/// host names, preserving order and zero padding. A name may have several bracket groups
+/// (`rack[1-2]-n[01-02]`), which expand like nested loops; Slurm's `None assigned` and
+/// `(null)` are empty.
+pub fn expand(hostlist: &str) -> Vec<String> {
+    let hostlist = hostlist.trim();
+    if hostlist == "None assigned" || hostlist == "(null)" {
+        return Vec::new();
+    }
+
+    let mut hosts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    // Split on commas outside of brackets
+    for (i, c) in hostlist.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&hostlist[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&hostlist[start..]);
+
+    for part in parts.into_iter().map(str::trim).filter(|p| !p.is_empty()) {
+        expand_part(part, &mut hosts);
+    }
+    hosts
+}
+
+/// The names of one comma-free part, expanding its first bracket group and recursing on the rest.
+fn expand_part(part: &str, hosts: &mut Vec<String>) {
+    let Some((prefix, rest)) = part.split_once('[') else {
+        hosts.push(part.to_string());
+        return;
+    };
+    let (ranges, suffix) = rest.split_once(']').unwrap_or((rest, ""));
+    let mut suffixes = Vec::new();
+    expand_part(suffix, &mut suffixes);
+
+    for range in ranges.split(',').map(str::trim) {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        let width = first.len();
+        let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>()) else {
+            continue;
+        };
+        for n in first..=last {
+            for suffix in &suffixes {
+                hosts.push(format!("{}{:0width$}{}", prefix, n, suffix, width = width));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_padded_range() {
+        assert_eq!(expand("node[001-003]"), vec!["node001", "node002", "node003"]);
+        assert_eq!(expand("node[8-10]"), vec!["node8", "node9", "node10"]);
+    }
+
+    #[test]
+    fn mixed_list() {
+        assert_eq!(
+            expand("node[001-002,040],gpu[01-02],login1"),
+            vec!["node001", "node002", "node040", "gpu01", "gpu02", "login1"]
+        );
+    }
+
+    #[test]
+    fn several_bracket_groups() {
+        assert_eq!(expand("rack[1-2]-n[01-02]"), vec!["rack1-n01", "rack1-n02", "rack2-n01", "rack2-n02"]);
+    }
+
+    #[test]
+    fn nothing_assigned() {
+        assert!(expand("None assigned").is_empty());
+        assert!(expand("(null)").is_empty());
+        assert!(expand("").is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        // An unclosed bracket runs to the end of the name
+        assert_eq!(expand("node[001-002"), vec!["node001", "node002"]);
+        // Ranges that aren't numbers are left out, the rest of the list isn't
+        assert_eq!(expand("node[a-c],login1"), vec!["login1"]);
+        assert_eq!(expand("node[1-x,3]"), vec!["node3"]);
+    }
+}
@@ -0,0 +1,29 @@
+//! The scheduler side of NodeStat, for other Rust tools that want the same view of a cluster:
+//! one [`schedulers::Scheduler`] trait over Slurm (commands or slurmrestd), Torque, PBS Pro,
+//! LSF, SGE, Flux, `exec` commands, WASM plugins and `nodestat serve` daemons, the
+//! [`models`] they all return and the [`models::ClusterStats`] over them.
+//!
+//! ```no_run
+//! use nodestat_core::clock::ClusterTz;
+//! use nodestat_core::models::ClusterStats;
+//! use nodestat_core::schedulers::{create_scheduler, resolve_scheduler, Transport};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let transport = Transport::local();
+//! let scheduler_type = resolve_scheduler("auto", &transport, None).await?;
+//! let scheduler = create_scheduler(scheduler_type, ClusterTz::Local, transport)?;
+//! let nodes = scheduler.get_nodes("batch").await?;
+//! let stats = ClusterStats::of(&nodes);
+//! println!("{} of {} cores free", stats.avail_cores, stats.total_cores);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod arrays;
+pub mod clock;
+pub mod hostlist;
+pub mod models;
+pub mod paths;
+pub mod recording;
+pub mod rpc;
+pub mod schedulers;
@@ -0,0 +1,599 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Memory is kept in bytes; schedulers report decimal units (Slurm's `RealMemory` is in MB).
+pub const MB: u64 = 1000 * 1000;
+pub const GB: u64 = 1000 * MB;
+pub const TB: u64 = 1000 * GB;
+
+/// `512 MB`, `256 GB` or `6.0 TB`: whole units up to a terabyte, then one decimal.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes >= TB {
+        format!("{:.1} TB", bytes as f64 / TB as f64)
+    } else if bytes >= GB {
+        format!("{} GB", bytes / GB)
+    } else {
+        format!("{} MB", bytes / MB)
+    }
+}
+
+/// Slurm-style duration: `HH:MM:SS`, or `D-HH:MM:SS` past a day.
+pub fn format_duration(duration: &Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    let (days, hours, minutes, seconds) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60, secs % 60);
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+/// `850 W`, `12.3 kW` or `1.20 MW`.
+pub fn format_watts(watts: u64) -> String {
+    match watts {
+        0..=999 => format!("{} W", watts),
+        1_000..=999_999 => format!("{:.1} kW", watts as f64 / 1e3),
+        _ => format!("{:.2} MW", watts as f64 / 1e6),
+    }
+}
+
+/// Energy in kWh (`456 kWh`), or MWh from a thousand of them on (`1.2 MWh`).
+pub fn format_joules(joules: u64) -> String {
+    let kwh = joules as f64 / 3.6e6;
+    if kwh >= 1000.0 {
+        format!("{:.1} MWh", kwh / 1000.0)
+    } else {
+        format!("{:.0} kWh", kwh)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NodeState {
+    Idle,
+    Running,
+    Down,
+    Offline,
+    Busy,
+    Drained,
+    /// Slurm `MIXED`: some CPUs allocated
+    Mixed,
+    /// Slurm `ALLOCATED`: every CPU allocated
+    Allocated,
+    /// Jobs are finishing; the node frees up shortly
+    Completing,
+    /// In a maintenance reservation
+    Maint,
+    /// Rebooting, or a reboot is pending
+    Reboot,
+    /// Not in service yet (`FUTURE`) or powering down
+    PlannedDown,
+    /// Idle but held by a reservation
+    Reserved,
+    /// Taken out of service because it is failing (`FAIL`)
+    Fail,
+}
+
+impl std::fmt::Display for NodeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeState::Idle => write!(f, "Idle"),
+            NodeState::Running => write!(f, "Running"),
+            NodeState::Down => write!(f, "Down"),
+            NodeState::Offline => write!(f, "Offline"),
+            NodeState::Busy => write!(f, "Busy"),
+            NodeState::Drained => write!(f, "Drained"),
+            NodeState::Mixed => write!(f, "Mixed"),
+            NodeState::Allocated => write!(f, "Allocated"),
+            NodeState::Completing => write!(f, "Completing"),
+            NodeState::Maint => write!(f, "Maint"),
+            NodeState::Reboot => write!(f, "Reboot"),
+            NodeState::PlannedDown => write!(f, "Planned down"),
+            NodeState::Reserved => write!(f, "Reserved"),
+            NodeState::Fail => write!(f, "Fail"),
+        }
+    }
+}
+
+impl NodeState {
+    /// States in which the node runs (or can run) jobs.
+    pub fn is_up(&self) -> bool {
+        matches!(
+            self,
+            NodeState::Idle
+                | NodeState::Running
+                | NodeState::Busy
+                | NodeState::Mixed
+                | NodeState::Allocated
+                | NodeState::Completing
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub state: NodeState,
+    pub total_cores: u32,
+    pub used_cores: u32,
+    /// Memory in bytes
+    pub total_mem: u64,
+    pub used_mem: u64,
+    pub partitions: Vec<String>,
+    /// Ids of the jobs on the node, as the scheduler reports them or else from the job list
+    /// (see [`link_jobs`])
+    pub jobs: Vec<String>,
+    /// Owners of the running jobs on the node, from the job list, sorted and without duplicates
+    #[serde(default)]
+    pub job_users: Vec<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Load average (Slurm `CPULoad`, Torque `loadave`)
+    #[serde(default)]
+    pub cpu_load: Option<f64>,
+    /// Power draw and energy used since the node booted, from Slurm's energy accounting
+    /// plugin (`CurrentWatts`, `ConsumedJoules`); `None` without one
+    #[serde(default)]
+    pub current_watts: Option<u32>,
+    #[serde(default)]
+    pub consumed_joules: Option<u64>,
+    /// Operating system and kernel the node reports (Slurm `OS`, Torque `uname`)
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub gpus: Vec<GpuGres>,
+    /// Active node features (Slurm `ActiveFeatures`, Torque properties), e.g. `avx512`
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Slurm state flags besides the base state, e.g. `DRAIN`, `CLOUD`, `POWERED_DOWN`, `NOT_RESPONDING`
+    #[serde(default)]
+    pub state_flags: Vec<String>,
+    /// Member cluster, in a multi-cluster view
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+/// Cross-link the running jobs to the nodes in their (expanded) node lists: nodes the scheduler
+/// reported no job ids for get those of the job list, and every node gets the jobs' owners.
+pub fn link_jobs(nodes: &mut [Node], jobs: &[Job]) {
+    let mut on_node: HashMap<(Option<&str>, &str), Vec<&Job>> = HashMap::new();
+    for job in jobs.iter().filter(|job| job.state == JobState::Running) {
+        for node_id in &job.node_list {
+            on_node.entry((job.cluster.as_deref(), node_id.as_str())).or_default().push(job);
+        }
+    }
+
+    for node in nodes.iter_mut() {
+        let jobs = on_node.get(&(node.cluster.as_deref(), node.id.as_str())).map(Vec::as_slice).unwrap_or_default();
+        if node.jobs.is_empty() {
+            node.jobs = jobs.iter().map(|job| job.id.clone()).collect();
+        }
+        let mut users: Vec<String> = jobs.iter().map(|job| job.user.clone()).collect();
+        users.sort();
+        users.dedup();
+        node.job_users = users;
+    }
+}
+
+/// GPUs of one model (or one MIG profile, e.g. `a100_3g.20gb`) on a node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuGres {
+    pub model: String,
+    pub total: u32,
+    pub used: u32,
+}
+
+impl GpuGres {
+    pub fn available(&self) -> u32 {
+        self.total.saturating_sub(self.used)
+    }
+
+    /// MIG slices are named `<gpu>_<compute>g.<mem>gb`, e.g. `a100_3g.20gb`.
+    pub fn is_mig(&self) -> bool {
+        self.model
+            .rsplit_once('_')
+            .is_some_and(|(_, profile)| profile.contains("g.") && profile.ends_with("gb"))
+    }
+}
+
+impl Node {
+    pub fn available_cores(&self) -> u32 {
+        self.total_cores.saturating_sub(self.used_cores)
+    }
+
+    pub fn available_mem(&self) -> u64 {
+        self.total_mem.saturating_sub(self.used_mem)
+    }
+
+    pub fn available_mem_gb(&self) -> u64 {
+        self.available_mem() / GB
+    }
+
+    pub fn total_mem_gb(&self) -> u64 {
+        self.total_mem / GB
+    }
+
+    pub fn used_mem_gb(&self) -> u64 {
+        self.used_mem / GB
+    }
+
+    /// GPUs of every model, counting each MIG slice as one GPU like Slurm's `gres/gpu`.
+    pub fn total_gpus(&self) -> u32 {
+        self.gpus.iter().map(|g| g.total).sum()
+    }
+
+    pub fn used_gpus(&self) -> u32 {
+        self.gpus.iter().map(|g| g.used).sum()
+    }
+
+    pub fn available_gpus(&self) -> u32 {
+        self.total_gpus().saturating_sub(self.used_gpus())
+    }
+
+    /// Load well beyond the allocated cores: rogue or runaway processes, or jobs running
+    /// more threads than they asked for.
+    pub fn is_overloaded(&self) -> bool {
+        self.cpu_load.is_some_and(|load| load > self.used_cores as f64 * 1.5 + 1.0)
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.state_flags.iter().any(|f| f == flag)
+    }
+
+    /// Still running jobs but drained: takes no new ones and turns `Drained` once they end.
+    pub fn is_draining(&self) -> bool {
+        self.has_flag("DRAIN") && self.state != NodeState::Drained
+    }
+
+    /// Short state for the table, `Draining` and the markers as `sinfo` prints them:
+    /// `*` not responding, `~` powered down (e.g. a `CLOUD` node), `#` powering up.
+    pub fn state_label(&self) -> String {
+        let mut label = if self.is_draining() { "Draining".to_string() } else { self.state.to_string() };
+        if self.has_flag("NOT_RESPONDING") {
+            label.push('*');
+        }
+        if self.has_flag("POWERED_DOWN") {
+            label.push('~');
+        } else if self.has_flag("POWERING_UP") {
+            label.push('#');
+        }
+        label
+    }
+
+    /// Nodes whose `Reason` can be edited without changing their state.
+    pub fn is_drained(&self) -> bool {
+        matches!(
+            self.state,
+            NodeState::Drained | NodeState::Down | NodeState::Offline | NodeState::Fail | NodeState::Maint
+        ) || self.has_flag("DRAIN")
+    }
+
+    pub fn is_available(&self) -> bool {
+        matches!(self.state, NodeState::Idle | NodeState::Running | NodeState::Mixed)
+            && !self.has_flag("DRAIN")
+            && !self.has_flag("NOT_RESPONDING")
+            && self.available_cores() > 0
+            // Some schedulers (e.g. Flux) don't track memory at all
+            && (self.total_mem == 0 || self.available_mem_gb() > 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JobState {
+    Running,
+    Pending,
+    Completed,
+    Cancelled,
+    Failed,
+    /// Killed at its time limit
+    Timeout,
+    /// Killed for exceeding its memory request
+    OutOfMemory,
+    /// Preempted by a higher-priority job
+    Preempted,
+    /// Suspended by an admin or the scheduler, holding its resources
+    Suspended,
+    /// Ended because one of its nodes failed
+    NodeFail,
+}
+
+impl std::fmt::Display for JobState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobState::Running => write!(f, "R"),
+            JobState::Pending => write!(f, "PD"),
+            JobState::Completed => write!(f, "C"),
+            JobState::Cancelled => write!(f, "CA"),
+            JobState::Failed => write!(f, "F"),
+            JobState::Timeout => write!(f, "TO"),
+            JobState::OutOfMemory => write!(f, "OOM"),
+            JobState::Preempted => write!(f, "PR"),
+            JobState::Suspended => write!(f, "S"),
+            JobState::NodeFail => write!(f, "NF"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub user: String,
+    pub name: String,
+    pub state: JobState,
+    pub node_list: Vec<String>,
+    pub partition: String,
+    pub req_nodes: u32,
+    pub req_cpus: u32,
+    /// Requested memory across all nodes, in bytes
+    pub req_mem: u64,
+    /// GPUs requested across all nodes
+    #[serde(default)]
+    pub req_gpus: u32,
+    /// Requested GPU model (e.g. `a100`), if the job asked for a specific one
+    #[serde(default)]
+    pub gpu_type: Option<String>,
+    /// Why a pending job is still waiting (Slurm `Reason`, PBS `comment`)
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Scheduling priority of a pending job
+    #[serde(default)]
+    pub priority: Option<JobPriority>,
+    /// When the scheduler expects a pending job to start, if it has planned it
+    #[serde(default)]
+    pub est_start: Option<DateTime<Utc>>,
+    pub time_limit: Duration,
+    pub elapsed: Duration,
+    /// CPU time the job actually used; zero where the scheduler doesn't account it
+    pub cpu_time: Duration,
+    pub submit_time: DateTime<Utc>,
+    /// Directory the job runs in, where the scheduler reports it
+    #[serde(default)]
+    pub work_dir: Option<String>,
+    /// Member cluster, in a multi-cluster view
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+impl Job {
+    /// Core-time allocated so far: elapsed × requested CPUs.
+    pub fn core_time(&self) -> Duration {
+        self.elapsed * self.req_cpus as i32
+    }
+
+    /// CPU time used as a percentage of [`Job::core_time`]; `None` until the scheduler has
+    /// accounted any CPU time.
+    pub fn cpu_efficiency(&self) -> Option<u64> {
+        let core_time = self.core_time().num_seconds();
+        let cpu_time = self.cpu_time.num_seconds();
+        (cpu_time > 0 && core_time > 0).then(|| (cpu_time * 100 / core_time) as u64)
+    }
+}
+
+/// How long a job that has started waited in the queue, for the partitions' wait times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobWait {
+    /// Where it started
+    pub partition: String,
+    pub started: DateTime<Utc>,
+    /// From submission to start
+    pub wait: Duration,
+}
+
+/// A pending job's priority and, where the scheduler breaks it down (Slurm `sprio`),
+/// the weighted factors adding up to it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobPriority {
+    pub total: u64,
+    pub age: u64,
+    pub fairshare: u64,
+    pub job_size: u64,
+    pub partition: u64,
+    pub qos: u64,
+}
+
+impl JobPriority {
+    /// The non-zero factors as (label, value) pairs.
+    pub fn factors(&self) -> Vec<(&'static str, u64)> {
+        [
+            ("Age", self.age),
+            ("Fairshare", self.fairshare),
+            ("Job size", self.job_size),
+            ("Partition", self.partition),
+            ("QOS", self.qos),
+        ]
+        .into_iter()
+        .filter(|(_, value)| *value > 0)
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterStats {
+    pub total_nodes: u32,
+    pub avail_nodes: u32,
+    pub total_cores: u32,
+    pub used_cores: u32,
+    pub avail_cores: u32,
+    /// Memory in bytes
+    pub total_memory: u64,
+    pub used_memory: u64,
+    pub avail_memory: u64,
+    /// Per GPU model / MIG profile totals across the partition
+    #[serde(default)]
+    pub gpu_profiles: Vec<GpuGres>,
+    /// Power draw and energy summed over the nodes that report them
+    #[serde(default)]
+    pub current_watts: u64,
+    #[serde(default)]
+    pub consumed_joules: u64,
+    #[serde(default)]
+    pub power_nodes: u32,
+}
+
+impl ClusterStats {
+    /// The totals of `nodes`.
+    pub fn of(nodes: &[Node]) -> Self {
+        let mut stats = Self {
+            total_nodes: nodes.len() as u32,
+            avail_nodes: 0,
+            total_cores: 0,
+            used_cores: 0,
+            avail_cores: 0,
+            total_memory: 0,
+            used_memory: 0,
+            avail_memory: 0,
+            gpu_profiles: Vec::new(),
+            current_watts: 0,
+            consumed_joules: 0,
+            power_nodes: 0,
+        };
+        
+        for node in nodes {
+            stats.total_cores += node.total_cores;
+            stats.used_cores += node.used_cores;
+            stats.total_memory += node.total_mem;
+            stats.used_memory += node.used_mem;
+
+            // GPUs on nodes that can't take jobs count as in use
+            let node_up = node.state.is_up();
+            for gpu in &node.gpus {
+                let used = if node_up { gpu.used } else { gpu.total };
+                match stats.gpu_profiles.iter_mut().find(|p| p.model == gpu.model) {
+                    Some(profile) => {
+                        profile.total += gpu.total;
+                        profile.used += used;
+                    },
+                    None => stats.gpu_profiles.push(GpuGres { model: gpu.model.clone(), total: gpu.total, used }),
+                }
+            }
+            
+            if let Some(watts) = node.current_watts {
+                stats.current_watts += u64::from(watts);
+                stats.consumed_joules += node.consumed_joules.unwrap_or(0);
+                stats.power_nodes += 1;
+            }
+
+            if node.is_available() {
+                stats.avail_nodes += 1;
+            }
+        }
+        
+        stats.avail_cores = stats.total_cores.saturating_sub(stats.used_cores);
+        stats.avail_memory = stats.total_memory.saturating_sub(stats.used_memory);
+        stats.gpu_profiles.sort_by(|a, b| a.is_mig().cmp(&b.is_mig()).then_with(|| a.model.cmp(&b.model)));
+        
+        stats
+    }
+
+    pub fn total_gpus(&self) -> u32 {
+        self.gpu_profiles.iter().map(|g| g.total).sum()
+    }
+
+    pub fn used_gpus(&self) -> u32 {
+        self.gpu_profiles.iter().map(|g| g.used).sum()
+    }
+}
+/// An advance reservation: a maintenance window, nodes set aside for a group, ...
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reservation {
+    pub name: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub nodes: Vec<String>,
+    /// Users and accounts allowed to run in it
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// Scheduler flags, e.g. `MAINT`
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// Member cluster, in a multi-cluster view
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+impl Reservation {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.start_time <= now && now < self.end_time
+    }
+
+    pub fn is_maintenance(&self) -> bool {
+        self.flags.iter().any(|f| f.eq_ignore_ascii_case("MAINT"))
+    }
+}
+
+/// A cluster-wide license pool (Slurm `Licenses=`), e.g. MATLAB or ANSYS seats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct License {
+    pub name: String,
+    pub total: u32,
+    pub used: u32,
+    /// Held for reservations
+    #[serde(default)]
+    pub reserved: u32,
+    /// Member cluster, in a multi-cluster view
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+impl License {
+    pub fn free(&self) -> u32 {
+        self.total.saturating_sub(self.used.saturating_add(self.reserved))
+    }
+}
+
+/// One `sshare` association: an account, or a user's share of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fairshare {
+    pub account: String,
+    /// `None` on the account's own row
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Configured shares as a fraction of the parent account's (`NormShares`)
+    pub norm_shares: f64,
+    /// Decayed usage in CPU-seconds (`RawUsage`)
+    pub raw_usage: u64,
+    /// Fraction of the parent's usage, half-life decay applied (`EffectvUsage`)
+    pub effective_usage: f64,
+    /// Fairshare factor from 0 to 1; below 0.5 means more than the fair share was used.
+    /// `None` where the algorithm doesn't rate the row (account rows under Fair Tree)
+    #[serde(default)]
+    pub fairshare: Option<f64>,
+    /// Member cluster, in a multi-cluster view
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+/// A partition (queue) and the limits jobs submitted to it get.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Partition {
+    pub name: String,
+    /// `UP`, `DOWN`, `DRAIN` or `INACTIVE` as Slurm puts it; disabled queues are `DOWN`
+    pub state: String,
+    /// Where jobs go without `-p`/`-q`
+    #[serde(default)]
+    pub is_default: bool,
+    /// Walltime limit, `None` if unlimited
+    #[serde(default)]
+    pub max_time: Option<Duration>,
+    /// Memory per CPU in bytes that jobs get without asking, and at most
+    #[serde(default)]
+    pub default_mem_per_cpu: Option<u64>,
+    #[serde(default)]
+    pub max_mem_per_cpu: Option<u64>,
+    #[serde(default)]
+    pub total_nodes: u32,
+    /// Accounts (Torque: groups) allowed to submit; empty means everyone
+    #[serde(default)]
+    pub allow_accounts: Vec<String>,
+    #[serde(default)]
+    pub deny_accounts: Vec<String>,
+    /// Member cluster, in a multi-cluster view
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+impl Partition {
+    pub fn is_up(&self) -> bool {
+        self.state == "UP"
+    }
+}
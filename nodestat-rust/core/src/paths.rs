@@ -0,0 +1,11 @@
+use std::env;
+use std::path::PathBuf;
+
+/// `$XDG_CONFIG_HOME/nodestat`, falling back to `~/.config/nodestat`.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir).join("nodestat");
+    }
+    let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+    home.join(".config").join("nodestat")
+}
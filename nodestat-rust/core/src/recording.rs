@@ -0,0 +1,154 @@
+//! `--record` and `--replay`: what every refresh fetched, one JSON line each, and playing such
+//! a file back in the TUI without running any scheduler command. A single frame is also the
+//! snapshot `export` and `:dump` write and `--from-snapshot` shows.
+
+use crate::models::{Job, License, Node, Reservation};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// One refresh as the TUI saw it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Frame {
+    pub time: DateTime<Utc>,
+    /// The partition shown, `all` or a comma-separated list
+    pub partition: String,
+    /// Every partition the scheduler knew of
+    #[serde(default)]
+    pub partitions: Vec<String>,
+    pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub jobs: Vec<Job>,
+    #[serde(default)]
+    pub pending_jobs: Vec<Job>,
+    #[serde(default)]
+    pub user_jobs: Vec<Job>,
+    #[serde(default)]
+    pub reservations: Vec<Reservation>,
+    #[serde(default)]
+    pub licenses: Vec<License>,
+    #[serde(default)]
+    pub server_time: Option<DateTime<Utc>>,
+    /// Why the nodes couldn't be fetched
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Frame {
+    /// Replace every username by `user1`, `user2`, … (the same name the same everywhere) and
+    /// drop the job directories, which tend to contain them, for sharing in a bug report.
+    pub fn anonymize(&mut self) {
+        let mut names: HashMap<String, String> = HashMap::new();
+        let mut alias = |user: &mut String| {
+            let next = format!("user{}", names.len() + 1);
+            *user = names.entry(user.clone()).or_insert(next).clone();
+        };
+        for job in self.jobs.iter_mut().chain(&mut self.pending_jobs).chain(&mut self.user_jobs) {
+            alias(&mut job.user);
+            job.work_dir = None;
+        }
+        for node in &mut self.nodes {
+            node.job_users.iter_mut().for_each(&mut alias);
+        }
+        for reservation in &mut self.reservations {
+            reservation.users.iter_mut().for_each(&mut alias);
+        }
+    }
+}
+
+/// Appends frames to a `--record` file.
+pub struct Recorder {
+    path: PathBuf,
+    file: File,
+}
+
+impl Recorder {
+    /// Open `path` for appending, so recording again continues the same file.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        Ok(Self { path: path.to_path_buf(), file })
+    }
+
+    pub fn record(&mut self, frame: &Frame) -> Result<()> {
+        writeln!(self.file, "{}", serde_json::to_string(frame)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// A recording being played back: its frames and the one shown.
+#[derive(Debug)]
+pub struct Replay {
+    frames: Vec<Frame>,
+    position: Mutex<usize>,
+}
+
+impl Replay {
+    /// A `--record` file, or a single snapshot as `export` and `:dump` write it.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if let Ok(frame) = serde_json::from_str(&content) {
+            return Ok(Self { frames: vec![frame], position: Mutex::new(0) });
+        }
+        let mut frames = Vec::new();
+        for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let frame = serde_json::from_str(line)
+                .with_context(|| format!("{} line {}: not a recorded snapshot", path.display(), number + 1))?;
+            frames.push(frame);
+        }
+        if frames.is_empty() {
+            return Err(anyhow!("{} holds no snapshots", path.display()));
+        }
+        Ok(Self { frames, position: Mutex::new(0) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Never true for a loaded file, which holds at least one snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Index of the frame shown.
+    pub fn position(&self) -> usize {
+        *self.position.lock().unwrap()
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frames[self.position()]
+    }
+
+    /// Move `delta` frames, stopping at either end. Returns whether the frame changed.
+    pub fn seek(&self, delta: isize) -> bool {
+        let mut position = self.position.lock().unwrap();
+        let target = position.saturating_add_signed(delta).min(self.frames.len() - 1);
+        let moved = target != *position;
+        *position = target;
+        moved
+    }
+
+    /// Every partition seen in the recording, in order of appearance, including those only
+    /// shown (snapshots may not list them).
+    pub fn partitions(&self) -> Vec<String> {
+        let mut partitions: Vec<String> = Vec::new();
+        for frame in &self.frames {
+            let shown = frame.partition.split(',').map(str::trim).filter(|name| !name.is_empty() && *name != "all");
+            for name in frame.partitions.iter().map(String::as_str).chain(shown) {
+                if !partitions.iter().any(|p| p == name) {
+                    partitions.push(name.to_string());
+                }
+            }
+        }
+        partitions
+    }
+}
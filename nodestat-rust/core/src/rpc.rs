@@ -0,0 +1,3 @@
+//! gRPC messages and service stubs generated from `proto/nodestat.proto`.
+
+tonic::include_proto!("nodestat");
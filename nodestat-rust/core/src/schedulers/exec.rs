@@ -0,0 +1,129 @@
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// External commands that print `Node`/`Job` JSON, for sites with homegrown schedulers.
+///
+/// Commands run through `sh -c`; `{partition}`, `{user}` and `{ids}` are replaced
+/// with shell-quoted values.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecConfig {
+    /// Prints a JSON array of nodes
+    pub nodes_cmd: String,
+    /// Prints a JSON array of jobs
+    pub jobs_cmd: String,
+    /// Prints a JSON array of partition names, default first (default: taken from the nodes)
+    pub partitions_cmd: Option<String>,
+    /// Prints a JSON array of the given jobs in any state (default: filter `jobs_cmd`)
+    pub jobs_by_id_cmd: Option<String>,
+}
+
+/// Runs user-configured commands that print JSON in NodeStat's own `Node`/`Job` schema.
+pub struct ExecScheduler {
+    config: ExecConfig,
+    transport: Transport,
+}
+
+impl ExecScheduler {
+    pub fn new(config: ExecConfig, transport: Transport) -> Self {
+        Self { config, transport }
+    }
+
+    fn quote(value: &str) -> String {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+
+    async fn run_json<T: DeserializeOwned>(&self, template: &str, partition: &str, user: &str, ids: &[String]) -> Result<T> {
+        let ids: Vec<String> = ids.iter().map(|id| Self::quote(id)).collect();
+        let command = template
+            .replace("{partition}", &Self::quote(partition))
+            .replace("{user}", &Self::quote(user))
+            .replace("{ids}", &ids.join(" "));
+
+        let output = self.transport.command("sh")
+            .arg("-c")
+            .arg(&command)
+            .output().await
+            .with_context(|| format!("Failed to execute '{}'", command))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "'{}' failed: {}",
+                command,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Output of '{}' doesn't match the expected JSON schema", command))
+    }
+
+    async fn jobs(&self, partition: &str, user: &str) -> Result<Vec<Job>> {
+        self.run_json(&self.config.jobs_cmd, partition, user, &[]).await
+    }
+}
+
+#[async_trait]
+impl Scheduler for ExecScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let nodes: Vec<Node> = self.run_json(&self.config.nodes_cmd, partition, "", &[]).await?;
+
+        // Commands may ignore {partition}, so filter here too
+        let nodes: Vec<Node> = nodes
+            .into_iter()
+            .filter(|node| node.partitions.is_empty() || node.partitions.iter().any(|p| p == partition))
+            .collect();
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No nodes found in partition: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        Ok(self
+            .jobs(partition, "").await?
+            .into_iter()
+            .filter(|job| job.partition == partition && job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        Ok(self
+            .jobs("", &current_user).await?
+            .into_iter()
+            .filter(|job| job.user == current_user && job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        if let Some(ref cmd) = self.config.partitions_cmd {
+            return self.run_json(cmd, "", "", &[]).await;
+        }
+
+        let nodes: Vec<Node> = self.run_json(&self.config.nodes_cmd, "", "", &[]).await?;
+        let mut partitions: Vec<String> = Vec::new();
+        for partition in nodes.into_iter().flat_map(|node| node.partitions) {
+            if !partitions.contains(&partition) {
+                partitions.push(partition);
+            }
+        }
+        Ok(partitions)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        match self.config.jobs_by_id_cmd {
+            Some(ref cmd) => self.run_json(cmd, "", "", job_ids).await,
+            None => Ok(self
+                .jobs("", "").await?
+                .into_iter()
+                .filter(|job| job_ids.contains(&job.id))
+                .collect()),
+        }
+    }
+}
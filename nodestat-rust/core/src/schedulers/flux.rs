@@ -0,0 +1,339 @@
+use crate::hostlist;
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use serde_json::Value;
+use std::collections::HashMap;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// Partition name used when the instance has no named queues configured.
+const DEFAULT_QUEUE: &str = "default";
+
+/// Flux (flux-core) via `flux resource list` and `flux jobs` JSON output.
+pub struct FluxScheduler {
+    transport: Transport,
+}
+
+/// Per-node totals taken from one Rv1 resource set.
+#[derive(Default, Clone, Copy)]
+struct RankResources {
+    cores: u32,
+    gpus: u32,
+}
+
+impl FluxScheduler {
+    pub fn new(transport: Transport) -> Self {
+        Self { transport }
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<String> {
+        let output = self.transport.command("flux")
+            .args(args)
+            .output().await
+            .context("Failed to execute flux command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "flux {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    async fn run_json(&self, args: &[&str]) -> Result<Value> {
+        let output = self.run(args).await?;
+        serde_json::from_str(&output).context("Failed to parse flux JSON output")
+    }
+
+    /// Flux idsets like `0-3,7`.
+    fn parse_idset(idset: &str) -> Vec<u32> {
+        idset
+            .split(',')
+            .filter(|r| !r.is_empty())
+            .flat_map(|range| match range.split_once('-') {
+                Some((start, end)) => {
+                    let start: u32 = start.trim().parse().unwrap_or(0);
+                    let end: u32 = end.trim().parse().unwrap_or(start);
+                    (start..=end).collect::<Vec<_>>()
+                }
+                None => range.trim().parse().into_iter().collect(),
+            })
+            .collect()
+    }
+
+    /// Map an Rv1 resource set to hostname → resources.
+    fn parse_r(r: &Value) -> HashMap<String, RankResources> {
+        let execution = &r["execution"];
+        let hostnames: Vec<String> = execution["nodelist"]
+            .as_array()
+            .map(|lists| lists.iter().filter_map(Value::as_str).flat_map(hostlist::expand).collect())
+            .unwrap_or_default();
+
+        let mut by_rank: Vec<(u32, RankResources)> = Vec::new();
+        for entry in execution["R_lite"].as_array().into_iter().flatten() {
+            let children = &entry["children"];
+            let resources = RankResources {
+                cores: Self::parse_idset(children["core"].as_str().unwrap_or("")).len() as u32,
+                gpus: Self::parse_idset(children["gpu"].as_str().unwrap_or("")).len() as u32,
+            };
+            for rank in Self::parse_idset(entry["rank"].as_str().unwrap_or("")) {
+                by_rank.push((rank, resources));
+            }
+        }
+
+        // The nodelist is ordered by rank, so the i-th rank in the set is the i-th host
+        by_rank.sort_by_key(|(rank, _)| *rank);
+        by_rank
+            .into_iter()
+            .zip(hostnames)
+            .map(|((_, resources), host)| (host, resources))
+            .collect()
+    }
+
+    /// Hosts carrying the property a queue `requires`, if the queue is restricted.
+    fn queue_hosts(r: &Value, hostnames: &[String], queue: &str) -> Option<Vec<String>> {
+        let ranks = r["execution"]["properties"][queue].as_str()?;
+        Some(
+            Self::parse_idset(ranks)
+                .into_iter()
+                .filter_map(|rank| hostnames.get(rank as usize).cloned())
+                .collect(),
+        )
+    }
+
+    /// Drained hosts and their reasons (don't fail on error).
+    async fn drain_reasons(&self) -> HashMap<String, String> {
+        let Ok(output) = self.run(&["resource", "drain", "--no-header", "--format={nodelist} {reason}"]).await else {
+            return HashMap::new();
+        };
+
+        output
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .flat_map(|(nodelist, reason)| {
+                hostlist::expand(nodelist)
+                    .into_iter()
+                    .map(move |host| (host, reason.trim().to_string()))
+            })
+            .collect()
+    }
+
+    fn parse_job_state(state: &str, result: &str) -> JobState {
+        match state {
+            "RUN" | "CLEANUP" => JobState::Running,
+            "INACTIVE" => match result {
+                "COMPLETED" => JobState::Completed,
+                "CANCELED" => JobState::Cancelled,
+                "TIMEOUT" => JobState::Timeout,
+                _ => JobState::Failed,
+            },
+            _ => JobState::Pending,
+        }
+    }
+
+    fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+        let secs = value.as_f64().filter(|t| *t > 0.0)?;
+        Utc.timestamp_opt(secs as i64, 0).single()
+    }
+
+    fn parse_job(info: &Value) -> Job {
+        let state = Self::parse_job_state(
+            info["state"].as_str().unwrap_or(""),
+            info["result"].as_str().unwrap_or(""),
+        );
+        let node_list = hostlist::expand(info["nodelist"].as_str().unwrap_or(""));
+
+        let run_time = Self::timestamp(&info["t_run"]);
+        let end_time = Self::timestamp(&info["t_inactive"]).unwrap_or_else(Utc::now);
+        let elapsed = run_time.map(|t| end_time - t).unwrap_or_else(Duration::zero);
+        let ncores = info["ncores"].as_u64().unwrap_or(1) as u32;
+
+        Job {
+            id: match &info["id"] {
+                Value::Number(n) => n.to_string(),
+                other => other.as_str().unwrap_or("").to_string(),
+            },
+            user: info["username"].as_str().unwrap_or("").to_string(),
+            name: info["name"].as_str().unwrap_or("").to_string(),
+            state,
+            req_nodes: info["nnodes"].as_u64().unwrap_or(node_list.len() as u64).max(1) as u32,
+            node_list: if node_list.is_empty() { vec!["?".to_string()] } else { node_list },
+            partition: info["queue"].as_str().unwrap_or(DEFAULT_QUEUE).to_string(),
+            req_cpus: ncores.max(1),
+            req_mem: 0,
+            req_gpus: 0,
+            gpu_type: None,
+            reason: None,
+            priority: None,
+            est_start: None,
+            time_limit: Duration::seconds(info["duration"].as_f64().unwrap_or(0.0) as i64),
+            elapsed,
+            // Flux doesn't account CPU time
+            cpu_time: Duration::zero(),
+            submit_time: Self::timestamp(&info["t_submit"]).unwrap_or_else(Utc::now),
+            work_dir: None,
+            cluster: None,
+        }
+    }
+
+    async fn flux_jobs(&self, args: &[&str]) -> Result<Vec<Job>> {
+        let mut full_args = vec!["jobs", "--json"];
+        full_args.extend_from_slice(args);
+        let output = self.run_json(&full_args).await?;
+        Ok(output["jobs"]
+            .as_array()
+            .map(|jobs| jobs.iter().map(Self::parse_job).collect())
+            .unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl Scheduler for FluxScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let status = self.run_json(&["resource", "list", "--format=json"]).await?;
+
+        let all = Self::parse_r(&status["all"]);
+        let allocated = Self::parse_r(&status["allocated"]);
+        let down = Self::parse_r(&status["down"]);
+        let reasons = self.drain_reasons().await;
+
+        let hostnames: Vec<String> = status["all"]["execution"]["nodelist"]
+            .as_array()
+            .map(|lists| lists.iter().filter_map(Value::as_str).flat_map(hostlist::expand).collect())
+            .unwrap_or_default();
+        let queue_hosts = Self::queue_hosts(&status["all"], &hostnames, partition);
+
+        let mut nodes = Vec::new();
+        for host in &hostnames {
+            if queue_hosts.as_ref().is_some_and(|hosts| !hosts.contains(host)) {
+                continue;
+            }
+
+            let total = all.get(host).copied().unwrap_or_default();
+            let used = allocated.get(host).copied().unwrap_or_default();
+            let reason = reasons.get(host).cloned();
+
+            let state = if down.contains_key(host) {
+                if reason.is_some() { NodeState::Drained } else { NodeState::Down }
+            } else if reason.is_some() {
+                // Draining: still running jobs but won't take new ones
+                NodeState::Drained
+            } else if used.cores >= total.cores && total.cores > 0 {
+                NodeState::Busy
+            } else if used.cores > 0 {
+                NodeState::Running
+            } else {
+                NodeState::Idle
+            };
+
+            nodes.push(Node {
+                id: host.clone(),
+                state,
+                total_cores: total.cores,
+                used_cores: used.cores,
+                // Flux doesn't schedule memory
+                total_mem: 0,
+                used_mem: 0,
+                partitions: vec![partition.to_string()],
+                jobs: Vec::new(),
+                job_users: Vec::new(),
+                reason,
+                cpu_load: None,
+                current_watts: None,
+                consumed_joules: None,
+                os: None,
+                gpus: if total.gpus > 0 {
+                    vec![GpuGres { model: "gpu".to_string(), total: total.gpus, used: used.gpus }]
+                } else {
+                    Vec::new()
+                },
+                features: Vec::new(),
+                state_flags: Vec::new(),
+                cluster: None,
+            });
+        }
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No nodes found in queue: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let jobs = self.flux_jobs(&["-A", "--filter=running"]).await?;
+        Ok(jobs.into_iter().filter(|job| job.partition == partition).collect())
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        self.flux_jobs(&["--user", &current_user, "--filter=running"]).await
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        // Named queues live in the instance config; without any there's a single anonymous queue
+        let queues = self.run_json(&["config", "get", "queues"]).await.unwrap_or(Value::Null);
+        let mut partitions: Vec<String> = queues
+            .as_object()
+            .map(|q| q.keys().cloned().collect())
+            .unwrap_or_default();
+
+        if partitions.is_empty() {
+            return Ok(vec![DEFAULT_QUEUE.to_string()]);
+        }
+
+        let default = self.run_json(&["config", "get", "policy.jobspec.defaults.system.queue"]).await.ok();
+        if let Some(pos) = default
+            .as_ref()
+            .and_then(Value::as_str)
+            .and_then(|d| partitions.iter().position(|p| p == d))
+        {
+            let default = partitions.remove(pos);
+            partitions.insert(0, default);
+        }
+
+        Ok(partitions)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let mut args = vec!["-A", "--filter=pending,running,inactive"];
+        args.extend(job_ids.iter().map(String::as_str));
+        self.flux_jobs(&args).await
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        // --update only changes the reason of nodes that are already drained
+        let targets = node_ids.join(",");
+        self.run(&["resource", "drain", "--update", &targets, reason]).await?;
+        Ok(())
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.run(&["resource", "drain", &node_ids.join(","), reason]).await?;
+        Ok(())
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        self.run(&["resource", "undrain", &node_ids.join(",")]).await?;
+        Ok(())
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run(&["cancel", job_id]).await?;
+        Ok(())
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run(&["job", "urgency", job_id, "hold"]).await?;
+        Ok(())
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run(&["job", "urgency", job_id, "default"]).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Utc};
+use crate::clock::ClusterTz;
+
+const BJOBS_FORMAT: &str =
+    "jobid user stat queue job_name exec_host slots memlimit run_time runtimelimit cpu_used submit_time delimiter='|'";
+
+pub struct LsfScheduler {
+    tz: ClusterTz,
+    transport: Transport,
+}
+
+impl LsfScheduler {
+    pub fn new(tz: ClusterTz, transport: Transport) -> Self {
+        Self { tz, transport }
+    }
+
+    async fn run(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = self.transport.command(program)
+            .args(args)
+            .output().await
+            .with_context(|| format!("Failed to execute {} command", program))?;
+
+        // bjobs exits non-zero for "No unfinished job found", which isn't an error for us
+        if !output.status.success() && output.stdout.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("No ") && stderr.contains("job found") {
+                return Ok(String::new());
+            }
+            return Err(anyhow::anyhow!("{} command failed: {}", program, stderr));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn parse_node_state(status: &str, running_slots: u32) -> NodeState {
+        match status {
+            "ok" if running_slots > 0 => NodeState::Running,
+            "ok" => NodeState::Idle,
+            "closed_Full" | "closed_Excl" => NodeState::Busy,
+            "closed_Adm" => NodeState::Drained,
+            "unavail" | "unreach" => NodeState::Down,
+            _ => NodeState::Offline,
+        }
+    }
+
+    fn parse_job_state(stat: &str) -> JobState {
+        match stat {
+            "RUN" => JobState::Running,
+            // PSUSP jobs were suspended before they started, so they're still queued
+            "PEND" | "PSUSP" | "WAIT" => JobState::Pending,
+            "USUSP" | "SSUSP" => JobState::Suspended,
+            "DONE" => JobState::Completed,
+            _ => JobState::Failed,
+        }
+    }
+
+    /// LSF sizes like `255.9G`, `1 G` or `512M` (MB without a unit), in bytes.
+    fn parse_size(size: &str) -> u64 {
+        let size: String = size.chars().filter(|c| !c.is_whitespace()).collect();
+        let split = size.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(size.len());
+        let (number, unit) = size.split_at(split);
+        let value: f64 = number.parse().unwrap_or(0.0);
+        let factor = match unit.to_uppercase().as_str() {
+            "K" | "KB" => MB / 1000,
+            "G" | "GB" => GB,
+            "T" | "TB" => TB,
+            _ => MB,
+        };
+        (value * factor as f64) as u64
+    }
+
+    /// `3600 second(s)` (run_time, cpu_used) or minutes like `1440.0/hostA` (runtimelimit).
+    fn parse_duration(value: &str, unit_minutes: bool) -> Duration {
+        let number = value
+            .split([' ', '/'])
+            .next()
+            .and_then(|n| n.parse::<f64>().ok())
+            .unwrap_or(0.0);
+        if unit_minutes {
+            Duration::seconds((number * 60.0) as i64)
+        } else {
+            Duration::seconds(number as i64)
+        }
+    }
+
+    /// bjobs prints `Mar  1 10:00` without a year; assume the most recent such date.
+    fn parse_timestamp(&self, time_str: &str) -> Option<DateTime<Utc>> {
+        let now = Utc::now();
+        let with_year = format!("{} {}", time_str.split_whitespace().collect::<Vec<_>>().join(" "), now.year());
+        let naive = NaiveDateTime::parse_from_str(&with_year, "%b %d %H:%M %Y").ok()?;
+        let time = self.tz.localize(&naive)?;
+        if time > now + Duration::days(1) {
+            self.tz.localize(&naive.with_year(now.year() - 1)?)
+        } else {
+            Some(time)
+        }
+    }
+
+    fn parse_job_line(&self, line: &str) -> Option<Job> {
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        if fields.len() < 12 {
+            return None;
+        }
+
+        // exec_host looks like "4*hostA:4*hostB"
+        let node_list: Vec<String> = fields[5]
+            .split(':')
+            .filter(|h| !h.is_empty() && *h != "-")
+            .map(|h| h.rsplit('*').next().unwrap_or(h).to_string())
+            .collect();
+
+        Some(Job {
+            id: fields[0].to_string(),
+            user: fields[1].to_string(),
+            name: fields[4].to_string(),
+            state: Self::parse_job_state(fields[2]),
+            req_nodes: node_list.len().max(1) as u32,
+            node_list: if node_list.is_empty() { vec!["?".to_string()] } else { node_list },
+            partition: fields[3].to_string(),
+            req_cpus: fields[6].parse().unwrap_or(1),
+            req_mem: Self::parse_size(fields[7]),
+            req_gpus: 0,
+            gpu_type: None,
+            reason: None,
+            priority: None,
+            est_start: None,
+            elapsed: Self::parse_duration(fields[8], false),
+            time_limit: Self::parse_duration(fields[9], true),
+            cpu_time: Self::parse_duration(fields[10], false),
+            submit_time: self.parse_timestamp(fields[11]).unwrap_or_else(Utc::now),
+            work_dir: None,
+            cluster: None,
+        })
+    }
+
+    /// Total memory (`maxmem`) per host from `lshosts -w`.
+    fn parse_lshosts(output: &str) -> HashMap<String, u64> {
+        let mut max_mem = HashMap::new();
+        for line in output.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() >= 6 {
+                max_mem.insert(fields[0].to_string(), Self::parse_size(fields[5]));
+            }
+        }
+        max_mem
+    }
+
+    /// 1-minute load (`r1m`) and free memory (`mem`) per host from `lsload -w`.
+    fn parse_lsload(output: &str) -> HashMap<String, (f64, u64)> {
+        let mut load = HashMap::new();
+        for line in output.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() >= 12 {
+                let r1m = fields[3].trim_end_matches('*').parse().unwrap_or(0.0);
+                load.insert(fields[0].to_string(), (r1m, Self::parse_size(fields[11])));
+            }
+        }
+        load
+    }
+
+    /// The hosts of `bhosts -w` that serve the queue (every one if `queue_hosts` is `None`).
+    fn parse_bhosts(
+        output: &str,
+        queue_hosts: Option<&[String]>,
+        max_mem: &HashMap<String, u64>,
+        load: &HashMap<String, (f64, u64)>,
+        partition: &str,
+    ) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        for line in output.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                continue;
+            }
+            let host = fields[0];
+            if queue_hosts.is_some_and(|hosts| !hosts.iter().any(|h| h == host)) {
+                continue;
+            }
+
+            let total_cores = fields[3].parse().unwrap_or(0);
+            let used_cores = fields[4].parse().unwrap_or(0);
+            let total_mem = max_mem.get(host).copied().unwrap_or(0);
+            let (cpu_load, free_mem) = match load.get(host) {
+                Some((r1m, free)) => (Some(*r1m), *free),
+                None => (None, total_mem),
+            };
+
+            nodes.push(Node {
+                id: host.to_string(),
+                state: Self::parse_node_state(fields[1], used_cores),
+                total_cores,
+                used_cores,
+                total_mem,
+                used_mem: total_mem.saturating_sub(free_mem),
+                partitions: vec![partition.to_string()],
+                jobs: Vec::new(),
+                job_users: Vec::new(),
+                reason: None,
+                cpu_load,
+                current_watts: None,
+                consumed_joules: None,
+                os: None,
+                gpus: Vec::new(),
+                features: Vec::new(),
+                state_flags: Vec::new(),
+                cluster: None,
+            });
+        }
+        nodes
+    }
+
+    async fn bjobs(&self, args: &[&str]) -> Result<Vec<Job>> {
+        let mut full_args = vec!["-o", BJOBS_FORMAT, "-noheader"];
+        full_args.extend_from_slice(args);
+        let output = self.run("bjobs", &full_args).await?;
+        Ok(output.lines().filter_map(|line| self.parse_job_line(line)).collect())
+    }
+
+    /// Hosts that serve `queue`, or `None` if the queue uses all hosts.
+    async fn queue_hosts(&self, queue: &str) -> Result<Option<Vec<String>>> {
+        let output = self.run("bqueues", &["-l", queue]).await?;
+        let Some(hosts_line) = output.lines().find_map(|l| l.trim().strip_prefix("HOSTS:")) else {
+            return Ok(None);
+        };
+
+        let mut hosts = Vec::new();
+        for entry in hosts_line.split_whitespace() {
+            // Drop host preference suffixes like "hostA+2"
+            let entry = entry.split('+').next().unwrap_or(entry);
+            if entry == "all" || entry == "allremote" {
+                return Ok(None);
+            }
+            if let Some(group) = entry.strip_suffix('/') {
+                // Host groups: "GROUP_NAME HOSTS" header, then "group host1 host2 ..."
+                let members = self.run("bmgroup", &["-w", group]).await?;
+                hosts.extend(
+                    members.lines().skip(1).flat_map(|l| l.split_whitespace().skip(1)).map(|h| h.to_string()),
+                );
+            } else {
+                hosts.push(entry.to_string());
+            }
+        }
+
+        Ok(Some(hosts))
+    }
+}
+
+#[async_trait]
+impl Scheduler for LsfScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let queue_hosts = self.queue_hosts(partition).await?;
+        let bhosts = self.run("bhosts", &["-w"]).await?;
+
+        // Total memory from lshosts, free memory and load from lsload (don't fail on error)
+        let max_mem = self.run("lshosts", &["-w"]).await.map(|output| Self::parse_lshosts(&output)).unwrap_or_default();
+        let load = self.run("lsload", &["-w"]).await.map(|output| Self::parse_lsload(&output)).unwrap_or_default();
+        let nodes = Self::parse_bhosts(&bhosts, queue_hosts.as_deref(), &max_mem, &load, partition);
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No hosts found in queue: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        self.bjobs(&["-u", "all", "-r", "-q", partition]).await
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        self.bjobs(&["-u", &current_user, "-r"]).await
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let output = self.run("bqueues", &["-w"]).await?;
+        let mut queues: Vec<String> = output
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|q| q.to_string())
+            .collect();
+
+        // bparams -l reports "DEFAULT_QUEUE = normal [short ...]" (don't fail on error)
+        if let Ok(params) = self.run("bparams", &["-l"]).await {
+            let default = params
+                .lines()
+                .find_map(|l| l.trim().strip_prefix("DEFAULT_QUEUE"))
+                .and_then(|v| v.trim_start_matches([' ', '=']).split_whitespace().next());
+            if let Some(pos) = default.and_then(|d| queues.iter().position(|q| q == d)) {
+                let default = queues.remove(pos);
+                queues.insert(0, default);
+            }
+        }
+
+        Ok(queues)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        // -a includes recently finished jobs
+        let mut args = vec!["-a"];
+        args.extend(job_ids.iter().map(String::as_str));
+        self.bjobs(&args).await
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        // Re-closing an already closed host just replaces its comment
+        let mut args = vec!["hclose", "-C", reason];
+        args.extend(node_ids.iter().map(String::as_str));
+        self.run("badmin", &args).await?;
+        Ok(())
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        // A closed host keeps its running jobs, like a Slurm drain
+        let mut args = vec!["hclose", "-C", reason];
+        args.extend(node_ids.iter().map(String::as_str));
+        self.run("badmin", &args).await?;
+        Ok(())
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        let mut args = vec!["hopen"];
+        args.extend(node_ids.iter().map(String::as_str));
+        self.run("badmin", &args).await?;
+        Ok(())
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run("bkill", &[job_id]).await?;
+        Ok(())
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        // bstop on a pending job suspends it there (PSUSP) until bresume
+        self.run("bstop", &[job_id]).await?;
+        Ok(())
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run("bresume", &[job_id]).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts() {
+        let bhosts = "\
+HOST_NAME          STATUS       JL/U    MAX  NJOBS    RUN  SSUSP  USUSP    RSV
+hostA              ok              -     16      8      8      0      0      0
+hostB              closed_Full     -     16     16     16      0      0      0
+hostC              unavail         -     16      0      0      0      0      0
+hostD              closed_Adm      -     32      0      0      0      0      0
+";
+        let lshosts = "\
+HOST_NAME                     type       model  cpuf ncpus maxmem maxswp server RESOURCES
+hostA                       X86_64    Intel_EM64T  60.0    16 255.9G  16G    Yes (mg)
+hostB                       X86_64    Intel_EM64T  60.0    16   256G  16G    Yes (mg)
+";
+        let lsload = "\
+HOST_NAME               status  r15s   r1m  r15m   ut    pg  ls    it   tmp   swp   mem
+hostA                       ok   7.2   7.9   8.1  50%   0.0   2     1  100G   16G  127.9G
+hostC                  -unavail
+";
+        let max_mem = LsfScheduler::parse_lshosts(lshosts);
+        let load = LsfScheduler::parse_lsload(lsload);
+        let nodes = LsfScheduler::parse_bhosts(bhosts, None, &max_mem, &load, "normal");
+        assert_eq!(nodes.len(), 4);
+
+        let host_a = &nodes[0];
+        assert_eq!(host_a.state, NodeState::Running);
+        assert_eq!((host_a.used_cores, host_a.total_cores), (8, 16));
+        assert_eq!(host_a.total_mem, (255.9 * GB as f64) as u64);
+        assert_eq!(host_a.used_mem, host_a.total_mem - (127.9 * GB as f64) as u64);
+        assert_eq!(host_a.cpu_load, Some(7.9));
+        assert_eq!(host_a.partitions, vec!["normal"]);
+        // Without an lsload line the node's memory counts as free
+        assert_eq!((&nodes[1].state, nodes[1].used_mem), (&NodeState::Busy, 0));
+        assert_eq!(nodes[2].state, NodeState::Down);
+        assert_eq!((&nodes[3].state, nodes[3].total_mem), (&NodeState::Drained, 0));
+
+        let queue_hosts = vec!["hostB".to_string(), "hostD".to_string()];
+        let nodes = LsfScheduler::parse_bhosts(bhosts, Some(&queue_hosts), &max_mem, &load, "short");
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["hostB", "hostD"]);
+    }
+
+    #[test]
+    fn jobs() {
+        let scheduler = LsfScheduler::new(ClusterTz::Named(chrono_tz::UTC), Transport::local());
+        let running = scheduler
+            .parse_job_line("1234|alice|RUN|normal|sim|8*hostA:8*hostB|16|4 G|3600 second(s)|1440.0/hostA|7200 second(s)|Mar  1 10:00")
+            .unwrap();
+        assert_eq!((running.id.as_str(), running.user.as_str(), running.name.as_str()), ("1234", "alice", "sim"));
+        assert_eq!(running.state, JobState::Running);
+        assert_eq!(running.node_list, vec!["hostA", "hostB"]);
+        assert_eq!((running.req_nodes, running.req_cpus, running.req_mem), (2, 16, 4 * GB));
+        assert_eq!(running.elapsed, Duration::hours(1));
+        assert_eq!(running.time_limit, Duration::days(1));
+        assert_eq!(running.cpu_time, Duration::hours(2));
+        assert_eq!(running.submit_time.format("%m-%d %H:%M").to_string(), "03-01 10:00");
+
+        let pending = scheduler
+            .parse_job_line("1235|bob|PSUSP|normal|post|-|1|-|0 second(s)|-|0 second(s)|Mar  1 11:30")
+            .unwrap();
+        assert_eq!(pending.state, JobState::Pending);
+        assert_eq!((pending.node_list, pending.req_nodes, pending.req_mem), (vec!["?".to_string()], 1, 0));
+        assert_eq!(pending.time_limit, Duration::zero());
+
+        assert!(scheduler.parse_job_line("1236|carol|RUN").is_none());
+    }
+}
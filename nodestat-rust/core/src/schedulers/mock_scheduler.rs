@@ -0,0 +1,543 @@
+use crate::models::*;
+use crate::schedulers::Scheduler;
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use chrono::{Utc, Duration};
+use rand::Rng;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Job array that is partly running and partly queued in every partition
+const MOCK_ARRAY_ID: &str = "299999";
+
+pub struct MockScheduler {
+    // Reasons set through `update_node_reason`, so edits survive the next refresh
+    reasons: Mutex<HashMap<String, String>>,
+    // Nodes drained through `drain_nodes` until resumed
+    drained: Mutex<HashSet<String>>,
+}
+
+impl MockScheduler {
+    pub fn new() -> Self {
+        Self {
+            reasons: Mutex::new(HashMap::new()),
+            drained: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+impl Default for MockScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Scheduler for MockScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let mut rng = rand::thread_rng();
+        
+        let (node_count, node_prefix) = match partition {
+            "batch" => (25, "batch"),
+            "highmem_q" => (8, "highmem"), 
+            "gpu_q" => (6, "gpu"),
+            _ => return Err(anyhow!("Unknown partition: {}", partition)),
+        };
+        
+        let mut nodes = Vec::new();
+        
+        for i in 0..node_count {
+            let mut node = Node {
+                id: format!("{}{:03}", node_prefix, i + 1),
+                state: NodeState::Idle,
+                total_cores: 0,
+                used_cores: 0,
+                total_mem: 0,
+                used_mem: 0,
+                partitions: vec![partition.to_string()],
+                jobs: Vec::new(),
+                job_users: Vec::new(),
+                reason: None,
+                cpu_load: None,
+                current_watts: None,
+                consumed_joules: None,
+                os: None,
+                gpus: Vec::new(),
+                features: Vec::new(),
+                state_flags: Vec::new(),
+                cluster: None,
+            };
+            
+            // Set specs based on partition
+            match partition {
+                "batch" => {
+                    node.total_cores = 32 + rng.gen_range(0..32);
+                    node.total_mem = (128 + rng.gen_range(0..256)) * GB;
+                },
+                "highmem_q" => {
+                    node.total_cores = 48 + rng.gen_range(0..16);
+                    node.total_mem = (512 + rng.gen_range(0..1024)) * GB;
+                },
+                "gpu_q" => {
+                    node.total_cores = 40 + rng.gen_range(0..20);
+                    node.total_mem = (256 + rng.gen_range(0..256)) * GB;
+                },
+                _ => {}
+            }
+            
+            // Random states
+            let states = [
+                NodeState::Idle,
+                NodeState::Mixed,
+                NodeState::Mixed,
+                NodeState::Down,
+                NodeState::Allocated,
+                NodeState::Drained,
+                NodeState::Completing,
+            ];
+            node.state = states[rng.gen_range(0..states.len())].clone();
+            if node.state == NodeState::Mixed && rng.gen_range(0..5) == 0 {
+                node.state_flags.push("DRAIN".to_string());
+            }
+            
+            // Set usage based on state
+            match node.state {
+                NodeState::Idle => {
+                    node.used_cores = 0;
+                    node.used_mem = rng.gen_range(0..node.total_mem / 10);
+                },
+                NodeState::Mixed | NodeState::Completing => {
+                    node.used_cores = rng.gen_range(0..node.total_cores);
+                    node.used_mem = rng.gen_range(0..node.total_mem);
+                },
+                NodeState::Allocated => {
+                    node.used_cores = node.total_cores;
+                    node.used_mem = node.total_mem - rng.gen_range(0..node.total_mem / 4);
+                },
+                _ => {
+                    node.used_cores = 0;
+                    node.used_mem = 0;
+                }
+            }
+            
+            // Half the GPU nodes are whole A100s, the other half MIG-sliced
+            if partition == "gpu_q" {
+                let layout: &[(&str, u32)] = if i % 2 == 0 {
+                    &[("a100", 4)]
+                } else {
+                    &[("a100_3g.20gb", 4), ("a100_1g.5gb", 2)]
+                };
+                node.gpus = layout
+                    .iter()
+                    .map(|(model, total)| GpuGres {
+                        model: model.to_string(),
+                        total: *total,
+                        used: match node.state {
+                            NodeState::Mixed | NodeState::Completing => rng.gen_range(0..=*total),
+                            NodeState::Allocated => *total,
+                            _ => 0,
+                        },
+                    })
+                    .collect();
+            }
+
+            // Older batch nodes lack AVX-512
+            let cpu_feature = if partition == "batch" && i % 3 == 0 { "avx2" } else { "avx512" };
+            node.features = vec![cpu_feature.to_string(), "ib".to_string()];
+            match partition {
+                "highmem_q" => node.features.push("bigmem".to_string()),
+                "gpu_q" => node.features.push("a100".to_string()),
+                _ => {}
+            }
+
+            // Load tracks allocation, with the occasional runaway process
+            if !node.is_drained() {
+                let noise = rng.gen_range(-1.0..1.0);
+                let rogue = if rng.gen_range(0..10) == 0 { node.total_cores as f64 * 0.8 } else { 0.0 };
+                node.cpu_load = Some((node.used_cores as f64 + noise + rogue).max(0.0));
+            }
+
+            // Idle draw plus the busy cores and GPUs; energy since a boot days to weeks ago
+            if node.state.is_up() {
+                let watts = 180 + node.used_cores * 6 + node.used_gpus() * 280 + rng.gen_range(0..20);
+                node.current_watts = Some(watts);
+                node.consumed_joules = Some(rng.gen_range(24..1000) * 3600 * 250);
+            }
+
+            if self.drained.lock().unwrap().contains(&node.id) && !node.has_flag("DRAIN") {
+                node.state_flags.push("DRAIN".to_string());
+            }
+            if node.is_drained() {
+                let reasons = self.reasons.lock().unwrap();
+                node.reason = Some(
+                    reasons
+                        .get(&node.id)
+                        .cloned()
+                        .unwrap_or_else(|| "Not responding".to_string()),
+                );
+            }
+            
+            // Generate job IDs for running nodes
+            if node.state.is_up() && node.used_cores > 0 {
+                let job_count = 1 + rng.gen_range(0..3);
+                for _ in 0..job_count {
+                    node.jobs.push(format!("{}", 100000 + rng.gen_range(0..999999)));
+                }
+            }
+            
+            nodes.push(node);
+        }
+        
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let mut rng = rand::thread_rng();
+        let job_count = 10 + rng.gen_range(0..20);
+        let mut jobs = Vec::new();
+        
+        let users = ["alice", "bob", "carol", "dave", "eve", "frank", "grace", "henry"];
+        
+        for i in 0..job_count {
+            let user = users[rng.gen_range(0..users.len())];
+            let job = Job {
+                id: format!("{}", 100000 + rng.gen_range(0..999999)),
+                user: user.to_string(),
+                name: format!("job_{}", i + 1),
+                state: JobState::Running,
+                partition: partition.to_string(),
+                req_nodes: 1 + rng.gen_range(0..4),
+                req_cpus: 8 + rng.gen_range(0..32),
+                req_mem: (16 + rng.gen_range(0..128)) * GB,
+                req_gpus: if partition == "gpu_q" { 1 + rng.gen_range(0..4) } else { 0 },
+                gpu_type: (partition == "gpu_q").then(|| "a100".to_string()),
+                reason: None,
+                priority: None,
+                est_start: None,
+                elapsed: Duration::seconds(rng.gen_range(0..86400)),
+                time_limit: Duration::hours(24),
+                cpu_time: Duration::seconds(rng.gen_range(0..86400)),
+                submit_time: Utc::now(),
+                node_list: vec![format!("{}{:03}", partition, rng.gen_range(1..21))],
+                work_dir: Some(format!("/home/{}/job_{}", user, i + 1)),
+                cluster: None,
+            };
+            
+            jobs.push(job);
+        }
+
+        // The first tasks of an array whose remaining tasks are queued (see get_pending_jobs)
+        for task in 1..=3 {
+            jobs.push(Job {
+                id: format!("{}_{}", MOCK_ARRAY_ID, task),
+                user: "grace".to_string(),
+                name: "sweep".to_string(),
+                node_list: vec![format!("{}{:03}", partition, task)],
+                ..jobs[0].clone()
+            });
+        }
+        
+        Ok(jobs)
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let mut rng = rand::thread_rng();
+        let users = ["alice", "bob", "carol", "dave", "eve", "frank", "grace", "henry"];
+        // Mostly ordinary waits, with a few limits and dependencies mixed in
+        let reasons = [
+            "Resources", "Priority", "Priority", "Priority", "QOSMaxJobsPerUserLimit",
+            "Dependency", "AssocGrpCpuLimit", "ReqNodeNotAvail, Reserved for maintenance",
+        ];
+        let now = Utc::now();
+
+        let mut jobs: Vec<Job> = (0..5 + rng.gen_range(0..10)).map(|i| {
+            let reason = if i == 0 { "Resources" } else { reasons[rng.gen_range(0..reasons.len())] };
+            let waiting = Duration::minutes(rng.gen_range(5..2880));
+            let priority = JobPriority {
+                age: waiting.num_minutes() as u64 * 2,
+                fairshare: rng.gen_range(0..5000),
+                job_size: rng.gen_range(0..500),
+                partition: 1000,
+                qos: [0, 0, 2000][rng.gen_range(0..3)],
+                ..JobPriority::default()
+            };
+            Job {
+                id: format!("{}", 300000 + i * 17 + rng.gen_range(0..17)),
+                user: users[rng.gen_range(0..users.len())].to_string(),
+                name: format!("queued_{}", i + 1),
+                state: JobState::Pending,
+                partition: partition.to_string(),
+                req_nodes: 1 + rng.gen_range(0..4),
+                req_cpus: 16 + rng.gen_range(0..64),
+                req_mem: (32 + rng.gen_range(0..256)) * GB,
+                req_gpus: if partition == "gpu_q" { 1 + rng.gen_range(0..4) } else { 0 },
+                gpu_type: (partition == "gpu_q").then(|| "a100".to_string()),
+                reason: Some(reason.to_string()),
+                priority: Some(JobPriority {
+                    total: priority.age + priority.fairshare + priority.job_size + priority.partition + priority.qos,
+                    ..priority
+                }),
+                // Only jobs waiting for resources get planned by backfill
+                est_start: matches!(reason, "Resources" | "Priority")
+                    .then(|| now + Duration::minutes(rng.gen_range(10..1440))),
+                elapsed: Duration::zero(),
+                time_limit: Duration::hours(1 + rng.gen_range(0..48)),
+                cpu_time: Duration::zero(),
+                submit_time: now - waiting,
+                node_list: Vec::new(),
+                work_dir: None,
+                cluster: None,
+            }
+        }).collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.priority.as_ref().map_or(0, |p| p.total)));
+
+        // The rest of the array whose first tasks are running; squeue compresses pending tasks
+        let mut array = jobs[0].clone();
+        array.id = format!("{}_[4-40%8]", MOCK_ARRAY_ID);
+        array.user = "grace".to_string();
+        array.name = "sweep".to_string();
+        array.reason = Some("JobArrayTaskLimit".to_string());
+        array.est_start = None;
+        jobs.insert(1, array);
+
+        Ok(jobs)
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let mut rng = rand::thread_rng();
+        let job_count = rng.gen_range(0..4);
+        let mut jobs = Vec::new();
+        
+        for i in 0..job_count {
+            let job = Job {
+                id: format!("{}", 200000 + rng.gen_range(0..999999)),
+                user: user.to_string(),
+                name: format!("my_job_{}", i + 1),
+                state: JobState::Running,
+                partition: "batch".to_string(),
+                req_nodes: 1,
+                req_cpus: 4 + rng.gen_range(0..16),
+                req_mem: (8 + rng.gen_range(0..64)) * GB,
+                req_gpus: 0,
+                gpu_type: None,
+                reason: None,
+                priority: None,
+                est_start: None,
+                elapsed: Duration::seconds(rng.gen_range(0..43200)),
+                time_limit: Duration::hours(12),
+                cpu_time: Duration::seconds(rng.gen_range(0..43200)),
+                submit_time: Utc::now(),
+                node_list: vec![format!("batch{:03}", rng.gen_range(1..11))],
+                work_dir: None,
+                cluster: None,
+            };
+            
+            jobs.push(job);
+        }
+        
+        Ok(jobs)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        Ok(vec!["batch".to_string(), "highmem_q".to_string(), "gpu_q".to_string()])
+    }
+
+    async fn get_partitions(&self) -> Result<Vec<Partition>> {
+        let partition = |name: &str, hours: i64, mem_per_cpu_gb: u64, total_nodes: u32| Partition {
+            name: name.to_string(),
+            state: "UP".to_string(),
+            is_default: name == "batch",
+            max_time: Some(Duration::hours(hours)),
+            default_mem_per_cpu: Some(mem_per_cpu_gb * GB),
+            max_mem_per_cpu: Some(mem_per_cpu_gb * 4 * GB),
+            total_nodes,
+            allow_accounts: Vec::new(),
+            deny_accounts: Vec::new(),
+            cluster: None,
+        };
+        let mut gpu = partition("gpu_q", 24, 8, 6);
+        gpu.allow_accounts = vec!["ml_lab".to_string(), "vision".to_string()];
+        Ok(vec![partition("batch", 72, 4, 25), partition("highmem_q", 168, 16, 8), gpu])
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        // Each job cycles pending → running → completed every few minutes so
+        // watchlist transitions can be seen in demo mode
+        let now = Utc::now();
+        let jobs = job_ids.iter().map(|id| {
+            let mut hasher = DefaultHasher::new();
+            id.hash(&mut hasher);
+            let seed = hasher.finish();
+            let phase = (seed + (now.timestamp() / 120) as u64) % 6;
+
+            let state = match phase {
+                0 => JobState::Pending,
+                // Some runs end badly, to show the different end states
+                5 => match seed % 4 {
+                    0 => JobState::Timeout,
+                    1 => JobState::OutOfMemory,
+                    _ => JobState::Completed,
+                },
+                _ => JobState::Running,
+            };
+            let elapsed = match state {
+                JobState::Pending => Duration::seconds(0),
+                _ => Duration::minutes(phase as i64 * 2),
+            };
+
+            Job {
+                id: id.clone(),
+                user: "mock".to_string(),
+                name: format!("watched_{}", id),
+                state,
+                partition: "batch".to_string(),
+                req_nodes: 1,
+                req_cpus: 8,
+                req_mem: 32 * GB,
+                req_gpus: 0,
+                gpu_type: None,
+                reason: None,
+                priority: None,
+                est_start: None,
+                elapsed,
+                time_limit: Duration::hours(1),
+                cpu_time: elapsed * 8,
+                submit_time: now - elapsed,
+                node_list: vec![format!("batch{:03}", seed % 25 + 1)],
+                work_dir: None,
+                cluster: None,
+            }
+        }).collect();
+
+        Ok(jobs)
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        // A maintenance window later today and a dedicated block that's already running
+        let now = Utc::now();
+        Ok(vec![
+            Reservation {
+                name: "maint_fs".to_string(),
+                start_time: now + Duration::hours(3),
+                end_time: now + Duration::hours(15),
+                nodes: (3..=5).map(|i| format!("batch{:03}", i)).collect(),
+                users: vec!["root".to_string()],
+                flags: vec!["MAINT".to_string(), "SPEC_NODES".to_string()],
+                cluster: None,
+            },
+            Reservation {
+                name: "course_hpc101".to_string(),
+                start_time: now - Duration::hours(2),
+                end_time: now + Duration::days(2),
+                nodes: vec!["gpu001".to_string(), "gpu002".to_string()],
+                users: vec!["alice".to_string(), "bob".to_string()],
+                flags: Vec::new(),
+                cluster: None,
+            },
+        ])
+    }
+
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        let mut rng = rand::thread_rng();
+        let license = |name: &str, total: u32, used: u32, reserved: u32| License {
+            name: name.to_string(),
+            total,
+            used,
+            reserved,
+            cluster: None,
+        };
+        // MATLAB tends to run out
+        Ok(vec![
+            license("matlab", 20, 18 + rng.gen_range(0..3), 0),
+            license("ansys", 16, rng.gen_range(4..12), 4),
+            license("comsol", 4, rng.gen_range(0..3), 0),
+        ])
+    }
+
+    async fn get_fairshare(&self, user: &str) -> Result<Vec<Fairshare>> {
+        let mut rng = rand::thread_rng();
+        // Usage drifts between refreshes so the trend arrows move
+        let physics_usage: u64 = 48_000_000 + rng.gen_range(0..2_000_000);
+        let user_usage: u64 = 31_000_000 + rng.gen_range(0..1_500_000);
+        Ok(vec![
+            Fairshare {
+                account: "physics".to_string(),
+                user: None,
+                norm_shares: 0.25,
+                raw_usage: physics_usage,
+                effective_usage: 0.32,
+                fairshare: None,
+                cluster: None,
+            },
+            Fairshare {
+                account: "physics".to_string(),
+                user: Some(user.to_string()),
+                norm_shares: 0.2,
+                raw_usage: user_usage,
+                effective_usage: user_usage as f64 / physics_usage as f64,
+                fairshare: Some(0.3 + rng.gen_range(0.0..0.05)),
+                cluster: None,
+            },
+            Fairshare {
+                account: "ml_lab".to_string(),
+                user: Some(user.to_string()),
+                norm_shares: 0.5,
+                raw_usage: 120_000 + rng.gen_range(0..50_000),
+                effective_usage: 0.02,
+                fairshare: Some(0.91),
+                cluster: None,
+            },
+        ])
+    }
+
+    async fn get_topology(&self) -> Result<HashMap<String, String>> {
+        // Ten batch nodes per leaf switch, the big-memory and GPU nodes on their own
+        let mut switches = HashMap::new();
+        for i in 1..=25 {
+            switches.insert(format!("batch{:03}", i), format!("sw-batch{}", (i - 1) / 10 + 1));
+        }
+        for i in 1..=8 {
+            switches.insert(format!("highmem{:03}", i), "sw-highmem".to_string());
+        }
+        for i in 1..=6 {
+            switches.insert(format!("gpu{:03}", i), "sw-gpu".to_string());
+        }
+        Ok(switches)
+    }
+
+    // The mock's jobs are made up afresh on every refresh, so job actions have nothing to change
+    async fn cancel_job(&self, _job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn hold_job(&self, _job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn release_job(&self, _job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.drained.lock().unwrap().extend(node_ids.iter().cloned());
+        self.update_node_reason(node_ids, reason).await
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        let mut drained = self.drained.lock().unwrap();
+        for id in node_ids {
+            drained.remove(id);
+        }
+        Ok(())
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        let mut reasons = self.reasons.lock().unwrap();
+        for id in node_ids {
+            reasons.insert(id.clone(), reason.to_string());
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
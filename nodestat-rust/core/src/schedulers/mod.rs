@@ -0,0 +1,239 @@
+mod slurm;
+mod slurmrest;
+mod torque;
+mod pbspro;
+mod lsf;
+mod sge;
+mod flux;
+mod exec;
+mod wasm;
+mod multi;
+mod ratelimit;
+mod remote;
+mod replay;
+mod transport;
+mod mock_scheduler;
+
+pub use slurm::{JobsSource, SlurmClusters, SlurmConfig, SlurmScheduler};
+pub use slurmrest::SlurmRestScheduler;
+pub use torque::TorqueScheduler;
+pub use pbspro::PbsProScheduler;
+pub use lsf::LsfScheduler;
+pub use sge::SgeScheduler;
+pub use flux::FluxScheduler;
+pub use exec::{ExecConfig, ExecScheduler};
+pub use wasm::{discover_plugins, WasmScheduler};
+pub use multi::{ClusterMember, ClusterStatus, MultiScheduler};
+pub use ratelimit::{RateLimit, RateLimitedScheduler};
+pub use remote::RemoteScheduler;
+pub use replay::ReplayScheduler;
+pub use transport::{shell_quote, Timeouts, Transport};
+pub use mock_scheduler::MockScheduler;
+
+use crate::clock::ClusterTz;
+use crate::models::{Fairshare, Node, Job, JobWait, License, Partition, Reservation};
+use async_trait::async_trait;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub enum SchedulerType {
+    /// The clusters to show, and `[slurm]` (the defaults until the caller sets it)
+    Slurm(SlurmClusters, SlurmConfig),
+    SlurmRest,
+    Torque,
+    PbsPro,
+    Lsf,
+    Sge,
+    Flux,
+    Exec(ExecConfig),
+    Plugin(PathBuf),
+    Mock,
+}
+
+#[async_trait]
+pub trait Scheduler: Send + Sync {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>>;
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>>;
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>>;
+
+    /// Names of the partitions/queues on the cluster. The default partition, if any, comes first.
+    async fn list_partitions(&self) -> Result<Vec<String>>;
+
+    /// Partitions with their limits, the default one first.
+    async fn get_partitions(&self) -> Result<Vec<Partition>> {
+        Err(anyhow!("Partition limits are not supported by this scheduler"))
+    }
+
+    /// Pending jobs of a partition with their `reason`, in the order the scheduler will consider them.
+    async fn get_pending_jobs(&self, _partition: &str) -> Result<Vec<Job>> {
+        Err(anyhow!("Listing pending jobs is not supported by this scheduler"))
+    }
+
+    /// Look up specific jobs in any state (running, pending or finished).
+    async fn get_jobs_by_id(&self, _job_ids: &[String]) -> Result<Vec<Job>> {
+        Err(anyhow!("Looking up jobs by ID is not supported by this scheduler"))
+    }
+
+    /// How long the jobs that started since `since` waited, for the wait times per partition.
+    async fn get_job_waits(&self, _since: DateTime<Utc>) -> Result<Vec<JobWait>> {
+        Err(anyhow!("Queue wait times are not supported by this scheduler"))
+    }
+
+    /// Active and upcoming reservations (maintenance windows and the like).
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        Err(anyhow!("Reservations are not supported by this scheduler"))
+    }
+
+    /// License pools and how many seats are in use.
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        Err(anyhow!("Licenses are not supported by this scheduler"))
+    }
+
+    /// Fairshare of the user's associations, with the rows of the accounts they belong to.
+    async fn get_fairshare(&self, _user: &str) -> Result<Vec<Fairshare>> {
+        Err(anyhow!("Fairshare is not supported by this scheduler"))
+    }
+
+    /// Leaf switch of each node by node name, from the scheduler's network topology.
+    async fn get_topology(&self) -> Result<HashMap<String, String>> {
+        Err(anyhow!("Network topology is not supported by this scheduler"))
+    }
+
+    /// When the data last returned was polled, for a backend that serves it from a cache (a
+    /// daemon's); `None` when it's fetched live.
+    async fn get_polled_at(&self) -> Result<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+
+    /// The controller's current time, used to detect clock skew. `None` if not reported.
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+
+    /// Set the drain/down `Reason` on one or more nodes (admin only).
+    async fn update_node_reason(&self, _node_ids: &[String], _reason: &str) -> Result<()> {
+        Err(anyhow!("Editing node reasons is not supported by this scheduler"))
+    }
+
+    /// Drain nodes: no new jobs start on them, running ones finish (admin only).
+    async fn drain_nodes(&self, _node_ids: &[String], _reason: &str) -> Result<()> {
+        Err(anyhow!("Draining nodes is not supported by this scheduler"))
+    }
+
+    /// Return drained or down nodes to service (admin only).
+    async fn resume_nodes(&self, _node_ids: &[String]) -> Result<()> {
+        Err(anyhow!("Resuming nodes is not supported by this scheduler"))
+    }
+
+    /// Cancel a job of the user's (`cluster`: the job's, in a multi-cluster view).
+    async fn cancel_job(&self, _job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        Err(anyhow!("Cancelling jobs is not supported by this scheduler"))
+    }
+
+    /// Keep a pending job of the user's from starting until released.
+    async fn hold_job(&self, _job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        Err(anyhow!("Holding jobs is not supported by this scheduler"))
+    }
+
+    /// Let a held job start again.
+    async fn release_job(&self, _job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        Err(anyhow!("Releasing jobs is not supported by this scheduler"))
+    }
+
+    /// Member clusters of a multi-cluster view, in display order. Empty for a single cluster.
+    fn clusters(&self) -> Vec<ClusterStatus> {
+        Vec::new()
+    }
+
+    /// Show or hide one member cluster of a multi-cluster view.
+    fn set_cluster_enabled(&self, _name: &str, _enabled: bool) {}
+}
+
+/// Map a `--scheduler` value (or a `[[clusters]]` `scheduler`) to a backend; `exec` runs the
+/// commands of the config's `[exec]` table.
+pub async fn resolve_scheduler(name: &str, transport: &Transport, exec: Option<&ExecConfig>) -> Result<SchedulerType> {
+    Ok(match name {
+        "auto" => detect_scheduler(transport).await.ok_or_else(|| anyhow!(
+            "Could not detect a scheduler (no scontrol, bhosts, qhost, qstat or flux found on PATH or --host). Use --scheduler to pick one, or 'mock' to try NodeStat out"
+        ))?,
+        "slurm" => SchedulerType::Slurm(SlurmClusters::Local, SlurmConfig::default()),
+        "slurmrest" => SchedulerType::SlurmRest,
+        "torque" => SchedulerType::Torque,
+        "pbspro" | "pbs" => SchedulerType::PbsPro,
+        "lsf" => SchedulerType::Lsf,
+        "sge" => SchedulerType::Sge,
+        "flux" => SchedulerType::Flux,
+        "exec" => match exec {
+            Some(exec) => SchedulerType::Exec(exec.clone()),
+            None => return Err(anyhow!("The exec scheduler needs an [exec] section with nodes_cmd and jobs_cmd in config.toml")),
+        },
+        "mock" => SchedulerType::Mock,
+        name => {
+            let plugins = discover_plugins();
+            match plugins.iter().find(|(plugin, _)| plugin == name) {
+                Some((_, path)) => SchedulerType::Plugin(path.clone()),
+                None => {
+                    let mut message = format!("Invalid scheduler type '{}'. Use 'auto', 'slurm', 'slurmrest', 'torque', 'pbspro', 'lsf', 'sge', 'flux', 'exec', 'mock', or a plugin name", name);
+                    if !plugins.is_empty() {
+                        let names: Vec<&str> = plugins.iter().map(|(plugin, _)| plugin.as_str()).collect();
+                        message.push_str(&format!("\nInstalled plugins: {}", names.join(", ")));
+                    }
+                    return Err(anyhow!(message));
+                }
+            }
+        }
+    })
+}
+
+pub fn create_scheduler(scheduler_type: SchedulerType, cluster_tz: ClusterTz, transport: Transport) -> Result<Box<dyn Scheduler>> {
+    Ok(match scheduler_type {
+        SchedulerType::Slurm(clusters, config) => Box::new(SlurmScheduler::new(cluster_tz, transport, clusters, config)),
+        SchedulerType::SlurmRest => Box::new(SlurmRestScheduler::new()),
+        SchedulerType::Torque => Box::new(TorqueScheduler::new(cluster_tz, transport)),
+        SchedulerType::PbsPro => Box::new(PbsProScheduler::new(cluster_tz, transport)),
+        SchedulerType::Lsf => Box::new(LsfScheduler::new(cluster_tz, transport)),
+        SchedulerType::Sge => Box::new(SgeScheduler::new(cluster_tz, transport)),
+        SchedulerType::Flux => Box::new(FluxScheduler::new(transport)),
+        SchedulerType::Exec(config) => Box::new(ExecScheduler::new(config, transport)),
+        SchedulerType::Plugin(path) => Box::new(WasmScheduler::load(&path, transport)?),
+        SchedulerType::Mock => Box::new(MockScheduler::new()),
+    })
+}
+
+/// Guess the scheduler from the client tools on `PATH` (locally or on the `--host`), for `--scheduler auto`.
+pub async fn detect_scheduler(transport: &Transport) -> Option<SchedulerType> {
+    // Inside a Flux instance (possibly nested under Slurm), Flux is what schedules our jobs
+    if transport.is_local() && std::env::var_os("FLUX_URI").is_some() && transport.has_program("flux").await {
+        return Some(SchedulerType::Flux);
+    }
+    if transport.has_program("scontrol").await {
+        return Some(SchedulerType::Slurm(SlurmClusters::Local, SlurmConfig::default()));
+    }
+    if transport.has_program("bhosts").await {
+        return Some(SchedulerType::Lsf);
+    }
+    if transport.has_program("qhost").await {
+        return Some(SchedulerType::Sge);
+    }
+    let pbs = transport.has_program("pbsnodes").await || transport.has_program("qstat").await || transport.has_program("mdiag").await;
+    if pbs {
+        // PBS Pro/OpenPBS report "pbs_version = ...", Torque reports "Version: ..."
+        let is_pbspro = transport
+            .command("qstat")
+            .arg("--version")
+            .output().await
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains("pbs_version"))
+            .unwrap_or(false);
+        return Some(if is_pbspro { SchedulerType::PbsPro } else { SchedulerType::Torque });
+    }
+    if transport.has_program("flux").await {
+        return Some(SchedulerType::Flux);
+    }
+    if std::env::var_os("SLURMRESTD_URL").is_some() {
+        return Some(SchedulerType::SlurmRest);
+    }
+    None
+}
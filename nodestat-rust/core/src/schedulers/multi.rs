@@ -0,0 +1,377 @@
+//! Several clusters merged into one view (`--clusters`), each with its own backend and transport.
+//!
+//! Nodes and jobs are tagged with their member's name in `cluster` (`<member>/<cluster>` for a
+//! federated Slurm member). A member that fails doesn't hide the others; its error is
+//! reported through [`Scheduler::clusters`].
+
+use crate::models::*;
+use crate::schedulers::Scheduler;
+use async_trait::async_trait;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// The only partition of the merged view; each member shows its own configured partition.
+const ALL_PARTITIONS: &str = "all";
+
+/// One member cluster's state, for the header and the toggle keys.
+#[derive(Debug, Clone)]
+pub struct ClusterStatus {
+    pub name: String,
+    pub enabled: bool,
+    /// Error from the last refresh, if the member failed
+    pub error: Option<String>,
+}
+
+pub struct ClusterMember {
+    name: String,
+    scheduler: Box<dyn Scheduler>,
+    /// Configured partition, or the member's default once looked up
+    partition: Mutex<Option<String>>,
+    enabled: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+impl ClusterMember {
+    pub fn new(name: String, scheduler: Box<dyn Scheduler>, partition: Option<String>) -> Self {
+        Self {
+            name,
+            scheduler,
+            partition: Mutex::new(partition),
+            enabled: AtomicBool::new(true),
+            error: Mutex::new(None),
+        }
+    }
+
+    async fn partition(&self) -> Result<String> {
+        let configured = self.partition.lock().unwrap().clone();
+        if let Some(partition) = configured {
+            return Ok(partition);
+        }
+
+        let default = self
+            .scheduler
+            .list_partitions()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No partitions found"))?;
+        *self.partition.lock().unwrap() = Some(default.clone());
+        Ok(default)
+    }
+
+    /// `-q` applies to every member; otherwise each uses its own partition.
+    async fn resolve_partition(&self, requested: &str) -> Result<String> {
+        if requested == ALL_PARTITIONS {
+            self.partition().await
+        } else {
+            Ok(requested.to_string())
+        }
+    }
+
+    /// `<member>`, or `<member>/<cluster>` if the member's backend spans several clusters itself.
+    fn tag(&self, cluster: Option<String>) -> Option<String> {
+        Some(match cluster {
+            Some(cluster) => format!("{}/{}", self.name, cluster),
+            None => self.name.clone(),
+        })
+    }
+
+    fn tag_jobs(&self, jobs: Vec<Job>) -> Vec<Job> {
+        jobs.into_iter()
+            .map(|mut job| {
+                job.cluster = self.tag(job.cluster.take());
+                job
+            })
+            .collect()
+    }
+}
+
+pub struct MultiScheduler {
+    members: Vec<ClusterMember>,
+    /// Member index of each node at the last refresh, to route node actions
+    node_clusters: Mutex<HashMap<String, usize>>,
+}
+
+impl MultiScheduler {
+    pub fn new(members: Vec<ClusterMember>) -> Self {
+        Self {
+            members,
+            node_clusters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn enabled_members(&self) -> impl Iterator<Item = (usize, &ClusterMember)> {
+        self.members
+            .iter()
+            .enumerate()
+            .filter(|(_, member)| member.enabled.load(Ordering::Relaxed))
+    }
+
+    /// `node_ids` split by the member they were listed by at the last refresh, to route node actions.
+    fn nodes_by_member(&self, node_ids: &[String]) -> Result<Vec<(&ClusterMember, Vec<String>)>> {
+        let mut by_member: Vec<Vec<String>> = vec![Vec::new(); self.members.len()];
+        let node_clusters = self.node_clusters.lock().unwrap();
+        for node_id in node_ids {
+            let index = node_clusters
+                .get(node_id)
+                .ok_or_else(|| anyhow!("Unknown node: {}", node_id))?;
+            by_member[*index].push(node_id.clone());
+        }
+        Ok(self.members.iter().zip(by_member).filter(|(_, ids)| !ids.is_empty()).collect())
+    }
+
+    /// The member a job belongs to, and its cluster within the member, from the job's
+    /// `<member>` or `<member>/<cluster>` tag.
+    fn member_of_job<'a>(&self, job_id: &str, cluster: Option<&'a str>) -> Result<(&ClusterMember, Option<&'a str>)> {
+        let tag = cluster.ok_or_else(|| anyhow!("Job {} has no cluster", job_id))?;
+        let (name, member_cluster) = match tag.split_once('/') {
+            Some((name, member_cluster)) => (name, Some(member_cluster)),
+            None => (tag, None),
+        };
+        let member = self.members
+            .iter()
+            .find(|member| member.name == name)
+            .ok_or_else(|| anyhow!("Unknown cluster: {}", name))?;
+        Ok((member, member_cluster))
+    }
+}
+
+#[async_trait]
+impl Scheduler for MultiScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        let mut node_clusters = HashMap::new();
+        let mut errors = Vec::new();
+
+        for (index, member) in self.enabled_members() {
+            let result = match member.resolve_partition(partition).await {
+                Ok(partition) => member.scheduler.get_nodes(&partition).await,
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(member_nodes) => {
+                    *member.error.lock().unwrap() = None;
+                    for mut node in member_nodes {
+                        node.cluster = member.tag(node.cluster.take());
+                        node_clusters.insert(node.id.clone(), index);
+                        nodes.push(node);
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("{}: {:#}", member.name, e));
+                    *member.error.lock().unwrap() = Some(format!("{:#}", e));
+                }
+            }
+        }
+
+        *self.node_clusters.lock().unwrap() = node_clusters;
+
+        if nodes.is_empty() && !errors.is_empty() {
+            return Err(anyhow!(errors.join("; ")));
+        }
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        for (_, member) in self.enabled_members() {
+            let Ok(partition) = member.resolve_partition(partition).await else {
+                continue;
+            };
+            if let Ok(member_jobs) = member.scheduler.get_jobs(&partition).await {
+                jobs.extend(member.tag_jobs(member_jobs));
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        // Each member's queue keeps its own order; members follow each other
+        let mut jobs = Vec::new();
+        for (_, member) in self.enabled_members() {
+            let Ok(partition) = member.resolve_partition(partition).await else {
+                continue;
+            };
+            if let Ok(member_jobs) = member.scheduler.get_pending_jobs(&partition).await {
+                jobs.extend(member.tag_jobs(member_jobs));
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        for (_, member) in self.enabled_members() {
+            if let Ok(member_jobs) = member.scheduler.get_user_jobs(user).await {
+                jobs.extend(member.tag_jobs(member_jobs));
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        Ok(vec![ALL_PARTITIONS.to_string()])
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        let mut last_error = None;
+        for (_, member) in self.enabled_members() {
+            match member.scheduler.get_jobs_by_id(job_ids).await {
+                Ok(member_jobs) => jobs.extend(member.tag_jobs(member_jobs)),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match last_error {
+            Some(e) if jobs.is_empty() => Err(e),
+            _ => Ok(jobs),
+        }
+    }
+
+    async fn get_job_waits(&self, since: DateTime<Utc>) -> Result<Vec<JobWait>> {
+        let mut waits = Vec::new();
+        let mut last_error = None;
+        for (_, member) in self.enabled_members() {
+            match member.scheduler.get_job_waits(since).await {
+                Ok(member_waits) => waits.extend(member_waits),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match last_error {
+            Some(e) if waits.is_empty() => Err(e),
+            _ => Ok(waits),
+        }
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        let mut reservations = Vec::new();
+        for (_, member) in self.enabled_members() {
+            if let Ok(member_reservations) = member.scheduler.get_reservations().await {
+                reservations.extend(member_reservations.into_iter().map(|mut reservation| {
+                    reservation.cluster = member.tag(reservation.cluster.take());
+                    reservation
+                }));
+            }
+        }
+        Ok(reservations)
+    }
+
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        let mut licenses = Vec::new();
+        for (_, member) in self.enabled_members() {
+            if let Ok(member_licenses) = member.scheduler.get_licenses().await {
+                licenses.extend(member_licenses.into_iter().map(|mut license| {
+                    license.cluster = member.tag(license.cluster.take());
+                    license
+                }));
+            }
+        }
+        Ok(licenses)
+    }
+
+    async fn get_fairshare(&self, user: &str) -> Result<Vec<Fairshare>> {
+        let mut fairshare = Vec::new();
+        for (_, member) in self.enabled_members() {
+            if let Ok(member_fairshare) = member.scheduler.get_fairshare(user).await {
+                fairshare.extend(member_fairshare.into_iter().map(|mut entry| {
+                    entry.cluster = member.tag(entry.cluster.take());
+                    entry
+                }));
+            }
+        }
+        Ok(fairshare)
+    }
+
+    async fn get_topology(&self) -> Result<HashMap<String, String>> {
+        let mut switches = HashMap::new();
+        for (_, member) in self.enabled_members() {
+            if let Ok(member_switches) = member.scheduler.get_topology().await {
+                switches.extend(member_switches);
+            }
+        }
+        Ok(switches)
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        for (member, ids) in self.nodes_by_member(node_ids)? {
+            member
+                .scheduler
+                .update_node_reason(&ids, reason)
+                .await
+                .map_err(|e| anyhow!("{}: {:#}", member.name, e))?;
+        }
+        Ok(())
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        for (member, ids) in self.nodes_by_member(node_ids)? {
+            member
+                .scheduler
+                .drain_nodes(&ids, reason)
+                .await
+                .map_err(|e| anyhow!("{}: {:#}", member.name, e))?;
+        }
+        Ok(())
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        for (member, ids) in self.nodes_by_member(node_ids)? {
+            member
+                .scheduler
+                .resume_nodes(&ids)
+                .await
+                .map_err(|e| anyhow!("{}: {:#}", member.name, e))?;
+        }
+        Ok(())
+    }
+
+    async fn cancel_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        let (member, member_cluster) = self.member_of_job(job_id, cluster)?;
+        member
+            .scheduler
+            .cancel_job(job_id, member_cluster)
+            .await
+            .map_err(|e| anyhow!("{}: {:#}", member.name, e))
+    }
+
+    async fn hold_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        let (member, member_cluster) = self.member_of_job(job_id, cluster)?;
+        member
+            .scheduler
+            .hold_job(job_id, member_cluster)
+            .await
+            .map_err(|e| anyhow!("{}: {:#}", member.name, e))
+    }
+
+    async fn release_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        let (member, member_cluster) = self.member_of_job(job_id, cluster)?;
+        member
+            .scheduler
+            .release_job(job_id, member_cluster)
+            .await
+            .map_err(|e| anyhow!("{}: {:#}", member.name, e))
+    }
+
+    fn clusters(&self) -> Vec<ClusterStatus> {
+        self.members
+            .iter()
+            .map(|member| ClusterStatus {
+                name: member.name.clone(),
+                enabled: member.enabled.load(Ordering::Relaxed),
+                error: member.error.lock().unwrap().clone(),
+            })
+            .collect()
+    }
+
+    fn set_cluster_enabled(&self, name: &str, enabled: bool) {
+        if let Some(member) = self.members.iter().find(|member| member.name == name) {
+            member.enabled.store(enabled, Ordering::Relaxed);
+        }
+    }
+}
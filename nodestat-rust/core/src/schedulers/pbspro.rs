@@ -0,0 +1,519 @@
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use serde_json::Value;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use crate::clock::ClusterTz;
+
+/// `Exit_status` of jobs the MoM killed for exceeding their `mem` / `walltime`
+const JOB_EXEC_KILL_MEM: i64 = -27;
+const JOB_EXEC_KILL_WALLTIME: i64 = -29;
+
+pub struct PbsProScheduler {
+    tz: ClusterTz,
+    transport: Transport,
+}
+
+impl PbsProScheduler {
+    pub fn new(tz: ClusterTz, transport: Transport) -> Self {
+        Self { tz, transport }
+    }
+
+    /// `pbsnodes <args> <nodes>`.
+    async fn run_pbsnodes(&self, args: &[&str], node_ids: &[String]) -> Result<()> {
+        let output = self.transport.command("pbsnodes")
+            .args(args)
+            .args(node_ids)
+            .output().await
+            .context("Failed to execute pbsnodes command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "pbsnodes command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `qdel`, `qhold` or `qrls` on one job.
+    async fn run_job_command(&self, program: &str, job_id: &str) -> Result<()> {
+        let output = self.transport.command(program)
+            .arg(job_id)
+            .output().await
+            .with_context(|| format!("Failed to execute {} command", program))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} command failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Run a PBS command that prints JSON (`-F json`) and parse its output.
+    async fn run_json(&self, program: &str, args: &[&str]) -> Result<Value> {
+        let output = self.transport.command(program)
+            .args(args)
+            .output().await
+            .with_context(|| format!("Failed to execute {} command", program))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} command failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Failed to parse {} JSON output", program))
+    }
+
+    fn parse_node_state(state_str: &str, assigned_cpus: u32) -> NodeState {
+        // States are comma-separated, e.g. "down,offline"; the most severe wins
+        let states: Vec<&str> = state_str.split(',').map(str::trim).collect();
+        if states.contains(&"down") {
+            NodeState::Down
+        } else if states.contains(&"offline") {
+            NodeState::Drained
+        } else if states.iter().any(|s| s.starts_with("job-")) {
+            NodeState::Busy
+        } else if states.contains(&"free") {
+            if assigned_cpus > 0 { NodeState::Running } else { NodeState::Idle }
+        } else {
+            NodeState::Offline
+        }
+    }
+
+    fn parse_job_state(state_str: &str) -> JobState {
+        match state_str {
+            "R" | "E" => JobState::Running,
+            "Q" | "H" | "W" | "T" => JobState::Pending,
+            "S" | "U" => JobState::Suspended,
+            "F" | "X" => JobState::Completed,
+            _ => JobState::Failed,
+        }
+    }
+
+    /// PBS sizes like `196608kb`, `32gb` or plain bytes, in bytes.
+    fn parse_size(size: &str) -> u64 {
+        let size = size.trim().to_lowercase();
+        let split = size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len());
+        let (number, unit) = size.split_at(split);
+        let value: u64 = number.parse().unwrap_or(0);
+        let factor = match unit {
+            "kb" | "k" => MB / 1000,
+            "mb" | "m" => MB,
+            "gb" | "g" => GB,
+            "tb" | "t" => TB,
+            _ => 1,
+        };
+        value.saturating_mul(factor)
+    }
+
+    fn parse_duration(time_str: &str) -> Duration {
+        // Parse time in format HH:MM:SS, or plain seconds
+        let parts: Vec<&str> = time_str.split(':').collect();
+        if parts.len() >= 3 {
+            let hours: i64 = parts[0].parse().unwrap_or(0);
+            let minutes: i64 = parts[1].parse().unwrap_or(0);
+            let seconds: i64 = parts[2].parse().unwrap_or(0);
+            Duration::seconds(hours * 3600 + minutes * 60 + seconds)
+        } else {
+            Duration::seconds(time_str.parse().unwrap_or(0))
+        }
+    }
+
+    /// Values in resource maps may be JSON numbers or strings.
+    fn as_u32(value: Option<&Value>) -> u32 {
+        match value {
+            Some(Value::Number(n)) => n.as_u64().unwrap_or(0) as u32,
+            Some(Value::String(s)) => s.parse().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn as_str(value: Option<&Value>) -> &str {
+        value.and_then(Value::as_str).unwrap_or("")
+    }
+
+    /// `qtime` etc. are ctime-style strings (`Tue Jan 30 12:34:56 2024`) in server local time.
+    fn parse_timestamp(&self, time_str: &str) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(time_str.trim(), "%a %b %e %H:%M:%S %Y").ok()?;
+        self.tz.localize(&naive)
+    }
+
+    fn parse_node(name: &str, info: &Value, queue: &str) -> Option<Node> {
+        let available = &info["resources_available"];
+        let assigned = &info["resources_assigned"];
+
+        // Nodes tied to specific queues list them in `queue` (or a site `Qlist`);
+        // nodes without either accept jobs from every queue
+        let queues: Vec<&str> = [info.get("queue"), available.get("Qlist")]
+            .into_iter()
+            .flat_map(|v| Self::as_str(v).split(','))
+            .filter(|q| !q.is_empty())
+            .collect();
+        if !queue.is_empty() && !queues.is_empty() && !queues.contains(&queue) {
+            return None;
+        }
+
+        let total_cores = Self::as_u32(available.get("ncpus"));
+        let used_cores = Self::as_u32(assigned.get("ncpus"));
+        let total_gpus = Self::as_u32(available.get("ngpus"));
+
+        let mut jobs: Vec<String> = info["jobs"]
+            .as_array()
+            .map(|jobs| {
+                jobs.iter()
+                    .filter_map(Value::as_str)
+                    .map(|j| j.split('/').next().unwrap_or(j).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        jobs.dedup();
+
+        let comment = Self::as_str(info.get("comment"));
+
+        Some(Node {
+            id: name.to_string(),
+            state: Self::parse_node_state(Self::as_str(info.get("state")), used_cores),
+            total_cores,
+            used_cores,
+            total_mem: Self::parse_size(Self::as_str(available.get("mem"))),
+            used_mem: Self::parse_size(Self::as_str(assigned.get("mem"))),
+            partitions: if queues.is_empty() { vec![queue.to_string()] } else { queues.iter().map(|q| q.to_string()).collect() },
+            jobs,
+            job_users: Vec::new(),
+            reason: (!comment.is_empty()).then(|| comment.to_string()),
+            cpu_load: None,
+            current_watts: None,
+            consumed_joules: None,
+            os: None,
+            gpus: if total_gpus > 0 {
+                vec![GpuGres { model: "gpu".to_string(), total: total_gpus, used: Self::as_u32(assigned.get("ngpus")) }]
+            } else {
+                Vec::new()
+            },
+            features: Vec::new(),
+            state_flags: Vec::new(),
+            cluster: None,
+        })
+    }
+
+    fn parse_job(&self, id: &str, info: &Value) -> Job {
+        let resource_list = &info["Resource_List"];
+        let resources_used = &info["resources_used"];
+
+        // exec_host looks like "node1/0*8+node2/0*8"
+        let node_list: Vec<String> = Self::as_str(info.get("exec_host"))
+            .split('+')
+            .filter(|h| !h.is_empty())
+            .map(|h| h.split('/').next().unwrap_or(h).to_string())
+            .collect();
+
+        let owner = Self::as_str(info.get("Job_Owner"));
+        // Finished jobs killed for a resource limit carry it in their exit status
+        let state = match (Self::parse_job_state(Self::as_str(info.get("job_state"))), info["Exit_status"].as_i64()) {
+            (JobState::Completed, Some(JOB_EXEC_KILL_MEM)) => JobState::OutOfMemory,
+            (JobState::Completed, Some(JOB_EXEC_KILL_WALLTIME)) => JobState::Timeout,
+            (JobState::Completed, Some(status)) if status != 0 => JobState::Failed,
+            (state, _) => state,
+        };
+        // The scheduler explains why a queued job didn't run in its comment
+        let comment = Self::as_str(info.get("comment"));
+        let reason = (state == JobState::Pending && !comment.is_empty()).then(|| comment.to_string());
+        // Set on queued jobs when the scheduler does estimation (est_start_time_freq)
+        let est_start = self
+            .parse_timestamp(Self::as_str(info["estimated"].get("start_time")))
+            .filter(|_| state == JobState::Pending);
+
+        Job {
+            id: id.to_string(),
+            user: owner.split('@').next().unwrap_or(owner).to_string(),
+            name: Self::as_str(info.get("Job_Name")).to_string(),
+            state,
+            node_list: if node_list.is_empty() { vec!["?".to_string()] } else { node_list },
+            partition: Self::as_str(info.get("queue")).to_string(),
+            req_nodes: Self::as_u32(resource_list.get("nodect")).max(1),
+            req_cpus: Self::as_u32(resource_list.get("ncpus")).max(1),
+            req_mem: Self::parse_size(Self::as_str(resource_list.get("mem"))),
+            req_gpus: Self::as_u32(resource_list.get("ngpus")),
+            gpu_type: None,
+            reason,
+            priority: None,
+            est_start,
+            time_limit: Self::parse_duration(Self::as_str(resource_list.get("walltime"))),
+            elapsed: Self::parse_duration(Self::as_str(resources_used.get("walltime"))),
+            cpu_time: Self::parse_duration(Self::as_str(resources_used.get("cput"))),
+            submit_time: self
+                .parse_timestamp(Self::as_str(info.get("qtime")))
+                .unwrap_or_else(Utc::now),
+            work_dir: None,
+            cluster: None,
+        }
+    }
+
+    fn parse_jobs(&self, output: &Value) -> Vec<Job> {
+        output["Jobs"]
+            .as_object()
+            .map(|jobs| jobs.iter().map(|(id, info)| self.parse_job(id, info)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Scheduler for PbsProScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let output = self.run_json("pbsnodes", &["-a", "-F", "json"]).await?;
+
+        let nodes: Vec<Node> = output["nodes"]
+            .as_object()
+            .map(|nodes| {
+                nodes.iter()
+                    .filter_map(|(name, info)| Self::parse_node(name, info, partition))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No nodes found in queue: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let output = self.run_json("qstat", &["-f", "-F", "json"]).await?;
+
+        Ok(self.parse_jobs(&output)
+            .into_iter()
+            .filter(|job| job.partition == partition && job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        let output = self.run_json("qstat", &["-f", "-F", "json"]).await?;
+
+        Ok(self.parse_jobs(&output)
+            .into_iter()
+            .filter(|job| job.user == current_user && job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let output = self.run_json("qstat", &["-f", "-F", "json"]).await?;
+
+        let mut jobs: Vec<Job> = self.parse_jobs(&output)
+            .into_iter()
+            .filter(|job| job.partition == partition && job.state == JobState::Pending)
+            .collect();
+        // qstat doesn't expose the scheduler's ordering; oldest first is the closest stand-in
+        jobs.sort_by_key(|job| job.submit_time);
+        Ok(jobs)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let queues = self.run_json("qstat", &["-Q", "-f", "-F", "json"]).await?;
+        let mut partitions: Vec<String> = queues["Queue"]
+            .as_object()
+            .map(|q| q.keys().cloned().collect())
+            .unwrap_or_default();
+
+        // Put the server's default queue first (don't fail on error)
+        if let Ok(server) = self.run_json("qstat", &["-B", "-f", "-F", "json"]).await {
+            let default = server["Server"]
+                .as_object()
+                .and_then(|servers| servers.values().next())
+                .and_then(|s| s["default_queue"].as_str());
+            if let Some(pos) = default.and_then(|d| partitions.iter().position(|p| p == d)) {
+                let default = partitions.remove(pos);
+                partitions.insert(0, default);
+            }
+        }
+
+        Ok(partitions)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        // -x includes finished jobs from the server's history
+        let mut args = vec!["-f", "-x", "-F", "json"];
+        args.extend(job_ids.iter().map(String::as_str));
+        let output = self.run_json("qstat", &args).await?;
+        Ok(self.parse_jobs(&output))
+    }
+
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        // Every JSON document carries the server's epoch timestamp
+        let output = self.run_json("qstat", &["-B", "-F", "json"]).await?;
+        Ok(output["timestamp"]
+            .as_i64()
+            .and_then(|ts| Utc.timestamp_opt(ts, 0).single()))
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.run_pbsnodes(&["-C", reason], node_ids).await
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        // -o marks the nodes offline; running jobs carry on
+        self.run_pbsnodes(&["-o", "-C", reason], node_ids).await
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        self.run_pbsnodes(&["-r"], node_ids).await
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run_job_command("qdel", job_id).await
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run_job_command("qhold", job_id).await
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run_job_command("qrls", job_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scheduler() -> PbsProScheduler {
+        PbsProScheduler::new(ClusterTz::Named(chrono_tz::UTC), Transport::local())
+    }
+
+    /// Trimmed from `pbsnodes -a -F json`.
+    fn pbsnodes() -> Value {
+        json!({
+            "timestamp": 1709294400,
+            "pbs_version": "2022.1.1",
+            "nodes": {
+                "cn001": {
+                    "Mom": "cn001",
+                    "state": "free",
+                    "resources_available": { "ncpus": 64, "mem": "257588mb", "ngpus": 0, "Qlist": "workq,long" },
+                    "resources_assigned": { "ncpus": 16, "mem": "65536mb" },
+                    "jobs": ["1001.pbs/0", "1001.pbs/1", "1002.pbs/2"]
+                },
+                "gpu001": {
+                    "state": "job-busy",
+                    "resources_available": { "ncpus": "32", "mem": "512gb", "ngpus": 4 },
+                    "resources_assigned": { "ncpus": "32", "mem": "128gb", "ngpus": 2 },
+                    "comment": ""
+                },
+                "cn002": {
+                    "state": "down,offline",
+                    "queue": "long",
+                    "resources_available": { "ncpus": 64, "mem": "257588mb" },
+                    "resources_assigned": {},
+                    "comment": "bad DIMM"
+                }
+            }
+        })
+    }
+
+    fn node<'a>(nodes: &'a [Node], id: &str) -> &'a Node {
+        nodes.iter().find(|node| node.id == id).unwrap()
+    }
+
+    #[test]
+    fn nodes() {
+        let output = pbsnodes();
+        let parse = |queue| -> Vec<Node> {
+            output["nodes"]
+                .as_object()
+                .unwrap()
+                .iter()
+                .filter_map(|(name, info)| PbsProScheduler::parse_node(name, info, queue))
+                .collect()
+        };
+
+        // Nodes without a queue or Qlist take jobs from every queue
+        let workq = parse("workq");
+        assert_eq!(workq.len(), 2);
+        let cn001 = node(&workq, "cn001");
+        assert_eq!(cn001.state, NodeState::Running);
+        assert_eq!((cn001.used_cores, cn001.total_cores), (16, 64));
+        assert_eq!((cn001.used_mem, cn001.total_mem), (65536 * MB, 257588 * MB));
+        assert_eq!(cn001.partitions, vec!["workq", "long"]);
+        assert_eq!(cn001.jobs, vec!["1001.pbs", "1002.pbs"]);
+        let gpu001 = node(&workq, "gpu001");
+        assert_eq!(gpu001.state, NodeState::Busy);
+        assert_eq!((gpu001.gpus[0].used, gpu001.gpus[0].total), (2, 4));
+        assert_eq!(gpu001.total_mem, 512 * GB);
+        assert_eq!(gpu001.reason, None);
+
+        let long = parse("long");
+        let cn002 = node(&long, "cn002");
+        assert_eq!(cn002.state, NodeState::Down);
+        assert_eq!(cn002.reason.as_deref(), Some("bad DIMM"));
+        assert!(cn002.gpus.is_empty());
+    }
+
+    #[test]
+    fn jobs() {
+        // Trimmed from `qstat -f -x -F json`
+        let output = json!({
+            "Jobs": {
+                "1001.pbs": {
+                    "Job_Name": "relax",
+                    "Job_Owner": "alice@login1",
+                    "job_state": "R",
+                    "queue": "workq",
+                    "exec_host": "cn001/0*8+cn003/0*8",
+                    "qtime": "Fri Mar  1 11:00:00 2024",
+                    "Resource_List": { "ncpus": 16, "nodect": 2, "mem": "32gb", "walltime": "04:00:00" },
+                    "resources_used": { "walltime": "01:30:00", "cput": "24:00:00" }
+                },
+                "1003.pbs": {
+                    "Job_Name": "train",
+                    "Job_Owner": "bob@login1",
+                    "job_state": "Q",
+                    "queue": "gpu",
+                    "comment": "Not Running: Insufficient amount of resource: ngpus",
+                    "estimated": { "start_time": "Fri Mar  1 14:00:00 2024" },
+                    "Resource_List": { "ncpus": "8", "ngpus": "2", "walltime": "12:00:00" }
+                },
+                "1000.pbs": {
+                    "Job_Owner": "alice@login1",
+                    "job_state": "F",
+                    "Exit_status": -29,
+                    "Resource_List": { "ncpus": 4 }
+                }
+            }
+        });
+        let jobs = scheduler().parse_jobs(&output);
+        let job = |id: &str| jobs.iter().find(|job| job.id == id).unwrap();
+
+        let running = job("1001.pbs");
+        assert_eq!(running.user, "alice");
+        assert_eq!(running.state, JobState::Running);
+        assert_eq!(running.node_list, vec!["cn001", "cn003"]);
+        assert_eq!((running.req_nodes, running.req_cpus, running.req_mem), (2, 16, 32 * GB));
+        assert_eq!(running.time_limit, Duration::hours(4));
+        assert_eq!(running.elapsed, Duration::minutes(90));
+        assert_eq!(running.cpu_time, Duration::hours(24));
+        assert_eq!(running.submit_time, Utc.with_ymd_and_hms(2024, 3, 1, 11, 0, 0).unwrap());
+        assert_eq!(running.reason, None);
+
+        let pending = job("1003.pbs");
+        assert_eq!(pending.state, JobState::Pending);
+        assert_eq!(pending.node_list, vec!["?"]);
+        assert_eq!(pending.req_gpus, 2);
+        assert_eq!(pending.reason.as_deref(), Some("Not Running: Insufficient amount of resource: ngpus"));
+        assert_eq!(pending.est_start, Some(Utc.with_ymd_and_hms(2024, 3, 1, 14, 0, 0).unwrap()));
+
+        assert_eq!(job("1000.pbs").state, JobState::Timeout);
+    }
+}
@@ -0,0 +1,292 @@
+//! `[rate_limit]`: a scheduler whose answers are reused for a minimum interval, so that
+//! mashing `r`, flipping between partitions or several views asking at once can't run the
+//! scheduler's commands more often than that. A call made while the same one is under way (the
+//! background refresh and a manual `r`) waits for its answer rather than running it again.
+//! Answers that came from the cache are reported through [`Scheduler::get_polled_at`], for the
+//! UI to show how old they are.
+
+use crate::models::*;
+use crate::schedulers::{ClusterStatus, Scheduler};
+use async_trait::async_trait;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How often the same scheduler call may run, in seconds. 0 runs every call.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimit {
+    pub min_interval: u64,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self { min_interval: 5 }
+    }
+}
+
+/// One call's result, failures included: a controller that's down isn't asked any more often.
+struct Answer {
+    /// When the call finished: one slower than the interval is still reused for the whole of it
+    done: Instant,
+    /// When the call started, the time the answer is from
+    at: DateTime<Utc>,
+    /// A `Result<T, String>`
+    result: Box<dyn Any + Send + Sync>,
+}
+
+pub struct RateLimitedScheduler {
+    inner: Box<dyn Scheduler>,
+    min_interval: Duration,
+    /// By method and arguments
+    answers: Mutex<HashMap<String, Answer>>,
+    /// The calls under way, by the same keys; held while the call runs
+    in_flight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// When the oldest answer served from the cache since the last `get_polled_at` was fetched
+    oldest_served: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl RateLimitedScheduler {
+    pub fn new(inner: Box<dyn Scheduler>, min_interval: Duration) -> Self {
+        Self {
+            inner,
+            min_interval,
+            answers: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            oldest_served: Mutex::new(None),
+        }
+    }
+
+    /// `call`'s result, or the same call's if it ran less than `min_interval` ago or is under way.
+    async fn cached<T>(&self, key: String, call: impl Future<Output = Result<T>>) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if let Some((at, result)) = self.lookup::<T>(&key) {
+            let mut oldest = self.oldest_served.lock().unwrap();
+            *oldest = Some(oldest.map_or(at, |oldest| oldest.min(at)));
+            return result.map_err(|e| anyhow!(e));
+        }
+
+        let flight = self.in_flight.lock().unwrap().entry(key.clone()).or_default().clone();
+        let _running = flight.lock().await;
+        // Just fetched by the call this one waited for, so no older than its own would be
+        if let Some((_, result)) = self.lookup::<T>(&key) {
+            return result.map_err(|e| anyhow!(e));
+        }
+
+        let at = Utc::now();
+        let result = call.await.map_err(|e| format!("{:#}", e));
+        let mut answers = self.answers.lock().unwrap();
+        // Calls whose arguments change every time (the wait times) would otherwise pile up
+        answers.retain(|_, answer| answer.done.elapsed() < self.min_interval);
+        answers.insert(key.clone(), Answer { done: Instant::now(), at, result: Box::new(result.clone()) });
+        // Those waiting hold on to theirs and find the answer; later calls find it first
+        self.in_flight.lock().unwrap().remove(&key);
+        result.map_err(|e| anyhow!(e))
+    }
+
+    fn lookup<T: Clone + 'static>(&self, key: &str) -> Option<(DateTime<Utc>, Result<T, String>)> {
+        let answers = self.answers.lock().unwrap();
+        let answer = answers.get(key).filter(|answer| answer.done.elapsed() < self.min_interval)?;
+        let result = answer.result.downcast_ref::<Result<T, String>>()?;
+        Some((answer.at, result.clone()))
+    }
+
+    fn forget(&self) {
+        self.answers.lock().unwrap().clear();
+    }
+
+    /// After a change made on the cluster, the next refresh shows it.
+    fn changed(&self, result: Result<()>) -> Result<()> {
+        self.forget();
+        result
+    }
+}
+
+#[async_trait]
+impl Scheduler for RateLimitedScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        self.cached(format!("nodes {}", partition), self.inner.get_nodes(partition)).await
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        self.cached(format!("jobs {}", partition), self.inner.get_jobs(partition)).await
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        self.cached(format!("user_jobs {}", user), self.inner.get_user_jobs(user)).await
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        self.cached("list_partitions".to_string(), self.inner.list_partitions()).await
+    }
+
+    async fn get_partitions(&self) -> Result<Vec<Partition>> {
+        self.cached("partitions".to_string(), self.inner.get_partitions()).await
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        self.cached(format!("pending_jobs {}", partition), self.inner.get_pending_jobs(partition)).await
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        self.cached(format!("jobs_by_id {}", job_ids.join(",")), self.inner.get_jobs_by_id(job_ids)).await
+    }
+
+    async fn get_job_waits(&self, since: DateTime<Utc>) -> Result<Vec<JobWait>> {
+        self.cached(format!("job_waits {}", since.to_rfc3339()), self.inner.get_job_waits(since)).await
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        self.cached("reservations".to_string(), self.inner.get_reservations()).await
+    }
+
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        self.cached("licenses".to_string(), self.inner.get_licenses()).await
+    }
+
+    async fn get_fairshare(&self, user: &str) -> Result<Vec<Fairshare>> {
+        self.cached(format!("fairshare {}", user), self.inner.get_fairshare(user)).await
+    }
+
+    async fn get_topology(&self) -> Result<HashMap<String, String>> {
+        self.cached("topology".to_string(), self.inner.get_topology()).await
+    }
+
+    /// The older of the daemon's poll, behind a `--connect`, and the answers served from the
+    /// cache since the last time it was asked.
+    async fn get_polled_at(&self) -> Result<Option<DateTime<Utc>>> {
+        let served = self.oldest_served.lock().unwrap().take();
+        let polled_at = self.inner.get_polled_at().await?;
+        Ok(match (polled_at, served) {
+            (Some(polled_at), Some(served)) => Some(polled_at.min(served)),
+            (polled_at, served) => polled_at.or(served),
+        })
+    }
+
+    /// A cached time has moved on since, as much as the clock here has.
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        if let Some((at, result)) = self.lookup::<Option<DateTime<Utc>>>("server_time") {
+            return result.map(|time| time.map(|time| time + (Utc::now() - at))).map_err(|e| anyhow!(e));
+        }
+        self.cached("server_time".to_string(), self.inner.get_server_time()).await
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.changed(self.inner.update_node_reason(node_ids, reason).await)
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.changed(self.inner.drain_nodes(node_ids, reason).await)
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        self.changed(self.inner.resume_nodes(node_ids).await)
+    }
+
+    async fn cancel_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        self.changed(self.inner.cancel_job(job_id, cluster).await)
+    }
+
+    async fn hold_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        self.changed(self.inner.hold_job(job_id, cluster).await)
+    }
+
+    async fn release_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        self.changed(self.inner.release_job(job_id, cluster).await)
+    }
+
+    fn clusters(&self) -> Vec<ClusterStatus> {
+        self.inner.clusters()
+    }
+
+    /// The answers so far include (or leave out) the cluster.
+    fn set_cluster_enabled(&self, name: &str, enabled: bool) {
+        self.forget();
+        self.inner.set_cluster_enabled(name, enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts how often the nodes are asked for, each call taking `delay`.
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Scheduler for Counting {
+        async fn get_nodes(&self, _partition: &str) -> Result<Vec<Node>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            Ok(Vec::new())
+        }
+
+        async fn get_jobs(&self, _partition: &str) -> Result<Vec<Job>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_user_jobs(&self, _user: &str) -> Result<Vec<Job>> {
+            Ok(Vec::new())
+        }
+
+        async fn list_partitions(&self) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn counting(delay: Duration) -> (RateLimitedScheduler, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let inner = Box::new(Counting { calls: calls.clone(), delay });
+        (RateLimitedScheduler::new(inner, Duration::from_secs(5)), calls)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repeats_served_from_cache() {
+        let (scheduler, calls) = counting(Duration::from_secs(1));
+        scheduler.get_nodes("batch").await.unwrap();
+        assert_eq!(scheduler.get_polled_at().await.unwrap(), None);
+
+        // Within the interval, repeats are served from the cache; another partition runs a call
+        for _ in 0..3 {
+            scheduler.get_nodes("batch").await.unwrap();
+        }
+        scheduler.get_nodes("gpu").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(scheduler.get_polled_at().await.unwrap().is_some());
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        scheduler.get_nodes("batch").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(scheduler.get_polled_at().await.unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_calls_run_once() {
+        // The background refresh and a manual `r`, with a call slower than the interval
+        let (scheduler, calls) = counting(Duration::from_secs(8));
+        let (first, second) = tokio::join!(scheduler.get_nodes("batch"), scheduler.get_nodes("batch"));
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Its answer is reused for the interval after it came back
+        tokio::time::advance(Duration::from_secs(4)).await;
+        scheduler.get_nodes("batch").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        scheduler.get_nodes("batch").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
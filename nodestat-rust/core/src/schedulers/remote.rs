@@ -0,0 +1,217 @@
+//! Client of a `nodestat serve` daemon (`--connect`): subscribes to its snapshots instead of
+//! running scheduler commands locally, over TCP or the daemon's Unix socket (`unix:<path>`).
+
+use crate::models::*;
+use crate::rpc::node_stat_client::NodeStatClient;
+use crate::rpc::{GetJobsByIdRequest, ListPartitionsRequest, Snapshot, SubscribeRequest};
+use crate::schedulers::Scheduler;
+use async_trait::async_trait;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use hyper_util::rt::TokioIo;
+use std::path::PathBuf;
+use tokio::net::UnixStream;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tonic::transport::{Channel, Endpoint, Uri};
+use tower::service_fn;
+
+/// Latest message of the snapshot stream; `Err` once the stream broke.
+type Latest = Option<Result<Snapshot, String>>;
+
+struct Subscription {
+    partition: String,
+    latest: watch::Receiver<Latest>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub struct RemoteScheduler {
+    client: NodeStatClient<Channel>,
+    subscription: Mutex<Option<Subscription>>,
+}
+
+impl RemoteScheduler {
+    /// `address` is the daemon's URL, e.g. `http://login1:50051`, or `unix:<path>` for its
+    /// socket. Connects on first use.
+    pub fn new(address: &str) -> Result<Self> {
+        let channel = match address.strip_prefix("unix:") {
+            // The URI only fills in the requests; every connection goes to the socket
+            Some(path) => {
+                let path = PathBuf::from(path);
+                Endpoint::from_static("http://localhost").connect_with_connector_lazy(service_fn(move |_: Uri| {
+                    let path = path.clone();
+                    async move { Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(path).await?)) }
+                }))
+            },
+            None => Channel::from_shared(address.to_string())
+                .with_context(|| format!("Invalid daemon address '{}'", address))?
+                .connect_lazy(),
+        };
+        Ok(Self {
+            client: NodeStatClient::new(channel),
+            subscription: Mutex::new(None),
+        })
+    }
+
+    async fn subscribe(&self, partition: &str) -> Result<Subscription> {
+        let mut stream = self
+            .client
+            .clone()
+            .subscribe(SubscribeRequest { partition: partition.to_string() })
+            .await
+            .map_err(|e| anyhow!("NodeStat daemon: {}", e.message()))?
+            .into_inner();
+
+        let (sender, latest) = watch::channel(None);
+        let task = tokio::spawn(async move {
+            loop {
+                let message = match stream.message().await {
+                    Ok(Some(snapshot)) => Ok(snapshot),
+                    Ok(None) => Err("Connection to the NodeStat daemon closed".to_string()),
+                    Err(e) => Err(format!("NodeStat daemon: {}", e.message())),
+                };
+                let done = message.is_err();
+                sender.send_replace(Some(message));
+                if done {
+                    return;
+                }
+            }
+        });
+
+        Ok(Subscription {
+            partition: partition.to_string(),
+            latest,
+            task,
+        })
+    }
+
+    /// The latest snapshot of `partition`, (re)subscribing if needed and waiting for the first one.
+    async fn snapshot(&self, partition: &str) -> Result<Snapshot> {
+        let mut subscription = self.subscription.lock().await;
+        if subscription.as_ref().map(|s| s.partition.as_str()) != Some(partition) {
+            *subscription = Some(self.subscribe(partition).await?);
+        }
+
+        let result = match subscription.as_mut().unwrap().latest.wait_for(Option::is_some).await {
+            Ok(latest) => latest.clone().unwrap(),
+            Err(_) => Err("Connection to the NodeStat daemon closed".to_string()),
+        };
+
+        // Resubscribe on the next call after the stream broke
+        result.map_err(|e| {
+            *subscription = None;
+            anyhow!(e)
+        })
+    }
+
+    /// The latest snapshot of whichever partition is subscribed, `None` before the first.
+    async fn current_snapshot(&self) -> Result<Option<Snapshot>> {
+        let partition = match *self.subscription.lock().await {
+            Some(ref subscription) => subscription.partition.clone(),
+            None => return Ok(None),
+        };
+        self.snapshot(&partition).await.map(Some)
+    }
+}
+
+#[async_trait]
+impl Scheduler for RemoteScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let snapshot = self.snapshot(partition).await?;
+        if let Some(error) = snapshot.error {
+            return Err(anyhow!(error));
+        }
+        serde_json::from_str(&snapshot.nodes_json).context("Malformed nodes from the NodeStat daemon")
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let snapshot = self.snapshot(partition).await?;
+        serde_json::from_str(&snapshot.jobs_json).context("Malformed jobs from the NodeStat daemon")
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let snapshot = self.snapshot(partition).await?;
+        serde_json::from_str(&snapshot.pending_jobs_json).context("Malformed pending jobs from the NodeStat daemon")
+    }
+
+    /// The user's running jobs in the subscribed partition; the daemon doesn't poll per user.
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let Some(snapshot) = self.current_snapshot().await? else {
+            return Ok(Vec::new());
+        };
+        let jobs: Vec<Job> = serde_json::from_str(&snapshot.jobs_json).context("Malformed jobs from the NodeStat daemon")?;
+        Ok(jobs.into_iter().filter(|job| job.user == user).collect())
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .clone()
+            .list_partitions(ListPartitionsRequest {})
+            .await
+            .map_err(|e| anyhow!("NodeStat daemon: {}", e.message()))?;
+        Ok(response.into_inner().partitions)
+    }
+
+    async fn get_partitions(&self) -> Result<Vec<Partition>> {
+        let response = self
+            .client
+            .clone()
+            .list_partitions(ListPartitionsRequest {})
+            .await
+            .map_err(|e| anyhow!("NodeStat daemon: {}", e.message()))?
+            .into_inner();
+        if response.partitions_json.is_empty() {
+            return Err(anyhow!("Partition limits are not supported by the daemon's scheduler"));
+        }
+        serde_json::from_str(&response.partitions_json).context("Malformed partitions from the NodeStat daemon")
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let response = self
+            .client
+            .clone()
+            .get_jobs_by_id(GetJobsByIdRequest { job_ids: job_ids.to_vec() })
+            .await
+            .map_err(|e| anyhow!("NodeStat daemon: {}", e.message()))?;
+        serde_json::from_str(&response.into_inner().jobs_json).context("Malformed jobs from the NodeStat daemon")
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        let Some(snapshot) = self.current_snapshot().await? else {
+            return Ok(Vec::new());
+        };
+        serde_json::from_str(&snapshot.reservations_json).context("Malformed reservations from the NodeStat daemon")
+    }
+
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        let Some(snapshot) = self.current_snapshot().await? else {
+            return Ok(Vec::new());
+        };
+        serde_json::from_str(&snapshot.licenses_json).context("Malformed licenses from the NodeStat daemon")
+    }
+
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        let Some(snapshot) = self.current_snapshot().await? else {
+            return Ok(None);
+        };
+        Ok(snapshot.server_time.as_deref().and_then(parse_time))
+    }
+
+    async fn get_polled_at(&self) -> Result<Option<DateTime<Utc>>> {
+        let Some(snapshot) = self.current_snapshot().await? else {
+            return Ok(None);
+        };
+        Ok(snapshot.polled_at.as_deref().and_then(parse_time))
+    }
+}
+
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text).ok().map(|t| t.with_timezone(&Utc))
+}
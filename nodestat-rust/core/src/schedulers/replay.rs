@@ -0,0 +1,76 @@
+//! `--replay`: answers from the frame of a recording the TUI is showing (see
+//! [`crate::recording`]); nothing runs on the cluster, and admin actions aren't available.
+
+use crate::models::*;
+use crate::recording::Replay;
+use crate::schedulers::Scheduler;
+use async_trait::async_trait;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+pub struct ReplayScheduler {
+    replay: Arc<Replay>,
+}
+
+impl ReplayScheduler {
+    pub fn new(replay: Arc<Replay>) -> Self {
+        Self { replay }
+    }
+}
+
+fn in_partition(job: &Job, partition: &str) -> bool {
+    job.partition.split(',').any(|name| name == partition)
+}
+
+#[async_trait]
+impl Scheduler for ReplayScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let frame = self.replay.frame();
+        if let Some(ref error) = frame.error {
+            return Err(anyhow!("{}", error));
+        }
+        Ok(frame.nodes.iter().filter(|node| node.partitions.iter().any(|p| p == partition)).cloned().collect())
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        Ok(self.replay.frame().jobs.iter().filter(|job| in_partition(job, partition)).cloned().collect())
+    }
+
+    /// The jobs of whoever recorded it, whoever watches.
+    async fn get_user_jobs(&self, _user: &str) -> Result<Vec<Job>> {
+        Ok(self.replay.frame().user_jobs.clone())
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        Ok(self.replay.partitions())
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        Ok(self.replay.frame().pending_jobs.iter().filter(|job| in_partition(job, partition)).cloned().collect())
+    }
+
+    /// Whichever of the jobs are in the frame; the rest ended before it or weren't recorded.
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let frame = self.replay.frame();
+        let mut jobs: Vec<Job> = Vec::new();
+        for job in frame.jobs.iter().chain(&frame.user_jobs).chain(&frame.pending_jobs) {
+            if job_ids.contains(&job.id) && !jobs.iter().any(|j| j.id == job.id) {
+                jobs.push(job.clone());
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        Ok(self.replay.frame().reservations.clone())
+    }
+
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        Ok(self.replay.frame().licenses.clone())
+    }
+
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        Ok(self.replay.frame().server_time)
+    }
+}
@@ -0,0 +1,485 @@
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use roxmltree::{Document, Node as XmlNode};
+use std::collections::HashMap;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use crate::clock::ClusterTz;
+
+/// Grid Engine (SGE, UGE, Son of Grid Engine) via the `-xml` output of `qhost`/`qstat`.
+pub struct SgeScheduler {
+    tz: ClusterTz,
+    transport: Transport,
+}
+
+impl SgeScheduler {
+    pub fn new(tz: ClusterTz, transport: Transport) -> Self {
+        Self { tz, transport }
+    }
+
+    async fn run(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = self.transport.command(program)
+            .args(args)
+            .output().await
+            .with_context(|| format!("Failed to execute {} command", program))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} command failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn child_text<'a>(node: XmlNode<'a, '_>, tag: &str) -> &'a str {
+        node.children()
+            .find(|c| c.has_tag_name(tag))
+            .and_then(|c| c.text())
+            .unwrap_or("")
+            .trim()
+    }
+
+    /// `<hostvalue name='mem_total'>125.8G</hostvalue>` style lookups.
+    fn named_value<'a>(node: XmlNode<'a, '_>, tag: &str, name: &str) -> &'a str {
+        node.children()
+            .find(|c| c.has_tag_name(tag) && c.attribute("name") == Some(name))
+            .and_then(|c| c.text())
+            .unwrap_or("")
+            .trim()
+    }
+
+    /// Queue instance state letters, e.g. `d` (disabled), `au` (alarm, unknown), `E` (error).
+    fn parse_node_state(state_str: &str, used_slots: u32, total_slots: u32) -> NodeState {
+        if state_str.contains('u') {
+            NodeState::Down
+        } else if state_str.contains('E') {
+            NodeState::Offline
+        } else if state_str.contains('d') || state_str.contains('D') || state_str.contains('S') {
+            NodeState::Drained
+        } else if total_slots > 0 && used_slots >= total_slots {
+            NodeState::Busy
+        } else if used_slots > 0 {
+            NodeState::Running
+        } else {
+            NodeState::Idle
+        }
+    }
+
+    fn parse_job_state(state_str: &str) -> JobState {
+        if state_str.contains('E') {
+            JobState::Failed
+        } else if state_str.contains('d') {
+            JobState::Cancelled
+        } else if state_str.contains(['s', 'S', 'T']) {
+            // Suspended by the user (s), its queue (S) or a load threshold (T)
+            JobState::Suspended
+        } else if state_str.contains('r') || state_str.contains('t') {
+            JobState::Running
+        } else {
+            JobState::Pending
+        }
+    }
+
+    /// SGE sizes like `125.8G`, `512M` or plain bytes, in bytes.
+    fn parse_size(size: &str) -> u64 {
+        let size = size.trim();
+        let split = size.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(size.len());
+        let (number, unit) = size.split_at(split);
+        let value: f64 = number.parse().unwrap_or(0.0);
+        let factor = match unit {
+            "K" | "k" => MB / 1000,
+            "M" | "m" => MB,
+            "G" | "g" => GB,
+            "T" | "t" => TB,
+            _ => 1,
+        };
+        (value * factor as f64) as u64
+    }
+
+    /// `h_rt` is either seconds or `HH:MM:SS`.
+    fn parse_duration(time_str: &str) -> Duration {
+        let parts: Vec<&str> = time_str.split(':').collect();
+        if parts.len() >= 3 {
+            let hours: i64 = parts[0].parse().unwrap_or(0);
+            let minutes: i64 = parts[1].parse().unwrap_or(0);
+            let seconds: i64 = parts[2].parse().unwrap_or(0);
+            Duration::seconds(hours * 3600 + minutes * 60 + seconds)
+        } else {
+            Duration::seconds(time_str.parse::<f64>().unwrap_or(0.0) as i64)
+        }
+    }
+
+    /// ISO timestamps in server local time, sometimes with milliseconds.
+    fn parse_timestamp(&self, time_str: &str) -> Option<DateTime<Utc>> {
+        let time_str = time_str.trim().split('.').next()?;
+        let naive = NaiveDateTime::parse_from_str(time_str, "%Y-%m-%dT%H:%M:%S").ok()?;
+        self.tz.localize(&naive)
+    }
+
+    fn parse_qhost(xml: &str, queue: &str) -> Result<Vec<Node>> {
+        let doc = Document::parse(xml).context("Failed to parse qhost XML output")?;
+        let mut nodes = Vec::new();
+
+        for host in doc.root_element().children().filter(|c| c.has_tag_name("host")) {
+            let name = host.attribute("name").unwrap_or("");
+            // The pseudo-host "global" holds cluster-wide complexes
+            if name.is_empty() || name == "global" {
+                continue;
+            }
+
+            let Some(queue_el) = host
+                .children()
+                .find(|c| c.has_tag_name("queue") && c.attribute("name") == Some(queue))
+            else {
+                continue;
+            };
+
+            let total_cores = Self::named_value(queue_el, "queuevalue", "slots").parse().unwrap_or(0);
+            let used_cores = Self::named_value(queue_el, "queuevalue", "slots_used").parse().unwrap_or(0);
+            let state = Self::named_value(queue_el, "queuevalue", "state_string");
+
+            let mut jobs: Vec<String> = host
+                .children()
+                .filter(|c| c.has_tag_name("job"))
+                .filter_map(|j| {
+                    let number = j.attribute("name")?;
+                    Some(match Self::named_value(j, "jobvalue", "taskid") {
+                        "" => number.to_string(),
+                        task => format!("{}.{}", number, task),
+                    })
+                })
+                .collect();
+            jobs.dedup();
+
+            nodes.push(Node {
+                id: name.to_string(),
+                state: Self::parse_node_state(state, used_cores, total_cores),
+                total_cores,
+                used_cores,
+                total_mem: Self::parse_size(Self::named_value(host, "hostvalue", "mem_total")),
+                used_mem: Self::parse_size(Self::named_value(host, "hostvalue", "mem_used")),
+                partitions: vec![queue.to_string()],
+                jobs,
+                job_users: Vec::new(),
+                reason: None,
+                cpu_load: Self::named_value(host, "hostvalue", "load_avg").parse().ok(),
+                current_watts: None,
+                consumed_joules: None,
+                os: None,
+                gpus: Vec::new(),
+                features: Vec::new(),
+                state_flags: Vec::new(),
+                cluster: None,
+            });
+        }
+
+        Ok(nodes)
+    }
+
+    fn parse_job(&self, el: XmlNode) -> Option<Job> {
+        let number = Self::child_text(el, "JB_job_number");
+        if number.is_empty() {
+            return None;
+        }
+        // Array tasks are jobs of their own: `<tasks>4</tasks>` for a running one, a range like
+        // `5-10:1` for the ones still pending
+        let id = match Self::child_text(el, "tasks") {
+            "" => number.to_string(),
+            tasks => format!("{}.{}", number, tasks),
+        };
+
+        // Running jobs carry `queue_name` as "queue@host"
+        let queue_name = Self::child_text(el, "queue_name");
+        let (partition, host) = queue_name.split_once('@').unwrap_or((queue_name, ""));
+
+        let hard_request = |name: &str| {
+            el.children()
+                .find(|c| c.has_tag_name("hard_request") && c.attribute("name") == Some(name))
+                .and_then(|c| c.text())
+                .unwrap_or("")
+        };
+
+        let start_time = self.parse_timestamp(Self::child_text(el, "JAT_start_time"));
+        let submit_time = self
+            .parse_timestamp(Self::child_text(el, "JB_submission_time"))
+            .or(start_time)
+            .unwrap_or_else(Utc::now);
+        let slots: u32 = Self::child_text(el, "slots").parse().unwrap_or(1);
+
+        Some(Job {
+            id,
+            user: Self::child_text(el, "JB_owner").to_string(),
+            name: Self::child_text(el, "JB_name").to_string(),
+            state: Self::parse_job_state(Self::child_text(el, "state")),
+            node_list: if host.is_empty() { vec!["?".to_string()] } else { vec![host.to_string()] },
+            partition: partition.to_string(),
+            req_nodes: 1,
+            req_cpus: slots.max(1),
+            // h_vmem and mem_free are per slot
+            req_mem: Self::parse_size(hard_request("h_vmem")).max(Self::parse_size(hard_request("mem_free"))) * slots.max(1) as u64,
+            req_gpus: 0,
+            gpu_type: None,
+            reason: None,
+            priority: None,
+            est_start: None,
+            time_limit: Self::parse_duration(hard_request("h_rt")),
+            elapsed: start_time.map(|t| Utc::now() - t).unwrap_or_else(Duration::zero),
+            cpu_time: Duration::zero(),
+            submit_time,
+            work_dir: None,
+            cluster: None,
+        })
+    }
+
+    /// Parse `qstat -xml -g t` output. Parallel jobs are listed once per task host, so entries
+    /// sharing a job number and array task (`123.4`) are merged.
+    fn parse_qstat(&self, xml: &str) -> Result<Vec<Job>> {
+        let doc = Document::parse(xml).context("Failed to parse qstat XML output")?;
+        let mut jobs: Vec<Job> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for el in doc.descendants().filter(|n| n.has_tag_name("job_list")) {
+            let Some(job) = self.parse_job(el) else { continue };
+            match index.get(&job.id) {
+                Some(&i) => {
+                    let existing = &mut jobs[i];
+                    if !existing.node_list.contains(&job.node_list[0]) {
+                        existing.node_list.extend(job.node_list);
+                        existing.req_nodes = existing.node_list.len() as u32;
+                    }
+                    existing.req_cpus += job.req_cpus;
+                }
+                None => {
+                    index.insert(job.id.clone(), jobs.len());
+                    jobs.push(job);
+                }
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    /// Finished jobs only show up in the accounting file (`qacct -j`).
+    async fn qacct_job(&self, job_id: &str) -> Option<Job> {
+        let output = match job_id.split_once('.') {
+            Some((number, task)) => self.run("qacct", &["-j", number, "-t", task]).await.ok()?,
+            None => self.run("qacct", &["-j", job_id]).await.ok()?,
+        };
+        let fields: HashMap<&str, &str> = output
+            .lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .map(|(k, v)| (k, v.trim()))
+            .collect();
+
+        let failed = fields.get("failed").is_some_and(|v| !v.starts_with('0'));
+        // failed 37: the qmaster enforced h_rt
+        let timed_out = fields.get("failed").is_some_and(|v| v.starts_with("37"));
+        let exit_ok = fields.get("exit_status").is_some_and(|v| *v == "0");
+        let slots: u32 = fields.get("slots").and_then(|v| v.parse().ok()).unwrap_or(1);
+        // qacct prints ctime-style times, e.g. "Fri Mar  1 10:00:00 2024"
+        let submit_time = fields
+            .get("qsub_time")
+            .and_then(|t| NaiveDateTime::parse_from_str(t, "%a %b %e %H:%M:%S %Y").ok())
+            .and_then(|naive| self.tz.localize(&naive))
+            .unwrap_or_else(Utc::now);
+
+        Some(Job {
+            id: job_id.to_string(),
+            user: fields.get("owner").unwrap_or(&"").to_string(),
+            name: fields.get("jobname").unwrap_or(&"").to_string(),
+            state: if timed_out {
+                JobState::Timeout
+            } else if failed || !exit_ok {
+                JobState::Failed
+            } else {
+                JobState::Completed
+            },
+            node_list: vec![fields.get("hostname").unwrap_or(&"?").to_string()],
+            partition: fields.get("qname").unwrap_or(&"").to_string(),
+            req_nodes: 1,
+            req_cpus: slots,
+            req_mem: 0,
+            req_gpus: 0,
+            gpu_type: None,
+            reason: None,
+            priority: None,
+            est_start: None,
+            time_limit: Duration::zero(),
+            elapsed: Self::parse_duration(fields.get("ru_wallclock").unwrap_or(&"0").trim_end_matches('s')),
+            cpu_time: Self::parse_duration(fields.get("cpu").unwrap_or(&"0").trim_end_matches('s')),
+            submit_time,
+            work_dir: None,
+            cluster: None,
+        })
+    }
+}
+
+#[async_trait]
+impl Scheduler for SgeScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let output = self.run("qhost", &["-xml", "-q", "-j"]).await?;
+        let nodes = Self::parse_qhost(&output, partition)?;
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No hosts found in queue: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let output = self.run("qstat", &["-xml", "-u", "*", "-s", "r", "-r", "-g", "t", "-q", partition]).await?;
+        self.parse_qstat(&output)
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        let output = self.run("qstat", &["-xml", "-u", &current_user, "-s", "r", "-r", "-g", "t"]).await?;
+        self.parse_qstat(&output)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let output = self.run("qconf", &["-sql"]).await?;
+        let mut queues: Vec<String> = output.lines().map(|q| q.trim().to_string()).filter(|q| !q.is_empty()).collect();
+
+        // Grid Engine has no default queue; all.q is the conventional catch-all
+        if let Some(pos) = queues.iter().position(|q| q == "all.q") {
+            let default = queues.remove(pos);
+            queues.insert(0, default);
+        }
+
+        Ok(queues)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let output = self.run("qstat", &["-xml", "-u", "*", "-r", "-g", "t"]).await?;
+        let mut jobs: Vec<Job> = self
+            .parse_qstat(&output)?
+            .into_iter()
+            .filter(|job| job_ids.contains(&job.id))
+            .collect();
+
+        for id in job_ids {
+            if !jobs.iter().any(|job| &job.id == id) {
+                jobs.extend(self.qacct_job(id).await);
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], _reason: &str) -> Result<()> {
+        // Disabling every queue instance on the hosts lets running jobs finish; SGE keeps no reason
+        for node in node_ids {
+            self.run("qmod", &["-d", &format!("*@{}", node)]).await?;
+        }
+        Ok(())
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        for node in node_ids {
+            self.run("qmod", &["-e", &format!("*@{}", node)]).await?;
+        }
+        Ok(())
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run("qdel", &[job_id]).await?;
+        Ok(())
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run("qhold", &[job_id]).await?;
+        Ok(())
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run("qrls", &[job_id]).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Trimmed from `qstat -xml -u '*' -s r -r -g t`: a parallel job over two hosts and two
+    /// tasks of an array job on one.
+    const QSTAT: &str = r#"<?xml version='1.0'?>
+<job_info xmlns:xsd="http://arc.liv.ac.uk/repos/darcs/sge/source/dist/util/resources/schemas/qstat/qstat.xsd">
+  <queue_info>
+    <job_list state="running">
+      <JB_job_number>101</JB_job_number>
+      <JB_name>mpi_run</JB_name>
+      <JB_owner>alice</JB_owner>
+      <state>r</state>
+      <JB_submission_time>2024-03-01T09:55:00.123</JB_submission_time>
+      <JAT_start_time>2024-03-01T10:00:00</JAT_start_time>
+      <queue_name>all.q@node01</queue_name>
+      <slots>8</slots>
+      <hard_request name="h_vmem" resource_contribution="0.000000">2G</hard_request>
+      <hard_request name="h_rt" resource_contribution="0.000000">04:00:00</hard_request>
+    </job_list>
+    <job_list state="running">
+      <JB_job_number>101</JB_job_number>
+      <JB_name>mpi_run</JB_name>
+      <JB_owner>alice</JB_owner>
+      <state>r</state>
+      <JAT_start_time>2024-03-01T10:00:00</JAT_start_time>
+      <queue_name>all.q@node02</queue_name>
+      <slots>8</slots>
+    </job_list>
+    <job_list state="running">
+      <JB_job_number>102</JB_job_number>
+      <JB_name>sweep</JB_name>
+      <JB_owner>bob</JB_owner>
+      <state>r</state>
+      <JAT_start_time>2024-03-01T11:00:00</JAT_start_time>
+      <queue_name>all.q@node03</queue_name>
+      <slots>1</slots>
+      <tasks>1</tasks>
+    </job_list>
+    <job_list state="running">
+      <JB_job_number>102</JB_job_number>
+      <JB_name>sweep</JB_name>
+      <JB_owner>bob</JB_owner>
+      <state>r</state>
+      <JAT_start_time>2024-03-01T11:05:00</JAT_start_time>
+      <queue_name>all.q@node03</queue_name>
+      <slots>1</slots>
+      <tasks>2</tasks>
+    </job_list>
+  </queue_info>
+</job_info>"#;
+
+    #[test]
+    fn parallel_and_array_jobs() {
+        let scheduler = SgeScheduler::new(ClusterTz::Named(chrono_tz::UTC), Transport::local());
+        let jobs = scheduler.parse_qstat(QSTAT).unwrap();
+        let ids: Vec<&str> = jobs.iter().map(|job| job.id.as_str()).collect();
+        assert_eq!(ids, vec!["101", "102.1", "102.2"]);
+
+        // One job over both hosts, with the slots of each
+        let parallel = &jobs[0];
+        assert_eq!((parallel.user.as_str(), parallel.name.as_str()), ("alice", "mpi_run"));
+        assert_eq!(parallel.state, JobState::Running);
+        assert_eq!(parallel.node_list, vec!["node01", "node02"]);
+        assert_eq!((parallel.req_nodes, parallel.req_cpus), (2, 16));
+        assert_eq!(parallel.req_mem, 16 * GB);
+        assert_eq!(parallel.partition, "all.q");
+        assert_eq!(parallel.time_limit, Duration::hours(4));
+        assert_eq!(parallel.submit_time, Utc.with_ymd_and_hms(2024, 3, 1, 9, 55, 0).unwrap());
+
+        // The array's tasks stay apart, each with its own slot
+        for task in &jobs[1..] {
+            assert_eq!(task.node_list, vec!["node03"]);
+            assert_eq!((task.req_nodes, task.req_cpus), (1, 1));
+        }
+        assert_eq!(jobs[2].submit_time, Utc.with_ymd_and_hms(2024, 3, 1, 11, 5, 0).unwrap());
+    }
+}
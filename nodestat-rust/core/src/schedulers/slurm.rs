@@ -0,0 +1,1484 @@
+use crate::hostlist;
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use super::transport::TransportCommand;
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use crate::clock::ClusterTz;
+use serde::Deserialize;
+
+/// sacct columns; `TotalCPU` is the CPU time the job's finished steps used (`CPUTime` would be
+/// the allocation, elapsed × CPUs)
+const SACCT_FORMAT: &str =
+    "partition,NodeList,JobID,User,jobname,State,ReqNodes,ReqCPUs,ReqMem,Timelimit,Elapsed,TotalCPU,Submit,ReqTRES,WorkDir";
+
+/// squeue columns for the queue view: ID, user, name, partition, nodes, CPUs, memory,
+/// time limit, submit time, reason, per-node GRES and expected start (what `squeue --start` shows)
+const SQUEUE_PENDING_FORMAT: &str = "%i|%u|%j|%P|%D|%C|%m|%l|%V|%r|%b|%S";
+
+/// squeue columns for the running jobs: ID, user, name, partition, nodes, CPUs, memory, time
+/// limit, submit time, node list, time used, per-node GRES and working directory
+const SQUEUE_RUNNING_FORMAT: &str = "%i|%u|%j|%P|%D|%C|%m|%l|%V|%N|%M|%b|%Z";
+
+/// sprio columns: job ID, priority, then the weighted age, fairshare, job size, partition and QOS factors
+const SPRIO_FORMAT: &str = "%i|%Y|%A|%F|%J|%P|%Q";
+
+/// sshare columns: account (indented by depth), user, normalized shares, raw usage,
+/// effective usage and the fairshare factor
+const SSHARE_FORMAT: &str = "Account,User,NormShares,RawUsage,EffectvUsage,FairShare";
+
+/// Which clusters of a multi-cluster or federated Slurm site to show.
+#[derive(Debug, Clone, Default)]
+pub enum SlurmClusters {
+    /// Only the cluster the client tools talk to by default
+    #[default]
+    Local,
+    /// The given clusters, passed as `-M`
+    Listed(Vec<String>),
+    /// Every member of the local cluster's federation (`--federation`)
+    Federation,
+}
+
+/// Where the running jobs come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobsSource {
+    /// squeue, else sacct if squeue fails
+    #[default]
+    Auto,
+    /// The controller's live view; needs no slurmdbd, but knows no CPU time
+    Squeue,
+    /// Accounting, which some sites only let users see their own jobs in
+    Sacct,
+}
+
+/// The `[slurm]` table: where the running jobs come from, and how much of accounting sacct
+/// looks for them in.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SlurmConfig {
+    pub jobs_source: JobsSource,
+    /// sacct's `-S` for the running jobs; with `-s`, a job counts if it was in one of the states
+    /// at any time since (default: `now-1day`, which still finds jobs started weeks ago)
+    pub jobs_since: String,
+    /// sacct's `-s` for the running jobs (default: `R`); empty lists every state
+    pub job_states: String,
+}
+
+impl Default for SlurmConfig {
+    fn default() -> Self {
+        Self { jobs_source: JobsSource::Auto, jobs_since: "now-1day".to_string(), job_states: "R".to_string() }
+    }
+}
+
+pub struct SlurmScheduler {
+    tz: ClusterTz,
+    transport: Transport,
+    clusters: SlurmClusters,
+    config: SlurmConfig,
+    /// Cluster of each node at the last refresh, since `scontrol update` takes one `-M`
+    node_clusters: Mutex<HashMap<String, String>>,
+}
+
+impl SlurmScheduler {
+    pub fn new(tz: ClusterTz, transport: Transport, clusters: SlurmClusters, config: SlurmConfig) -> Self {
+        Self {
+            tz,
+            transport,
+            clusters,
+            config,
+            node_clusters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn is_multi_cluster(&self) -> bool {
+        !matches!(self.clusters, SlurmClusters::Local)
+    }
+
+    /// `sacct`/`sinfo` flags selecting the clusters.
+    fn cluster_args(&self) -> Vec<String> {
+        match self.clusters {
+            SlurmClusters::Local => Vec::new(),
+            SlurmClusters::Listed(ref names) => vec!["-M".to_string(), names.join(",")],
+            SlurmClusters::Federation => vec!["--federation".to_string()],
+        }
+    }
+
+    /// `sacct` flags limiting the running jobs to the configured window and states, so it
+    /// doesn't read all of accounting.
+    fn running_window_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.config.jobs_since.is_empty() {
+            args.extend(["-S".to_string(), self.config.jobs_since.clone()]);
+        }
+        if !self.config.job_states.is_empty() {
+            args.extend(["-s".to_string(), self.config.job_states.clone()]);
+        }
+        args
+    }
+
+    fn sacct_format(&self) -> String {
+        if self.is_multi_cluster() {
+            format!("{},Cluster", SACCT_FORMAT)
+        } else {
+            SACCT_FORMAT.to_string()
+        }
+    }
+
+    /// `scontrol update NodeName=... <settings>`, once per cluster of the nodes.
+    async fn update_nodes(&self, node_ids: &[String], settings: &[String]) -> Result<()> {
+        // Group by cluster; nodes of the default cluster go without -M
+        let mut by_cluster: Vec<(Option<String>, Vec<String>)> = Vec::new();
+        {
+            let node_clusters = self.node_clusters.lock().unwrap();
+            for node_id in node_ids {
+                let cluster = node_clusters.get(node_id).cloned();
+                match by_cluster.iter_mut().find(|(c, _)| *c == cluster) {
+                    Some((_, ids)) => ids.push(node_id.clone()),
+                    None => by_cluster.push((cluster, vec![node_id.clone()])),
+                }
+            }
+        }
+
+        for (cluster, ids) in by_cluster {
+            let output = self.scontrol(cluster.as_deref())
+                .arg("update")
+                .arg(format!("NodeName={}", ids.join(",")))
+                .args(settings)
+                .output().await
+                .context("Failed to execute scontrol command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "scontrol update failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `scontrol hold|release <job>`; for an array's ID it applies to every pending task.
+    async fn scontrol_job(&self, action: &str, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        let output = self.scontrol(cluster)
+            .args([action, job_id])
+            .output().await
+            .context("Failed to execute scontrol command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "scontrol {} failed: {}",
+                action,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `scontrol`, aimed at one cluster (`None`: the default one).
+    fn scontrol(&self, cluster: Option<&str>) -> TransportCommand {
+        let command = self.transport.command("scontrol");
+        match cluster {
+            Some(cluster) => command.args(["-M", cluster]),
+            None => command,
+        }
+    }
+
+    /// Clusters to query one at a time with `scontrol`, which only accepts a single `-M`.
+    async fn scontrol_clusters(&self) -> Result<Vec<Option<String>>> {
+        Ok(match self.clusters {
+            SlurmClusters::Local => vec![None],
+            SlurmClusters::Listed(ref names) => names.iter().cloned().map(Some).collect(),
+            SlurmClusters::Federation => self.federation_members().await?.into_iter().map(Some).collect(),
+        })
+    }
+
+    /// Member names from `scontrol show federation`, whose
+    /// `Self:`/`Sibling:` lines start with `<name>:<host>:<port>`.
+    async fn federation_members(&self) -> Result<Vec<String>> {
+        let output = self.scontrol(None)
+            .args(["show", "federation"])
+            .output().await
+            .context("Failed to execute scontrol command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "scontrol show federation failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let members: Vec<String> = output_str
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                let rest = line.strip_prefix("Self:").or_else(|| line.strip_prefix("Sibling:"))?;
+                rest.split_whitespace().next()?.split(':').next().map(str::to_string)
+            })
+            .collect();
+
+        if members.is_empty() {
+            return Err(anyhow::anyhow!("This cluster is not part of a federation"));
+        }
+        Ok(members)
+    }
+
+    fn parse_nodes(output_str: &str, partition: &str) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut current_node_info = String::new();
+
+        for line in output_str.lines() {
+            if line.starts_with("NodeName=") {
+                if !current_node_info.is_empty() {
+                    if let Some(node) = Self::parse_node_info(&current_node_info, partition) {
+                        nodes.push(node);
+                    }
+                }
+                current_node_info = line.to_string();
+            } else {
+                current_node_info.push('\n');
+                current_node_info.push_str(line);
+            }
+        }
+
+        // Don't forget the last node
+        if !current_node_info.is_empty() {
+            if let Some(node) = Self::parse_node_info(&current_node_info, partition) {
+                nodes.push(node);
+            }
+        }
+
+        nodes
+    }
+
+    /// Parse Slurm's `YYYY-MM-DDTHH:MM:SS` timestamps, which are in the controller's local time.
+    fn parse_timestamp(&self, time_str: &str) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(time_str.trim(), "%Y-%m-%dT%H:%M:%S").ok()?;
+        self.tz.localize(&naive)
+    }
+
+    /// Base state and flags of a node, e.g. `["MIXED", "DRAIN"]` (`scontrol` prints
+    /// `MIXED+DRAIN`). A trailing `*` on the base state means the node isn't responding.
+    pub(crate) fn parse_node_state(states: &[String]) -> (NodeState, Vec<String>) {
+        let base = states.first().map(|s| s.to_uppercase()).unwrap_or_default();
+        let mut flags: Vec<String> = states.iter().skip(1).map(|s| s.to_uppercase()).collect();
+        let base = match base.strip_suffix('*') {
+            Some(base) => {
+                flags.push("NOT_RESPONDING".to_string());
+                base.to_string()
+            }
+            None => base,
+        };
+        let has = |flag: &str| flags.iter().any(|f| f == flag);
+
+        let state = match base.as_str() {
+            "DOWN" => NodeState::Down,
+            _ if base == "FAIL" || has("FAIL") => NodeState::Fail,
+            _ if has("MAINT") || has("MAINTENANCE") => NodeState::Maint,
+            _ if base == "REBOOT" || has("REBOOT_REQUESTED") || has("REBOOT_ISSUED") => NodeState::Reboot,
+            _ if base == "FUTURE" || has("POWERING_DOWN") || has("POWER_DOWN") => NodeState::PlannedDown,
+            // Drained once idle; allocated nodes keep their state and are "draining"
+            "DRAINED" | "IDLE" if has("DRAIN") => NodeState::Drained,
+            "DRAINED" => NodeState::Drained,
+            _ if base == "COMPLETING" || has("COMPLETING") => NodeState::Completing,
+            "IDLE" if has("RESERVED") => NodeState::Reserved,
+            "IDLE" => NodeState::Idle,
+            "MIXED" => NodeState::Mixed,
+            "ALLOC" | "ALLOCATED" => NodeState::Allocated,
+            _ => NodeState::Offline,
+        };
+        (state, flags)
+    }
+
+    /// sacct states (`RUNNING`, `CANCELLED by 1234`, `OUT_OF_MEMORY`) or squeue's short codes.
+    fn parse_job_state(state_str: &str) -> JobState {
+        match state_str.split_whitespace().next().unwrap_or("") {
+            "RUNNING" | "R" | "COMPLETING" | "CG" => JobState::Running,
+            "PENDING" | "PD" | "REQUEUED" | "RQ" => JobState::Pending,
+            "COMPLETED" | "CD" => JobState::Completed,
+            "CANCELLED" | "CA" => JobState::Cancelled,
+            "TIMEOUT" | "TO" | "DEADLINE" | "DL" => JobState::Timeout,
+            "OUT_OF_MEMORY" | "OOM" => JobState::OutOfMemory,
+            "PREEMPTED" | "PR" => JobState::Preempted,
+            "SUSPENDED" | "S" | "STOPPED" | "ST" => JobState::Suspended,
+            "NODE_FAIL" | "NF" => JobState::NodeFail,
+            _ => JobState::Failed,
+        }
+    }
+
+    fn parse_duration(time_str: &str) -> Duration {
+        // Parse time in format HH:MM:SS or days-HH:MM:SS; TotalCPU also has MM:SS.mmm
+        let (days, time_str) = match time_str.split_once('-') {
+            Some((days, rest)) => (days.parse::<i64>().unwrap_or(0), rest),
+            None => (0, time_str),
+        };
+        let time_str = time_str.split('.').next().unwrap_or_default();
+        let parts: Vec<i64> = time_str.split(':').map(|part| part.parse().unwrap_or(0)).collect();
+        let (hours, minutes, seconds) = match parts[..] {
+            [.., hours, minutes, seconds] => (hours, minutes, seconds),
+            [minutes, seconds] if days == 0 => (0, minutes, seconds),
+            _ => return Duration::seconds(0),
+        };
+        Duration::seconds(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+    }
+
+    /// The `cpu=` entry of a `TRESUsageInTot` value such as `cpu=00:10:23,energy=0,mem=1.2G`.
+    fn parse_tres_cpu(tres: &str) -> Option<Duration> {
+        tres.split(',')
+            .find_map(|entry| entry.strip_prefix("cpu="))
+            .map(Self::parse_duration)
+    }
+
+    /// Add the CPU time of the steps still running, which sacct's `TotalCPU` leaves out until
+    /// they finish, from `sstat`. Skipped in a multi-cluster view, which sstat can't address.
+    async fn add_running_cpu_time(&self, jobs: &mut [Job]) -> Result<()> {
+        let ids: Vec<String> = jobs.iter()
+            .filter(|job| job.cluster.is_none())
+            .map(|job| job.id.clone())
+            .collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let output = self.transport.command("sstat")
+            .args(["-n", "-P", "-a", "--format", "JobID,TRESUsageInTot", "-j", &ids.join(",")])
+            .output().await
+            .context("Failed to execute sstat command")?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "sstat command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let mut running: HashMap<String, Duration> = HashMap::new();
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        for line in output_str.lines() {
+            // One line per step: 1234.batch, 1234.0, ...
+            let Some((step, tres)) = line.split_once('|') else {
+                continue;
+            };
+            let job_id = step.split('.').next().unwrap_or(step);
+            if let Some(cpu) = Self::parse_tres_cpu(tres) {
+                *running.entry(job_id.to_string()).or_insert_with(Duration::zero) += cpu;
+            }
+        }
+
+        for job in jobs.iter_mut() {
+            if let Some(cpu) = running.get(&job.id) {
+                job.cpu_time += *cpu;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse GPU entries of a `Gres=`/`GresUsed=` value such as
+    /// `gpu:a100:2(S:0-1),gpu:a100_3g.20gb:4(IDX:0,2-4)` into (model, count) pairs.
+    pub(crate) fn parse_gpu_gres(value: &str) -> Vec<(String, u32)> {
+        let mut entries = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        // Split on commas outside of parentheses
+        for (i, c) in value.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    entries.push(&value[start..i]);
+                    start = i + 1;
+                },
+                _ => {}
+            }
+        }
+        entries.push(&value[start..]);
+
+        entries
+            .into_iter()
+            .filter_map(|entry| {
+                let entry = entry.split('(').next().unwrap_or(entry);
+                let parts: Vec<&str> = entry.split(':').collect();
+                match parts.as_slice() {
+                    ["gpu", count] => Some(("gpu".to_string(), count.parse().ok()?)),
+                    ["gpu", model, count] => Some((model.to_string(), count.parse().ok()?)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// GPU counts of a TRES list such as `cpu=16,mem=64G,gres/gpu=2,gres/gpu:a100=2`
+    /// as (model, count) pairs; the untyped total comes out as model `gpu`.
+    pub(crate) fn parse_tres_gpus(value: &str) -> Vec<(String, u32)> {
+        value
+            .split(',')
+            .filter_map(|entry| {
+                let (key, count) = entry.split_once('=')?;
+                let model = match key.strip_prefix("gres/gpu")? {
+                    "" => "gpu",
+                    typed => typed.strip_prefix(':')?,
+                };
+                Some((model.to_string(), count.parse().ok()?))
+            })
+            .collect()
+    }
+
+    /// Requested GPU count and model from a job's `ReqTRES`; the untyped total wins if both are listed.
+    pub(crate) fn parse_gpu_request(value: &str) -> (u32, Option<String>) {
+        let gpus = Self::parse_tres_gpus(value);
+        let total = gpus
+            .iter()
+            .find(|(model, _)| model == "gpu")
+            .map(|(_, count)| *count)
+            .unwrap_or_else(|| gpus.iter().map(|(_, count)| count).sum());
+        let gpu_type = gpus.into_iter().map(|(model, _)| model).find(|model| model != "gpu");
+        (total, gpu_type)
+    }
+
+    /// Comma-separated feature list; `(null)` when a node has none.
+    pub(crate) fn parse_features(value: &str) -> Vec<String> {
+        value
+            .split(',')
+            .filter(|f| !f.is_empty() && *f != "(null)")
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn parse_node_info(node_info: &str, partition: &str) -> Option<Node> {
+        let mut node = Node {
+            id: String::new(),
+            state: NodeState::Offline,
+            total_cores: 0,
+            used_cores: 0,
+            total_mem: 0,
+            used_mem: 0,
+            jobs: Vec::new(),
+            job_users: Vec::new(),
+            partitions: Vec::new(),
+            reason: None,
+            cpu_load: None,
+            current_watts: None,
+            consumed_joules: None,
+            os: None,
+            gpus: Vec::new(),
+            features: Vec::new(),
+            state_flags: Vec::new(),
+            cluster: None,
+        };
+
+        let mut has_partition = false;
+        let mut gres_used = Vec::new();
+        let mut alloc_tres = Vec::new();
+
+        // Reason and OS are free text and may contain spaces, so they are taken from their own line
+        for line in node_info.lines() {
+            if let Some(reason) = line.trim().strip_prefix("Reason=") {
+                let reason = reason.split(" [").next().unwrap_or(reason).trim();
+                if !reason.is_empty() && reason != "(null)" {
+                    node.reason = Some(reason.to_string());
+                }
+            } else if let Some(os) = line.trim().strip_prefix("OS=") {
+                node.os = Some(os.to_string()).filter(|os| !os.is_empty() && os != "(null)");
+            }
+        }
+        
+        let free_text = |l: &&str| l.trim().starts_with("Reason=") || l.trim().starts_with("OS=");
+        for info in node_info.lines().filter(|l| !free_text(l)).flat_map(|l| l.split_whitespace()) {
+            if let Some((key, value)) = info.split_once('=') {
+                match key {
+                    "NodeName" => node.id = value.to_string(),
+                    "State" => {
+                        let states: Vec<String> = value.split('+').map(str::to_string).collect();
+                        (node.state, node.state_flags) = Self::parse_node_state(&states);
+                    },
+                    "CPUAlloc" => {
+                        if let Ok(val) = value.parse::<u32>() {
+                            node.used_cores = val;
+                        }
+                    },
+                    "CPUTot" => {
+                        if let Ok(val) = value.parse::<u32>() {
+                            node.total_cores = val;
+                        }
+                    },
+                    "AllocMem" => {
+                        if let Ok(val) = value.parse::<u64>() {
+                            node.used_mem = val * MB;
+                        }
+                    },
+                    "CPULoad" => node.cpu_load = value.parse::<f64>().ok(),
+                    // 0 (or n/s) when no energy plugin is configured
+                    "CurrentWatts" => node.current_watts = value.parse().ok().filter(|w| *w > 0),
+                    "ConsumedJoules" => node.consumed_joules = value.parse().ok().filter(|j| *j > 0),
+                    "Gres" => {
+                        node.gpus = Self::parse_gpu_gres(value)
+                            .into_iter()
+                            .map(|(model, total)| GpuGres { model, total, used: 0 })
+                            .collect();
+                    },
+                    "GresUsed" => gres_used = Self::parse_gpu_gres(value),
+                    "AllocTRES" => alloc_tres = Self::parse_tres_gpus(value),
+                    // Printed before ActiveFeatures, which wins when both are set
+                    "AvailableFeatures" | "ActiveFeatures" => node.features = Self::parse_features(value),
+                    "RealMemory" => {
+                        if let Ok(val) = value.parse::<u64>() {
+                            node.total_mem = val * MB;
+                        }
+                    },
+                    "Partitions" => {
+                        node.partitions = value.split(',').map(|s| s.to_string()).collect();
+                        if node.partitions.iter().any(|p| p == partition) {
+                            has_partition = true;
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        for (model, used) in gres_used {
+            if let Some(gpu) = node.gpus.iter_mut().find(|g| g.model == model) {
+                gpu.used = used;
+            }
+        }
+
+        // Older Slurm versions don't print GresUsed; AllocTRES has the same counts
+        if !node_info.contains("GresUsed=") {
+            let single_model = node.gpus.len() == 1;
+            for (model, used) in alloc_tres {
+                match node.gpus.iter_mut().find(|g| g.model == model) {
+                    Some(gpu) => gpu.used = used,
+                    None if model == "gpu" && single_model => node.gpus[0].used = used,
+                    None => {}
+                }
+            }
+        }
+
+        if has_partition && !node.id.is_empty() {
+            Some(node)
+        } else {
+            None
+        }
+    }
+
+    /// Parse `scontrol show reservations`, one `ReservationName=...` record per reservation.
+    fn parse_reservations(&self, output_str: &str) -> Vec<Reservation> {
+        let mut records: Vec<String> = Vec::new();
+        for line in output_str.lines() {
+            match records.last_mut() {
+                Some(record) if !line.starts_with("ReservationName=") => {
+                    record.push(' ');
+                    record.push_str(line);
+                }
+                _ if line.starts_with("ReservationName=") => records.push(line.to_string()),
+                _ => {} // "No reservations in the system"
+            }
+        }
+
+        records
+            .iter()
+            .filter_map(|record| {
+                let fields: HashMap<&str, &str> = record
+                    .split_whitespace()
+                    .filter_map(|field| field.split_once('='))
+                    .collect();
+                let list = |key: &str| -> Vec<String> {
+                    fields.get(key).map(|v| Self::parse_features(v)).unwrap_or_default()
+                };
+
+                let mut users = list("Users");
+                users.extend(list("Accounts"));
+                Some(Reservation {
+                    name: fields.get("ReservationName")?.to_string(),
+                    start_time: self.parse_timestamp(fields.get("StartTime")?)?,
+                    end_time: self.parse_timestamp(fields.get("EndTime")?)?,
+                    nodes: fields
+                        .get("Nodes")
+                        .filter(|n| **n != "(null)")
+                        .map(|n| hostlist::expand(n))
+                        .unwrap_or_default(),
+                    users,
+                    flags: list("Flags"),
+                    cluster: None,
+                })
+            })
+            .collect()
+    }
+
+    /// `scontrol show licenses -o`: `LicenseName=matlab Total=10 Used=8 Free=2 Reserved=0 Remote=no`
+    /// (`Reserved` since Slurm 20.11).
+    fn parse_licenses(output_str: &str) -> Vec<License> {
+        output_str
+            .lines()
+            .filter_map(|line| {
+                let fields: HashMap<&str, &str> = line
+                    .split_whitespace()
+                    .filter_map(|field| field.split_once('='))
+                    .collect();
+                let count = |key: &str| fields.get(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+                Some(License {
+                    name: fields.get("LicenseName")?.to_string(),
+                    total: count("Total"),
+                    used: count("Used"),
+                    reserved: count("Reserved"),
+                    cluster: None,
+                })
+            })
+            .collect()
+    }
+
+    /// `sshare -P -n` output in `SSHARE_FORMAT`. The `root` row is left out.
+    fn parse_sshare(output_str: &str) -> Vec<Fairshare> {
+        output_str
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+                if fields.len() < 6 || fields[0].is_empty() || (fields[0] == "root" && fields[1].is_empty()) {
+                    return None;
+                }
+                Some(Fairshare {
+                    account: fields[0].to_string(),
+                    user: Some(fields[1].to_string()).filter(|user| !user.is_empty()),
+                    norm_shares: fields[2].parse().unwrap_or(0.0),
+                    raw_usage: fields[3].parse().unwrap_or(0),
+                    effective_usage: fields[4].parse().unwrap_or(0.0),
+                    fairshare: fields[5].parse().ok(),
+                    cluster: None,
+                })
+            })
+            .collect()
+    }
+
+    /// `scontrol show topology`: `SwitchName=s0 Level=0 LinkSpeed=1 Nodes=c[001-020]` per
+    /// switch. Nodes hang off the level-0 (leaf) switches, which is usually one per rack.
+    fn parse_topology(output_str: &str) -> HashMap<String, String> {
+        let mut switches = HashMap::new();
+        for line in output_str.lines() {
+            let fields: HashMap<&str, &str> = line
+                .split_whitespace()
+                .filter_map(|field| field.split_once('='))
+                .collect();
+            let (Some(name), Some(nodes)) = (fields.get("SwitchName"), fields.get("Nodes")) else {
+                continue;
+            };
+            if fields.get("Level").is_some_and(|level| *level != "0") {
+                continue;
+            }
+            for node in hostlist::expand(nodes) {
+                switches.insert(node, name.to_string());
+            }
+        }
+        switches
+    }
+
+    /// `scontrol show partitions -o`: one `Key=Value ...` line per partition.
+    fn parse_partitions(output_str: &str) -> Vec<Partition> {
+        output_str
+            .lines()
+            .filter_map(|line| {
+                let fields: HashMap<&str, &str> = line
+                    .split_whitespace()
+                    .filter_map(|field| field.split_once('='))
+                    .collect();
+                // ALL, or a list of accounts
+                let accounts = |key: &str| -> Vec<String> {
+                    fields
+                        .get(key)
+                        .filter(|v| **v != "ALL")
+                        .map(|v| Self::parse_features(v))
+                        .unwrap_or_default()
+                };
+                let mem_per_cpu = |key: &str| -> Option<u64> {
+                    fields.get(key).and_then(|v| v.parse::<u64>().ok()).map(|mb| mb * MB)
+                };
+
+                Some(Partition {
+                    name: fields.get("PartitionName")?.to_string(),
+                    state: fields.get("State").unwrap_or(&"UP").to_string(),
+                    is_default: fields.get("Default") == Some(&"YES"),
+                    max_time: fields
+                        .get("MaxTime")
+                        .filter(|t| **t != "UNLIMITED")
+                        .map(|t| Self::parse_duration(t)),
+                    default_mem_per_cpu: mem_per_cpu("DefMemPerCPU"),
+                    max_mem_per_cpu: mem_per_cpu("MaxMemPerCPU"),
+                    total_nodes: fields.get("TotalNodes").and_then(|n| n.parse().ok()).unwrap_or(0),
+                    allow_accounts: accounts("AllowAccounts"),
+                    deny_accounts: accounts("DenyAccounts"),
+                    cluster: None,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_job_line(&self, line: &str, partition: &str) -> Option<Job> {
+        let job = self.parse_job_fields(line)?;
+
+        // Check partition and only include running jobs; steps (.batch, .0) belong to their job
+        if !job.partition.contains(partition) || job.state != JobState::Running || job.id.contains('.') {
+            return None;
+        }
+
+        Some(job)
+    }
+
+    fn parse_job_fields(&self, line: &str) -> Option<Job> {
+        let fields: Vec<&str> = line.split('|').collect();
+        if fields.len() < 12 {
+            return None;
+        }
+
+        // Skip .extern jobs
+        if fields[2].contains(".extern") {
+            return None;
+        }
+
+        // ReqMem is suffixed with n/c (per node/CPU) before Slurm 21.08
+        let req_mem = Self::parse_mem(fields[8].trim_end_matches(['n', 'c']));
+        let (req_gpus, gpu_type) = fields.get(13).map(|tres| Self::parse_gpu_request(tres)).unwrap_or_default();
+
+        Some(Job {
+            id: fields[2].to_string(),
+            user: fields[3].to_string(),
+            name: fields[4].to_string(),
+            state: Self::parse_job_state(fields[5]),
+            node_list: hostlist::expand(fields[1]),
+            partition: fields[0].to_string(),
+            req_nodes: fields[6].parse().unwrap_or(1),
+            req_cpus: fields[7].parse().unwrap_or(0),
+            req_mem,
+            req_gpus,
+            gpu_type,
+            reason: None,
+            priority: None,
+            est_start: None,
+            time_limit: Self::parse_duration(fields[9]),
+            elapsed: Self::parse_duration(fields[10]),
+            cpu_time: Self::parse_duration(fields[11]),
+            submit_time: fields.get(12)
+                .and_then(|t| self.parse_timestamp(t))
+                .unwrap_or_else(Utc::now),
+            work_dir: fields.get(14).filter(|d| !d.is_empty()).map(|d| d.to_string()),
+            // Only requested in multi-cluster mode; with -p every line ends in '|'
+            cluster: fields.get(15)
+                .filter(|c| self.is_multi_cluster() && !c.is_empty())
+                .map(|c| c.to_string()),
+        })
+    }
+
+    /// The running jobs of `partition` from accounting.
+    async fn sacct_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let output = self.transport.command("sacct")
+            .args([
+                "-a",
+                "--format",
+                &self.sacct_format(),
+                "-p"
+            ])
+            .args(self.running_window_args())
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute sacct command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "sacct command failed: {}", 
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut jobs = Vec::new();
+
+        for (ln, line) in output_str.lines().enumerate() {
+            if ln > 0 { // Skip header
+                if let Some(job) = self.parse_job_line(line, partition) {
+                    jobs.push(job);
+                }
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    /// The running jobs of `partition` from the controller, with those still completing as
+    /// sacct's `-s R` has them.
+    async fn squeue_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let output = self.transport.command("squeue")
+            .args(["-h", "-t", "RUNNING,COMPLETING", "-p", partition, "-o", SQUEUE_RUNNING_FORMAT])
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute squeue command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "squeue command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(self.parse_running_jobs(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Memory such as `4000M`, `16G` or `1.5T` (squeue `%m`, sacct `ReqMem`) in bytes;
+    /// without a unit it's MB.
+    fn parse_mem(value: &str) -> u64 {
+        let value = value.trim();
+        let (number, factor) = match value.chars().last() {
+            Some('K') => (&value[..value.len() - 1], MB / 1000),
+            Some('M') => (&value[..value.len() - 1], MB),
+            Some('G') => (&value[..value.len() - 1], GB),
+            Some('T') => (&value[..value.len() - 1], TB),
+            _ => (value, MB),
+        };
+        (number.parse::<f64>().unwrap_or(0.0) * factor as f64) as u64
+    }
+
+    /// Running jobs from squeue output in `SQUEUE_RUNNING_FORMAT`.
+    fn parse_running_jobs(&self, output: &str) -> Vec<Job> {
+        let mut jobs = Vec::new();
+        let mut cluster = None;
+
+        for line in output.lines() {
+            // With -M/--federation, each cluster's jobs are preceded by a "CLUSTER: <name>" line
+            if let Some(name) = line.strip_prefix("CLUSTER:") {
+                cluster = Some(name.trim().to_string());
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 13 {
+                continue;
+            }
+
+            let req_nodes: u32 = fields[4].parse().unwrap_or(1);
+            let gres = fields[11].trim_start_matches("gres/").trim_start_matches("gres:");
+            let gpus = Self::parse_gpu_gres(gres);
+
+            jobs.push(Job {
+                id: fields[0].to_string(),
+                user: fields[1].to_string(),
+                name: fields[2].to_string(),
+                state: JobState::Running,
+                node_list: hostlist::expand(fields[9]),
+                partition: fields[3].to_string(),
+                req_nodes,
+                req_cpus: fields[5].parse().unwrap_or(0),
+                // Both per node in squeue
+                req_mem: Self::parse_mem(fields[6]) * req_nodes as u64,
+                req_gpus: gpus.iter().map(|(_, count)| count).sum::<u32>() * req_nodes,
+                gpu_type: gpus.into_iter().map(|(model, _)| model).find(|model| model != "gpu"),
+                reason: None,
+                priority: None,
+                est_start: None,
+                time_limit: Self::parse_duration(fields[7]),
+                elapsed: Self::parse_duration(fields[10]),
+                cpu_time: Duration::zero(),
+                submit_time: self.parse_timestamp(fields[8]).unwrap_or_else(Utc::now),
+                work_dir: Some(fields[12].to_string()).filter(|dir| !dir.is_empty()),
+                cluster: cluster.clone().filter(|_| self.is_multi_cluster()),
+            });
+        }
+
+        jobs
+    }
+
+    /// Pending jobs from squeue output in `SQUEUE_PENDING_FORMAT`, keeping squeue's order.
+    fn parse_pending_jobs(&self, output: &str) -> Vec<Job> {
+        let mut jobs = Vec::new();
+        let mut cluster = None;
+
+        for line in output.lines() {
+            // With -M/--federation, each cluster's jobs are preceded by a "CLUSTER: <name>" line
+            if let Some(name) = line.strip_prefix("CLUSTER:") {
+                cluster = Some(name.trim().to_string());
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 11 {
+                continue;
+            }
+
+            let req_nodes: u32 = fields[4].parse().unwrap_or(1);
+            // Per-node GRES like "gres/gpu:a100:2" (or "gpu:a100:2" before 21.08)
+            let gres = fields[10].trim_start_matches("gres/").trim_start_matches("gres:");
+            let gpus = Self::parse_gpu_gres(gres);
+            let reason = fields[9].trim();
+
+            jobs.push(Job {
+                id: fields[0].to_string(),
+                user: fields[1].to_string(),
+                name: fields[2].to_string(),
+                state: JobState::Pending,
+                node_list: Vec::new(),
+                partition: fields[3].to_string(),
+                req_nodes,
+                req_cpus: fields[5].parse().unwrap_or(0),
+                req_mem: Self::parse_mem(fields[6]),
+                req_gpus: gpus.iter().map(|(_, count)| count).sum::<u32>() * req_nodes,
+                gpu_type: gpus.into_iter().map(|(model, _)| model).find(|model| model != "gpu"),
+                reason: (!reason.is_empty() && reason != "None").then(|| reason.to_string()),
+                priority: None,
+                // N/A until the backfill scheduler has planned the job
+                est_start: fields.get(11).and_then(|t| self.parse_timestamp(t)),
+                time_limit: Self::parse_duration(fields[7]),
+                elapsed: Duration::zero(),
+                cpu_time: Duration::zero(),
+                submit_time: self.parse_timestamp(fields[8]).unwrap_or_else(Utc::now),
+                work_dir: None,
+                cluster: cluster.clone().filter(|_| self.is_multi_cluster()),
+            });
+        }
+
+        jobs
+    }
+
+    /// Priorities from sprio output in `SPRIO_FORMAT`, by cluster and job ID.
+    fn parse_sprio(output: &str) -> HashMap<(Option<String>, String), JobPriority> {
+        let mut priorities = HashMap::new();
+        let mut cluster = None;
+        // Factors are printed as floats with -n and as integers otherwise
+        let number = |value: &str| value.trim().parse::<f64>().unwrap_or(0.0).max(0.0) as u64;
+
+        for line in output.lines() {
+            if let Some(name) = line.strip_prefix("CLUSTER:") {
+                cluster = Some(name.trim().to_string());
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 7 {
+                continue;
+            }
+
+            priorities.insert((cluster.clone(), fields[0].trim().to_string()), JobPriority {
+                total: number(fields[1]),
+                age: number(fields[2]),
+                fairshare: number(fields[3]),
+                job_size: number(fields[4]),
+                partition: number(fields[5]),
+                qos: number(fields[6]),
+            });
+        }
+
+        priorities
+    }
+}
+
+#[async_trait]
+impl Scheduler for SlurmScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        let mut node_clusters = HashMap::new();
+
+        for cluster in self.scontrol_clusters().await? {
+            // -d adds GresUsed, needed for per-device GPU usage
+            let output = self.scontrol(cluster.as_deref())
+                .args(["-d", "show", "nodes"])
+                .output().await
+                .context("Failed to execute scontrol command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "scontrol command failed: {}", 
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            for mut node in Self::parse_nodes(&String::from_utf8_lossy(&output.stdout), partition) {
+                if let Some(ref cluster) = cluster {
+                    node_clusters.insert(node.id.clone(), cluster.clone());
+                    node.cluster = Some(cluster.clone());
+                }
+                nodes.push(node);
+            }
+        }
+
+        *self.node_clusters.lock().unwrap() = node_clusters;
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No nodes found in partition: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        match self.config.jobs_source {
+            JobsSource::Squeue => self.squeue_jobs(partition).await,
+            JobsSource::Sacct => self.sacct_jobs(partition).await,
+            JobsSource::Auto => match self.squeue_jobs(partition).await {
+                Ok(jobs) => Ok(jobs),
+                Err(squeue) => self.sacct_jobs(partition).await.map_err(|sacct| anyhow::anyhow!("{:#}; {:#}", squeue, sacct)),
+            },
+        }
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = env::var("USER").unwrap_or_else(|_| user.to_string());
+        
+        let output = self.transport.command("sacct")
+            .args([
+                "-u", &current_user,
+                "--format",
+                &self.sacct_format(),
+                "-p"
+            ])
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute sacct command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "sacct command failed: {}", 
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut jobs = Vec::new();
+
+        for (ln, line) in output_str.lines().enumerate() {
+            if ln > 0 { // Skip header
+                let fields: Vec<&str> = line.split('|').collect();
+                if fields.len() >= 12 && !fields[2].contains(".extern") && fields[5].starts_with('R') {
+                    if let Some(job) = self.parse_job_line(line, "") { // Don't filter by partition for user jobs
+                        jobs.push(job);
+                    }
+                }
+            }
+        }
+
+        // Without it the efficiency of a running job is unknown, not an error
+        let _ = self.add_running_cpu_time(&mut jobs).await;
+
+        Ok(jobs)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let output = self.transport.command("sinfo")
+            .args(["-h", "-o", "%P"])
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute sinfo command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "sinfo command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        // The default partition is marked with a trailing '*'
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut partitions: Vec<String> = Vec::new();
+        // With -M/--federation, each cluster's list is preceded by a "CLUSTER: <name>" line
+        for name in output_str.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with("CLUSTER:")) {
+            match name.strip_suffix('*') {
+                Some(default) => partitions.insert(0, default.to_string()),
+                None if !partitions.iter().any(|p| p == name) => partitions.push(name.to_string()),
+                None => {}
+            }
+        }
+
+        Ok(partitions)
+    }
+
+    async fn get_partitions(&self) -> Result<Vec<Partition>> {
+        let mut partitions = Vec::new();
+
+        for cluster in self.scontrol_clusters().await? {
+            let output = self.scontrol(cluster.as_deref())
+                .args(["show", "partitions", "-o"])
+                .output().await
+                .context("Failed to execute scontrol command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "scontrol command failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            for mut partition in Self::parse_partitions(&String::from_utf8_lossy(&output.stdout)) {
+                partition.cluster = cluster.clone();
+                partitions.push(partition);
+            }
+        }
+
+        // Stable, so the first cluster's default wins
+        partitions.sort_by_key(|p| !p.is_default);
+        Ok(partitions)
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        // Highest priority first, which is the order the backfill scheduler considers them in
+        let output = self.transport.command("squeue")
+            .args(["-h", "-t", "PENDING", "-p", partition, "--sort=-p,i", "-o", SQUEUE_PENDING_FORMAT])
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute squeue command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "squeue command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let mut jobs = self.parse_pending_jobs(&String::from_utf8_lossy(&output.stdout));
+
+        // Priority factors (don't fail on error; sprio needs the multifactor plugin)
+        let sprio = self.transport.command("sprio")
+            .args(["-h", "-p", partition, "-o", SPRIO_FORMAT])
+            .args(self.cluster_args())
+            .output().await;
+        if let Some(output) = sprio.ok().filter(|o| o.status.success()) {
+            let mut priorities = Self::parse_sprio(&String::from_utf8_lossy(&output.stdout));
+            for job in &mut jobs {
+                // The CLUSTER: lines only appear with -M/--federation, where jobs carry their cluster
+                job.priority = priorities.remove(&(job.cluster.clone(), job.id.clone()));
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let output = self.transport.command("sacct")
+            .args([
+                "-j", &job_ids.join(","),
+                "--format",
+                &self.sacct_format(),
+                "-p"
+            ])
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute sacct command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "sacct command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        Ok(output_str
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(|line| self.parse_job_fields(line))
+            .filter(|job| !job.id.contains('.')) // Job steps
+            .collect())
+    }
+
+    /// Allocations only (`-X`); jobs still pending have an `Unknown` start.
+    async fn get_job_waits(&self, since: DateTime<Utc>) -> Result<Vec<JobWait>> {
+        let start = self.tz.format(&since, "%Y-%m-%dT%H:%M:%S");
+        let output = self.transport.command("sacct")
+            .args(["-a", "-X", "-n", "-p", "-S", &start, "-E", "now", "--format", "Partition,Submit,Start"])
+            .args(self.cluster_args())
+            .output().await
+            .context("Failed to execute sacct command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "sacct command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        Ok(output_str
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('|').collect();
+                let submit = self.parse_timestamp(fields.get(1)?)?;
+                let started = self.parse_timestamp(fields.get(2)?)?;
+                (started >= since && started >= submit).then(|| JobWait {
+                    partition: fields[0].to_string(),
+                    started,
+                    wait: started - submit,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        let mut reservations = Vec::new();
+
+        for cluster in self.scontrol_clusters().await? {
+            let output = self.scontrol(cluster.as_deref())
+                .args(["show", "reservations"])
+                .output().await
+                .context("Failed to execute scontrol command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "scontrol command failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            for mut reservation in self.parse_reservations(&String::from_utf8_lossy(&output.stdout)) {
+                reservation.cluster = cluster.clone();
+                reservations.push(reservation);
+            }
+        }
+
+        Ok(reservations)
+    }
+
+    async fn get_licenses(&self) -> Result<Vec<License>> {
+        let mut licenses = Vec::new();
+
+        for cluster in self.scontrol_clusters().await? {
+            let output = self.scontrol(cluster.as_deref())
+                .args(["show", "licenses", "-o"])
+                .output().await
+                .context("Failed to execute scontrol command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "scontrol command failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            for mut license in Self::parse_licenses(&String::from_utf8_lossy(&output.stdout)) {
+                license.cluster = cluster.clone();
+                licenses.push(license);
+            }
+        }
+
+        Ok(licenses)
+    }
+
+    async fn get_fairshare(&self, user: &str) -> Result<Vec<Fairshare>> {
+        let mut fairshare = Vec::new();
+
+        // One cluster at a time: sshare has no --federation
+        for cluster in self.scontrol_clusters().await? {
+            let mut command = self.transport.command("sshare")
+                .args(["-P", "-n", "-u", user, "-o", SSHARE_FORMAT]);
+            if let Some(ref cluster) = cluster {
+                command = command.args(["-M", cluster]);
+            }
+            let output = command.output().await.context("Failed to execute sshare command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "sshare command failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            for mut entry in Self::parse_sshare(&String::from_utf8_lossy(&output.stdout)) {
+                entry.cluster = cluster.clone();
+                fairshare.push(entry);
+            }
+        }
+
+        Ok(fairshare)
+    }
+
+    async fn get_topology(&self) -> Result<HashMap<String, String>> {
+        let mut switches = HashMap::new();
+
+        for cluster in self.scontrol_clusters().await? {
+            let output = self.scontrol(cluster.as_deref())
+                .args(["show", "topology"])
+                .output().await
+                .context("Failed to execute scontrol command")?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "scontrol command failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            switches.extend(Self::parse_topology(&String::from_utf8_lossy(&output.stdout)));
+        }
+
+        Ok(switches)
+    }
+
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        let cluster = match self.clusters {
+            SlurmClusters::Listed(ref names) => names.first().map(String::as_str),
+            _ => None,
+        };
+        let output = self.scontrol(cluster)
+            .args(["show", "config"])
+            .output().await
+            .context("Failed to execute scontrol command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "scontrol command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        // First line: "Configuration data as of 2024-01-30T12:34:56"
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        Ok(output_str
+            .lines()
+            .next()
+            .and_then(|line| line.rsplit(' ').next())
+            .and_then(|t| self.parse_timestamp(t)))
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.update_nodes(node_ids, &[format!("Reason={}", reason)]).await
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.update_nodes(node_ids, &["State=DRAIN".to_string(), format!("Reason={}", reason)]).await
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        self.update_nodes(node_ids, &["State=RESUME".to_string()]).await
+    }
+
+    async fn cancel_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        let mut command = self.transport.command("scancel");
+        if let Some(cluster) = cluster {
+            command = command.args(["-M", cluster]);
+        }
+        let output = command
+            .arg(job_id)
+            .output().await
+            .context("Failed to execute scancel command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "scancel failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn hold_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        self.scontrol_job("hold", job_id, cluster).await
+    }
+
+    async fn release_job(&self, job_id: &str, cluster: Option<&str>) -> Result<()> {
+        self.scontrol_job("release", job_id, cluster).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::{Path, PathBuf};
+
+    fn gpus(entries: &[(&str, u32)]) -> Vec<(String, u32)> {
+        entries.iter().map(|(model, count)| (model.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn gpu_gres_with_mig_profiles() {
+        let gres = SlurmScheduler::parse_gpu_gres("gpu:a100:2(S:0-1),gpu:a100_3g.20gb:4(IDX:0,2-4)");
+        assert_eq!(gres, gpus(&[("a100", 2), ("a100_3g.20gb", 4)]));
+    }
+
+    #[test]
+    fn untyped_gpu_gres() {
+        assert_eq!(SlurmScheduler::parse_gpu_gres("gpu:2"), gpus(&[("gpu", 2)]));
+        // Other GRES and an empty value count no GPUs
+        assert_eq!(SlurmScheduler::parse_gpu_gres("gpu:2,nvme:1"), gpus(&[("gpu", 2)]));
+        assert!(SlurmScheduler::parse_gpu_gres("(null)").is_empty());
+    }
+
+    #[test]
+    fn tres_gpus() {
+        let tres = SlurmScheduler::parse_tres_gpus("cpu=16,mem=64G,gres/gpu=2,gres/gpu:a100=2");
+        assert_eq!(tres, gpus(&[("gpu", 2), ("a100", 2)]));
+        assert!(SlurmScheduler::parse_tres_gpus("cpu=16,mem=64G,gres/gpumem=80G").is_empty());
+        // The untyped total wins over the typed counts
+        assert_eq!(SlurmScheduler::parse_gpu_request("cpu=16,gres/gpu=2,gres/gpu:a100=2"), (2, Some("a100".to_string())));
+        assert_eq!(SlurmScheduler::parse_gpu_request("cpu=16,gres/gpu:v100=1,gres/gpu:a100=2"), (3, Some("v100".to_string())));
+    }
+
+    /// A directory for fake commands, removed with it.
+    struct FixtureDir(PathBuf);
+
+    impl Drop for FixtureDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Put `script` in `dir` as `name`; each run leaves its arguments in `<name>.args` beside
+    /// it, one per line.
+    fn fake_command(dir: &Path, name: &str, script: &str) -> PathBuf {
+        let path = dir.join(name);
+        let args = dir.join(format!("{}.args", name));
+        std::fs::write(&path, format!("#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\n{}\n", args.display(), script)).unwrap();
+        std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        args
+    }
+
+    #[tokio::test]
+    async fn running_jobs() {
+        let fixture = FixtureDir(std::env::temp_dir().join(format!("nodestat-slurm-{}", std::process::id())));
+        let dir = &fixture.0;
+        std::fs::create_dir_all(dir).unwrap();
+        let slurm = |config: SlurmConfig| {
+            let transport = Transport::local().with_bin_dir(dir);
+            SlurmScheduler::new(ClusterTz::Named(chrono_tz::UTC), transport, SlurmClusters::Local, config)
+        };
+        let sacct = fake_command(
+            dir,
+            "sacct",
+            "echo 'Partition|NodeList|JobID|User|JobName|State|ReqNodes|ReqCPUS|ReqMem|Timelimit|Elapsed|TotalCPU|Submit|ReqTRES|WorkDir|'
+echo 'batch|c001|1001|alice|run|RUNNING|1|8|8G|1-00:00:00|02:00:00|01:00:00|2024-03-01T10:00:00|cpu=8|/home/alice|'",
+        );
+
+        // Accounting, in a window
+        let config = SlurmConfig { jobs_source: JobsSource::Sacct, ..SlurmConfig::default() };
+        let jobs = slurm(config.clone()).get_jobs("batch").await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        let sent = std::fs::read_to_string(&sacct).unwrap();
+        assert!(sent.ends_with("-p\n-S\nnow-1day\n-s\nR\n"), "{}", sent);
+        let week = SlurmConfig { jobs_since: "now-7days".to_string(), job_states: String::new(), ..config };
+        slurm(week).get_jobs("batch").await.unwrap();
+        assert!(std::fs::read_to_string(&sacct).unwrap().ends_with("-p\n-S\nnow-7days\n"));
+
+        // The controller first
+        let squeue = fake_command(
+            dir,
+            "squeue",
+            "echo '1002|bob|train|batch|2|16|64G|2-00:00:00|2024-03-01T09:00:00|c[002-003]|1-02:03:04|gres/gpu:a100:2|/home/bob/train'",
+        );
+        let jobs = slurm(SlurmConfig::default()).get_jobs("batch").await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        let job = &jobs[0];
+        assert_eq!((job.id.as_str(), job.user.as_str(), job.state.clone()), ("1002", "bob", JobState::Running));
+        assert_eq!(job.node_list, ["c002", "c003"]);
+        assert_eq!((job.req_cpus, job.req_mem, job.req_gpus, job.gpu_type.as_deref()), (16, 128 * GB, 4, Some("a100")));
+        assert_eq!(job.elapsed, Duration::seconds(93784));
+        assert_eq!(job.work_dir.as_deref(), Some("/home/bob/train"));
+        assert!(std::fs::read_to_string(&squeue).unwrap().starts_with("-h\n-t\nRUNNING,COMPLETING\n-p\nbatch\n"));
+
+        // Then accounting, if the controller can't be asked
+        fake_command(dir, "squeue", "echo 'slurm_load_jobs error: Socket timed out' >&2; exit 1");
+        let jobs = slurm(SlurmConfig::default()).get_jobs("batch").await.unwrap();
+        assert_eq!(jobs[0].id, "1001");
+        let squeue_only = SlurmConfig { jobs_source: JobsSource::Squeue, ..SlurmConfig::default() };
+        let error = slurm(squeue_only).get_jobs("batch").await.unwrap_err();
+        assert_eq!(error.to_string(), "squeue command failed: slurm_load_jobs error: Socket timed out\n");
+    }
+}
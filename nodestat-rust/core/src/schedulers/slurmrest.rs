@@ -0,0 +1,396 @@
+use crate::hostlist;
+use crate::models::*;
+use crate::schedulers::{Scheduler, SlurmScheduler};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use serde_json::{json, Value};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+const DEFAULT_URL: &str = "http://localhost:6820";
+const DEFAULT_API_VERSION: &str = "v0.0.40";
+
+/// Slurm via the `slurmrestd` REST API, so no Slurm client binaries are needed.
+///
+/// Configured with `SLURMRESTD_URL`, `SLURMRESTD_API_VERSION`, `SLURM_JWT`
+/// (from `scontrol token`) and `SLURM_USER_NAME` (defaults to `$USER`).
+pub struct SlurmRestScheduler {
+    client: reqwest::Client,
+    base_url: String,
+    api_version: String,
+    user: String,
+    token: String,
+}
+
+impl Default for SlurmRestScheduler {
+    /// Configured from the environment, like [`SlurmRestScheduler::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SlurmRestScheduler {
+    /// Reads `SLURMRESTD_URL`, `SLURMRESTD_API_VERSION`, `SLURM_JWT` and `SLURM_USER_NAME`.
+    pub fn new() -> Self {
+        let env = |key: &str, default: &str| std::env::var(key).unwrap_or_else(|_| default.to_string());
+
+        Self {
+            client: reqwest::Client::new(),
+            base_url: env("SLURMRESTD_URL", DEFAULT_URL).trim_end_matches('/').to_string(),
+            api_version: env("SLURMRESTD_API_VERSION", DEFAULT_API_VERSION),
+            user: std::env::var("SLURM_USER_NAME").unwrap_or_else(|_| env("USER", "")),
+            token: env("SLURM_JWT", ""),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/slurm/{}/{}", self.base_url, self.api_version, path)
+    }
+
+    fn request(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        builder
+            .header("X-SLURM-USER-NAME", &self.user)
+            .header("X-SLURM-USER-TOKEN", &self.token)
+    }
+
+    /// Surface the API's own error messages rather than a bare HTTP status.
+    async fn check(response: reqwest::Response, path: &str) -> Result<Value> {
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse slurmrestd response for {}", path))?;
+
+        let errors: Vec<&str> = body["errors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e["description"].as_str().or(e["error"].as_str()))
+            .collect();
+
+        if !status.is_success() || !errors.is_empty() {
+            return Err(anyhow::anyhow!(
+                "slurmrestd request {} failed ({}): {}",
+                path,
+                status,
+                errors.join("; ")
+            ));
+        }
+
+        Ok(body)
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        let response = self
+            .request(self.client.get(self.url(path)))
+            .send()
+            .await
+            .with_context(|| format!("Failed to connect to slurmrestd at {}", self.base_url))?;
+        Self::check(response, path).await
+    }
+
+    async fn update_nodes(&self, node_ids: &[String], update: Value) -> Result<()> {
+        for node in node_ids {
+            let path = format!("node/{}", node);
+            let response = self
+                .request(self.client.post(self.url(&path)))
+                .json(&update)
+                .send()
+                .await
+                .with_context(|| format!("Failed to connect to slurmrestd at {}", self.base_url))?;
+            Self::check(response, &path).await?;
+        }
+        Ok(())
+    }
+
+    async fn update_job(&self, job_id: &str, update: Value) -> Result<()> {
+        let path = format!("job/{}", job_id);
+        let response = self
+            .request(self.client.post(self.url(&path)))
+            .json(&update)
+            .send()
+            .await
+            .with_context(|| format!("Failed to connect to slurmrestd at {}", self.base_url))?;
+        Self::check(response, &path).await?;
+        Ok(())
+    }
+
+    /// Numbers are either plain or `{"set": true, "infinite": false, "number": n}`.
+    fn number(value: &Value) -> Option<f64> {
+        match value {
+            Value::Number(n) => n.as_f64(),
+            Value::Object(o) if o.get("set").and_then(Value::as_bool) == Some(true) => {
+                o.get("number").and_then(Value::as_f64)
+            }
+            _ => None,
+        }
+    }
+
+    fn u32_of(value: &Value) -> u32 {
+        Self::number(value).unwrap_or(0.0) as u32
+    }
+
+    /// Memory, reported in MB, in bytes.
+    fn mem_of(value: &Value) -> u64 {
+        (Self::number(value).unwrap_or(0.0) * MB as f64) as u64
+    }
+
+    fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+        let secs = Self::number(value).filter(|t| *t > 0.0)?;
+        Utc.timestamp_opt(secs as i64, 0).single()
+    }
+
+    /// States come as a list of base state plus flags, e.g. `["IDLE", "DRAIN"]`.
+    fn strings(value: &Value) -> Vec<String> {
+        match value {
+            Value::Array(items) => items.iter().filter_map(Value::as_str).map(str::to_uppercase).collect(),
+            Value::String(s) => vec![s.to_uppercase()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_job_state(states: &[String]) -> JobState {
+        match states.first().map(String::as_str) {
+            Some("RUNNING") | Some("COMPLETING") => JobState::Running,
+            Some("PENDING") | Some("REQUEUED") => JobState::Pending,
+            Some("COMPLETED") => JobState::Completed,
+            Some("CANCELLED") => JobState::Cancelled,
+            Some("TIMEOUT") | Some("DEADLINE") => JobState::Timeout,
+            Some("OUT_OF_MEMORY") => JobState::OutOfMemory,
+            Some("PREEMPTED") => JobState::Preempted,
+            Some("SUSPENDED") => JobState::Suspended,
+            Some("NODE_FAIL") => JobState::NodeFail,
+            _ => JobState::Failed,
+        }
+    }
+
+    fn parse_node(info: &Value, partition: &str) -> Option<Node> {
+        let partitions: Vec<String> = info["partitions"]
+            .as_array()
+            .map(|p| p.iter().filter_map(Value::as_str).map(String::from).collect())
+            .unwrap_or_default();
+        if !partitions.iter().any(|p| p == partition) {
+            return None;
+        }
+
+        let mut gpus: Vec<GpuGres> = SlurmScheduler::parse_gpu_gres(info["gres"].as_str().unwrap_or(""))
+            .into_iter()
+            .map(|(model, total)| GpuGres { model, total, used: 0 })
+            .collect();
+        for (model, used) in SlurmScheduler::parse_gpu_gres(info["gres_used"].as_str().unwrap_or("")) {
+            if let Some(gpu) = gpus.iter_mut().find(|g| g.model == model) {
+                gpu.used = used;
+            }
+        }
+
+        let reason = info["reason"].as_str().unwrap_or("");
+        let (state, state_flags) = SlurmScheduler::parse_node_state(&Self::strings(&info["state"]));
+
+        Some(Node {
+            id: info["name"].as_str()?.to_string(),
+            state,
+            total_cores: Self::u32_of(&info["cpus"]),
+            used_cores: Self::u32_of(&info["alloc_cpus"]),
+            total_mem: Self::mem_of(&info["real_memory"]),
+            used_mem: Self::mem_of(&info["alloc_memory"]),
+            partitions,
+            jobs: Vec::new(),
+            job_users: Vec::new(),
+            reason: (!reason.is_empty()).then(|| reason.to_string()),
+            // Reported in hundredths
+            cpu_load: Self::number(&info["cpu_load"]).map(|load| load / 100.0),
+            current_watts: Self::number(&info["energy"]["current_watts"]).map(|w| w as u32).filter(|w| *w > 0),
+            consumed_joules: Self::number(&info["energy"]["consumed_energy"]).map(|j| j as u64).filter(|j| *j > 0),
+            os: info["operating_system"].as_str().filter(|os| !os.is_empty()).map(String::from),
+            gpus,
+            // A list in recent API versions, a comma-separated string in older ones
+            features: match info["active_features"] {
+                Value::Array(ref items) => items.iter().filter_map(Value::as_str).map(String::from).collect(),
+                Value::String(ref list) => SlurmScheduler::parse_features(list),
+                _ => Vec::new(),
+            },
+            state_flags,
+            cluster: None,
+        })
+    }
+
+    fn parse_job(info: &Value) -> Job {
+        let node_list = hostlist::expand(info["nodes"].as_str().unwrap_or(""));
+        let start_time = Self::timestamp(&info["start_time"]);
+        let state = Self::parse_job_state(&Self::strings(&info["job_state"]));
+        let end_time = match state {
+            JobState::Running => Utc::now(),
+            _ => Self::timestamp(&info["end_time"]).unwrap_or_else(Utc::now),
+        };
+        let req_nodes = Self::u32_of(&info["node_count"]).max(1);
+        let req_cpus = Self::u32_of(&info["cpus"]).max(1);
+
+        // Memory is requested either per node or per CPU
+        let req_mem = match Self::number(&info["memory_per_node"]) {
+            Some(_) => Self::mem_of(&info["memory_per_node"]) * req_nodes as u64,
+            None => Self::mem_of(&info["memory_per_cpu"]) * req_cpus as u64,
+        };
+        let (req_gpus, gpu_type) = SlurmScheduler::parse_gpu_request(info["tres_req_str"].as_str().unwrap_or(""));
+        let pending = state == JobState::Pending;
+        let reason = info["state_reason"].as_str().filter(|r| pending && *r != "None");
+        let elapsed = start_time
+            .filter(|t| *t <= end_time)
+            .map(|t| end_time - t)
+            .unwrap_or_else(Duration::zero);
+
+        Job {
+            id: Self::u32_of(&info["job_id"]).to_string(),
+            user: info["user_name"].as_str().unwrap_or("").to_string(),
+            name: info["name"].as_str().unwrap_or("").to_string(),
+            state,
+            node_list: if node_list.is_empty() { vec!["?".to_string()] } else { node_list },
+            partition: info["partition"].as_str().unwrap_or("").to_string(),
+            req_nodes,
+            req_cpus,
+            req_mem,
+            req_gpus,
+            gpu_type,
+            reason: reason.map(String::from),
+            // The API has the total only; sprio's breakdown isn't exposed
+            priority: pending.then(|| JobPriority {
+                total: Self::number(&info["priority"]).unwrap_or(0.0) as u64,
+                ..JobPriority::default()
+            }),
+            // For pending jobs, start_time is the backfill scheduler's estimate
+            est_start: start_time.filter(|_| pending),
+            // time_limit is in minutes
+            time_limit: Duration::minutes(Self::number(&info["time_limit"]).unwrap_or(0.0) as i64),
+            elapsed,
+            // The controller doesn't track CPU time
+            cpu_time: Duration::zero(),
+            submit_time: Self::timestamp(&info["submit_time"]).unwrap_or_else(Utc::now),
+            work_dir: info["current_working_directory"].as_str().filter(|d| !d.is_empty()).map(String::from),
+            cluster: None,
+        }
+    }
+
+    async fn all_jobs(&self) -> Result<Vec<Job>> {
+        let body = self.get("jobs").await?;
+        Ok(body["jobs"]
+            .as_array()
+            .map(|jobs| jobs.iter().map(Self::parse_job).collect())
+            .unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl Scheduler for SlurmRestScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let body = self.get("nodes").await?;
+        let nodes: Vec<Node> = body["nodes"]
+            .as_array()
+            .map(|nodes| nodes.iter().filter_map(|n| Self::parse_node(n, partition)).collect())
+            .unwrap_or_default();
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No nodes found in partition: {}", partition));
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        Ok(self
+            .all_jobs()
+            .await?
+            .into_iter()
+            .filter(|job| job.partition == partition && job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        Ok(self
+            .all_jobs()
+            .await?
+            .into_iter()
+            .filter(|job| job.user == current_user && job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        let body = self.get("jobs").await?;
+        let mut pending: Vec<Job> = body["jobs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(Self::parse_job)
+            .filter(|job| job.partition == partition && job.state == JobState::Pending)
+            .collect();
+        // Highest priority first, like squeue --sort=-p
+        pending.sort_by_key(|job| std::cmp::Reverse(job.priority.as_ref().map_or(0, |p| p.total)));
+        Ok(pending)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let body = self.get("partitions").await?;
+        let mut partitions = Vec::new();
+        let mut default = None;
+
+        for partition in body["partitions"].as_array().into_iter().flatten() {
+            let Some(name) = partition["name"].as_str() else { continue };
+            if Self::strings(&partition["flags"]).iter().any(|f| f == "DEFAULT") {
+                default = Some(name.to_string());
+            }
+            partitions.push(name.to_string());
+        }
+
+        if let Some(pos) = default.and_then(|d| partitions.iter().position(|p| *p == d)) {
+            let default = partitions.remove(pos);
+            partitions.insert(0, default);
+        }
+
+        Ok(partitions)
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        // The controller still knows recently finished jobs (MinJobAge)
+        let mut jobs = Vec::new();
+        for id in job_ids {
+            if let Ok(body) = self.get(&format!("job/{}", id)).await {
+                jobs.extend(body["jobs"].as_array().into_iter().flatten().map(Self::parse_job));
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn get_server_time(&self) -> Result<Option<DateTime<Utc>>> {
+        let body = self.get("diag").await?;
+        Ok(Self::timestamp(&body["statistics"]["req_time"]))
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.update_nodes(node_ids, json!({ "reason": reason })).await
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.update_nodes(node_ids, json!({ "state": ["DRAIN"], "reason": reason })).await
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        self.update_nodes(node_ids, json!({ "state": ["RESUME"] })).await
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        let path = format!("job/{}", job_id);
+        let response = self
+            .request(self.client.delete(self.url(&path)))
+            .send()
+            .await
+            .with_context(|| format!("Failed to connect to slurmrestd at {}", self.base_url))?;
+        Self::check(response, &path).await?;
+        Ok(())
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.update_job(job_id, json!({ "hold": true })).await
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.update_job(job_id, json!({ "hold": false })).await
+    }
+}
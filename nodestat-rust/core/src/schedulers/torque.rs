@@ -0,0 +1,723 @@
+use crate::models::*;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{Result, Context};
+use std::collections::HashMap;
+use std::env;
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Utc};
+use crate::clock::ClusterTz;
+
+/// What `pbsnodes` knows about a node beyond `mdiag`.
+struct PbsNodeExtras {
+    gpu: GpuGres,
+    properties: Vec<String>,
+    /// `loadave` from the MOM's `status`
+    load: Option<f64>,
+    /// `uname` from the MOM's `status`
+    os: Option<String>,
+}
+
+impl Default for PbsNodeExtras {
+    fn default() -> Self {
+        Self {
+            gpu: GpuGres { model: "gpu".to_string(), total: 0, used: 0 },
+            properties: Vec::new(),
+            load: None,
+            os: None,
+        }
+    }
+}
+
+pub struct TorqueScheduler {
+    tz: ClusterTz,
+    transport: Transport,
+}
+
+impl TorqueScheduler {
+    pub fn new(tz: ClusterTz, transport: Transport) -> Self {
+        Self { tz, transport }
+    }
+
+    /// `pbsnodes <args> <nodes>`.
+    async fn run_pbsnodes(&self, args: &[&str], node_ids: &[String]) -> Result<()> {
+        let output = self.transport.command("pbsnodes")
+            .args(args)
+            .args(node_ids)
+            .output().await
+            .context("Failed to execute pbsnodes command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "pbsnodes command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `qdel`, `qhold` or `qrls` on one job.
+    async fn run_job_command(&self, program: &str, job_id: &str) -> Result<()> {
+        let output = self.transport.command(program)
+            .arg(job_id)
+            .output().await
+            .with_context(|| format!("Failed to execute {} command", program))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} command failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Parse qstat's ctime-style timestamps (`Tue Jan 30 12:34:56 2024`) in the server's local time.
+    fn parse_timestamp(&self, time_str: &str) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(time_str.trim(), "%a %b %e %H:%M:%S %Y").ok()?;
+        self.tz.localize(&naive)
+    }
+
+    fn parse_node_state(state_str: &str) -> NodeState {
+        match state_str.to_uppercase().as_str() {
+            "IDLE" => NodeState::Idle,
+            "BUSY" => NodeState::Busy,
+            "DOWN" | "DOWN*" => NodeState::Down,
+            "OFFLINE" => NodeState::Offline,
+            "DRAINED" => NodeState::Drained,
+            _ => NodeState::Offline,
+        }
+    }
+
+    fn parse_job_state(state_str: &str) -> JobState {
+        match state_str {
+            "R" => JobState::Running,
+            "Q" | "H" | "W" => JobState::Pending,
+            "S" => JobState::Suspended,
+            "C" => JobState::Completed,
+            "E" => JobState::Failed,
+            _ => JobState::Failed,
+        }
+    }
+
+    fn parse_duration(time_str: &str) -> Duration {
+        // Parse time in format HH:MM:SS
+        let parts: Vec<&str> = time_str.split(':').collect();
+        if parts.len() >= 3 {
+            let hours: i64 = parts[0].parse().unwrap_or(0);
+            let minutes: i64 = parts[1].parse().unwrap_or(0);
+            let seconds: i64 = parts[2].parse().unwrap_or(0);
+            Duration::seconds(hours * 3600 + minutes * 60 + seconds)
+        } else {
+            Duration::seconds(0)
+        }
+    }
+
+    /// Torque sizes like `4gb` or `512mb`, in bytes.
+    fn parse_size(size: &str) -> Option<u64> {
+        let size = size.trim().to_lowercase();
+        let split = size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len());
+        let (number, unit) = size.split_at(split);
+        let factor = match unit {
+            "kb" => MB / 1000,
+            "mb" => MB,
+            "gb" => GB,
+            "tb" => TB,
+            "b" | "" => 1,
+            _ => return None,
+        };
+        Some(number.parse::<u64>().ok()? * factor)
+    }
+
+    /// `qstat -Q -f`: a `Queue: <name>` line followed by indented `key = value` attributes.
+    /// Torque has no per-CPU memory, so the per-process `pmem` stands in for it.
+    fn parse_queues(output_str: &str) -> Vec<Partition> {
+        let mut queues: Vec<(String, HashMap<String, String>)> = Vec::new();
+        for line in output_str.lines() {
+            if let Some(name) = line.strip_prefix("Queue:") {
+                queues.push((name.trim().to_string(), HashMap::new()));
+            } else if let (Some((_, attributes)), Some((key, value))) = (queues.last_mut(), line.split_once(" = ")) {
+                attributes.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+
+        queues
+            .into_iter()
+            .filter(|(_, attributes)| attributes.get("queue_type").is_some_and(|t| t == "Execution"))
+            .map(|(name, attributes)| {
+                let get = |key: &str| attributes.get(key).map(String::as_str);
+                let enabled = get("enabled") == Some("True") && get("started") == Some("True");
+                let groups = if get("acl_group_enable") == Some("True") {
+                    get("acl_groups").map(|g| g.split(',').map(str::to_string).collect()).unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
+                Partition {
+                    name,
+                    state: if enabled { "UP" } else { "DOWN" }.to_string(),
+                    is_default: false,
+                    max_time: get("resources_max.walltime").map(Self::parse_duration),
+                    default_mem_per_cpu: get("resources_default.pmem").and_then(Self::parse_size),
+                    max_mem_per_cpu: get("resources_max.pmem").and_then(Self::parse_size),
+                    total_nodes: get("resources_available.nodect").and_then(|n| n.parse().ok()).unwrap_or(0),
+                    allow_accounts: groups,
+                    deny_accounts: Vec::new(),
+                    cluster: None,
+                }
+            })
+            .collect()
+    }
+
+    /// GPUs, properties, load and OS per node from `pbsnodes -a`. GPUs are the `gpus = N` count, used
+    /// being every device in `gpu_status` whose `gpu_state` isn't `Unallocated`.
+    fn parse_pbsnodes(output_str: &str) -> HashMap<String, PbsNodeExtras> {
+        let mut extras: HashMap<String, PbsNodeExtras> = HashMap::new();
+        let mut node_id = String::new();
+
+        for line in output_str.lines() {
+            if !line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                node_id = line.trim().to_string();
+                continue;
+            }
+            let Some((key, value)) = line.trim().split_once(" = ") else {
+                continue;
+            };
+            let node = extras.entry(node_id.clone()).or_default();
+            match key {
+                "gpus" => node.gpu.total = value.parse().unwrap_or(0),
+                "gpu_status" => {
+                    node.gpu.used = value
+                        .split(';')
+                        .filter_map(|field| field.split_once("gpu_state="))
+                        .filter(|(_, state)| !state.starts_with("Unallocated"))
+                        .count() as u32;
+                },
+                "status" => {
+                    node.load = value
+                        .split(',')
+                        .find_map(|field| field.strip_prefix("loadave="))
+                        .and_then(|load| load.parse().ok());
+                    node.os = value
+                        .split(',')
+                        .find_map(|field| field.strip_prefix("uname="))
+                        .map(str::to_string);
+                },
+                "properties" => node.properties = value.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect(),
+                _ => {}
+            }
+        }
+
+        extras
+    }
+
+    fn parse_node_info(node_info: &str, partition: &str) -> Option<Node> {
+        // Clean up excessive spaces (from Python: for x in range(30,1, -1): node_info = node_info.replace(" "*x, " "))
+        let mut cleaned_info = node_info.to_string();
+        for x in (2..=30).rev() {
+            cleaned_info = cleaned_info.replace(&" ".repeat(x), " ");
+        }
+
+        let fields: Vec<&str> = cleaned_info.split_whitespace().collect();
+        if fields.len() < 4 {
+            return None;
+        }
+
+        // Check if this node belongs to the partition
+        if !node_info.contains(&format!("[{}]", partition)) || node_info.contains(&format!("[{}][", partition)) {
+            return None;
+        }
+
+        let id = fields[0].to_string();
+        let state = Self::parse_node_state(fields[1]);
+        
+        // Parse CPU info (format: "available:total")
+        let cpu_info: Vec<&str> = fields[2].split(':').collect();
+        let total_cores = cpu_info.get(1)?.parse::<u32>().ok()?;
+        let available_cores = cpu_info.first()?.parse::<u32>().ok()?;
+        let used_cores = total_cores.saturating_sub(available_cores);
+
+        // Parse memory info (format: "available:total" in MB)
+        let mem_info: Vec<&str> = fields[3].split(':').collect();
+        let total_mem = mem_info.get(1)?.parse::<u64>().ok()? * MB;
+        let available_mem = mem_info.first()?.parse::<u64>().ok()? * MB;
+        let used_mem = total_mem.saturating_sub(available_mem);
+
+        Some(Node {
+            id,
+            state,
+            total_cores,
+            used_cores,
+            total_mem,
+            used_mem,
+            jobs: Vec::new(),
+            job_users: Vec::new(),
+            partitions: vec![partition.to_string()],
+            reason: None,
+            cpu_load: None,
+            current_watts: None,
+            consumed_joules: None,
+            os: None,
+            gpus: Vec::new(),
+            features: Vec::new(),
+            state_flags: Vec::new(),
+            cluster: None,
+        })
+    }
+
+    fn parse_job_info(&self, job_text: &str) -> Option<Job> {
+        let mut job_id = String::new();
+        let mut name = String::new();
+        let mut owner = String::new();
+        let mut cpu_time = "00:00:00".to_string();
+        let mut wall_time = "00:00:00".to_string();
+        let mut req_time = "00:00:00".to_string();
+        let mut state = String::new();
+        let mut req_mem = "1gb".to_string();
+        let mut req_cpu = "1".to_string();
+        let mut req_gpus = 0;
+        let mut node_id = "?".to_string();
+        let mut queue_time = None;
+
+        for line in job_text.lines() {
+            if line.contains("Job Id:") {
+                if let Some(pos) = line.find(':') {
+                    job_id = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("Job_Name =") {
+                if let Some(pos) = line.find('=') {
+                    name = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("Job_Owner =") {
+                if let Some(pos) = line.find('=') {
+                    let line_part = &line[pos + 2..];
+                    if let Some(at_pos) = line_part.find('@') {
+                        owner = line_part[..at_pos].to_string();
+                    }
+                }
+            } else if line.contains("resources_used.cput =") {
+                if let Some(pos) = line.find('=') {
+                    cpu_time = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("resources_used.walltime =") {
+                if let Some(pos) = line.find('=') {
+                    wall_time = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("Resource_List.walltime =") {
+                if let Some(pos) = line.find('=') {
+                    req_time = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("job_state =") {
+                if let Some(pos) = line.find('=') {
+                    state = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("Resource_List.mem =") {
+                if let Some(pos) = line.find('=') {
+                    req_mem = line[pos + 2..].trim().to_string();
+                }
+            } else if line.contains("Resource_List.nodes =") {
+                if let Some(pos) = line.find('=') {
+                    let line_part = &line[pos + 2..];
+                    if line_part.contains(':') {
+                        if let Some(eq_pos) = line_part.find('=') {
+                            let cpu_part = &line_part[eq_pos + 1..];
+                            if let Some(colon_pos2) = cpu_part.find(':') {
+                                req_cpu = cpu_part[..colon_pos2].to_string();
+                            } else {
+                                req_cpu = cpu_part.trim().to_string();
+                            }
+                        }
+                    }
+
+                    // e.g. "2:ppn=8:gpus=2", with GPUs per node
+                    let node_count = line_part.split(':').next().and_then(|n| n.trim().parse::<u32>().ok()).unwrap_or(1);
+                    if let Some(gpus) = line_part.split(':').find_map(|p| p.trim().strip_prefix("gpus=")) {
+                        req_gpus = gpus.parse::<u32>().unwrap_or(0) * node_count;
+                    }
+                }
+            } else if line.contains("qtime =") {
+                if let Some(pos) = line.find('=') {
+                    queue_time = self.parse_timestamp(&line[pos + 2..]);
+                }
+            } else if line.contains("exec_host =") {
+                if let Some(pos) = line.find('=') {
+                    let line_part = &line[pos + 2..];
+                    if let Some(slash_pos) = line_part.find('/') {
+                        node_id = line_part[..slash_pos].to_string();
+                    }
+                }
+            }
+        }
+
+        if job_id.is_empty() {
+            return None;
+        }
+
+        // Parse memory (remove 'gb' and convert to number, in bytes)
+        let memory_str = req_mem.to_lowercase().replace("gb", "").replace("mb", "");
+        let memory = if req_mem.to_lowercase().contains("gb") {
+            memory_str.parse::<u64>().unwrap_or(1) * GB
+        } else {
+            memory_str.parse::<u64>().unwrap_or(1000) * MB
+        };
+        
+        Some(Job {
+            id: job_id,
+            user: owner,
+            name,
+            state: Self::parse_job_state(&state),
+            node_list: vec![node_id],
+            partition: "default".to_string(), // Torque doesn't use partitions like SLURM
+            req_nodes: 1,
+            req_cpus: req_cpu.parse().unwrap_or(1),
+            req_mem: memory,
+            req_gpus,
+            gpu_type: None,
+            reason: None,
+            priority: None,
+            est_start: None,
+            time_limit: Self::parse_duration(&req_time),
+            elapsed: Self::parse_duration(&wall_time),
+            cpu_time: Self::parse_duration(&cpu_time),
+            submit_time: queue_time.unwrap_or_else(Utc::now),
+            work_dir: None,
+            cluster: None,
+        })
+    }
+
+    /// Moab durations: `[-][[D:]HH:]MM:SS`, e.g. `1:00:00:00` for a day.
+    fn parse_moab_duration(time_str: &str) -> Duration {
+        let parts: Vec<i64> = time_str.trim_start_matches('-').split(':').filter_map(|p| p.parse().ok()).collect();
+        let multipliers = [1, 60, 3600, 86400];
+        let seconds: i64 = parts.iter().rev().zip(multipliers).map(|(value, unit)| value * unit).sum();
+        Duration::seconds(seconds)
+    }
+
+    /// `showres` start times omit the year (`Sat Mar  2 08:00:00`); pick the one closest to now.
+    fn parse_showres_time(&self, time_str: &str) -> Option<DateTime<Utc>> {
+        let now = Utc::now();
+        [now.year(), now.year() + 1, now.year() - 1]
+            .iter()
+            .filter_map(|year| self.parse_timestamp(&format!("{} {}", time_str, year)))
+            .min_by_key(|t| (*t - now).num_seconds().abs())
+    }
+
+    /// Parse `showres -n`, one line per node and reservation; job reservations are skipped.
+    fn parse_showres(&self, output_str: &str) -> Vec<Reservation> {
+        let mut reservations: Vec<Reservation> = Vec::new();
+
+        for line in output_str.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // NodeName Type ReservationID JobState Task Start Duration StartTime...
+            if fields.len() < 8 || fields[0] == "NodeName" || fields[1] == "Job" {
+                continue;
+            }
+            let Some(start_time) = self.parse_showres_time(&fields[7..].join(" ")) else {
+                continue;
+            };
+
+            match reservations.iter_mut().find(|r| r.name == fields[2]) {
+                Some(reservation) => reservation.nodes.push(fields[0].to_string()),
+                None => reservations.push(Reservation {
+                    name: fields[2].to_string(),
+                    start_time,
+                    end_time: start_time + Self::parse_moab_duration(fields[6]),
+                    nodes: vec![fields[0].to_string()],
+                    users: Vec::new(),
+                    flags: Vec::new(),
+                    cluster: None,
+                }),
+            }
+        }
+
+        reservations
+    }
+
+    /// All jobs of a queue, with array jobs expanded into their tasks (`-t`).
+    async fn queue_jobs(&self, queue: &str) -> Result<Vec<Job>> {
+        let output = self.transport.command("qstat")
+            .args(["-f", "-t", queue])
+            .output().await
+            .context("Failed to execute qstat command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "qstat command failed: {}", 
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(self.parse_full_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Split `qstat -f` output into per-job records and parse each one.
+    fn parse_full_output(&self, output_str: &str) -> Vec<Job> {
+        let mut jobs = Vec::new();
+        let mut current_job_info = String::new();
+
+        for line in output_str.lines() {
+            if line.contains("Job Id:") {
+                if !current_job_info.is_empty() {
+                    if let Some(job) = self.parse_job_info(&current_job_info) {
+                        jobs.push(job);
+                    }
+                }
+                current_job_info = line.to_string();
+            } else {
+                current_job_info.push('\n');
+                current_job_info.push_str(line);
+            }
+        }
+
+        // Don't forget the last job
+        if !current_job_info.is_empty() {
+            if let Some(job) = self.parse_job_info(&current_job_info) {
+                jobs.push(job);
+            }
+        }
+
+        jobs
+    }
+}
+
+#[async_trait]
+impl Scheduler for TorqueScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        let output = self.transport.command("mdiag")
+            .args(["-n", "-v"])
+            .output().await
+            .context("Failed to execute mdiag command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "mdiag command failed: {}", 
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut nodes = Vec::new();
+
+        for line in output_str.lines() {
+            if let Some(node) = Self::parse_node_info(line, partition) {
+                nodes.push(node);
+            }
+        }
+
+        if nodes.is_empty() {
+            return Err(anyhow::anyhow!("No nodes found in partition: {}", partition));
+        }
+
+        // mdiag doesn't report GPUs, properties, load or the OS; take them from pbsnodes (don't fail on error)
+        if let Ok(output) = self.transport.command("pbsnodes").arg("-a").output().await {
+            let mut extras = Self::parse_pbsnodes(&String::from_utf8_lossy(&output.stdout));
+            for node in &mut nodes {
+                if let Some(extra) = extras.remove(&node.id) {
+                    if extra.gpu.total > 0 {
+                        node.gpus = vec![extra.gpu];
+                    }
+                    node.features = extra.properties;
+                    node.cpu_load = extra.load;
+                    node.os = extra.os;
+                }
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        // Only return running jobs
+        Ok(self.queue_jobs(partition).await?
+            .into_iter()
+            .filter(|job| job.state == JobState::Running)
+            .collect())
+    }
+
+    async fn get_pending_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        // qstat doesn't expose Moab's ordering; oldest first is the closest stand-in
+        let mut jobs: Vec<Job> = self.queue_jobs(partition).await?
+            .into_iter()
+            .filter(|job| job.state == JobState::Pending)
+            .collect();
+        jobs.sort_by_key(|job| job.submit_time);
+        Ok(jobs)
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        let output = self.transport.command("qstat")
+            .arg("-Q")
+            .output().await
+            .context("Failed to execute qstat command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "qstat command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        Ok(output_str
+            .lines()
+            .skip(2) // Skip header lines
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|name| name.to_string())
+            .collect())
+    }
+
+    async fn get_partitions(&self) -> Result<Vec<Partition>> {
+        let output = self.transport.command("qstat")
+            .args(["-Q", "-f"])
+            .output().await
+            .context("Failed to execute qstat command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "qstat command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(Self::parse_queues(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        let output = self.transport.command("qstat")
+            .arg("-f")
+            .args(job_ids)
+            .output().await
+            .context("Failed to execute qstat command")?;
+
+        // qstat exits non-zero when some IDs are unknown but still prints the rest
+        if !output.status.success() && output.stdout.is_empty() {
+            return Err(anyhow::anyhow!(
+                "qstat command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        Ok(self.parse_full_output(&output_str))
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = env::var("USER").unwrap_or_else(|_| user.to_string());
+        
+        let output = self.transport.command("qstat")
+            .args(["-u", &current_user])
+            .output().await
+            .context("Failed to execute qstat command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "qstat command failed: {}", 
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut jobs = Vec::new();
+
+        for line in output_str.lines().skip(2) { // Skip header lines
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() >= 6 && fields[4] == "R" { // Running jobs
+                let job = Job {
+                    id: fields[0].to_string(),
+                    user: fields[1].to_string(),
+                    name: fields[2].to_string(),
+                    state: JobState::Running,
+                    node_list: vec![fields.get(7).unwrap_or(&"?").to_string()],
+                    partition: "default".to_string(),
+                    req_nodes: 1,
+                    req_cpus: 1, // qstat doesn't show cores directly
+                    req_mem: GB, // qstat doesn't show memory directly
+                    req_gpus: 0,
+                    gpu_type: None,
+                    reason: None,
+                    priority: None,
+                    est_start: None,
+                    time_limit: Self::parse_duration(fields.get(8).unwrap_or(&"00:00:00")),
+                    elapsed: Self::parse_duration(fields.get(10).unwrap_or(&"00:00:00")),
+                    cpu_time: Duration::seconds(0),
+                    submit_time: Utc::now(),
+                    work_dir: None,
+                    cluster: None,
+                };
+                jobs.push(job);
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    async fn get_reservations(&self) -> Result<Vec<Reservation>> {
+        let output = self.transport.command("showres")
+            .arg("-n")
+            .output().await
+            .context("Failed to execute showres command")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "showres command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(self.parse_showres(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        self.run_pbsnodes(&["-N", reason], node_ids).await
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        // -o marks the nodes offline; running jobs carry on
+        self.run_pbsnodes(&["-o", "-N", reason], node_ids).await
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        self.run_pbsnodes(&["-c"], node_ids).await
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run_job_command("qdel", job_id).await
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run_job_command("qhold", job_id).await
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        self.run_job_command("qrls", job_id).await
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pbsnodes_gpu_status() {
+        let output = "\
+gpu01
+     state = free
+     properties = gpu,k80
+     status = rectime=1709294400,loadave=3.50,uname=Linux gpu01 3.10.0 x86_64
+     gpus = 4
+     gpu_status = gpu[3]=gpu_id=0000:84:00.0;gpu_state=Unallocated;gpu_utilization=0%,gpu[2]=gpu_id=0000:83:00.0;gpu_state=Exclusive;gpu_utilization=98%,gpu[1]=gpu_id=0000:04:00.0;gpu_state=Shared;gpu_utilization=40%,gpu[0]=gpu_id=0000:03:00.0;gpu_state=Unallocated;gpu_utilization=0%
+
+c001
+     state = free
+     properties = batch
+     gpus = 0
+";
+        let extras = TorqueScheduler::parse_pbsnodes(output);
+        let gpu01 = &extras["gpu01"];
+        assert_eq!((gpu01.gpu.total, gpu01.gpu.used), (4, 2));
+        assert_eq!(gpu01.properties, vec!["gpu", "k80"]);
+        assert_eq!(gpu01.load, Some(3.5));
+        assert_eq!(gpu01.os.as_deref(), Some("Linux gpu01 3.10.0 x86_64"));
+        assert_eq!((extras["c001"].gpu.total, extras["c001"].gpu.used), (0, 0));
+    }
+}
@@ -0,0 +1,234 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Duration;
+
+/// How long scheduler commands may run before they're killed, in seconds: `default` for every
+/// command, or a command's own, e.g. `sacct = 10`. 0 waits as long as it takes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    pub default: u64,
+    #[serde(flatten)]
+    pub commands: BTreeMap<String, u64>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self { default: 60, commands: BTreeMap::new() }
+    }
+}
+
+impl Timeouts {
+    /// The limit for `program`, if it has one.
+    pub fn of(&self, program: &str) -> Option<Duration> {
+        let secs = self.commands.get(program).copied().unwrap_or(self.default);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    /// The longest any command may run, if none may run as long as it takes.
+    pub fn longest(&self) -> Option<Duration> {
+        let mut secs = std::iter::once(&self.default).chain(self.commands.values());
+        secs.try_fold(0, |longest, &secs| (secs > 0).then(|| longest.max(secs))).map(Duration::from_secs)
+    }
+}
+
+/// Where scheduler commands run (on this machine, or on a login node over SSH), and how long
+/// they may take there.
+#[derive(Debug, Clone, Default)]
+pub struct Transport {
+    /// Uses the user's ssh config and agent; any ssh destination (`user@host`, an alias, ...)
+    host: Option<String>,
+    timeouts: Timeouts,
+    /// Where the commands are, rather than on PATH
+    bin_dir: Option<PathBuf>,
+}
+
+impl Transport {
+    pub fn local() -> Self {
+        Self::default()
+    }
+
+    pub fn ssh(host: impl Into<String>) -> Self {
+        Self { host: Some(host.into()), ..Self::default() }
+    }
+
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Run the commands from `dir` (on the login node, over SSH) instead of looking them up on PATH.
+    pub fn with_bin_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.bin_dir = Some(dir.into());
+        self
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.host.is_none()
+    }
+
+    pub fn command(&self, program: &str) -> TransportCommand {
+        TransportCommand {
+            transport: self.clone(),
+            program: program.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Whether `program` is installed where commands run.
+    pub async fn has_program(&self, program: &str) -> bool {
+        match (&self.host, &self.bin_dir) {
+            (None, Some(dir)) => dir.join(program).is_file(),
+            (None, None) => std::env::var_os("PATH")
+                .map(|paths| std::env::split_paths(&paths).any(|dir| Path::new(&dir).join(program).is_file()))
+                .unwrap_or(false),
+            (Some(_), _) => self
+                .command("sh")
+                .args(["-c", &format!("command -v {}", shell_quote(&self.path_of(program)))])
+                .output()
+                .await
+                .map(|output| output.status.success())
+                .unwrap_or(false),
+        }
+    }
+
+    /// `program` as it's run: in `bin_dir`, or as is for PATH to find.
+    fn path_of(&self, program: &str) -> String {
+        match &self.bin_dir {
+            // The shell `has_program` and `[exec]` commands run under is always the system's
+            Some(dir) if program != "sh" => dir.join(program).to_string_lossy().into_owned(),
+            _ => program.to_string(),
+        }
+    }
+}
+
+/// A command line for a [`Transport`], built like `std::process::Command` and run on tokio's
+/// process driver, so a slow `sacct` doesn't hold up the runtime's thread.
+pub struct TransportCommand {
+    transport: Transport,
+    program: String,
+    args: Vec<String>,
+}
+
+impl TransportCommand {
+    pub fn arg(mut self, arg: impl AsRef<str>) -> Self {
+        self.args.push(arg.as_ref().to_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.args.extend(args.into_iter().map(|a| a.as_ref().to_string()));
+        self
+    }
+
+    /// Run it to completion, or until its timeout kills it; so does dropping the future.
+    pub async fn output(&self) -> io::Result<Output> {
+        let (program, args) = self.command_line();
+        let output = tokio::process::Command::new(program).args(args).kill_on_drop(true).output();
+        let Some(limit) = self.transport.timeouts.of(&self.program) else {
+            return output.await;
+        };
+        match tokio::time::timeout(limit, output).await {
+            Ok(output) => output,
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("{} timed out after {}s", self.program, limit.as_secs()),
+            )),
+        }
+    }
+
+    /// Like [`TransportCommand::output`], timeout included, blocking the thread, for callers
+    /// that can't await (a WASM plugin's host calls, which run on a blocking thread).
+    pub fn output_blocking(&self) -> io::Result<Output> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime.block_on(self.output()),
+            Err(_) => tokio::runtime::Builder::new_current_thread().enable_all().build()?.block_on(self.output()),
+        }
+    }
+
+    /// The program to run here and its arguments.
+    fn command_line(&self) -> (String, Vec<String>) {
+        let program = self.transport.path_of(&self.program);
+        match self.transport.host {
+            None => (program, self.args.clone()),
+            Some(ref host) => {
+                // ssh hands the remote shell a single string, so every word must be quoted
+                let remote: Vec<String> = std::iter::once(&program)
+                    .chain(&self.args)
+                    .map(|word| shell_quote(word))
+                    .collect();
+
+                let mut args: Vec<String> = [
+                    "-o", "BatchMode=yes",
+                    // Reuse one connection across the many commands of a refresh
+                    "-o", "ControlMaster=auto",
+                    "-o", "ControlPath=~/.ssh/nodestat-%C",
+                    "-o", "ControlPersist=60",
+                    host.as_str(),
+                    "--",
+                ]
+                .map(String::from)
+                .to_vec();
+                args.push(remote.join(" "));
+                ("ssh".to_string(), args)
+            }
+        }
+    }
+}
+
+/// `word` as a single `sh` word, quoted only when it needs to be.
+pub fn shell_quote(word: &str) -> String {
+    if !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c)) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeouts(default: u64, commands: &[(&str, u64)]) -> Timeouts {
+        let commands = commands.iter().map(|(name, secs)| (name.to_string(), *secs)).collect();
+        Timeouts { default, commands }
+    }
+
+    #[test]
+    fn limits() {
+        let limits = timeouts(30, &[("sacct", 90), ("sleep", 10)]);
+        assert_eq!(limits.of("sleep"), Some(Duration::from_secs(10)));
+        assert_eq!(limits.of("squeue"), Some(Duration::from_secs(30)));
+        assert_eq!(limits.longest(), Some(Duration::from_secs(90)));
+        // 0 waits as long as it takes, so nothing does
+        assert_eq!(timeouts(30, &[("sacct", 0)]).of("sacct"), None);
+        assert_eq!(timeouts(30, &[("sacct", 0)]).longest(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn command_timeout() {
+        let transport = Transport::local().with_timeouts(timeouts(60, &[("sleep", 10)]));
+        let error = transport.command("sleep").arg("600").output().await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(error.to_string(), "sleep timed out after 10s");
+    }
+
+    #[tokio::test]
+    async fn blocking_command_timeout() {
+        let transport = Transport::local().with_timeouts(timeouts(60, &[("sleep", 1)]));
+        let command = transport.command("sleep").arg("600");
+        let error = tokio::task::spawn_blocking(move || command.output_blocking()).await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "sleep timed out after 1s");
+        // Outside a runtime too
+        let command = transport.command("sleep").arg("600");
+        let error = std::thread::spawn(move || command.output_blocking()).join().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,242 @@
+//! Scheduler adapters shipped as WebAssembly plugins in `~/.config/nodestat/plugins/`.
+//!
+//! A plugin is a `.wasm` (or `.wat`) module selected with `--scheduler <file stem>`.
+//! All values cross the boundary as UTF-8 JSON in the plugin's linear memory:
+//!
+//! - The plugin exports `memory` and `nodestat_alloc(len: i32) -> i32`, which the host
+//!   uses to place arguments and command output in plugin memory.
+//! - It exports `get_nodes`, `get_jobs`, `get_user_jobs` and `list_partitions`, and
+//!   optionally `get_jobs_by_id`, `update_node_reason`, `drain_nodes`, `resume_nodes`, `cancel_job`,
+//!   `hold_job` and `release_job`. Each takes `(ptr: i32, len: i32)`
+//!   pointing at a JSON argument and returns `i64` packed as `(ptr << 32) | len` pointing at
+//!   `{"ok": <result>}` or `{"error": "<message>"}`. Results use the `Node`/`Job` serde schema.
+//! - The host provides `nodestat.run_command(ptr: i32, len: i32) -> i64`, which runs (via `--host` if set)
+//!   `{"program": "...", "args": [...]}` and returns `{"status": 0, "stdout": "...", "stderr": "..."}`.
+
+use crate::models::*;
+use crate::paths::config_dir;
+use crate::schedulers::{Scheduler, Transport};
+use async_trait::async_trait;
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use wasmtime::{Caller, Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+const HOST_MODULE: &str = "nodestat";
+
+/// Plugins found in the plugin directory, as (name, path).
+pub fn discover_plugins() -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(config_dir().join("plugins")) else {
+        return Vec::new();
+    };
+
+    let mut plugins: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("wasm") | Some("wat")))
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+        .collect();
+    plugins.sort();
+    plugins
+}
+
+struct Plugin {
+    store: Store<Transport>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+}
+
+impl Plugin {
+    fn write(&mut self, bytes: &[u8]) -> Result<(i32, i32)> {
+        let ptr = self
+            .alloc
+            .call(&mut self.store, bytes.len() as i32)
+            .map_err(|e| anyhow!("Plugin nodestat_alloc trapped: {}", e))?;
+        self.memory.write(&mut self.store, ptr as usize, bytes)?;
+        Ok((ptr, bytes.len() as i32))
+    }
+
+    fn read(&self, packed: i64) -> Result<Vec<u8>> {
+        let (ptr, len) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+        let mut bytes = vec![0; len];
+        self.memory.read(&self.store, ptr, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn has_export(&mut self, name: &str) -> bool {
+        self.instance.get_func(&mut self.store, name).is_some()
+    }
+
+    fn call(&mut self, export: &str, arg: &Value) -> Result<Value> {
+        let func = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&mut self.store, export)
+            .map_err(|e| anyhow!("Plugin export {} is missing or has the wrong signature: {}", export, e))?;
+
+        let (ptr, len) = self.write(arg.to_string().as_bytes())?;
+        let packed = func
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| anyhow!("Plugin {} trapped: {}", export, e))?;
+
+        let response: Value = serde_json::from_slice(&self.read(packed)?)
+            .with_context(|| format!("Plugin {} returned invalid JSON", export))?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{}", error.as_str().unwrap_or("plugin error")));
+        }
+        Ok(response.get("ok").cloned().unwrap_or(Value::Null))
+    }
+}
+
+/// Host side of `nodestat.run_command`.
+fn run_command(mut caller: Caller<'_, Transport>, ptr: i32, len: i32) -> wasmtime::Result<i64> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| wasmtime::format_err!("plugin doesn't export memory"))?;
+    let alloc = caller
+        .get_export("nodestat_alloc")
+        .and_then(|e| e.into_func())
+        .ok_or_else(|| wasmtime::format_err!("plugin doesn't export nodestat_alloc"))?
+        .typed::<i32, i32>(&caller)?;
+
+    let mut request = vec![0; len as usize];
+    memory.read(&caller, ptr as usize, &mut request)?;
+    let request: Value = serde_json::from_slice(&request)?;
+
+    let program = request["program"].as_str().unwrap_or("");
+    let args: Vec<&str> = request["args"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+    let response = match caller.data().command(program).args(&args).output_blocking() {
+        Ok(output) => json!({
+            "status": output.status.code().unwrap_or(-1),
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+        }),
+        Err(e) => json!({ "status": -1, "stdout": "", "stderr": format!("Failed to execute {}: {}", program, e) }),
+    };
+
+    let bytes = response.to_string().into_bytes();
+    let out = alloc.call(&mut caller, bytes.len() as i32)?;
+    memory.write(&mut caller, out as usize, &bytes)?;
+    Ok(((out as u32 as i64) << 32) | bytes.len() as i64)
+}
+
+pub struct WasmScheduler {
+    /// Calls run on tokio's blocking pool: a plugin's `run_command` waits for its command there
+    plugin: Arc<Mutex<Plugin>>,
+}
+
+impl WasmScheduler {
+    pub fn load(path: &Path, transport: Transport) -> Result<Self> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path)
+            .map_err(|e| anyhow!("Failed to load plugin {}: {}", path.display(), e))?;
+
+        let mut linker = Linker::new(&engine);
+        linker
+            .func_wrap(HOST_MODULE, "run_command", run_command)
+            .map_err(|e| anyhow!("{}", e))?;
+
+        let mut store = Store::new(&engine, transport);
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| anyhow!("Failed to instantiate plugin {}: {}", path.display(), e))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("Plugin {} doesn't export memory", path.display()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "nodestat_alloc")
+            .map_err(|e| anyhow!("Plugin {} doesn't export nodestat_alloc: {}", path.display(), e))?;
+
+        Ok(Self {
+            plugin: Arc::new(Mutex::new(Plugin { store, instance, memory, alloc })),
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, export: &'static str, arg: Value) -> Result<T> {
+        let plugin = self.plugin.clone();
+        let result = tokio::task::spawn_blocking(move || plugin.lock().unwrap().call(export, &arg)).await??;
+        serde_json::from_value(result)
+            .with_context(|| format!("Plugin {} result doesn't match the expected schema", export))
+    }
+
+    fn has_export(&self, name: &str) -> bool {
+        self.plugin.lock().unwrap().has_export(name)
+    }
+}
+
+#[async_trait]
+impl Scheduler for WasmScheduler {
+    async fn get_nodes(&self, partition: &str) -> Result<Vec<Node>> {
+        self.call("get_nodes", json!({ "partition": partition })).await
+    }
+
+    async fn get_jobs(&self, partition: &str) -> Result<Vec<Job>> {
+        self.call("get_jobs", json!({ "partition": partition })).await
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> Result<Vec<Job>> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| user.to_string());
+        self.call("get_user_jobs", json!({ "user": current_user })).await
+    }
+
+    async fn list_partitions(&self) -> Result<Vec<String>> {
+        self.call("list_partitions", json!({})).await
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> Result<Vec<Job>> {
+        if !self.has_export("get_jobs_by_id") {
+            return Err(anyhow!("Looking up jobs by ID is not supported by this scheduler"));
+        }
+        self.call("get_jobs_by_id", json!({ "ids": job_ids })).await
+    }
+
+    async fn update_node_reason(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        if !self.has_export("update_node_reason") {
+            return Err(anyhow!("Editing node reasons is not supported by this scheduler"));
+        }
+        let _: Value = self.call("update_node_reason", json!({ "nodes": node_ids, "reason": reason })).await?;
+        Ok(())
+    }
+
+    async fn drain_nodes(&self, node_ids: &[String], reason: &str) -> Result<()> {
+        if !self.has_export("drain_nodes") {
+            return Err(anyhow!("Draining nodes is not supported by this scheduler"));
+        }
+        let _: Value = self.call("drain_nodes", json!({ "nodes": node_ids, "reason": reason })).await?;
+        Ok(())
+    }
+
+    async fn resume_nodes(&self, node_ids: &[String]) -> Result<()> {
+        if !self.has_export("resume_nodes") {
+            return Err(anyhow!("Resuming nodes is not supported by this scheduler"));
+        }
+        let _: Value = self.call("resume_nodes", json!({ "nodes": node_ids })).await?;
+        Ok(())
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        if !self.has_export("cancel_job") {
+            return Err(anyhow!("Cancelling jobs is not supported by this scheduler"));
+        }
+        let _: Value = self.call("cancel_job", json!({ "id": job_id })).await?;
+        Ok(())
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        if !self.has_export("hold_job") {
+            return Err(anyhow!("Holding jobs is not supported by this scheduler"));
+        }
+        let _: Value = self.call("hold_job", json!({ "id": job_id })).await?;
+        Ok(())
+    }
+
+    async fn release_job(&self, job_id: &str, _cluster: Option<&str>) -> Result<()> {
+        if !self.has_export("release_job") {
+            return Err(anyhow!("Releasing jobs is not supported by this scheduler"));
+        }
+        let _: Value = self.call("release_job", json!({ "id": job_id })).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+"""NodeStat's scheduler adapters for Python.
+
+    import nodestat
+
+    idle = [node["id"] for node in nodestat.get_nodes("batch") if node["state"] == "Idle"]
+    free = nodestat.get_stats("gpu", scheduler="slurm", host="login1")["avail_cores"]
+
+Every function takes the backend the way ``nodestat`` does: ``scheduler`` (``"auto"``,
+``"slurm"``, ``"slurmrest"``, ``"torque"``, ``"pbspro"``, ``"lsf"``, ``"sge"``, ``"flux"``,
+``"mock"`` or a plugin name), ``host`` to run the scheduler's commands there over SSH, and
+``tz`` for the cluster's timezone (an IANA name, default: local). Nodes, jobs and partitions
+are dicts in the JSON schema of the ``exec`` backend: memory in bytes, durations as
+``[seconds, nanoseconds]`` and times in RFC 3339.
+A scheduler that can't be reached raises ``RuntimeError``.
+"""
+
+import json
+from typing import Any, Dict, List, Optional
+
+from . import _nodestat
+
+__version__ = "1.0.0"
+
+__all__ = [
+    "get_nodes",
+    "get_jobs",
+    "get_pending_jobs",
+    "get_user_jobs",
+    "get_jobs_by_id",
+    "list_partitions",
+    "get_partitions",
+    "get_stats",
+]
+
+
+def get_nodes(partition: str, scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[Dict[str, Any]]:
+    """The nodes of ``partition``."""
+    return json.loads(_nodestat.get_nodes_json(partition, scheduler, host, tz))
+
+
+def get_jobs(partition: str, scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[Dict[str, Any]]:
+    """The running jobs of ``partition``."""
+    return json.loads(_nodestat.get_jobs_json(partition, scheduler, host, tz))
+
+
+def get_pending_jobs(partition: str, scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[Dict[str, Any]]:
+    """The pending jobs of ``partition`` with their ``reason``, in scheduling order."""
+    return json.loads(_nodestat.get_pending_jobs_json(partition, scheduler, host, tz))
+
+
+def get_user_jobs(user: str, scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[Dict[str, Any]]:
+    """The jobs of ``user`` in every partition."""
+    return json.loads(_nodestat.get_user_jobs_json(user, scheduler, host, tz))
+
+
+def get_jobs_by_id(job_ids: List[str], scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[Dict[str, Any]]:
+    """The given jobs in any state, finished ones included where accounting still knows them."""
+    return json.loads(_nodestat.get_jobs_by_id_json(job_ids, scheduler, host, tz))
+
+
+def list_partitions(scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[str]:
+    """The names of the partitions (queues), the default one first."""
+    return json.loads(_nodestat.list_partitions_json(scheduler, host, tz))
+
+
+def get_partitions(scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> List[Dict[str, Any]]:
+    """The partitions with their limits, where the scheduler reports them."""
+    return json.loads(_nodestat.get_partitions_json(scheduler, host, tz))
+
+
+def get_stats(partition: str, scheduler: str = "auto", host: Optional[str] = None, tz: str = "local") -> Dict[str, Any]:
+    """The totals over the nodes of ``partition``, as ``nodestat stats`` and ``/stats`` give them."""
+    return json.loads(_nodestat.get_stats_json(partition, scheduler, host, tz))
@@ -0,0 +1,131 @@
+//! `import nodestat`: the scheduler adapters of `nodestat-core` for Python. Every function takes
+//! the backend like the TUI does (`scheduler="auto"`, `host=` to run the commands over SSH,
+//! `tz=` for the cluster's timezone) and returns the same JSON the `exec` backend reads and the
+//! daemon serves, which `nodestat/__init__.py` turns into lists and dicts.
+
+use anyhow::Result;
+use nodestat_core::clock::ClusterTz;
+use nodestat_core::models::ClusterStats;
+use nodestat_core::schedulers::{create_scheduler, resolve_scheduler, Scheduler, Transport};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+/// One runtime for every call; the scheduler commands run on it without the GIL.
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| Runtime::new().expect("Failed to start the tokio runtime"))
+}
+
+/// What a Python call asks the scheduler for.
+enum Query {
+    Nodes(String),
+    Jobs(String),
+    PendingJobs(String),
+    UserJobs(String),
+    JobsById(Vec<String>),
+    Partitions,
+    PartitionLimits,
+    Stats(String),
+}
+
+/// Which backend to ask, as the Python keyword arguments give it.
+struct Backend {
+    scheduler: String,
+    host: Option<String>,
+    tz: String,
+}
+
+impl Backend {
+    async fn create(&self) -> Result<Box<dyn Scheduler>> {
+        let cluster_tz = ClusterTz::parse(&self.tz)?;
+        let transport = match self.host {
+            Some(ref host) => Transport::ssh(host.clone()),
+            None => Transport::local(),
+        };
+        let scheduler_type = resolve_scheduler(&self.scheduler, &transport, None).await?;
+        create_scheduler(scheduler_type, cluster_tz, transport)
+    }
+
+    async fn query(&self, query: Query) -> Result<String> {
+        let scheduler = self.create().await?;
+        Ok(match query {
+            Query::Nodes(partition) => serde_json::to_string(&scheduler.get_nodes(&partition).await?)?,
+            Query::Jobs(partition) => serde_json::to_string(&scheduler.get_jobs(&partition).await?)?,
+            Query::PendingJobs(partition) => serde_json::to_string(&scheduler.get_pending_jobs(&partition).await?)?,
+            Query::UserJobs(user) => serde_json::to_string(&scheduler.get_user_jobs(&user).await?)?,
+            Query::JobsById(ids) => serde_json::to_string(&scheduler.get_jobs_by_id(&ids).await?)?,
+            Query::Partitions => serde_json::to_string(&scheduler.list_partitions().await?)?,
+            Query::PartitionLimits => serde_json::to_string(&scheduler.get_partitions().await?)?,
+            Query::Stats(partition) => serde_json::to_string(&ClusterStats::of(&scheduler.get_nodes(&partition).await?))?,
+        })
+    }
+}
+
+/// Run `query` with the GIL released, so other Python threads go on while `sacct` runs.
+fn run(py: Python<'_>, backend: Backend, query: Query) -> PyResult<String> {
+    py.allow_threads(move || runtime().block_on(backend.query(query)))
+        .map_err(|e| PyRuntimeError::new_err(format!("{:#}", e)))
+}
+
+#[pyfunction]
+#[pyo3(signature = (partition, scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_nodes_json(py: Python<'_>, partition: String, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::Nodes(partition))
+}
+
+#[pyfunction]
+#[pyo3(signature = (partition, scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_jobs_json(py: Python<'_>, partition: String, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::Jobs(partition))
+}
+
+#[pyfunction]
+#[pyo3(signature = (partition, scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_pending_jobs_json(py: Python<'_>, partition: String, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::PendingJobs(partition))
+}
+
+#[pyfunction]
+#[pyo3(signature = (user, scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_user_jobs_json(py: Python<'_>, user: String, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::UserJobs(user))
+}
+
+#[pyfunction]
+#[pyo3(signature = (job_ids, scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_jobs_by_id_json(py: Python<'_>, job_ids: Vec<String>, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::JobsById(job_ids))
+}
+
+#[pyfunction]
+#[pyo3(signature = (scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn list_partitions_json(py: Python<'_>, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::Partitions)
+}
+
+#[pyfunction]
+#[pyo3(signature = (scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_partitions_json(py: Python<'_>, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::PartitionLimits)
+}
+
+#[pyfunction]
+#[pyo3(signature = (partition, scheduler = "auto".to_string(), host = None, tz = "local".to_string()))]
+fn get_stats_json(py: Python<'_>, partition: String, scheduler: String, host: Option<String>, tz: String) -> PyResult<String> {
+    run(py, Backend { scheduler, host, tz }, Query::Stats(partition))
+}
+
+#[pymodule]
+fn _nodestat(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(get_nodes_json, m)?)?;
+    m.add_function(wrap_pyfunction!(get_jobs_json, m)?)?;
+    m.add_function(wrap_pyfunction!(get_pending_jobs_json, m)?)?;
+    m.add_function(wrap_pyfunction!(get_user_jobs_json, m)?)?;
+    m.add_function(wrap_pyfunction!(get_jobs_by_id_json, m)?)?;
+    m.add_function(wrap_pyfunction!(list_partitions_json, m)?)?;
+    m.add_function(wrap_pyfunction!(get_partitions_json, m)?)?;
+    m.add_function(wrap_pyfunction!(get_stats_json, m)?)?;
+    Ok(())
+}
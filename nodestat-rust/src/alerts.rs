@@ -0,0 +1,400 @@
+//! `[[alerts]]`: rules such as `partition:gpu avail_gpus == 0 for 10m`, checked at every refresh
+//! of the TUI or poll of `serve`. A rule fires once its condition has held for its duration,
+//! then stays quiet for its cooldown however long the condition keeps holding.
+
+use crate::config::AlertConfig;
+use crate::models::{ClusterStats, Job, JobState, Node, GB};
+use crate::webhook::Webhook;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+
+/// How long a rule stays quiet after firing, unless it sets `cooldown`.
+const DEFAULT_COOLDOWN: Duration = Duration::minutes(30);
+
+/// A number a rule can compare, from the nodes and jobs of its partition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    TotalNodes,
+    AvailNodes,
+    /// Down, failed, in maintenance or rebooting
+    DownNodes,
+    DrainedNodes,
+    AvailCores,
+    UsedCores,
+    /// Memory in GB
+    AvailMem,
+    UsedMem,
+    AvailGpus,
+    UsedGpus,
+    /// Percent of the partition's cores, memory or GPUs in use
+    CpuUtil,
+    MemUtil,
+    GpuUtil,
+    RunningJobs,
+    PendingJobs,
+    Watts,
+}
+
+impl Metric {
+    const NAMES: [(&'static str, Metric); 16] = [
+        ("total_nodes", Metric::TotalNodes),
+        ("avail_nodes", Metric::AvailNodes),
+        ("down_nodes", Metric::DownNodes),
+        ("drained_nodes", Metric::DrainedNodes),
+        ("avail_cores", Metric::AvailCores),
+        ("used_cores", Metric::UsedCores),
+        ("avail_mem", Metric::AvailMem),
+        ("used_mem", Metric::UsedMem),
+        ("avail_gpus", Metric::AvailGpus),
+        ("used_gpus", Metric::UsedGpus),
+        ("cpu_util", Metric::CpuUtil),
+        ("mem_util", Metric::MemUtil),
+        ("gpu_util", Metric::GpuUtil),
+        ("running_jobs", Metric::RunningJobs),
+        ("pending_jobs", Metric::PendingJobs),
+        ("watts", Metric::Watts),
+    ];
+
+    fn parse(name: &str) -> Result<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, metric)| *metric)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::NAMES.iter().map(|(n, _)| *n).collect();
+                anyhow!("Unknown metric '{}' (metrics: {})", name, names.join(", "))
+            })
+    }
+
+    /// `None` where the partition has nothing to measure, like the GPU use without GPUs.
+    fn value(self, observation: &Observation) -> Option<f64> {
+        let stats = observation.stats;
+        let percent = |used: f64, total: f64| (total > 0.0).then(|| used * 100.0 / total);
+        let nodes = observation.nodes;
+        Some(match self {
+            Metric::TotalNodes => stats.total_nodes.into(),
+            Metric::AvailNodes => stats.avail_nodes.into(),
+            Metric::DownNodes => nodes.iter().filter(|node| !node.state.is_up()).count() as f64,
+            Metric::DrainedNodes => nodes.iter().filter(|node| node.has_flag("DRAIN")).count() as f64,
+            Metric::AvailCores => stats.avail_cores.into(),
+            Metric::UsedCores => stats.used_cores.into(),
+            Metric::AvailMem => (stats.avail_memory / GB) as f64,
+            Metric::UsedMem => (stats.used_memory / GB) as f64,
+            Metric::AvailGpus => stats.total_gpus().saturating_sub(stats.used_gpus()).into(),
+            Metric::UsedGpus => stats.used_gpus().into(),
+            Metric::CpuUtil => percent(stats.used_cores.into(), stats.total_cores.into())?,
+            Metric::MemUtil => percent(stats.used_memory as f64, stats.total_memory as f64)?,
+            Metric::GpuUtil => percent(stats.used_gpus().into(), stats.total_gpus().into())?,
+            Metric::RunningJobs => observation.running as f64,
+            Metric::PendingJobs => observation.pending? as f64,
+            Metric::Watts => (stats.power_nodes > 0).then_some(stats.current_watts as f64)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn parse(text: &str) -> Result<Self> {
+        Ok(match text {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            "==" | "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            ">=" => Comparison::GreaterOrEqual,
+            ">" => Comparison::Greater,
+            _ => return Err(anyhow!("Unknown comparison '{}' (use <, <=, ==, !=, >=, >)", text)),
+        })
+    }
+
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Equal => value == threshold,
+            Comparison::NotEqual => value != threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Greater => value > threshold,
+        }
+    }
+}
+
+/// `30s`, `10m`, `2h` or `1d`.
+fn parse_duration(text: &str) -> Result<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: i64 = number.parse().map_err(|_| anyhow!("Expected a duration like 10m, not '{}'", text))?;
+    match unit {
+        "s" => Ok(Duration::seconds(value)),
+        "m" | "min" => Ok(Duration::minutes(value)),
+        "h" => Ok(Duration::hours(value)),
+        "d" => Ok(Duration::days(value)),
+        _ => Err(anyhow!("Unknown unit in '{}' (use s, m, h, d)", text)),
+    }
+}
+
+/// What an alert does when it fires, besides the TUI's banner and alerts pane.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// A desktop notification, as `[notifications] desktop` sends them
+    Desktop,
+    /// The terminal bell
+    Bell,
+    /// A POST of NodeStat's JSON or a Slack/Mattermost message
+    Webhook(Webhook),
+}
+
+impl Target {
+    fn parse(text: &str) -> Result<Self> {
+        match text {
+            "desktop" => Ok(Target::Desktop),
+            "bell" => Ok(Target::Bell),
+            _ => match Webhook::parse(text) {
+                Some(webhook) => webhook.map(Target::Webhook),
+                None => Err(anyhow!(
+                    "Unknown notify target '{}' (use desktop, bell, webhook:<url>, slack:<url> or mattermost:<url>)",
+                    text
+                )),
+            },
+        }
+    }
+}
+
+/// A partition at one refresh, as the rules see it.
+pub struct Observation<'a> {
+    pub stats: &'a ClusterStats,
+    pub nodes: &'a [Node],
+    pub running: usize,
+    /// `None` if the queue wasn't fetched
+    pub pending: Option<usize>,
+}
+
+impl<'a> Observation<'a> {
+    pub fn new(stats: &'a ClusterStats, nodes: &'a [Node], jobs: &[Job], pending: Option<usize>) -> Self {
+        let running = jobs.iter().filter(|job| job.state == JobState::Running).count();
+        Self { stats, nodes, running, pending }
+    }
+}
+
+/// An alert that just fired.
+#[derive(Debug, Clone)]
+pub struct Firing {
+    pub name: String,
+    pub partition: String,
+    /// `GPUs gone: avail_gpus == 0 for 10m in gpu (now 0)`
+    pub message: String,
+    pub targets: Vec<Target>,
+    pub time: DateTime<Utc>,
+}
+
+/// One `[[alerts]]` rule and what it has seen so far.
+#[derive(Debug, Clone)]
+pub struct Alert {
+    name: Option<String>,
+    /// The `when` as written
+    rule: String,
+    /// `None`: the partition the TUI shows
+    partition: Option<String>,
+    metric: Metric,
+    comparison: Comparison,
+    threshold: f64,
+    hold: Duration,
+    targets: Vec<Target>,
+    cooldown: Duration,
+    /// The metric at the last check
+    value: Option<f64>,
+    /// Since when the condition holds, if it does
+    since: Option<DateTime<Utc>>,
+    last_fired: Option<DateTime<Utc>>,
+}
+
+impl Alert {
+    /// `[partition:<name>] <metric> <comparison> <value> [for <duration>]`
+    fn parse(config: &AlertConfig) -> Result<Self> {
+        let rule = config.when.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut words: Vec<&str> = rule.split(' ').collect();
+        let partition = match words.first().and_then(|word| word.strip_prefix("partition:")) {
+            Some(partition) => {
+                words.remove(0);
+                Some(partition.to_string())
+            },
+            None => None,
+        };
+        let hold = match words.iter().position(|word| *word == "for") {
+            Some(index) => {
+                let duration = words.get(index + 1).ok_or_else(|| anyhow!("For how long?"))?;
+                if words.len() > index + 2 {
+                    return Err(anyhow!("Unexpected '{}' after the duration", words[index + 2]));
+                }
+                let hold = parse_duration(duration)?;
+                words.truncate(index);
+                hold
+            },
+            None => Duration::zero(),
+        };
+        let [metric, comparison, threshold] = words[..] else {
+            return Err(anyhow!("Expected '<metric> <comparison> <value>', e.g. avail_gpus == 0"));
+        };
+        let threshold = threshold
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| anyhow!("Expected a number, not '{}'", threshold))?;
+        Ok(Self {
+            name: config.name.clone(),
+            partition,
+            metric: Metric::parse(metric)?,
+            comparison: Comparison::parse(comparison)?,
+            threshold,
+            hold,
+            targets: config.notify.iter().map(|target| Target::parse(target)).collect::<Result<_>>()?,
+            cooldown: config.cooldown.as_deref().map(parse_duration).transpose()?.unwrap_or(DEFAULT_COOLDOWN),
+            rule,
+            value: None,
+            since: None,
+            last_fired: None,
+        })
+    }
+
+    /// The `name`, else the rule.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.rule)
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn partition(&self) -> Option<&str> {
+        self.partition.as_deref()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    /// Since when the condition holds, if it does.
+    pub fn since(&self) -> Option<DateTime<Utc>> {
+        self.since
+    }
+
+    pub fn last_fired(&self) -> Option<DateTime<Utc>> {
+        self.last_fired
+    }
+
+    /// Whether the condition has held long enough to fire.
+    pub fn is_firing(&self, now: DateTime<Utc>) -> bool {
+        self.since.is_some_and(|since| now - since >= self.hold)
+    }
+
+    fn check(&mut self, partition: &str, observation: &Observation, now: DateTime<Utc>) -> Option<Firing> {
+        self.value = self.metric.value(observation);
+        if !self.value.is_some_and(|value| self.comparison.holds(value, self.threshold)) {
+            self.since = None;
+            return None;
+        }
+        self.since.get_or_insert(now);
+        let quiet = self.last_fired.is_some_and(|fired| now - fired < self.cooldown);
+        if !self.is_firing(now) || quiet {
+            return None;
+        }
+        self.last_fired = Some(now);
+        let condition = self.rule.strip_prefix(&format!("partition:{} ", partition)).unwrap_or(&self.rule);
+        let mut message = format!("{} in {} (now {})", condition, partition, format_value(self.value?));
+        if let Some(ref name) = self.name {
+            message = format!("{}: {}", name, message);
+        }
+        Some(Firing {
+            name: self.name().to_string(),
+            partition: partition.to_string(),
+            message,
+            targets: self.targets.clone(),
+            time: now,
+        })
+    }
+}
+
+/// Whole numbers without decimals, the percentages with one.
+pub fn format_value(value: f64) -> String {
+    match value.fract() == 0.0 {
+        true => format!("{}", value),
+        false => format!("{:.1}", value),
+    }
+}
+
+/// Every `[[alerts]]` rule, in the order of config.toml.
+#[derive(Debug, Clone, Default)]
+pub struct Alerts {
+    alerts: Vec<Alert>,
+}
+
+impl Alerts {
+    /// A rule that doesn't parse is an error naming it.
+    pub fn new(configs: &[AlertConfig]) -> Result<Self> {
+        let alerts = configs
+            .iter()
+            .map(|config| Alert::parse(config).with_context(|| format!("[[alerts]] '{}'", config.when)))
+            .collect::<Result<_>>()?;
+        Ok(Self { alerts })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Alert> {
+        self.alerts.iter()
+    }
+
+    /// The partitions named by the rules, besides `shown`, each once.
+    pub fn other_partitions(&self, shown: &str) -> Vec<String> {
+        let mut partitions: Vec<String> = Vec::new();
+        for partition in self.alerts.iter().filter_map(Alert::partition) {
+            if partition != shown && !partitions.iter().any(|p| p == partition) {
+                partitions.push(partition.to_string());
+            }
+        }
+        partitions
+    }
+
+    /// Whether a rule on `partition` (the one shown, if `shown`) counts its pending jobs.
+    pub fn needs_pending_jobs(&self, partition: &str, shown: bool) -> bool {
+        self.alerts
+            .iter()
+            .any(|alert| alert.metric == Metric::PendingJobs && alert.partition.as_deref().map_or(shown, |p| p == partition))
+    }
+
+    /// Check the rules on `partition` (and those without one, if it's the `shown` partition)
+    /// against `observation`, returning the ones that fire.
+    pub fn check(&mut self, partition: &str, shown: bool, observation: &Observation, now: DateTime<Utc>) -> Vec<Firing> {
+        self.alerts
+            .iter_mut()
+            .filter(|alert| alert.partition.as_deref().map_or(shown, |p| p == partition))
+            .filter_map(|alert| alert.check(partition, observation, now))
+            .collect()
+    }
+
+    /// The rules grouped by partition, for `serve`, which has no partition of its own to show;
+    /// a rule without one is an error.
+    pub fn by_partition(self) -> Result<Vec<(String, Alerts)>> {
+        let mut groups: Vec<(String, Alerts)> = Vec::new();
+        for alert in self.alerts {
+            let partition = alert
+                .partition
+                .clone()
+                .ok_or_else(|| anyhow!("[[alerts]] '{}': serve needs a partition:<name>", alert.rule))?;
+            match groups.iter_mut().find(|(p, _)| *p == partition) {
+                Some((_, alerts)) => alerts.alerts.push(alert),
+                None => groups.push((partition, Alerts { alerts: vec![alert] })),
+            }
+        }
+        Ok(groups)
+    }
+}
@@ -0,0 +1,83 @@
+//! Availability watches: "tell me when 8 cores and 32 GB are free in gpu" or "when c001 goes
+//! idle", checked on every refresh and dropped once they fire.
+
+use crate::models::Node;
+use crate::placement::ResourceRequest;
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    /// Enough nodes with room for the per-node request
+    Capacity(ResourceRequest),
+    /// A node in a state, matched like `:filter state=`
+    NodeState { node: String, state: String },
+}
+
+/// One watch, as typed after `N` or `:notify`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvailabilityWatch {
+    source: String,
+    condition: Condition,
+    /// Where to look; `None` for the partition shown
+    partition: Option<String>,
+}
+
+impl AvailabilityWatch {
+    /// `<request> [in <partition>]`, the request as for `i` and `c` (`8c 32g 1gpu 2n`), or
+    /// `node <name> [<state>] [in <partition>]`, the state defaulting to idle.
+    pub fn parse(input: &str) -> Result<Self> {
+        let source = input.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (condition, partition) = match source.rsplit_once(" in ") {
+            Some((condition, partition)) => (condition, Some(partition.trim().to_string())),
+            None => (source.as_str(), None),
+        };
+
+        let mut words = condition.split_whitespace();
+        let condition = match words.next() {
+            Some("node") => {
+                let node = words.next().ok_or_else(|| anyhow!("Which node?"))?.to_string();
+                let state = words.next().unwrap_or("idle").to_string();
+                if let Some(extra) = words.next() {
+                    return Err(anyhow!("Unexpected '{}' after the state", extra));
+                }
+                Condition::NodeState { node, state }
+            },
+            Some(_) => Condition::Capacity(ResourceRequest::parse(condition)?),
+            None => return Err(anyhow!("Notify when what? e.g. 8c 32g in gpu, node c001 idle")),
+        };
+        Ok(Self { source, condition, partition })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn partition(&self) -> Option<&str> {
+        self.partition.as_deref()
+    }
+
+    /// What to announce if `nodes`, those of the watched partition, satisfy the watch.
+    pub fn check(&self, nodes: &[Node]) -> Option<String> {
+        match &self.condition {
+            Condition::Capacity(request) => {
+                let fitting: Vec<&str> = nodes
+                    .iter()
+                    .filter(|node| request.slots(node) > 0)
+                    .map(|node| node.id.as_str())
+                    .collect();
+                if fitting.len() < request.nodes as usize {
+                    return None;
+                }
+                let shown: Vec<&str> = fitting.iter().copied().take(5).collect();
+                let more = if fitting.len() > shown.len() { format!(" and {} more", fitting.len() - shown.len()) } else { String::new() };
+                Some(format!("{} is free: {}{}", self.source, shown.join(", "), more))
+            },
+            Condition::NodeState { node, state } => {
+                let node = nodes.iter().find(|n| &n.id == node)?;
+                let is = |value: &str| value.eq_ignore_ascii_case(state);
+                let reached = is(&node.state_label()) || (!node.has_flag("DRAIN") && is(&node.state.to_string()));
+                reached.then(|| format!("{} is {}", node.id, node.state_label()))
+            },
+        }
+    }
+}
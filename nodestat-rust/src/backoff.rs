@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+/// First retry after a failed refresh; doubles with every further failure.
+const INITIAL_RETRY: Duration = Duration::from_secs(5);
+/// Never wait longer than this between attempts, however long the scheduler has been failing.
+const MAX_RETRY: Duration = Duration::from_secs(300);
+/// Consecutive failures after which the connection counts as failing rather than degraded.
+const FAILING_AFTER: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HealthLevel {
+    /// The last refresh succeeded
+    Ok,
+    /// A refresh or two failed; the data shown may be stale
+    Degraded,
+    /// The scheduler keeps failing
+    Failing,
+}
+
+/// Success/failure history of the refreshes, deciding when to retry and how healthy the data is.
+#[derive(Debug, Default)]
+pub struct FetchHealth {
+    failures: u32,
+    last_attempt: Option<Instant>,
+    last_success: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl FetchHealth {
+    pub fn record_success(&mut self) {
+        let now = Instant::now();
+        self.failures = 0;
+        self.last_attempt = Some(now);
+        self.last_success = Some(now);
+        self.last_error = None;
+    }
+
+    pub fn record_failure(&mut self, error: String) {
+        self.failures += 1;
+        self.last_attempt = Some(Instant::now());
+        self.last_error = Some(error);
+    }
+
+    /// A refresh stopped before it got an answer either way.
+    pub fn record_cancelled(&mut self) {
+        self.last_attempt = Some(Instant::now());
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Time since the last refresh that got fresh data, `None` if none did yet.
+    pub fn since_success(&self) -> Option<Duration> {
+        self.last_success.map(|t| t.elapsed())
+    }
+
+    /// `interval` while healthy; after failures, 5s doubling up to 5 minutes so a struggling
+    /// controller isn't hammered but a transient failure is retried quickly.
+    pub fn retry_delay(&self, interval: Duration) -> Duration {
+        if self.failures == 0 {
+            return interval;
+        }
+        let backoff = INITIAL_RETRY.saturating_mul(1 << (self.failures - 1).min(16));
+        backoff.min(MAX_RETRY)
+    }
+
+    /// Whether the next automatic refresh is due.
+    pub fn is_due(&self, interval: Duration) -> bool {
+        self.last_attempt
+            .is_none_or(|t| t.elapsed() >= self.retry_delay(interval))
+    }
+
+    /// Time left until the next automatic refresh.
+    pub fn next_attempt_in(&self, interval: Duration) -> Duration {
+        self.last_attempt
+            .map_or(Duration::ZERO, |t| self.retry_delay(interval).saturating_sub(t.elapsed()))
+    }
+
+    pub fn level(&self) -> HealthLevel {
+        match self.failures {
+            0 => HealthLevel::Ok,
+            n if n < FAILING_AFTER => HealthLevel::Degraded,
+            _ => HealthLevel::Failing,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! What changed between refreshes: the node table cells to highlight for a while, and a feed
+//! of the latest changes.
+
+use crate::models::{format_bytes, Node};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Changed cells stay highlighted this long.
+const HIGHLIGHT_FOR: Duration = Duration::from_secs(15);
+
+/// Entries kept in the feed.
+const MAX_FEED: usize = 50;
+
+/// The node values a change is tracked for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    State,
+    Cores,
+    Memory,
+    Gpus,
+    Jobs,
+    Reason,
+}
+
+/// One line of the feed.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub at: DateTime<Utc>,
+    pub node: String,
+    pub cluster: Option<String>,
+    pub description: String,
+}
+
+/// What a node looked like at the last fetch.
+struct Snapshot {
+    state: String,
+    used_cores: u32,
+    used_mem: u64,
+    used_gpus: u32,
+    jobs: usize,
+    reason: Option<String>,
+}
+
+impl Snapshot {
+    fn of(node: &Node) -> Self {
+        Self {
+            state: node.state_label(),
+            used_cores: node.used_cores,
+            used_mem: node.used_mem,
+            used_gpus: node.used_gpus(),
+            jobs: node.jobs.len(),
+            reason: node.reason.clone(),
+        }
+    }
+}
+
+/// `12 cores freed` or `4 GPUs allocated`.
+fn freed_or_allocated(before: u64, after: u64, what: impl Fn(u64) -> String) -> String {
+    if after < before {
+        format!("{} freed", what(before - after))
+    } else {
+        format!("{} allocated", what(after - before))
+    }
+}
+
+type NodeKey = (Option<String>, String);
+
+#[derive(Default)]
+pub struct ChangeTracker {
+    /// Every node seen, as last fetched
+    previous: HashMap<NodeKey, Snapshot>,
+    /// The fields that changed at a node's last change, and when
+    changed: HashMap<NodeKey, (Instant, Vec<Field>)>,
+    /// Newest first
+    feed: VecDeque<Change>,
+}
+
+impl ChangeTracker {
+    /// Compare `nodes` with the last fetch; `now` timestamps the feed. Nodes seen for the first
+    /// time (at startup, or in a partition not shown before) have nothing to compare with.
+    pub fn record(&mut self, nodes: &[Node], now: DateTime<Utc>) {
+        let at = Instant::now();
+        for node in nodes {
+            let key = (node.cluster.clone(), node.id.clone());
+            let after = Snapshot::of(node);
+            let Some(before) = self.previous.insert(key.clone(), Snapshot::of(node)) else {
+                continue;
+            };
+
+            let mut fields = Vec::new();
+            let mut descriptions = Vec::new();
+            if before.state != after.state {
+                fields.push(Field::State);
+                descriptions.push(format!("{} → {}", before.state, after.state));
+            }
+            if before.used_cores != after.used_cores {
+                fields.push(Field::Cores);
+                descriptions.push(freed_or_allocated(before.used_cores.into(), after.used_cores.into(), |n| format!("{} cores", n)));
+            }
+            if before.used_mem != after.used_mem {
+                fields.push(Field::Memory);
+                descriptions.push(freed_or_allocated(before.used_mem, after.used_mem, format_bytes));
+            }
+            if before.used_gpus != after.used_gpus {
+                fields.push(Field::Gpus);
+                descriptions.push(freed_or_allocated(before.used_gpus.into(), after.used_gpus.into(), |n| format!("{} GPUs", n)));
+            }
+            if before.jobs != after.jobs {
+                fields.push(Field::Jobs);
+            }
+            if before.reason != after.reason {
+                fields.push(Field::Reason);
+                descriptions.push(match after.reason {
+                    Some(ref reason) => format!("reason: {}", reason),
+                    None => "reason cleared".to_string(),
+                });
+            }
+
+            if fields.is_empty() {
+                continue;
+            }
+            self.changed.insert(key, (at, fields));
+            if !descriptions.is_empty() {
+                self.feed.push_front(Change {
+                    at: now,
+                    node: node.id.clone(),
+                    cluster: node.cluster.clone(),
+                    description: descriptions.join(", "),
+                });
+            }
+        }
+        self.feed.truncate(MAX_FEED);
+        self.changed.retain(|_, (changed_at, _)| at.duration_since(*changed_at) < HIGHLIGHT_FOR);
+    }
+
+    /// Whether `field` of `node` changed within the highlight time.
+    pub fn is_changed(&self, node: &Node, field: Field) -> bool {
+        self.changed
+            .get(&(node.cluster.clone(), node.id.clone()))
+            .is_some_and(|(at, fields)| at.elapsed() < HIGHLIGHT_FOR && fields.contains(&field))
+    }
+
+    /// The latest changes, newest first.
+    pub fn feed(&self) -> &VecDeque<Change> {
+        &self.feed
+    }
+}
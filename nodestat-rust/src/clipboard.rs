@@ -0,0 +1,72 @@
+//! Copying to the system clipboard: through the platform's clipboard command when there is
+//! one, else with an OSC 52 escape, which the terminal turns into a clipboard write even when
+//! NodeStat runs on the far side of an SSH session.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// How the text reached the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Copied {
+    /// The named clipboard command took it
+    Command(&'static str),
+    /// Sent to the terminal, which may or may not honor it
+    Osc52,
+}
+
+/// Clipboard commands worth trying here, best first; none over SSH, where they'd fill the
+/// remote machine's clipboard.
+fn commands() -> Vec<(&'static str, &'static [&'static str])> {
+    let env = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    if env("SSH_CONNECTION") || env("SSH_TTY") {
+        return Vec::new();
+    }
+
+    let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if cfg!(target_os = "macos") {
+        commands.push(("pbcopy", &[]));
+    }
+    if env("WAYLAND_DISPLAY") {
+        commands.push(("wl-copy", &[]));
+    }
+    if env("DISPLAY") {
+        commands.push(("xclip", &["-selection", "clipboard"]));
+        commands.push(("xsel", &["--clipboard", "--input"]));
+    }
+    commands
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().context("no stdin")?.write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+/// The escape sequence asking the terminal to put `text` on the clipboard.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text))
+}
+
+pub fn copy(text: &str) -> Result<Copied> {
+    for (program, args) in commands() {
+        if pipe_to(program, args, text).is_ok() {
+            return Ok(Copied::Command(program));
+        }
+    }
+
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52(text).as_bytes()).context("Failed to write to the terminal")?;
+    stdout.flush()?;
+    Ok(Copied::Osc52)
+}
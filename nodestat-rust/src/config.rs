@@ -0,0 +1,212 @@
+use crate::paths::config_dir;
+use anyhow::{Context, Result};
+pub use nodestat_core::schedulers::{ExecConfig, RateLimit, SlurmConfig, Timeouts};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Settings from `~/.config/nodestat/config.toml`. Every section is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Commands for the `exec` scheduler
+    pub exec: Option<ExecConfig>,
+    /// How the Slurm adapter asks accounting for the running jobs
+    pub slurm: SlurmConfig,
+    /// Clusters merged into one view with `--clusters`
+    pub clusters: Vec<ClusterConfig>,
+    /// Named sets of defaults, selected with `-p <name>`
+    pub profiles: BTreeMap<String, Profile>,
+    /// How to group nodes into racks and name prefixes
+    pub topology: Option<TopologyConfig>,
+    /// Color theme: `dark` (default), `light`, `solarized`, `colorblind` or one of `[themes.<name>]`
+    pub theme: Option<String>,
+    /// Like `--colorblind`
+    pub colorblind: bool,
+    /// Like `--columns`
+    pub columns: Option<Vec<String>>,
+    /// User-defined color themes
+    pub themes: BTreeMap<String, ThemeConfig>,
+    /// Command `o` runs to log into the selected node, through `sh -c`; `{node}` is replaced
+    /// with the shell-quoted node name (default: `ssh {node}`)
+    pub ssh_command: Option<String>,
+    /// How watches and watched jobs get the user's attention
+    pub notifications: NotificationsConfig,
+    /// Actions bound to other keys than the built-in ones, e.g. `quit = "Q"`, `refresh = "F5"`
+    pub keys: BTreeMap<String, String>,
+    /// Utilization samples kept across sessions
+    pub history: HistoryConfig,
+    /// Rules checked at every refresh that notify when the cluster needs attention
+    pub alerts: Vec<AlertConfig>,
+    /// Where `serve` publishes its polls over MQTT
+    pub mqtt: MqttConfig,
+    /// How long scheduler commands may run before they're killed
+    pub timeouts: Timeouts,
+    /// How often the same scheduler call may run
+    pub rate_limit: RateLimit,
+}
+
+/// The `[mqtt]` table. `serve` publishes once a broker is set here or with `--mqtt`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// `mqtt://host:port`, `host:port` or `host` (port 1883)
+    pub broker: Option<String>,
+    /// The first level of every topic (default: `nodestat`)
+    pub topic_prefix: String,
+    /// The partitions to publish (default: all of them)
+    pub partitions: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Default: `nodestat-<pid>`
+    pub client_id: Option<String>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            broker: None,
+            topic_prefix: "nodestat".to_string(),
+            partitions: Vec::new(),
+            username: None,
+            password: None,
+            client_id: None,
+        }
+    }
+}
+
+/// An `[[alerts]]` entry, checked by the TUI and by `serve`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertConfig {
+    /// Shown in the alerts pane and the notifications (default: the rule)
+    pub name: Option<String>,
+    /// `[partition:<name>] <metric> <comparison> <value> [for <duration>]`, e.g.
+    /// `partition:gpu avail_gpus == 0 for 10m`; without a partition, the one shown
+    pub when: String,
+    /// `desktop`, `bell`, `webhook:<url>` (NodeStat's JSON) and `slack:<url>` or `mattermost:<url>`;
+    /// the TUI's banner and alerts pane always show it
+    #[serde(default)]
+    pub notify: Vec<String>,
+    /// Don't fire again for this long, e.g. `2h` (default: 30m)
+    pub cooldown: Option<String>,
+}
+
+/// The `[history]` table: every refresh's usage stored in a local SQLite database (through the
+/// `sqlite3` command), for charts and reports. Off unless `enabled`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    /// The database file (default: history.db in the config directory)
+    pub path: Option<PathBuf>,
+    /// Delete samples older than this many days; 0 keeps them all (default: 30)
+    pub retention_days: u32,
+    /// Store each node's usage as well as the partition totals (default: true)
+    pub nodes: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self { enabled: false, path: None, retention_days: 30, nodes: true }
+    }
+}
+
+/// The `[notifications]` table. The banner in the TUI always shows.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Ring the terminal bell (default: true)
+    pub bell: bool,
+    /// Also send a desktop notification: notify-send or osascript, else an OSC 777 escape
+    /// for the terminal (default: false)
+    pub desktop: bool,
+    /// Send a desktop notification when one of your running jobs ends, even without
+    /// `desktop` (default: true)
+    pub job_end: bool,
+    /// Also send the ends of your jobs to these `webhook:`, `slack:` or `mattermost:` URLs
+    pub job_end_webhooks: Vec<String>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self { bell: true, desktop: false, job_end: true, job_end_webhooks: Vec::new() }
+    }
+}
+
+/// A `[profiles.<name>]` table. Flags given on the command line override it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Profile {
+    /// Any `--scheduler` value
+    pub scheduler: Option<String>,
+    /// Like `--host`
+    pub host: Option<String>,
+    /// Like `--partition`
+    pub partition: Option<String>,
+    /// Like `--tz`
+    pub tz: Option<String>,
+    /// Like `--connect`
+    pub connect: Option<String>,
+    /// Seconds between automatic refreshes, at least 5
+    pub refresh_interval: Option<u64>,
+    /// Like `--theme`
+    pub theme: Option<String>,
+    /// Like `--colorblind`
+    pub colorblind: Option<bool>,
+    /// Like `--columns`
+    pub columns: Option<Vec<String>>,
+    /// Like `ssh_command`, e.g. to hop through this cluster's login node
+    pub ssh_command: Option<String>,
+}
+
+/// One member of the multi-cluster view, as a `[[clusters]]` entry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterConfig {
+    /// Shown in the Cluster column
+    pub name: String,
+    /// Any `--scheduler` value (default: auto)
+    #[serde(default = "default_scheduler")]
+    pub scheduler: String,
+    /// Like `--host`: run this cluster's commands over SSH
+    pub host: Option<String>,
+    /// Partition to show (default: the cluster's default partition)
+    pub partition: Option<String>,
+}
+
+/// The `[topology]` table, for clusters whose node names encode the rack or the hardware.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TopologyConfig {
+    /// Regex matched against node names; the first capture group (or the whole match) is
+    /// the rack, e.g. `^(r\d+)` for `r12n07`. Takes precedence over the scheduler's topology.
+    pub rack_pattern: Option<String>,
+    /// Like `rack_pattern`, for the prefix groups (`g` twice), e.g. `^([a-z0-9]+)-` for
+    /// `c6420-017` and `a100-03` (default: the leading letters)
+    pub prefix_pattern: Option<String>,
+}
+
+/// A `[themes.<name>]` table: a built-in theme to start from and the colors to change, e.g.
+/// `special = "#5f0087"`. Keys are the roles of [`crate::ui::Theme`]; values are color names,
+/// `#rrggbb` or ANSI indexes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThemeConfig {
+    /// `dark`, `light`, `solarized` or `colorblind` (default: dark)
+    pub base: Option<String>,
+    /// Prefix node and job states with symbols (default: as in the base)
+    pub symbols: Option<bool>,
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, String>,
+}
+
+fn default_scheduler() -> String {
+    "auto".to_string()
+}
+
+impl Config {
+    /// A missing file is fine, a malformed one is an error.
+    pub fn load() -> Result<Self> {
+        let path = config_dir().join("config.toml");
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Ok(Self::default());
+        };
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
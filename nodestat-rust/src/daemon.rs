@@ -0,0 +1,286 @@
+//! `nodestat serve`: polls the scheduler once for everyone and streams snapshots over gRPC, on a
+//! TCP address and/or a Unix socket (`--socket`, by default [`DEFAULT_SOCKET`], which plain
+//! `nodestat` connects to by itself when it exists).
+//!
+//! Each partition some client subscribed to gets one polling task; it stops once the last
+//! subscriber of that partition disconnects. Clients connect with `--connect`
+//! (see [`crate::schedulers::RemoteScheduler`]), and with `--http` other tools get the same
+//! snapshots as JSON (see [`crate::http`]) and an MQTT broker its polls (see [`crate::mqtt`]). The `[[alerts]]` rules' partitions are polled for as
+//! long as the daemon runs, and their firings logged and sent to their webhooks.
+
+use crate::alerts::{Alerts, Observation, Target};
+use crate::config::MqttConfig;
+use crate::http;
+use crate::models::{ClusterStats, Job, Node};
+use crate::mqtt;
+use crate::rpc::node_stat_server::{NodeStat, NodeStatServer};
+use crate::rpc::{
+    GetJobsByIdRequest, JobsResponse, ListPartitionsRequest, ListPartitionsResponse, Snapshot, SubscribeRequest,
+};
+use crate::schedulers::Scheduler;
+use crate::webhook::Notice;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::UnixListener;
+use tokio::sync::{watch, OnceCell};
+use tokio_stream::wrappers::{UnixListenerStream, WatchStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+/// Where a site's daemon listens for the local users, and where `nodestat` looks for one.
+pub const DEFAULT_SOCKET: &str = "/run/nodestat/nodestat.sock";
+
+type SnapshotStream = Pin<Box<dyn Stream<Item = Result<Snapshot, Status>> + Send>>;
+
+pub struct Daemon {
+    scheduler: Arc<dyn Scheduler>,
+    interval: Duration,
+    /// Latest snapshot per polled partition; the map keeps one receiver of its own
+    partitions: Arc<Mutex<HashMap<String, watch::Receiver<Option<Snapshot>>>>>,
+    partition_list: OnceCell<ListPartitionsResponse>,
+}
+
+impl Daemon {
+    pub fn new(scheduler: Box<dyn Scheduler>, interval: Duration) -> Self {
+        Self {
+            scheduler: Arc::from(scheduler),
+            interval,
+            partitions: Arc::new(Mutex::new(HashMap::new())),
+            partition_list: OnceCell::new(),
+        }
+    }
+
+    pub fn subscribe(&self, partition: &str) -> watch::Receiver<Option<Snapshot>> {
+        let mut partitions = self.partitions.lock().unwrap();
+        if let Some(receiver) = partitions.get(partition) {
+            return receiver.clone();
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        partitions.insert(partition.to_string(), receiver.clone());
+        tokio::spawn(poll_partition(
+            self.scheduler.clone(),
+            partition.to_string(),
+            self.interval,
+            sender,
+            self.partitions.clone(),
+        ));
+        receiver
+    }
+
+    /// The latest snapshot of `partition`, polling it first if nobody watches it.
+    pub async fn snapshot(&self, partition: &str) -> Result<Snapshot> {
+        let mut receiver = self.subscribe(partition);
+        let snapshot = receiver.wait_for(Option::is_some).await.context("The poller of the partition stopped")?;
+        Ok(snapshot.clone().unwrap_or_default())
+    }
+
+    /// Partitions rarely change, so every client shares the first answer.
+    pub async fn partition_list(&self) -> Result<&ListPartitionsResponse> {
+        self.partition_list
+            .get_or_try_init(|| async {
+                let partitions = self.scheduler.list_partitions().await?;
+                let partitions_json = match self.scheduler.get_partitions().await {
+                    Ok(limits) => serde_json::to_string(&limits).unwrap_or_default(),
+                    Err(_) => String::new(),
+                };
+                anyhow::Ok(ListPartitionsResponse { partitions, partitions_json })
+            })
+            .await
+    }
+}
+
+async fn poll_partition(
+    scheduler: Arc<dyn Scheduler>,
+    partition: String,
+    interval: Duration,
+    sender: watch::Sender<Option<Snapshot>>,
+    partitions: Arc<Mutex<HashMap<String, watch::Receiver<Option<Snapshot>>>>>,
+) {
+    loop {
+        sender.send_replace(Some(take_snapshot(scheduler.as_ref(), &partition).await));
+        tokio::time::sleep(interval).await;
+
+        // Only the map's own receiver left: nobody watches this partition anymore
+        let mut partitions = partitions.lock().unwrap();
+        if sender.receiver_count() <= 1 {
+            partitions.remove(&partition);
+            return;
+        }
+    }
+}
+
+async fn take_snapshot(scheduler: &dyn Scheduler, partition: &str) -> Snapshot {
+    let polled_at = chrono::Utc::now();
+    let (nodes_json, error) = match scheduler.get_nodes(partition).await {
+        Ok(nodes) => (serde_json::to_string(&nodes).unwrap_or_default(), None),
+        Err(e) => ("[]".to_string(), Some(format!("{:#}", e))),
+    };
+
+    // Like the TUI, a failed job query just leaves the job list empty
+    let jobs = scheduler.get_jobs(partition).await.unwrap_or_default();
+    let server_time = scheduler.get_server_time().await.ok().flatten();
+    let reservations = scheduler.get_reservations().await.unwrap_or_default();
+    let pending_jobs = scheduler.get_pending_jobs(partition).await.unwrap_or_default();
+    let licenses = scheduler.get_licenses().await.unwrap_or_default();
+    // Answers the rate limit served from its cache are as old as when they were fetched
+    let polled_at = match scheduler.get_polled_at().await.ok().flatten() {
+        Some(cached) => cached.min(polled_at),
+        None => polled_at,
+    };
+
+    Snapshot {
+        nodes_json,
+        jobs_json: serde_json::to_string(&jobs).unwrap_or_default(),
+        error,
+        server_time: server_time.map(|t| t.to_rfc3339()),
+        reservations_json: serde_json::to_string(&reservations).unwrap_or_default(),
+        pending_jobs_json: serde_json::to_string(&pending_jobs).unwrap_or_default(),
+        licenses_json: serde_json::to_string(&licenses).unwrap_or_default(),
+        polled_at: Some(polled_at.to_rfc3339()),
+    }
+}
+
+/// Check `alerts` against every snapshot of `partition`. Desktop notifications and the bell
+/// have nobody to reach here; firings are logged and POSTed to their webhooks.
+async fn watch_alerts(mut receiver: watch::Receiver<Option<Snapshot>>, partition: String, mut alerts: Alerts) {
+    while receiver.changed().await.is_ok() {
+        let Some(snapshot) = receiver.borrow_and_update().clone() else {
+            continue;
+        };
+        // Rules aren't checked against a partition that couldn't be fetched
+        if snapshot.error.is_some() {
+            continue;
+        }
+        let nodes: Vec<Node> = serde_json::from_str(&snapshot.nodes_json).unwrap_or_default();
+        let jobs: Vec<Job> = serde_json::from_str(&snapshot.jobs_json).unwrap_or_default();
+        let pending: Vec<Job> = serde_json::from_str(&snapshot.pending_jobs_json).unwrap_or_default();
+        let stats = ClusterStats::of(&nodes);
+        let observation = Observation::new(&stats, &nodes, &jobs, Some(pending.len()));
+        for firing in alerts.check(&partition, false, &observation, chrono::Utc::now()) {
+            eprintln!("Alert: {}", firing.message);
+            for target in &firing.targets {
+                if let Target::Webhook(webhook) = target {
+                    if let Err(e) = webhook.send(&Notice::Alert(&firing)).await {
+                        eprintln!("Warning: {:#}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl NodeStat for Daemon {
+    type SubscribeStream = SnapshotStream;
+
+    async fn subscribe(&self, request: Request<SubscribeRequest>) -> Result<Response<Self::SubscribeStream>, Status> {
+        let receiver = self.subscribe(&request.into_inner().partition);
+        let stream = WatchStream::new(receiver).filter_map(|snapshot| snapshot.map(Ok));
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn list_partitions(
+        &self,
+        _request: Request<ListPartitionsRequest>,
+    ) -> Result<Response<ListPartitionsResponse>, Status> {
+        let response = self.partition_list().await.map_err(|e| Status::unavailable(format!("{:#}", e)))?;
+        Ok(Response::new(response.clone()))
+    }
+
+    async fn get_jobs_by_id(&self, request: Request<GetJobsByIdRequest>) -> Result<Response<JobsResponse>, Status> {
+        let jobs = self
+            .scheduler
+            .get_jobs_by_id(&request.into_inner().job_ids)
+            .await
+            .map_err(|e| Status::unavailable(format!("{:#}", e)))?;
+        let jobs_json = serde_json::to_string(&jobs).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(JobsResponse { jobs_json }))
+    }
+}
+
+/// Listen on a Unix socket at `path` that every local user may connect to, replacing one a
+/// daemon that is gone left behind.
+fn bind_socket(path: &Path) -> Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(anyhow!("Another daemon is listening on {}", path.display()));
+        }
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove the stale socket {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666))
+        .with_context(|| format!("Failed to open {} to every user", path.display()))?;
+    Ok(listener)
+}
+
+/// Serve gRPC on `listen` and the `socket`, the HTTP API on `http` and publish to the `mqtt`
+/// broker, if given.
+pub async fn serve(
+    scheduler: Box<dyn Scheduler>,
+    listen: Option<SocketAddr>,
+    socket: Option<PathBuf>,
+    interval: Duration,
+    alerts: Alerts,
+    http: Option<http::Options>,
+    mqtt: Option<(String, MqttConfig)>,
+) -> Result<()> {
+    let daemon = Arc::new(Daemon::new(scheduler, interval));
+    for (partition, alerts) in alerts.by_partition()? {
+        tokio::spawn(watch_alerts(daemon.subscribe(&partition), partition, alerts));
+    }
+
+    if let Some((broker, config)) = mqtt {
+        let daemon = daemon.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mqtt::publish(daemon, &broker, config).await {
+                eprintln!("Warning: MQTT: {:#}", e);
+            }
+        });
+    }
+
+    let service = NodeStatServer::from_arc(daemon.clone());
+    let unix = match socket {
+        Some(ref path) => Some(bind_socket(path)?),
+        None => None,
+    };
+    let addresses: Vec<String> = listen.iter().map(ToString::to_string).chain(socket.iter().map(|path| path.display().to_string())).collect();
+    eprintln!("NodeStat daemon listening on {} (polling every {}s)", addresses.join(" and "), interval.as_secs());
+    let tcp = async {
+        let Some(listen) = listen else {
+            return Ok(());
+        };
+        Server::builder()
+            .add_service(service.clone())
+            .serve(listen)
+            .await
+            .with_context(|| format!("Failed to serve on {}", listen))
+    };
+    let unix = async {
+        let Some(listener) = unix else {
+            return Ok(());
+        };
+        Server::builder()
+            .add_service(service.clone())
+            .serve_with_incoming(UnixListenerStream::new(listener))
+            .await
+            .context("Failed to serve on the socket")
+    };
+    let http = async {
+        match http {
+            Some(options) => http::serve(daemon.clone(), options).await,
+            None => Ok(()),
+        }
+    };
+    tokio::try_join!(tcp, unix, http).map(drop)
+}
@@ -0,0 +1,57 @@
+//! Desktop notifications: through notify-send or osascript when NodeStat runs on the user's
+//! own machine, else with an OSC 777 escape, which terminals such as kitty, foot, WezTerm and
+//! Ghostty turn into a notification on the far side of an SSH session.
+
+use anyhow::{anyhow, Result};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Whether a local notification command can reach the screen; not over SSH, where it would
+/// pop up on the remote machine (if anywhere).
+fn local_command() -> Option<&'static str> {
+    let env = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    if env("SSH_CONNECTION") || env("SSH_TTY") {
+        return None;
+    }
+    if cfg!(target_os = "macos") {
+        Some("osascript")
+    } else if env("WAYLAND_DISPLAY") || env("DISPLAY") {
+        Some("notify-send")
+    } else {
+        None
+    }
+}
+
+fn run(program: &'static str, summary: &str, body: &str) -> Result<()> {
+    let mut command = Command::new(program);
+    if program == "osascript" {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        command.arg("-e").arg(format!("display notification {} with title {}", quote(body), quote(summary)));
+    } else {
+        command.args(["--app-name", "NodeStat", summary, body]);
+    }
+    let status = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+/// The escape sequence asking the terminal for a notification; `;` would end a field early.
+pub fn osc777(summary: &str, body: &str) -> String {
+    let clean = |text: &str| text.replace([';', '\x07', '\x1b'], " ");
+    format!("\x1b]777;notify;{};{}\x07", clean(summary), clean(body))
+}
+
+/// Show a notification with the local command, falling back to OSC 777 if there is none or it fails.
+pub fn notify(summary: &str, body: &str) -> Result<()> {
+    if let Some(program) = local_command() {
+        if run(program, summary, body).is_ok() {
+            return Ok(());
+        }
+    }
+    let mut stdout = io::stdout();
+    stdout.write_all(osc777(summary, body).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
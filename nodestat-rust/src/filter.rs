@@ -0,0 +1,414 @@
+//! Filter expressions over node fields, for `:filter` and `--filter`: comparisons joined with
+//! `&&`, `||`, `!` and parentheses, e.g. `state==idle && avail_mem_gb>=64 && features~a100`.
+//! Terms side by side must all match, so `state=idle feature=a100` works too.
+
+use crate::models::{Node, GB};
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+
+/// The fields a comparison can test.
+pub const FIELDS: [&str; 18] = [
+    "state", "feature", "user", "partition", "name", "reason", "cluster",
+    "total_cores", "used_cores", "avail_cores",
+    "total_mem_gb", "used_mem_gb", "avail_mem_gb",
+    "total_gpus", "used_gpus", "avail_gpus",
+    "load", "jobs",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    /// The state or its label (`Draining`), or a state flag
+    State,
+    Feature,
+    /// The owner of a job on the node
+    User,
+    Partition,
+    Name,
+    Reason,
+    Cluster,
+    TotalCores,
+    UsedCores,
+    AvailCores,
+    TotalMem,
+    UsedMem,
+    AvailMem,
+    TotalGpus,
+    UsedGpus,
+    AvailGpus,
+    Load,
+    Jobs,
+}
+
+impl Field {
+    const ALL: [Field; 18] = [
+        Field::State, Field::Feature, Field::User, Field::Partition, Field::Name, Field::Reason, Field::Cluster,
+        Field::TotalCores, Field::UsedCores, Field::AvailCores,
+        Field::TotalMem, Field::UsedMem, Field::AvailMem,
+        Field::TotalGpus, Field::UsedGpus, Field::AvailGpus,
+        Field::Load, Field::Jobs,
+    ];
+
+    fn name(self) -> &'static str {
+        FIELDS[Self::ALL.iter().position(|field| *field == self).unwrap_or_default()]
+    }
+
+    /// A name from [`FIELDS`], a plural, `node` or `id` for the name, or the `--format` spelling
+    /// of a memory field (`avail_mem`).
+    fn parse(name: &str) -> Result<Self> {
+        let name = name.to_lowercase();
+        let name = match name.as_str() {
+            "features" | "users" | "partitions" => name.trim_end_matches('s'),
+            "node" | "id" => "name",
+            "total_mem" | "used_mem" | "avail_mem" => return Self::parse(&format!("{}_gb", name)),
+            other => other,
+        };
+        FIELDS
+            .iter()
+            .position(|field| *field == name)
+            .map(|i| Self::ALL[i])
+            .ok_or_else(|| anyhow!("Unknown field '{}' (fields: {})", name, FIELDS.join(", ")))
+    }
+
+    /// The node's value of a numeric field, `None` for the text ones.
+    fn number(self, node: &Node) -> Option<f64> {
+        let value = match self {
+            Field::TotalCores => node.total_cores.into(),
+            Field::UsedCores => node.used_cores.into(),
+            Field::AvailCores => node.available_cores().into(),
+            Field::TotalMem => node.total_mem as f64 / GB as f64,
+            Field::UsedMem => node.used_mem as f64 / GB as f64,
+            Field::AvailMem => node.available_mem() as f64 / GB as f64,
+            Field::TotalGpus => node.total_gpus().into(),
+            Field::UsedGpus => node.used_gpus().into(),
+            Field::AvailGpus => node.available_gpus().into(),
+            Field::Load => node.cpu_load.unwrap_or(0.0),
+            Field::Jobs => node.jobs.len() as f64,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// The node's values of a text field, any of which may match.
+    fn texts(self, node: &Node) -> Vec<String> {
+        match self {
+            Field::State => {
+                let mut states = vec![node.state.to_string(), node.state_label()];
+                states.extend(node.state_flags.iter().cloned());
+                states
+            },
+            Field::Feature => node.features.clone(),
+            Field::User => node.job_users.clone(),
+            Field::Partition => node.partitions.clone(),
+            Field::Name => vec![node.id.clone()],
+            Field::Reason => node.reason.iter().cloned().collect(),
+            Field::Cluster => node.cluster.iter().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn is_number(self) -> bool {
+        !matches!(self, Field::State | Field::Feature | Field::User | Field::Partition | Field::Name | Field::Reason | Field::Cluster)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Regex match, case-insensitive
+    Match,
+    NotMatch,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Match => "~",
+            Op::NotMatch => "!~",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare { field: Field, op: Op, value: String, regex: Option<Regex> },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    fn matches(&self, node: &Node) -> bool {
+        match self {
+            Expr::Compare { field, op, value, regex } => {
+                if let Some(number) = field.number(node) {
+                    let wanted: f64 = value.parse().unwrap_or_default();
+                    return match op {
+                        Op::Eq => number == wanted,
+                        Op::Ne => number != wanted,
+                        Op::Lt => number < wanted,
+                        Op::Le => number <= wanted,
+                        Op::Gt => number > wanted,
+                        Op::Ge => number >= wanted,
+                        Op::Match | Op::NotMatch => false,
+                    };
+                }
+                let texts = field.texts(node);
+                match (op, regex) {
+                    (Op::Match, Some(regex)) => texts.iter().any(|text| regex.is_match(text)),
+                    (Op::NotMatch, Some(regex)) => !texts.iter().any(|text| regex.is_match(text)),
+                    (Op::Ne, _) => !texts.iter().any(|text| text.eq_ignore_ascii_case(value)),
+                    _ => texts.iter().any(|text| text.eq_ignore_ascii_case(value)),
+                }
+            },
+            Expr::And(left, right) => left.matches(node) && right.matches(node),
+            Expr::Or(left, right) => left.matches(node) || right.matches(node),
+            Expr::Not(inner) => !inner.matches(node),
+        }
+    }
+
+    /// The terms of a chain of `&&`s.
+    fn conjuncts(&self) -> Vec<&Expr> {
+        match self {
+            Expr::And(left, right) => {
+                let mut terms = left.conjuncts();
+                terms.extend(right.conjuncts());
+                terms
+            },
+            other => vec![other],
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Compare { field, op, value, .. } => {
+                let plain = !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || "()&|!=<>~\"'".contains(c));
+                if plain {
+                    write!(f, "{}{}{}", field.name(), op.symbol(), value)
+                } else {
+                    write!(f, "{}{}\"{}\"", field.name(), op.symbol(), value)
+                }
+            },
+            Expr::And(left, right) => write!(f, "{} && {}", left, right),
+            Expr::Or(left, right) => write!(f, "({} || {})", left, right),
+            Expr::Not(inner) if matches!(**inner, Expr::And(..)) => write!(f, "!({})", inner),
+            Expr::Not(inner) => write!(f, "!{}", inner),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+/// Split an expression into words (bare or quoted), operators and parentheses.
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('(', _) => Token::Open,
+            (')', _) => Token::Close,
+            ('&', Some('&')) | ('|', Some('|')) => {
+                chars.next();
+                if c == '&' { Token::And } else { Token::Or }
+            },
+            ('=', Some('=')) | ('!', Some('=')) | ('!', Some('~')) | ('<', Some('=')) | ('>', Some('=')) => {
+                chars.next();
+                Token::Op(match (c, next) {
+                    ('=', _) => Op::Eq,
+                    ('!', Some('=')) => Op::Ne,
+                    ('!', _) => Op::NotMatch,
+                    ('<', _) => Op::Le,
+                    _ => Op::Ge,
+                })
+            },
+            ('=', _) => Token::Op(Op::Eq),
+            ('<', _) => Token::Op(Op::Lt),
+            ('>', _) => Token::Op(Op::Gt),
+            ('~', _) => Token::Op(Op::Match),
+            ('!', _) => Token::Not,
+            ('&', _) | ('|', _) => return Err(anyhow!("Use && and || to combine terms (quote a value with '{}' in it)", c)),
+            ('"', _) | ('\'', _) => {
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some(quote) if quote == c => break,
+                        Some(ch) => word.push(ch),
+                        None => return Err(anyhow!("Unclosed {} in the filter", c)),
+                    }
+                }
+                Token::Word(word)
+            },
+            (c, _) => {
+                let mut word = c.to_string();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()&|!=<>~\"'".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                Token::Word(word)
+            },
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens: `||` binds loosest, then `&&` (or terms side by side),
+/// then `!`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                Some(Token::Word(_) | Token::Not | Token::Open) => {},
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(anyhow!("Missing ')'")),
+                }
+            },
+            Some(Token::Word(field)) => self.compare(&field),
+            Some(token) => Err(anyhow!("Expected a field, found {}", describe(&token))),
+            None => Err(anyhow!("Expected a field at the end")),
+        }
+    }
+
+    fn compare(&mut self, field_name: &str) -> Result<Expr> {
+        let field = Field::parse(field_name)?;
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(anyhow!("'{}' needs a comparison, e.g. {}=value", field_name, field.name())),
+        };
+        let value = match self.next() {
+            Some(Token::Word(value)) => value,
+            _ => return Err(anyhow!("'{}{}' has no value", field_name, op.symbol())),
+        };
+
+        let mut regex = None;
+        if field.is_number() {
+            if matches!(op, Op::Match | Op::NotMatch) {
+                return Err(anyhow!("{} is a number; use ==, !=, <, <=, > or >=", field.name()));
+            }
+            value.parse::<f64>().map_err(|_| anyhow!("{} is a number, not '{}'", field.name(), value))?;
+        } else if matches!(op, Op::Match | Op::NotMatch) {
+            let pattern = RegexBuilder::new(&value).case_insensitive(true).build().map_err(|e| anyhow!("Invalid pattern '{}': {}", value, e))?;
+            regex = Some(pattern);
+        } else if !matches!(op, Op::Eq | Op::Ne) {
+            return Err(anyhow!("{} is text; use ==, !=, ~ or !~", field.name()));
+        }
+        Ok(Expr::Compare { field, op, value, regex })
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Op(op) => format!("'{}'", op.symbol()),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeFilter {
+    source: String,
+    expr: Expr,
+}
+
+impl NodeFilter {
+    /// `None` for an empty expression, which matches everything.
+    pub fn parse(source: &str) -> Result<Option<Self>> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow!("Unexpected {}", describe(token)));
+        }
+        Ok(Some(Self { source: source.split_whitespace().collect::<Vec<_>>().join(" "), expr }))
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The same filter with its `user=` term replaced by `user`, or dropped for `None`.
+    pub fn with_user(filter: Option<&Self>, user: Option<&str>) -> Option<Self> {
+        let is_user = |term: &&Expr| matches!(term, Expr::Compare { field: Field::User, op: Op::Eq, .. });
+        let mut words: Vec<String> = filter
+            .map(|filter| filter.expr.conjuncts().into_iter().filter(|term| !is_user(term)).map(Expr::to_string).collect())
+            .unwrap_or_default();
+        words.extend(user.map(|user| format!("user={}", user)));
+        Self::parse(&words.join(" ")).ok().flatten()
+    }
+
+    /// The nodes matching, in table order.
+    pub fn filter<'a>(&self, nodes: Vec<&'a Node>) -> Vec<&'a Node> {
+        nodes.into_iter().filter(|node| self.expr.matches(node)).collect()
+    }
+}
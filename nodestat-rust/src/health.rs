@@ -0,0 +1,72 @@
+use crate::models::{Node, NodeState};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// State changes older than this no longer count as flaps.
+const FLAP_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Recent state per node, used to spot nodes that keep flapping between states.
+#[derive(Default)]
+pub struct NodeHistory {
+    states: HashMap<String, VecDeque<(Instant, NodeState)>>,
+}
+
+impl NodeHistory {
+    pub fn record(&mut self, nodes: &[Node]) {
+        let now = Instant::now();
+        for node in nodes {
+            let samples = self.states.entry(node.id.clone()).or_default();
+            if samples.back().map(|(_, state)| state) != Some(&node.state) {
+                samples.push_back((now, node.state.clone()));
+            }
+            while samples.front().is_some_and(|(t, _)| now.duration_since(*t) > FLAP_WINDOW) {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Number of state changes within the flap window.
+    pub fn flaps(&self, node_id: &str) -> usize {
+        self.states
+            .get(node_id)
+            .map(|samples| samples.len().saturating_sub(1))
+            .unwrap_or(0)
+    }
+}
+
+/// Composite 0–100 score (100 = healthy) from state, health-check reasons,
+/// load vs. allocation, memory pressure and recent flaps.
+pub fn score(node: &Node, flaps: usize) -> u8 {
+    let mut penalty: u32 = match node.state {
+        NodeState::Down | NodeState::Offline | NodeState::Fail => 60,
+        NodeState::Drained => 40,
+        _ if node.has_flag("NOT_RESPONDING") => 40,
+        _ => 0,
+    };
+
+    // Reasons set by NHC or similar health checks
+    if let Some(ref reason) = node.reason {
+        let reason = reason.to_lowercase();
+        if reason.contains("nhc") || reason.contains("health") {
+            penalty += 30;
+        }
+    }
+
+    // Load well beyond what the scheduler allocated means rogue or runaway processes
+    if node.is_overloaded() {
+        penalty += if node.cpu_load.unwrap_or(0.0) > node.total_cores as f64 { 30 } else { 20 };
+    }
+
+    if node.total_mem > 0 {
+        let mem_ratio = node.used_mem as f64 / node.total_mem as f64;
+        if mem_ratio > 0.95 {
+            penalty += 20;
+        } else if mem_ratio > 0.9 {
+            penalty += 10;
+        }
+    }
+
+    penalty += (flaps as u32 * 10).min(30);
+
+    100u32.saturating_sub(penalty) as u8
+}
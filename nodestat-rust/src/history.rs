@@ -0,0 +1,295 @@
+//! Opt-in `[history]`: the partition totals and every node's usage at each refresh, and how long
+//! the jobs seen running had waited, kept in a local SQLite database for charts and reports. Written through the `sqlite3` command, one
+//! transaction per refresh, so several NodeStats can share the file. The commands run in order on a
+//! thread of their own: a refresh's write waiting on another NodeStat's lock doesn't hold up the UI.
+
+use crate::config::HistoryConfig;
+use crate::models::{ClusterStats, Job, JobState, JobWait, Node};
+use crate::paths;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS cluster_samples (
+    time INTEGER NOT NULL,
+    partition TEXT NOT NULL,
+    total_nodes INTEGER NOT NULL,
+    avail_nodes INTEGER NOT NULL,
+    total_cores INTEGER NOT NULL,
+    used_cores INTEGER NOT NULL,
+    total_mem INTEGER NOT NULL,
+    used_mem INTEGER NOT NULL,
+    total_gpus INTEGER NOT NULL,
+    used_gpus INTEGER NOT NULL,
+    watts INTEGER NOT NULL,
+    running_jobs INTEGER NOT NULL,
+    pending_jobs INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS cluster_samples_by_time ON cluster_samples (partition, time);
+CREATE TABLE IF NOT EXISTS node_samples (
+    time INTEGER NOT NULL,
+    node TEXT NOT NULL,
+    cluster TEXT,
+    state TEXT NOT NULL,
+    total_cores INTEGER NOT NULL,
+    used_cores INTEGER NOT NULL,
+    total_mem INTEGER NOT NULL,
+    used_mem INTEGER NOT NULL,
+    total_gpus INTEGER NOT NULL,
+    used_gpus INTEGER NOT NULL,
+    load REAL,
+    jobs INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS node_samples_by_time ON node_samples (node, time);
+CREATE TABLE IF NOT EXISTS job_starts (
+    job TEXT NOT NULL,
+    cluster TEXT NOT NULL,
+    partition TEXT NOT NULL,
+    started INTEGER NOT NULL,
+    wait INTEGER NOT NULL,
+    PRIMARY KEY (cluster, job)
+);
+";
+
+/// Old samples are deleted at most this often, not on every refresh.
+const PRUNE_EVERY: chrono::Duration = chrono::Duration::hours(1);
+
+/// `text` as an SQL string literal.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// A partition's usage averaged over a slice of time, for the charts.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterSample {
+    /// Unix time of the slice's first sample
+    pub time: i64,
+    /// Percent used; `None` without any cores, memory or GPUs
+    pub cpu: Option<f64>,
+    pub mem: Option<f64>,
+    pub gpu: Option<f64>,
+    pub running_jobs: f64,
+    pub pending_jobs: f64,
+}
+
+/// A `job_starts` row.
+#[derive(Deserialize)]
+struct JobStart {
+    partition: String,
+    started: i64,
+    wait: i64,
+}
+
+/// SQL for the writer thread, in `mode` (`-list`, `-json`, ...), and where to send what it
+/// printed; a write's isn't waited for.
+struct Statement {
+    mode: &'static str,
+    sql: String,
+    reply: Option<mpsc::Sender<Result<String>>>,
+}
+
+pub struct HistoryDb {
+    path: PathBuf,
+    /// `None` keeps every sample
+    retention: Option<chrono::Duration>,
+    nodes: bool,
+    last_prune: Option<DateTime<Utc>>,
+    /// Closed on drop, for the writer to finish
+    statements: Option<mpsc::Sender<Statement>>,
+    writer: Option<JoinHandle<()>>,
+    /// Why the last write failed, reported by the next
+    write_error: Arc<Mutex<Option<String>>>,
+}
+
+impl HistoryDb {
+    /// Create the database and its tables if needed; fails if `sqlite3` can't run.
+    pub fn open(config: &HistoryConfig) -> Result<Self> {
+        let path = config.path.clone().unwrap_or_else(|| paths::config_dir().join("history.db"));
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).context("Failed to create the history directory")?;
+        }
+        let (statements, incoming) = mpsc::channel::<Statement>();
+        let write_error = Arc::new(Mutex::new(None));
+        let writer = {
+            let (path, write_error) = (path.clone(), write_error.clone());
+            std::thread::spawn(move || {
+                for statement in incoming {
+                    let output = run(&path, statement.mode, &statement.sql);
+                    match (statement.reply, output) {
+                        (Some(reply), output) => drop(reply.send(output)),
+                        (None, Err(e)) => *write_error.lock().unwrap() = Some(format!("{:#}", e)),
+                        (None, Ok(_)) => {},
+                    }
+                }
+            })
+        };
+        let db = Self {
+            path,
+            retention: (config.retention_days > 0).then(|| chrono::Duration::days(config.retention_days.into())),
+            nodes: config.nodes,
+            last_prune: None,
+            statements: Some(statements),
+            writer: Some(writer),
+            write_error,
+        };
+        db.run("-list", SCHEMA)?;
+        Ok(db)
+    }
+
+    /// What `sql` printed in `mode`, once the writes before it are done.
+    fn run(&self, mode: &'static str, sql: &str) -> Result<String> {
+        let (reply, output) = mpsc::channel();
+        let statement = Statement { mode, sql: sql.to_string(), reply: Some(reply) };
+        self.statements.as_ref().and_then(|statements| statements.send(statement).ok()).context("The history writer stopped")?;
+        output.recv().context("The history writer stopped")?
+    }
+
+    /// The rows of a query, one object per row; sqlite3 prints nothing at all for none.
+    fn query<T: DeserializeOwned>(&self, sql: &str) -> Result<Vec<T>> {
+        let output = self.run("-json", sql)?;
+        if output.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&output).with_context(|| format!("Unexpected output of sqlite3 for {}", self.path.display()))
+    }
+
+    /// `partition`'s samples of the `window` up to `until`, averaged over `slices` equal slices
+    /// of it, oldest first; slices without samples are left out.
+    pub fn cluster_samples(&self, partition: &str, until: DateTime<Utc>, window: chrono::Duration, slices: i64) -> Result<Vec<ClusterSample>> {
+        let since = (until - window).timestamp();
+        let slice = (window.num_seconds() / slices).max(1);
+        self.query(&format!(
+            "SELECT MIN(time) AS time,
+                    AVG(used_cores * 100.0 / NULLIF(total_cores, 0)) AS cpu,
+                    AVG(used_mem * 100.0 / NULLIF(total_mem, 0)) AS mem,
+                    AVG(used_gpus * 100.0 / NULLIF(total_gpus, 0)) AS gpu,
+                    AVG(running_jobs) AS running_jobs,
+                    AVG(pending_jobs) AS pending_jobs
+             FROM cluster_samples
+             WHERE partition = {} AND time > {} AND time <= {}
+             GROUP BY (time - {}) / {}
+             ORDER BY time;",
+            quote(partition), since, until.timestamp(), since, slice,
+        ))
+    }
+
+    /// How long the jobs seen starting since `since` waited.
+    pub fn job_waits(&self, since: DateTime<Utc>) -> Result<Vec<JobWait>> {
+        let starts: Vec<JobStart> = self.query(&format!(
+            "SELECT partition, started, wait FROM job_starts WHERE started >= {};",
+            since.timestamp(),
+        ))?;
+        Ok(starts
+            .into_iter()
+            .filter_map(|start| Some(JobWait {
+                partition: start.partition,
+                started: DateTime::from_timestamp(start.started, 0)?,
+                wait: chrono::Duration::seconds(start.wait),
+            }))
+            .collect())
+    }
+
+    /// Store one refresh of `partition`: its totals, job counts, when its running jobs started
+    /// (once each) and, unless turned off, its nodes.
+    pub fn record(
+        &mut self,
+        time: DateTime<Utc>,
+        partition: &str,
+        stats: &ClusterStats,
+        jobs: &[Job],
+        pending: usize,
+        nodes: &[Node],
+    ) -> Result<()> {
+        let secs = time.timestamp();
+        let mut sql = String::from("BEGIN;\n");
+        sql += &format!(
+            "INSERT INTO cluster_samples VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {});\n",
+            secs, quote(partition),
+            stats.total_nodes, stats.avail_nodes,
+            stats.total_cores, stats.used_cores,
+            stats.total_memory, stats.used_memory,
+            stats.total_gpus(), stats.used_gpus(),
+            stats.current_watts, jobs.len(), pending,
+        );
+        for job in jobs.iter().filter(|job| job.state == JobState::Running) {
+            let started = time - job.elapsed;
+            // Schedulers that don't report the submit time give the time of the refresh
+            if started < job.submit_time {
+                continue;
+            }
+            sql += &format!(
+                "INSERT OR IGNORE INTO job_starts VALUES ({}, {}, {}, {}, {});\n",
+                quote(&job.id), quote(job.cluster.as_deref().unwrap_or("")), quote(&job.partition),
+                started.timestamp(), (started - job.submit_time).num_seconds(),
+            );
+        }
+        if self.nodes {
+            for node in nodes {
+                sql += &format!(
+                    "INSERT INTO node_samples VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {});\n",
+                    secs, quote(&node.id),
+                    node.cluster.as_deref().map_or("NULL".to_string(), quote),
+                    quote(&node.state_label()),
+                    node.total_cores, node.used_cores,
+                    node.total_mem, node.used_mem,
+                    node.total_gpus(), node.used_gpus(),
+                    // NaN and inf aren't SQL
+                    node.cpu_load.filter(|load| load.is_finite()).map_or("NULL".to_string(), |load| load.to_string()),
+                    node.jobs.len(),
+                );
+            }
+        }
+        if let Some(retention) = self.retention.filter(|_| self.last_prune.is_none_or(|last| time - last >= PRUNE_EVERY)) {
+            let cutoff = (time - retention).timestamp();
+            sql += &format!("DELETE FROM cluster_samples WHERE time < {};\n", cutoff);
+            sql += &format!("DELETE FROM node_samples WHERE time < {};\n", cutoff);
+            sql += &format!("DELETE FROM job_starts WHERE started < {};\n", cutoff);
+            self.last_prune = Some(time);
+        }
+        sql += "COMMIT;\n";
+        if let Some(ref statements) = self.statements {
+            let _ = statements.send(Statement { mode: "-list", sql, reply: None });
+        }
+        match self.write_error.lock().unwrap().take() {
+            Some(e) => Err(anyhow!(e)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The writes still queued are finished first.
+impl Drop for HistoryDb {
+    fn drop(&mut self) {
+        self.statements = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Run `sql` against the database at `path`, waiting a little if another NodeStat is writing,
+/// and return what it printed in `mode`.
+fn run(path: &Path, mode: &str, sql: &str) -> Result<String> {
+    let mut child = Command::new("sqlite3")
+        .args(["-batch", "-bail", mode, "-cmd", ".timeout 5000"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run sqlite3, which [history] needs")?;
+    child.stdin.take().context("no stdin")?.write_all(sql.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("{}: {}", path.display(), message.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
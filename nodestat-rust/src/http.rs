@@ -0,0 +1,169 @@
+//! `serve --http`: the daemon's snapshots as JSON over plain HTTP, for tools that would rather
+//! not speak gRPC or run scheduler commands themselves. `/nodes`, `/jobs` and `/stats` take an
+//! optional `?partition=` (default: the scheduler's default partition); `/partitions` lists them
+//! with their limits where the scheduler reports them. A partition nobody watches is polled on
+//! the first request and its snapshot reused until the next poll.
+
+use crate::daemon::Daemon;
+use crate::models::{ClusterStats, Node, Partition};
+use anyhow::{anyhow, Context, Result};
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// Where the API listens and the token it asks for, if any.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub listen: SocketAddr,
+    /// Requests must send `Authorization: Bearer <token>`
+    pub token: Option<String>,
+}
+
+/// `:8080` listens on every address, like `0.0.0.0:8080`.
+pub fn parse_listen(text: &str) -> Result<SocketAddr, String> {
+    let text = match text.starts_with(':') {
+        true => format!("0.0.0.0{}", text),
+        false => text.to_string(),
+    };
+    text.parse().map_err(|e| format!("{} (expected an address like :8080 or 127.0.0.1:8080)", e))
+}
+
+struct AppState {
+    daemon: Arc<Daemon>,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PartitionQuery {
+    partition: Option<String>,
+}
+
+/// A partition's name and limits, as `/partitions` lists them.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum PartitionEntry {
+    Name { name: String },
+    Limits(Partition),
+}
+
+/// A failed request, answered as `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        // The scheduler behind the daemon failed, not the request
+        ApiError(StatusCode::BAD_GATEWAY, format!("{:#}", e))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// Already-serialized JSON from the snapshot, passed on as it is.
+fn raw_json(json: String) -> Response {
+    ([(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))], json).into_response()
+}
+
+impl AppState {
+    /// The queried partition, else the one the scheduler marks as its default, else its first.
+    async fn partition(&self, query: PartitionQuery) -> Result<String> {
+        if let Some(partition) = query.partition {
+            return Ok(partition);
+        }
+        let list = self.daemon.partition_list().await?;
+        let limits: Vec<Partition> = serde_json::from_str(&list.partitions_json).unwrap_or_default();
+        limits
+            .into_iter()
+            .find(|partition| partition.is_default)
+            .map(|partition| partition.name)
+            .or_else(|| list.partitions.first().cloned())
+            .ok_or_else(|| anyhow!("The scheduler lists no partitions; ask for one with ?partition="))
+    }
+
+    /// The snapshot of the queried partition; one whose nodes couldn't be fetched is an error.
+    async fn snapshot(&self, query: PartitionQuery) -> Result<crate::rpc::Snapshot> {
+        let snapshot = self.daemon.snapshot(&self.partition(query).await?).await?;
+        match snapshot.error {
+            Some(error) => Err(anyhow!(error)),
+            None => Ok(snapshot),
+        }
+    }
+}
+
+async fn nodes(State(state): State<Arc<AppState>>, Query(query): Query<PartitionQuery>) -> Result<Response, ApiError> {
+    Ok(raw_json(state.snapshot(query).await?.nodes_json))
+}
+
+async fn jobs(State(state): State<Arc<AppState>>, Query(query): Query<PartitionQuery>) -> Result<Response, ApiError> {
+    Ok(raw_json(state.snapshot(query).await?.jobs_json))
+}
+
+async fn stats(State(state): State<Arc<AppState>>, Query(query): Query<PartitionQuery>) -> Result<Json<ClusterStats>, ApiError> {
+    let snapshot = state.snapshot(query).await?;
+    let nodes: Vec<Node> = serde_json::from_str(&snapshot.nodes_json).context("Unexpected nodes in the snapshot")?;
+    Ok(Json(ClusterStats::of(&nodes)))
+}
+
+async fn partitions(State(state): State<Arc<AppState>>) -> Result<Json<Vec<PartitionEntry>>, ApiError> {
+    let list = state.daemon.partition_list().await?;
+    let limits: Vec<Partition> = serde_json::from_str(&list.partitions_json).unwrap_or_default();
+    let entries = list
+        .partitions
+        .iter()
+        .map(|name| match limits.iter().find(|partition| partition.name == *name) {
+            Some(partition) => PartitionEntry::Limits(partition.clone()),
+            None => PartitionEntry::Name { name: name.clone() },
+        })
+        .collect();
+    Ok(Json(entries))
+}
+
+async fn require_token(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(ref token) = state.token else {
+        return next.run(request).await;
+    };
+    let sent = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if sent != Some(token.as_str()) {
+        let mut response = ApiError(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token".to_string()).into_response();
+        response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return response;
+    }
+    next.run(request).await
+}
+
+pub async fn serve(daemon: Arc<Daemon>, options: Options) -> Result<()> {
+    let listener = TcpListener::bind(options.listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", options.listen))?;
+    let auth = if options.token.is_some() { ", bearer token required" } else { "" };
+    eprintln!("NodeStat HTTP API listening on {}{}", options.listen, auth);
+    run(listener, daemon, options.token).await
+}
+
+/// Answer the API's requests on `listener` until it fails.
+pub async fn run(listener: TcpListener, daemon: Arc<Daemon>, token: Option<String>) -> Result<()> {
+    let state = Arc::new(AppState { daemon, token });
+    let router = Router::new()
+        .route("/nodes", get(nodes))
+        .route("/jobs", get(jobs))
+        .route("/stats", get(stats))
+        .route("/partitions", get(partitions))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state);
+    axum::serve(listener, router).await.context("Failed to serve HTTP")
+}
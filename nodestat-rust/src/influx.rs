@@ -0,0 +1,54 @@
+//! InfluxDB line protocol, as `nodes --output influx` and `stats --output influx` print it for
+//! `telegraf exec` or a write to `/api/v2/write`:
+//! `nodestat_node,node=c001,partition=batch used_cores=8i,load=7.5 1709294400000000000`.
+
+use chrono::{DateTime, Utc};
+
+/// One line: a measurement, its tags and fields, and when.
+pub struct Point {
+    line: String,
+    fields: Vec<String>,
+}
+
+/// Commas, spaces and (in tags) equals signs are escaped with a backslash.
+fn escape(text: &str, equals: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == ',' || c == ' ' || (equals && c == '=') || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl Point {
+    pub fn new(measurement: &str) -> Self {
+        Self { line: escape(measurement, false), fields: Vec::new() }
+    }
+
+    /// Tags must be added before fields; empty values are left out, as the protocol wants.
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        if !value.is_empty() {
+            self.line += &format!(",{}={}", escape(key, true), escape(value, true));
+        }
+        self
+    }
+
+    pub fn int(mut self, key: &str, value: impl Into<i64>) -> Self {
+        self.fields.push(format!("{}={}i", escape(key, true), value.into()));
+        self
+    }
+
+    pub fn float(mut self, key: &str, value: f64) -> Self {
+        if value.is_finite() {
+            self.fields.push(format!("{}={}", escape(key, true), value));
+        }
+        self
+    }
+
+    /// The line with its newline, timestamped in nanoseconds.
+    pub fn line(self, time: DateTime<Utc>) -> String {
+        format!("{} {} {}\n", self.line, self.fields.join(","), time.timestamp_nanos_opt().unwrap_or_default())
+    }
+}
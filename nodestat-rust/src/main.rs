@@ -1,32 +1,281 @@
-use clap::Parser;
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-mod models;
-mod schedulers;
+mod alerts;
+mod availability;
+mod backoff;
+mod changes;
+mod clipboard;
+mod config;
+mod daemon;
+mod desktop;
+mod filter;
+mod health;
+mod history;
+mod http;
+mod influx;
+mod mqtt;
+mod placement;
+mod recent;
+mod search;
+mod session;
+mod template;
+mod topology;
 mod ui;
+mod users;
+mod utilization;
+mod waits;
+mod watchlist;
+mod webhook;
 
+use nodestat_core::{arrays, clock, models, paths, recording, rpc, schedulers};
+
+use clock::ClusterTz;
+use config::{Config, Profile};
+use filter::NodeFilter;
 use schedulers::*;
+use session::SessionState;
 use ui::App;
 
 #[derive(Parser)]
 #[command(name = "nodestat")]
 #[command(about = "Modern TUI for cluster monitoring")]
 struct Cli {
-    /// Partition/queue to display
-    #[arg(short = 'q', long = "partition", default_value = "batch")]
-    partition: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Partition/queue to display, a comma-separated list of them, or `all` (default: the
+    /// scheduler's default partition)
+    #[arg(short = 'q', long = "partition", global = true)]
+    partition: Option<String>,
+
+    /// Scheduler system (auto, slurm, slurmrest, torque, pbspro, lsf, sge, flux, exec, mock, or a plugin name; default: auto)
+    #[arg(short = 's', long = "scheduler", global = true)]
+    scheduler: Option<String>,
+
+    /// Run scheduler commands on this host over SSH (uses your ssh config and agent)
+    #[arg(long = "host", global = true)]
+    host: Option<String>,
+
+    /// Slurm: show these clusters of a multi-cluster site (comma-separated, passed as -M)
+    #[arg(long = "slurm-clusters", value_delimiter = ',', global = true)]
+    slurm_clusters: Vec<String>,
+
+    /// Slurm: show every cluster of the local cluster's federation
+    #[arg(long = "federation", conflicts_with = "slurm_clusters", global = true)]
+    federation: bool,
+
+    /// Get snapshots from a `nodestat serve` daemon instead of running scheduler commands (e.g.
+    /// http://login1:50051, or unix:/path/to/socket; the default socket is used when it exists)
+    #[arg(long = "connect", conflicts_with_all = ["scheduler", "host", "clusters", "slurm_clusters", "federation"], global = true)]
+    connect: Option<String>,
+
+    /// Show every cluster from the [[clusters]] entries in config.toml in one view
+    #[arg(long = "clusters", global = true)]
+    clusters: bool,
+
+    /// Cluster timezone for displayed times (IANA name, e.g. America/Chicago; default: local)
+    #[arg(long = "tz", global = true)]
+    tz: Option<String>,
+
+    /// Color theme: dark, light, solarized, or a [themes.<name>] from config.toml (default: dark)
+    #[arg(long = "theme", global = true)]
+    theme: Option<String>,
+
+    /// Colorblind-friendly: state symbols, and the colorblind palette unless --theme is given
+    #[arg(long = "colorblind", global = true)]
+    colorblind: bool,
+
+    /// Node table columns in order, e.g. node,state,reason (node, cluster, partition, cpu, memory,
+    /// avail-cpu, avail-mem, gpu, load, state, jobs, health, features, reason)
+    #[arg(long = "columns", value_delimiter = ',', global = true)]
+    columns: Option<Vec<String>>,
+
+    /// Only the nodes matching this expression, in the TUI (like :filter) and in `nodes`, e.g.
+    /// 'state==idle && avail_mem_gb>=64 && features~a100'
+    #[arg(long = "filter", global = true)]
+    filter: Option<String>,
+
+    /// Seconds between automatic refreshes, at least 5 (default: the profile's refresh_interval, or 30)
+    #[arg(long = "refresh", global = true)]
+    refresh: Option<u64>,
+
+    /// Append what every refresh fetched to this file, one JSON snapshot per line, for --replay
+    #[arg(long = "record", global = true)]
+    record: Option<PathBuf>,
+
+    /// Show a --record file instead of the cluster, without running any scheduler command (P plays
+    /// and pauses, [ and ] step, { and } jump ten snapshots)
+    #[arg(long = "replay", conflicts_with_all = ["scheduler", "host", "connect", "clusters", "slurm_clusters", "federation"], global = true)]
+    replay: Option<PathBuf>,
+
+    /// Show a snapshot from `export` or :dump instead of the cluster, e.g. one attached to a bug report
+    #[arg(long = "from-snapshot", conflicts_with_all = ["replay", "scheduler", "host", "connect", "clusters", "slurm_clusters", "federation"], global = true)]
+    from_snapshot: Option<PathBuf>,
+
+    /// Load defaults from [profiles.<name>] in config.toml
+    #[arg(short = 'p', long = "profile", global = true)]
+    profile: Option<String>,
+
+    /// Start the TUI with the default partition, sort, filters, columns and theme instead of last time's
+    #[arg(long = "fresh", global = true)]
+    fresh: bool,
 
-    /// Scheduler system (slurm, torque, mock)
-    #[arg(short = 's', long = "scheduler", default_value = "slurm")]
-    scheduler: String,
+    /// Enable admin actions (editing node reasons, draining and resuming nodes)
+    #[arg(long = "admin", global = true)]
+    admin: bool,
 
     /// Show version
     #[arg(short = 'v', long = "version")]
     version: bool,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// The interactive monitor (the default)
+    Tui,
+
+    /// Print the stats and the node table, or a line per node with --format, and exit
+    Nodes {
+        /// A line per node from this template, e.g. '{id}\t{state}\t{avail_cores}/{total_cores}'
+        /// (fields: id, state, partitions, cluster, total_cores, used_cores, avail_cores, total_mem,
+        /// used_mem, avail_mem, total_gpus, used_gpus, avail_gpus, load, jobs, users, features,
+        /// reason, health, eta; memory in GB)
+        #[arg(long = "format", conflicts_with = "output")]
+        format: Option<String>,
+
+        /// influx: InfluxDB line protocol, a point per partition and per node (for telegraf exec)
+        #[arg(long = "output", value_enum, default_value = "text")]
+        output: Output,
+    },
+
+    /// Print the running jobs of the partition and exit
+    Jobs {
+        /// A line per job from this template (fields: id, user, name, state, partition, cluster,
+        /// nodes, num_nodes, cpus, mem, gpus, elapsed, time_limit, priority, reason)
+        #[arg(long = "format")]
+        format: Option<String>,
+
+        /// Only this user's jobs
+        #[arg(short = 'u', long = "user")]
+        user: Option<String>,
+    },
+
+    /// Print the pending jobs of the partition in scheduling order and exit
+    Queue {
+        /// A line per job from this template, with the fields of `jobs`
+        #[arg(long = "format")]
+        format: Option<String>,
+
+        /// Only this user's jobs
+        #[arg(short = 'u', long = "user")]
+        user: Option<String>,
+    },
+
+    /// Print the partition's usage totals and exit
+    Stats {
+        /// One line from this template (fields: partition, total_nodes, avail_nodes, total_cores,
+        /// used_cores, avail_cores, total_mem, used_mem, avail_mem, total_gpus, used_gpus, watts)
+        #[arg(long = "format", conflicts_with = "output")]
+        format: Option<String>,
+
+        /// influx: InfluxDB line protocol, a point per partition
+        #[arg(long = "output", value_enum, default_value = "text")]
+        output: Output,
+    },
+
+    /// Print a summary of the partition for a ticket (stats, top users, down and draining nodes)
+    /// and exit
+    Report {
+        /// GitHub/GitLab-flavoured Markdown to paste into an issue
+        #[arg(long = "markdown")]
+        markdown: bool,
+    },
+
+    /// Write the nodes, jobs, queue and stats as JSON (a snapshot --from-snapshot can show) and exit
+    Export {
+        /// File to write instead of stdout
+        #[arg(short = 'o', long = "output")]
+        output: Option<PathBuf>,
+
+        /// Replace the usernames with user1, user2, … and leave out job directories
+        #[arg(long = "anonymize")]
+        anonymize: bool,
+    },
+
+    /// Monitoring plugin (Nagios, Icinga): one status line, exit 0 (OK), 1 (WARNING) or 2 (CRITICAL,
+    /// the scheduler can't be reached)
+    Check {
+        /// Warn when more nodes than this are down, drained or draining
+        #[arg(long = "max-down")]
+        max_down: Option<u32>,
+
+        /// Warn when fewer nodes than this have free cores
+        #[arg(long = "min-free")]
+        min_free: Option<u32>,
+    },
+
+    /// Poll the scheduler once for everyone and stream snapshots to `--connect` clients over gRPC
+    Serve {
+        /// Address to listen on (default: 0.0.0.0:50051, or none with --socket)
+        #[arg(long = "listen")]
+        listen: Option<SocketAddr>,
+
+        /// Listen on a Unix socket every local user can connect to (default path:
+        /// /run/nodestat/nodestat.sock, which nodestat connects to by itself)
+        #[arg(long = "socket", value_name = "PATH", num_args = 0..=1, default_missing_value = daemon::DEFAULT_SOCKET)]
+        socket: Option<PathBuf>,
+
+        /// Seconds between polls, at least 5 (default: --refresh, the profile's refresh_interval, or 30)
+        #[arg(long = "interval")]
+        interval: Option<u64>,
+
+        /// Also serve the snapshots as JSON over HTTP on this address (`:8080` for every interface):
+        /// /nodes, /jobs and /stats with ?partition=, and /partitions
+        #[arg(long = "http", value_name = "ADDRESS", value_parser = http::parse_listen)]
+        http: Option<SocketAddr>,
+
+        /// Require `Authorization: Bearer <token>` on HTTP requests, with the token read from this file
+        #[arg(long = "http-token-file", value_name = "PATH", requires = "http")]
+        http_token_file: Option<PathBuf>,
+
+        /// Publish every poll to this MQTT broker (host[:port]); the rest is set under [mqtt] in config.toml
+        #[arg(long = "mqtt", value_name = "BROKER")]
+        mqtt: Option<String>,
+    },
+}
+
+/// How `nodes` and `stats` print.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Output {
+    Text,
+    Influx,
+}
+
+impl Command {
+    /// The `--format` template and the fields it may use.
+    fn format(&self) -> Option<(&str, &'static [&'static str])> {
+        match self {
+            Command::Nodes { format, .. } => Some((format.as_deref()?, &template::NODE_FIELDS)),
+            Command::Jobs { format, .. } | Command::Queue { format, .. } => Some((format.as_deref()?, &template::JOB_FIELDS)),
+            Command::Stats { format, .. } => Some((format.as_deref()?, &template::STATS_FIELDS)),
+            _ => None,
+        }
+    }
+
+    /// Whether it prints InfluxDB line protocol, which counts the queue too.
+    fn is_influx(&self) -> bool {
+        matches!(self, Command::Nodes { output: Output::Influx, .. } | Command::Stats { output: Output::Influx, .. })
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     if cli.version {
         println!("NodeStat TUI v1.0.0 (Rust)");
@@ -34,20 +283,364 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
-    let scheduler_type = match cli.scheduler.as_str() {
-        "slurm" => SchedulerType::Slurm,
-        "torque" => SchedulerType::Torque,
-        "mock" => SchedulerType::Mock,
-        _ => {
-            eprintln!("Error: Invalid scheduler type '{}'. Use 'slurm', 'torque', or 'mock'", cli.scheduler);
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let profile = match cli.profile {
+        Some(ref name) => match config.profiles.get(name) {
+            Some(profile) => profile.clone(),
+            None => {
+                eprintln!("Error: No profile '{}' in config.toml", name);
+                let names: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+                if !names.is_empty() {
+                    eprintln!("Available profiles: {}", names.join(", "));
+                }
+                std::process::exit(1);
+            }
+        },
+        None => Profile::default(),
+    };
+    cli.scheduler = cli.scheduler.or(profile.scheduler);
+    cli.host = cli.host.or(profile.host);
+    if cli.scheduler.is_none() && cli.host.is_none() && !cli.clusters {
+        cli.connect = cli.connect.or(profile.connect);
+    }
+
+    // The TUI picks up where it was left; flags and the profile still win, and config.toml's
+    // defaults only fill in what neither set. Replays keep to what was recorded.
+    let command = cli.command.take().unwrap_or(Command::Tui);
+    let live = cli.replay.is_none() && cli.from_snapshot.is_none();
+    // A site's daemon already polls for everyone here; --scheduler or --host go around it
+    let direct = cli.connect.is_some() || cli.scheduler.is_some() || cli.host.is_some() || cli.clusters
+        || cli.federation || !cli.slurm_clusters.is_empty();
+    if live && !direct && !matches!(command, Command::Serve { .. }) && Path::new(daemon::DEFAULT_SOCKET).exists() {
+        cli.connect = Some(format!("unix:{}", daemon::DEFAULT_SOCKET));
+    }
+    let site = site(&cli);
+    let saved = match command {
+        Command::Tui if live && !cli.fresh => SessionState::load(&site),
+        _ => SessionState::default(),
+    };
+    cli.partition = cli.partition.or(profile.partition).or(saved.partition.clone());
+    cli.tz = cli.tz.or(profile.tz);
+    cli.theme = cli.theme.or(profile.theme).or(saved.theme.clone().filter(|name| ui::Theme::resolve(name, &config.themes).is_ok()));
+    cli.columns = cli.columns.or(profile.columns).or(saved.columns.clone().filter(|names| ui::Column::parse_list(names).is_ok()));
+    let (chosen_theme, chosen_columns) = (cli.theme.clone(), cli.columns.clone());
+    cli.theme = cli.theme.or(config.theme.clone());
+    cli.colorblind |= profile.colorblind.unwrap_or(config.colorblind);
+    cli.columns = cli.columns.or(config.columns.clone());
+    if cli.colorblind && cli.theme.is_none() {
+        cli.theme = Some("colorblind".to_string());
+    }
+
+    let cluster_tz = match ClusterTz::parse(cli.tz.as_deref().unwrap_or("local")) {
+        Ok(tz) => tz,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let rack_pattern = config.topology.as_ref().and_then(|t| t.rack_pattern.as_deref());
+    let rack_pattern = match rack_pattern.map(regex::Regex::new).transpose() {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            eprintln!("Error: Invalid [topology] rack_pattern: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let prefix_pattern = config.topology.as_ref().and_then(|t| t.prefix_pattern.as_deref());
+    let prefix_pattern = match prefix_pattern.map(regex::Regex::new).transpose() {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            eprintln!("Error: Invalid [topology] prefix_pattern: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut theme = match cli.theme.as_deref().map(|name| ui::Theme::resolve(name, &config.themes)).transpose() {
+        Ok(theme) => theme.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    theme.symbols |= cli.colorblind;
+
+    let keymap = match ui::Keymap::new(&config.keys) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let columns = match cli.columns.as_deref().map(ui::Column::parse_list).transpose() {
+        Ok(columns) => columns,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let format = match command.format().map(|(source, fields)| template::Template::parse(source, fields)).transpose() {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("Error: Invalid --format: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    let node_filter = match cli.filter.as_deref().map(NodeFilter::parse).transpose() {
+        Ok(node_filter) => node_filter.flatten(),
+        Err(e) => {
+            eprintln!("Error: Invalid --filter: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let alerts = match alerts::Alerts::new(&config.alerts) {
+        Ok(alerts) => alerts,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let replay = match cli.replay.as_deref().or(cli.from_snapshot.as_deref()).map(recording::Replay::load).transpose() {
+        Ok(replay) => replay.map(Arc::new),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(ref replay) = replay {
+        cli.partition = cli.partition.or(Some(replay.frame().partition.clone()));
+    }
+    let scheduler = match replay {
+        Some(ref replay) => Ok(Box::new(ReplayScheduler::new(replay.clone())) as Box<dyn Scheduler>),
+        None => build_scheduler(&cli, &config, cluster_tz).await.map(|scheduler| rate_limited(scheduler, &config)),
+    };
+    let scheduler = match scheduler {
+        Ok(scheduler) => scheduler,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
             std::process::exit(1);
         }
     };
 
-    let scheduler = create_scheduler(scheduler_type);
-    let mut app = App::new(scheduler, cli.partition).await?;
-    
-    app.run().await?;
+    if let Command::Serve { listen, socket, interval, http, http_token_file, mqtt } = command {
+        if cli.connect.is_some() {
+            eprintln!("Error: serve polls the scheduler itself and can't use --connect");
+            std::process::exit(1);
+        }
+        let interval = interval.or(cli.refresh).or(profile.refresh_interval).unwrap_or(30).max(ui::MIN_REFRESH_SECS);
+        let token = match http_token_file.as_deref().map(read_token).transpose() {
+            Ok(token) => token,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        };
+        let http = http.map(|listen| http::Options { listen, token });
+        let mqtt = mqtt.or(config.mqtt.broker.clone()).map(|broker| (broker, config.mqtt.clone()));
+        let listen = match socket {
+            Some(_) => listen,
+            None => listen.or(Some(SocketAddr::from(([0, 0, 0, 0], 50051)))),
+        };
+        return daemon::serve(scheduler, listen, socket, Duration::from_secs(interval), alerts, http, mqtt).await;
+    }
 
+    let mut app = App::new(scheduler, cli.partition, cli.admin, cluster_tz, &config.timeouts).await?;
+    if let Some(secs) = cli.refresh.or(profile.refresh_interval) {
+        app.set_refresh_interval(Duration::from_secs(secs));
+    }
+    app.set_rack_pattern(rack_pattern);
+    let notifications = &config.notifications;
+    app.set_notifications(notifications.bell, notifications.desktop, notifications.job_end);
+    match webhook::Webhook::parse_list(&notifications.job_end_webhooks) {
+        Ok(webhooks) => app.set_job_end_webhooks(webhooks),
+        Err(e) => {
+            eprintln!("Error: [notifications] job_end_webhooks: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Some(pattern) = prefix_pattern {
+        app.set_prefix_pattern(pattern);
+    }
+    app.set_theme(theme);
+    if let Some(columns) = columns {
+        app.set_columns(columns);
+    }
+    if let Some(command) = profile.ssh_command.or(config.ssh_command) {
+        app.set_ssh_command(command);
+    }
+    app.set_keymap(keymap);
+    app.restore_session(&saved).await;
+    if let Some(replay) = replay {
+        app.set_replay(replay).await;
+    }
+    if live && matches!(command, Command::Tui) {
+        app.set_alerts(alerts);
+    }
+    if config.history.enabled && live && matches!(command, Command::Tui) {
+        match history::HistoryDb::open(&config.history) {
+            Ok(history) => app.set_history(history),
+            Err(e) => {
+                eprintln!("Error: [history]: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(ref path) = cli.record {
+        match recording::Recorder::create(path) {
+            Ok(recorder) => app.set_recorder(recorder),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(node_filter) = node_filter {
+        app.set_filter(node_filter);
+    }
+    if matches!(command, Command::Queue { .. } | Command::Report { .. }) || command.is_influx() {
+        app.load_queue().await;
+    }
+
+    match command {
+        Command::Tui if live => {
+            let result = app.run().await;
+            let state = SessionState { columns: chosen_columns, theme: chosen_theme, ..app.session_state() };
+            if let Err(e) = state.save(&site) {
+                eprintln!("Warning: {:#}", e);
+            }
+            return result;
+        },
+        Command::Tui | Command::Serve { .. } => return app.run().await,
+        Command::Check { max_down, min_free } => {
+            let (status, line) = match app.error() {
+                Some(error) => (2, format!("CRITICAL - {}", error)),
+                None => app.check(max_down, min_free),
+            };
+            println!("{}", line);
+            std::process::exit(status);
+        },
+        _ => {},
+    }
+    if let Some(error) = app.error() {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+
+    let format = format.as_ref();
+    let output = match command {
+        Command::Nodes { output: Output::Influx, .. } => app.influx_output(true),
+        Command::Nodes { .. } => app.nodes_output(format),
+        Command::Jobs { user, .. } => app.jobs_output(format, false, user.as_deref()),
+        Command::Queue { user, .. } => app.jobs_output(format, true, user.as_deref()),
+        Command::Stats { output: Output::Influx, .. } => app.influx_output(false),
+        Command::Stats { .. } => app.stats_output(format),
+        Command::Report { markdown } => app.report_output(markdown),
+        Command::Export { output: Some(path), anonymize } => {
+            return std::fs::write(&path, app.export_json(anonymize)?).with_context(|| format!("Writing {}", path.display()));
+        },
+        Command::Export { output: None, anonymize } => app.export_json(anonymize)?,
+        Command::Tui | Command::Serve { .. } | Command::Check { .. } => unreachable!(),
+    };
+    print!("{}", output);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Which saved session applies: the profile's, the daemon's, or the scheduler's on a host.
+fn site(cli: &Cli) -> String {
+    if let Some(ref profile) = cli.profile {
+        return format!("profile:{}", profile);
+    }
+    if let Some(ref address) = cli.connect {
+        return address.clone();
+    }
+    if cli.clusters {
+        return "clusters".to_string();
+    }
+    format!("{}@{}", cli.scheduler.as_deref().unwrap_or("auto"), cli.host.as_deref().unwrap_or("local"))
+}
+
+/// The `--http-token-file`'s token, without the trailing newline.
+fn read_token(path: &std::path::Path) -> anyhow::Result<String> {
+    let token = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(anyhow::anyhow!("{} is empty", path.display()));
+    }
+    Ok(token.to_string())
+}
+
+/// Where a cluster's commands run, with `[timeouts]` from config.toml.
+fn transport_for(host: Option<&String>, config: &Config) -> Transport {
+    let transport = match host {
+        Some(host) => Transport::ssh(host.clone()),
+        None => Transport::local(),
+    };
+    transport.with_timeouts(config.timeouts.clone())
+}
+
+/// `[slurm]` from config.toml, if it's Slurm.
+fn apply_slurm_config(scheduler_type: &mut SchedulerType, config: &Config) {
+    if let SchedulerType::Slurm(_, ref mut slurm) = scheduler_type {
+        *slurm = config.slurm.clone();
+    }
+}
+
+/// `[rate_limit]` from config.toml, shared by everything that asks the scheduler.
+fn rate_limited(scheduler: Box<dyn Scheduler>, config: &Config) -> Box<dyn Scheduler> {
+    match config.rate_limit.min_interval {
+        0 => scheduler,
+        secs => Box::new(RateLimitedScheduler::new(scheduler, Duration::from_secs(secs))),
+    }
+}
+
+async fn build_scheduler(cli: &Cli, config: &Config, cluster_tz: ClusterTz) -> anyhow::Result<Box<dyn Scheduler>> {
+    if let Some(ref address) = cli.connect {
+        return Ok(Box::new(RemoteScheduler::new(address)?));
+    }
+
+    if !cli.clusters {
+        let transport = transport_for(cli.host.as_ref(), config);
+        let mut scheduler_type = resolve_scheduler(cli.scheduler.as_deref().unwrap_or("auto"), &transport, config.exec.as_ref()).await?;
+        apply_slurm_config(&mut scheduler_type, config);
+        if cli.federation || !cli.slurm_clusters.is_empty() {
+            let SchedulerType::Slurm(ref mut clusters, _) = scheduler_type else {
+                anyhow::bail!("--slurm-clusters and --federation only apply to Slurm");
+            };
+            *clusters = if cli.federation {
+                SlurmClusters::Federation
+            } else {
+                SlurmClusters::Listed(cli.slurm_clusters.clone())
+            };
+        }
+        return create_scheduler(scheduler_type, cluster_tz, transport);
+    }
+
+    if config.clusters.is_empty() {
+        anyhow::bail!("--clusters needs at least one [[clusters]] entry in config.toml");
+    }
+
+    let mut members = Vec::new();
+    for cluster in &config.clusters {
+        let transport = transport_for(cluster.host.as_ref(), config);
+        let mut scheduler_type = resolve_scheduler(&cluster.scheduler, &transport, config.exec.as_ref())
+            .await
+            .with_context(|| format!("Cluster '{}'", cluster.name))?;
+        apply_slurm_config(&mut scheduler_type, config);
+        let scheduler = create_scheduler(scheduler_type, cluster_tz, transport)
+            .with_context(|| format!("Cluster '{}'", cluster.name))?;
+        members.push(ClusterMember::new(cluster.name.clone(), scheduler, cluster.partition.clone()));
+    }
+    Ok(Box::new(MultiScheduler::new(members)))
+}
@@ -0,0 +1,169 @@
+//! `serve --mqtt` / `[mqtt]`: every poll of the partitions published to an MQTT broker, for
+//! dashboards and status displays. Under the `topic_prefix` (default `nodestat`):
+//!
+//! - `<prefix>/status`: `online`, or `offline` once the daemon is gone (retained)
+//! - `<prefix>/<partition>/stats`: the totals and job counts at each poll (retained)
+//! - `<prefix>/<partition>/nodes/<node>`: a node, whenever it changed (retained)
+//! - `<prefix>/<partition>/events`: `{"node", "from", "to", ...}` when a node changes state
+//!
+//! `/`, `+` and `#` in partition and node names become `_`.
+
+use crate::config::MqttConfig;
+use crate::daemon::Daemon;
+use crate::models::{ClusterStats, Job, Node};
+use crate::rpc::Snapshot;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 1883;
+
+/// Wait this long before reconnecting to a broker that went away.
+const RECONNECT_AFTER: Duration = Duration::from_secs(5);
+
+/// `name` as one level of a topic.
+fn topic_level(name: &str) -> String {
+    name.replace(['/', '+', '#'], "_")
+}
+
+/// `mqtt://host:port`, `host:port` or just `host`.
+fn parse_broker(broker: &str) -> Result<(String, u16)> {
+    let address = broker.strip_prefix("mqtt://").unwrap_or(broker).trim_end_matches('/');
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| anyhow!("Invalid port in MQTT broker '{}'", broker))?),
+        None => (address, DEFAULT_PORT),
+    };
+    if host.is_empty() {
+        return Err(anyhow!("No host in MQTT broker '{}'", broker));
+    }
+    Ok((host.to_string(), port))
+}
+
+/// Publish every snapshot of the configured partitions (default: all of them) until the
+/// daemon stops. A broker that can't be reached is retried; the polls go on meanwhile.
+pub async fn publish(daemon: Arc<Daemon>, broker: &str, config: MqttConfig) -> Result<()> {
+    let (host, port) = parse_broker(broker)?;
+    let prefix = config.topic_prefix.trim_end_matches('/').to_string();
+    let client_id = config.client_id.clone().unwrap_or_else(|| format!("nodestat-{}", std::process::id()));
+    let mut options = MqttOptions::new(client_id, host, port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(format!("{}/status", prefix), "offline", QoS::AtLeastOnce, true));
+    if let Some(ref username) = config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    let (client, mut events) = AsyncClient::new(options, 1000);
+    // The event loop does the actual sending and reconnects on the next poll after an error;
+    // each connection says it's online again, after the broker sent the will for the last one
+    let (label, status, announcer) = (broker.to_string(), format!("{}/status", prefix), client.clone());
+    tokio::spawn(async move {
+        loop {
+            match events.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    let _ = announcer.try_publish(&status, QoS::AtLeastOnce, true, "online");
+                },
+                Ok(_) => {},
+                Err(e) => {
+                    eprintln!("Warning: MQTT broker {}: {}", label, e);
+                    tokio::time::sleep(RECONNECT_AFTER).await;
+                },
+            }
+        }
+    });
+
+    let partitions = match config.partitions.is_empty() {
+        true => daemon.partition_list().await?.partitions.clone(),
+        false => config.partitions.clone(),
+    };
+    eprintln!("Publishing {} to MQTT broker {} under {}/", partitions.join(", "), broker, prefix);
+    let mut tasks = Vec::new();
+    for partition in partitions {
+        let publisher = Publisher {
+            client: client.clone(),
+            topic: format!("{}/{}", prefix, topic_level(&partition)),
+            partition,
+            nodes: HashMap::new(),
+        };
+        tasks.push(tokio::spawn(publisher.run(daemon.clone())));
+    }
+    for task in tasks {
+        task.await?;
+    }
+    Ok(())
+}
+
+/// One partition's topics and what was last published to them.
+struct Publisher {
+    client: AsyncClient,
+    partition: String,
+    /// `<prefix>/<partition>`
+    topic: String,
+    /// Each node's JSON and state label as last published
+    nodes: HashMap<String, (String, String)>,
+}
+
+impl Publisher {
+    async fn run(mut self, daemon: Arc<Daemon>) {
+        let mut receiver = daemon.subscribe(&self.partition);
+        while receiver.changed().await.is_ok() {
+            let Some(snapshot) = receiver.borrow_and_update().clone() else {
+                continue;
+            };
+            // Queued for the event loop; only a closed client fails, and then nothing can be sent
+            if let Err(e) = self.publish(&snapshot).await {
+                eprintln!("Warning: MQTT: {}", e);
+                return;
+            }
+        }
+    }
+
+    async fn publish(&mut self, snapshot: &Snapshot) -> Result<()> {
+        let time = Utc::now().to_rfc3339();
+        if let Some(ref error) = snapshot.error {
+            let payload = json!({ "partition": self.partition, "time": time, "error": error });
+            return self.send(format!("{}/stats", self.topic), true, payload.to_string()).await;
+        }
+
+        let nodes: Vec<Node> = serde_json::from_str(&snapshot.nodes_json).unwrap_or_default();
+        let jobs: Vec<Job> = serde_json::from_str(&snapshot.jobs_json).unwrap_or_default();
+        let pending: Vec<Job> = serde_json::from_str(&snapshot.pending_jobs_json).unwrap_or_default();
+        let stats = json!({
+            "partition": self.partition,
+            "time": time,
+            "stats": ClusterStats::of(&nodes),
+            "running_jobs": jobs.len(),
+            "pending_jobs": pending.len(),
+        });
+        self.send(format!("{}/stats", self.topic), true, stats.to_string()).await?;
+
+        for node in &nodes {
+            let json = serde_json::to_string(node)?;
+            let state = node.state_label();
+            let previous = self.nodes.insert(node.id.clone(), (json.clone(), state.clone()));
+            if previous.as_ref().is_some_and(|(before, _)| *before == json) {
+                continue;
+            }
+            self.send(format!("{}/nodes/{}", self.topic, topic_level(&node.id)), true, json).await?;
+            if let Some((_, from)) = previous.filter(|(_, from)| *from != state) {
+                let event = json!({
+                    "node": node.id,
+                    "cluster": node.cluster,
+                    "partition": self.partition,
+                    "from": from,
+                    "to": state,
+                    "reason": node.reason,
+                    "time": time,
+                });
+                self.send(format!("{}/events", self.topic), false, event.to_string()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&self, topic: String, retain: bool, payload: String) -> Result<()> {
+        Ok(self.client.publish(topic, QoS::AtLeastOnce, retain, payload).await?)
+    }
+}
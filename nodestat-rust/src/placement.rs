@@ -0,0 +1,297 @@
+use crate::models::{Job, JobState, Node, Partition, GB, TB};
+use anyhow::{anyhow, Result};
+use chrono::Duration;
+use std::collections::HashMap;
+
+/// A hypothetical job request, e.g. `2n 16c 64g 1gpu 4h`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceRequest {
+    pub nodes: u32,
+    pub cores: u32,
+    /// Memory per node, in bytes
+    pub mem: u64,
+    pub gpus: u32,
+    pub walltime: Option<Duration>,
+}
+
+impl ResourceRequest {
+    /// Parse whitespace-separated `<n>n`, `<n>c`, `<n>g`/`<n>t`, `<n>gpu` and `<n>h`/`<n>m`/`<n>d` tokens.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut request = ResourceRequest {
+            nodes: 1,
+            cores: 1,
+            mem: 0,
+            gpus: 0,
+            walltime: None,
+        };
+
+        for token in input.split_whitespace() {
+            let token = token.to_lowercase();
+            let split = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+            let (number, unit) = token.split_at(split);
+            let value: u32 = number
+                .parse()
+                .map_err(|_| anyhow!("Expected a number in '{}'", token))?;
+
+            match unit {
+                "n" | "node" | "nodes" => request.nodes = value.max(1),
+                "c" | "core" | "cores" | "cpu" | "cpus" => request.cores = value,
+                "g" | "gb" => request.mem = value as u64 * GB,
+                "t" | "tb" => request.mem = value as u64 * TB,
+                "gpu" | "gpus" => request.gpus = value,
+                "m" | "min" => request.walltime = Some(Duration::minutes(value as i64)),
+                "h" => request.walltime = Some(Duration::hours(value as i64)),
+                "d" => request.walltime = Some(Duration::days(value as i64)),
+                _ => return Err(anyhow!("Unknown unit in '{}' (use n, c, g, t, gpu, m, h, d)", token)),
+            }
+        }
+
+        Ok(request)
+    }
+
+    fn fits(&self, free_cores: u32, free_mem: u64, free_gpus: u32) -> bool {
+        free_cores >= self.cores && free_mem >= self.mem && free_gpus >= self.gpus
+    }
+
+    /// Whether the walltime is within the limit of one of `node`'s partitions; limits that
+    /// aren't known don't count against it.
+    fn within_time_limit(&self, node: &Node, partitions: &[Partition]) -> bool {
+        let Some(walltime) = self.walltime else {
+            return true;
+        };
+        let limits: Vec<Option<Duration>> = partitions
+            .iter()
+            .filter(|partition| node.partitions.contains(&partition.name))
+            .map(|partition| partition.max_time)
+            .collect();
+        limits.is_empty() || limits.iter().any(|limit| limit.is_none_or(|limit| walltime <= limit))
+    }
+
+    /// How many copies of the per-node request fit in what `node` has free right now.
+    pub fn slots(&self, node: &Node) -> u32 {
+        if !node.state.is_up() || node.has_flag("DRAIN") {
+            return 0;
+        }
+        // Memory and GPUs only limit the count when the request asks for them
+        let mut slots = node.available_cores() / self.cores.max(1);
+        if let Some(by_mem) = node.available_mem().checked_div(self.mem) {
+            slots = slots.min(by_mem.min(u32::MAX.into()) as u32);
+        }
+        if let Some(by_gpus) = node.available_gpus().checked_div(self.gpus) {
+            slots = slots.min(by_gpus);
+        }
+        slots
+    }
+}
+
+impl std::fmt::Display for ResourceRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}n {}c {}g", self.nodes, self.cores, self.mem / GB)?;
+        if self.gpus > 0 {
+            write!(f, " {}gpu", self.gpus)?;
+        }
+        if let Some(walltime) = self.walltime {
+            if walltime.num_minutes() % 60 == 0 {
+                write!(f, " {}h", walltime.num_hours())?;
+            } else {
+                write!(f, " {}m", walltime.num_minutes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a request could run: per-node wait until it fits (zero means now).
+pub struct Placement {
+    pub request: ResourceRequest,
+    pub node_eta: HashMap<String, Duration>,
+    pub start_in: Option<Duration>,
+}
+
+impl Placement {
+    pub fn fits_now(&self) -> usize {
+        self.node_eta.values().filter(|eta| eta.is_zero()).count()
+    }
+
+    pub fn summary(&self) -> String {
+        match self.start_in {
+            Some(wait) if wait.is_zero() => format!(
+                "What-if {}: fits now ({} node(s) available)",
+                self.request,
+                self.fits_now()
+            ),
+            Some(wait) => format!(
+                "What-if {}: could start in ~{} once running jobs end",
+                self.request,
+                format_wait(&wait)
+            ),
+            None => format!("What-if {}: no node configuration fits this request", self.request),
+        }
+    }
+}
+
+/// Where a request fits right now, without waiting for any job to end.
+pub struct Fit {
+    pub request: ResourceRequest,
+    /// Copies of the request each node has room for; nodes without room are left out
+    pub node_slots: HashMap<String, u32>,
+    /// Slots per partition, in the order given to [`find_fits`]
+    pub partitions: Vec<(String, u32)>,
+}
+
+impl Fit {
+    pub fn slots(&self, node_id: &str) -> u32 {
+        self.node_slots.get(node_id).copied().unwrap_or(0)
+    }
+
+    pub fn total_slots(&self) -> u32 {
+        self.node_slots.values().sum()
+    }
+
+    /// How many jobs of the request could start at once, each on its own set of nodes:
+    /// the largest `k` for which the nodes hold `k` copies of each of the request's nodes.
+    pub fn jobs(&self) -> u32 {
+        let nodes = self.request.nodes.max(1);
+        let capacity = |k: u32| self.node_slots.values().map(|slots| (*slots).min(k)).sum::<u32>();
+        (1..=self.total_slots() / nodes).take_while(|k| capacity(*k) >= nodes * k).last().unwrap_or(0)
+    }
+
+    /// `batch 3, gpu 2`: the partitions with room, or `None` without any.
+    pub fn by_partition(&self) -> Option<String> {
+        let parts: Vec<String> = self.partitions
+            .iter()
+            .filter(|(_, slots)| *slots > 0)
+            .map(|(name, slots)| format!("{} {}", name, slots))
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    pub fn summary(&self) -> String {
+        let Some(partitions) = self.by_partition() else {
+            return format!("Fit {}: no node has room for it right now", self.request);
+        };
+        let slots = self.total_slots();
+        let mut summary = format!(
+            "Fit {}: {} slot{} on {} node{} ({})",
+            self.request,
+            slots,
+            if slots == 1 { "" } else { "s" },
+            self.node_slots.len(),
+            if self.node_slots.len() == 1 { "" } else { "s" },
+            partitions,
+        );
+        if self.request.nodes > 1 {
+            summary.push_str(&format!(", enough for {} such job(s) now", self.jobs()));
+        }
+        summary
+    }
+}
+
+/// Count the slots `request` has on each node and in each of `partitions` right now.
+pub fn find_fits(request: &ResourceRequest, nodes: &[Node], partitions: &[String]) -> Fit {
+    let node_slots: HashMap<String, u32> = nodes
+        .iter()
+        .map(|node| (node.id.clone(), request.slots(node)))
+        .filter(|(_, slots)| *slots > 0)
+        .collect();
+    let partitions = partitions
+        .iter()
+        .map(|partition| {
+            let slots = nodes
+                .iter()
+                .filter(|node| node.partitions.contains(partition))
+                .map(|node| node_slots.get(&node.id).copied().unwrap_or(0))
+                .sum();
+            (partition.clone(), slots)
+        })
+        .collect();
+
+    Fit {
+        request: request.clone(),
+        node_slots,
+        partitions,
+    }
+}
+
+/// Simulate running jobs ending at their time limits to find when each node could host
+/// `request`, on the nodes of `partitions` whose time limit allows its walltime.
+pub fn simulate(request: &ResourceRequest, nodes: &[Node], jobs: &[Job], partitions: &[Partition]) -> Placement {
+    let mut node_eta = HashMap::new();
+    for node in nodes.iter().filter(|node| request.within_time_limit(node, partitions)) {
+        if let Some(eta) = node_eta_for(request, node, jobs) {
+            node_eta.insert(node.id.clone(), eta);
+        }
+    }
+
+    // A multi-node request starts once the N-th soonest node is free
+    let mut etas: Vec<Duration> = node_eta.values().copied().collect();
+    etas.sort();
+    let start_in = etas.get(request.nodes as usize - 1).copied();
+
+    Placement {
+        request: request.clone(),
+        node_eta,
+        start_in,
+    }
+}
+
+fn node_eta_for(request: &ResourceRequest, node: &Node, jobs: &[Job]) -> Option<Duration> {
+    if !node.state.is_up() || node.has_flag("DRAIN") {
+        return None;
+    }
+    if !request.fits(node.total_cores, node.total_mem, node.total_gpus()) {
+        return None;
+    }
+    if request.fits(node.available_cores(), node.available_mem(), node.available_gpus()) {
+        return Some(Duration::zero());
+    }
+
+    // Release resources job by job in order of their remaining walltime
+    let mut releases: Vec<(Duration, u32, u64, u32)> = jobs
+        .iter()
+        .filter(|job| job.state == JobState::Running && job.node_list.iter().any(|n| n == &node.id))
+        .map(|job| {
+            let per_node = job.req_nodes.max(1);
+            let remaining = (job.time_limit - job.elapsed).max(Duration::zero());
+            (remaining, job.req_cpus / per_node, job.req_mem / per_node as u64, job.req_gpus / per_node)
+        })
+        .collect();
+    releases.sort_by_key(|(remaining, _, _, _)| *remaining);
+
+    let (mut free_cores, mut free_mem, mut free_gpus) = (node.available_cores(), node.available_mem(), node.available_gpus());
+    for (remaining, cores, mem, gpus) in &releases {
+        free_cores = (free_cores + cores).min(node.total_cores);
+        free_mem = (free_mem + mem).min(node.total_mem);
+        free_gpus = (free_gpus + gpus).min(node.total_gpus());
+        if request.fits(free_cores, free_mem, free_gpus) {
+            return Some(*remaining);
+        }
+    }
+
+    // Once every known job has ended the node is empty
+    releases.last().map(|(remaining, _, _, _)| *remaining)
+}
+
+/// The shortest remaining walltime among the running jobs on each node, by cluster and node
+/// name: when the node frees up at the latest, short of jobs ending early.
+pub fn time_until_free(jobs: &[Job]) -> HashMap<(Option<String>, String), Duration> {
+    let mut etas: HashMap<(Option<String>, String), Duration> = HashMap::new();
+    for job in jobs.iter().filter(|job| job.state == JobState::Running) {
+        let remaining = (job.time_limit - job.elapsed).max(Duration::zero());
+        for node_id in &job.node_list {
+            let eta = etas.entry((job.cluster.clone(), node_id.clone())).or_insert(remaining);
+            *eta = (*eta).min(remaining);
+        }
+    }
+    etas
+}
+
+/// Human-friendly wait, e.g. `2h13m` or `45m`.
+pub fn format_wait(wait: &Duration) -> String {
+    let minutes = wait.num_minutes().max(0);
+    if minutes >= 60 {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
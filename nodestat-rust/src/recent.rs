@@ -0,0 +1,45 @@
+use crate::paths;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+/// How many partitions the picker remembers.
+const MAX_RECENT: usize = 5;
+
+/// Partitions the user switched to lately, most recent first, persisted across sessions.
+pub struct RecentPartitions {
+    path: PathBuf,
+    names: Vec<String>,
+}
+
+impl RecentPartitions {
+    pub fn load() -> Self {
+        let path = paths::config_dir().join("recent_partitions.json");
+        let names = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self { path, names }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.names)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Move `name` to the front, dropping the oldest beyond [`MAX_RECENT`].
+    pub fn record(&mut self, name: &str) -> Result<()> {
+        self.names.retain(|n| n != name);
+        self.names.insert(0, name.to_string());
+        self.names.truncate(MAX_RECENT);
+        self.save()
+    }
+}
@@ -0,0 +1,40 @@
+//! The `/` search: narrows the node table to nodes whose name, state or job owners match.
+
+use crate::models::Node;
+use regex::{Regex, RegexBuilder};
+
+/// A case-insensitive pattern, taken as a regex when it is one and literally otherwise
+/// (so a half-typed `c[0` still finds something).
+pub struct NodeSearch {
+    pattern: String,
+    regex: Regex,
+}
+
+impl NodeSearch {
+    /// `None` for an empty pattern, which matches everything.
+    pub fn new(pattern: &str) -> Option<Self> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let build = |source: &str| RegexBuilder::new(source).case_insensitive(true).build();
+        let regex = build(pattern).or_else(|_| build(&regex::escape(pattern))).ok()?;
+        Some(Self { pattern: pattern.to_string(), regex })
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The nodes matching, in table order.
+    pub fn filter<'a>(&self, nodes: &'a [Node]) -> Vec<&'a Node> {
+        nodes
+            .iter()
+            .filter(|node| {
+                self.regex.is_match(&node.id)
+                    || self.regex.is_match(&node.state.to_string())
+                    || node.state_flags.iter().any(|flag| self.regex.is_match(flag))
+                    || node.job_users.iter().any(|user| self.regex.is_match(user))
+            })
+            .collect()
+    }
+}
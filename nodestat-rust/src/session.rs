@@ -0,0 +1,53 @@
+use crate::paths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// How the TUI was set up when it was last closed, restored at the next start unless `--fresh`
+/// is given or a flag says otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub partition: Option<String>,
+    /// A `:sort` name and whether it was descending
+    pub sort: Option<(String, bool)>,
+    /// The `:filter` expression
+    pub filter: Option<String>,
+    /// The `f` features
+    pub features: Vec<String>,
+    pub columns: Option<Vec<String>>,
+    pub theme: Option<String>,
+}
+
+/// `state.json`: one [`SessionState`] per site (profile, daemon or scheduler and host), so a
+/// partition of one cluster isn't asked of another.
+fn path() -> PathBuf {
+    paths::config_dir().join("state.json")
+}
+
+fn load_all() -> BTreeMap<String, SessionState> {
+    fs::read_to_string(path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+impl SessionState {
+    pub fn load(site: &str) -> Self {
+        load_all().remove(site).unwrap_or_default()
+    }
+
+    /// Store this as `site`'s state, keeping those of the other sites.
+    pub fn save(&self, site: &str) -> Result<()> {
+        let path = path();
+        let mut sites = load_all();
+        sites.insert(site.to_string(), self.clone());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&sites)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,170 @@
+//! `--format` templates: text with `{field}` placeholders, printed once per node, job or
+//! pending job, or once for the stats, e.g. `{id}\t{state}\t{avail_cores}/{total_cores}`.
+
+use crate::models::{format_duration, ClusterStats, Job, Node, GB};
+use anyhow::{anyhow, Result};
+
+/// The fields of a node line. Memory is in whole GB.
+pub const NODE_FIELDS: [&str; 20] = [
+    "id", "state", "partitions", "cluster",
+    "total_cores", "used_cores", "avail_cores",
+    "total_mem", "used_mem", "avail_mem",
+    "total_gpus", "used_gpus", "avail_gpus",
+    "load", "jobs", "users", "features", "reason", "health", "eta",
+];
+
+/// The fields of a job line. Memory is in whole GB, times Slurm-style.
+pub const JOB_FIELDS: [&str; 15] = [
+    "id", "user", "name", "state", "partition", "cluster", "nodes",
+    "num_nodes", "cpus", "mem", "gpus", "elapsed", "time_limit", "priority", "reason",
+];
+
+/// The fields of the stats line. Memory is in whole GB.
+pub const STATS_FIELDS: [&str; 12] = [
+    "partition", "total_nodes", "avail_nodes",
+    "total_cores", "used_cores", "avail_cores",
+    "total_mem", "used_mem", "avail_mem",
+    "total_gpus", "used_gpus", "watts",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// `{name}` for one of `fields`, `{{` and `}}` for braces, and `\t`, `\n` and `\\` escapes
+    /// (shells pass them through single quotes as-is).
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') | None => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    },
+                },
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                },
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                },
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| anyhow!("Unclosed '{{' in the format"))?;
+                    let name = rest[..end].trim();
+                    if !fields.contains(&name) {
+                        return Err(anyhow!("Unknown field '{{{}}}' (available: {})", name, fields.join(", ")));
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(name.to_string()));
+                    chars = rest[end + 1..].chars();
+                },
+                '}' => return Err(anyhow!("Unmatched '}}' in the format (use }}}} for a brace)")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// The template with each field replaced by `value(name)`.
+    pub fn render(&self, value: impl Fn(&str) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(name) => value(name),
+            })
+            .collect()
+    }
+}
+
+/// `-` for a missing value, so whitespace-split lines keep their columns.
+fn or_dash(value: Option<String>) -> String {
+    value.filter(|v| !v.is_empty()).unwrap_or_else(|| "-".to_string())
+}
+
+/// A node field that doesn't need the rest of the snapshot; `health` and `eta` come from the app.
+pub fn node_field(node: &Node, name: &str) -> String {
+    match name {
+        "id" => node.id.clone(),
+        "state" => node.state_label(),
+        "partitions" => or_dash(Some(node.partitions.join(","))),
+        "cluster" => or_dash(node.cluster.clone()),
+        "total_cores" => node.total_cores.to_string(),
+        "used_cores" => node.used_cores.to_string(),
+        "avail_cores" => node.available_cores().to_string(),
+        "total_mem" => node.total_mem_gb().to_string(),
+        "used_mem" => node.used_mem_gb().to_string(),
+        "avail_mem" => node.available_mem_gb().to_string(),
+        "total_gpus" => node.total_gpus().to_string(),
+        "used_gpus" => node.used_gpus().to_string(),
+        "avail_gpus" => node.available_gpus().to_string(),
+        "load" => or_dash(node.cpu_load.map(|load| format!("{:.2}", load))),
+        "jobs" => node.jobs.len().to_string(),
+        "users" => or_dash(Some(node.job_users.join(","))),
+        "features" => or_dash(Some(node.features.join(","))),
+        "reason" => or_dash(node.reason.clone()),
+        _ => "-".to_string(),
+    }
+}
+
+pub fn job_field(job: &Job, name: &str) -> String {
+    match name {
+        "id" => job.id.clone(),
+        "user" => job.user.clone(),
+        "name" => job.name.clone(),
+        "state" => job.state.to_string(),
+        "partition" => job.partition.clone(),
+        "cluster" => or_dash(job.cluster.clone()),
+        "nodes" => or_dash(Some(job.node_list.join(","))),
+        "num_nodes" => job.req_nodes.to_string(),
+        "cpus" => job.req_cpus.to_string(),
+        "mem" => (job.req_mem / GB).to_string(),
+        "gpus" => job.req_gpus.to_string(),
+        "elapsed" => format_duration(&job.elapsed),
+        "time_limit" => format_duration(&job.time_limit),
+        "priority" => or_dash(job.priority.as_ref().map(|priority| priority.total.to_string())),
+        "reason" => or_dash(job.reason.clone()),
+        _ => "-".to_string(),
+    }
+}
+
+pub fn stats_field(stats: &ClusterStats, partition: &str, name: &str) -> String {
+    match name {
+        "partition" => partition.to_string(),
+        "total_nodes" => stats.total_nodes.to_string(),
+        "avail_nodes" => stats.avail_nodes.to_string(),
+        "total_cores" => stats.total_cores.to_string(),
+        "used_cores" => stats.used_cores.to_string(),
+        "avail_cores" => stats.avail_cores.to_string(),
+        "total_mem" => (stats.total_memory / GB).to_string(),
+        "used_mem" => (stats.used_memory / GB).to_string(),
+        "avail_mem" => (stats.avail_memory / GB).to_string(),
+        "total_gpus" => stats.total_gpus().to_string(),
+        "used_gpus" => stats.used_gpus().to_string(),
+        "watts" => stats.current_watts.to_string(),
+        _ => "-".to_string(),
+    }
+}
+
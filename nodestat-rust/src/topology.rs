@@ -0,0 +1,121 @@
+//! Racks: grouping nodes by the leaf switch they hang off (Slurm's `scontrol show topology`)
+//! or by a node-name pattern from `[topology] rack_pattern` in config.toml. The same groups
+//! serve for node-name prefixes (`[topology] prefix_pattern`), e.g. hardware generations.
+
+use crate::models::Node;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Name of the group of nodes neither the pattern nor the topology place in a rack.
+pub const NO_RACK: &str = "unracked";
+
+/// Name of the group of nodes the prefix pattern doesn't match.
+pub const NO_PREFIX: &str = "other";
+
+/// Letters up to the first digit or dash: `c6420-01` and `c6420-02` are `c`, `amd-07` is `amd`.
+pub const DEFAULT_PREFIX_PATTERN: &str = "^([A-Za-z]+)";
+
+/// Nodes sharing a rack (or a name prefix), in table order.
+pub struct Rack<'a> {
+    pub name: String,
+    pub nodes: Vec<&'a Node>,
+}
+
+impl Rack<'_> {
+    pub fn total_cores(&self) -> u32 {
+        self.nodes.iter().map(|n| n.total_cores).sum()
+    }
+
+    pub fn used_cores(&self) -> u32 {
+        self.nodes.iter().map(|n| n.used_cores).sum()
+    }
+
+    pub fn total_mem(&self) -> u64 {
+        self.nodes.iter().map(|n| n.total_mem).sum()
+    }
+
+    pub fn used_mem(&self) -> u64 {
+        self.nodes.iter().map(|n| n.used_mem).sum()
+    }
+
+    pub fn total_gpus(&self) -> u32 {
+        self.nodes.iter().map(|n| n.total_gpus()).sum()
+    }
+
+    pub fn used_gpus(&self) -> u32 {
+        self.nodes.iter().map(|n| n.used_gpus()).sum()
+    }
+
+    /// Cores and memory on nodes that can take jobs right now.
+    pub fn available(&self) -> (u32, u64) {
+        self.nodes
+            .iter()
+            .filter(|n| n.is_available())
+            .fold((0, 0), |(cores, mem), n| (cores + n.available_cores(), mem + n.available_mem()))
+    }
+
+    /// Nodes that are down, drained or draining; a whole rack of them usually means a
+    /// switch or PDU problem rather than bad nodes.
+    pub fn down(&self) -> usize {
+        self.nodes.iter().filter(|n| !n.state.is_up() || n.is_drained()).count()
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.nodes.iter().map(|n| n.jobs.len()).sum()
+    }
+}
+
+/// The first capture group of `pattern` in the node's name (or its whole match).
+pub fn prefix_of(node: &Node, pattern: &Regex) -> Option<String> {
+    let captures = pattern.captures(&node.id)?;
+    let name = captures.get(1).or_else(|| captures.get(0)).map(|m| m.as_str());
+    name.filter(|n| !n.is_empty()).map(str::to_string)
+}
+
+/// The rack of a node: the first capture group of `pattern` (or its whole match), else the
+/// node's leaf switch.
+pub fn rack_of(node: &Node, pattern: Option<&Regex>, switches: &HashMap<String, String>) -> Option<String> {
+    pattern
+        .and_then(|pattern| prefix_of(node, pattern))
+        .or_else(|| switches.get(&node.id).cloned())
+}
+
+/// Group nodes by `group_of` (see [`rack_of`] and [`prefix_of`]), ordered by name (`r2`
+/// before `r10`) and keeping the table order within a group. Nodes without one come last
+/// under `leftover`.
+pub fn group<'a>(nodes: impl IntoIterator<Item = &'a Node>, group_of: impl Fn(&Node) -> Option<String>, leftover: &str) -> Vec<Rack<'a>> {
+    let mut racks: Vec<Rack<'a>> = Vec::new();
+    let mut unracked = Vec::new();
+
+    for node in nodes {
+        let Some(name) = group_of(node) else {
+            unracked.push(node);
+            continue;
+        };
+        match racks.iter_mut().find(|rack| rack.name == name) {
+            Some(rack) => rack.nodes.push(node),
+            None => racks.push(Rack { name, nodes: vec![node] }),
+        }
+    }
+
+    racks.sort_by_cached_key(|rack| natural_key(&rack.name));
+    if !unracked.is_empty() {
+        racks.push(Rack { name: leftover.to_string(), nodes: unracked });
+    }
+    racks
+}
+
+/// Sort key comparing runs of digits by value, so `r2` sorts before `r10`.
+pub fn natural_key(name: &str) -> Vec<(String, u64)> {
+    let mut key = Vec::new();
+    let mut rest = name;
+    while !rest.is_empty() {
+        let text_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let (text, digits) = rest.split_at(text_len);
+        let digits_len = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+        let (number, tail) = digits.split_at(digits_len);
+        key.push((text.to_string(), number.parse().unwrap_or(0)));
+        rest = tail;
+    }
+    key
+}
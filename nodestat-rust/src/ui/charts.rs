@@ -0,0 +1,174 @@
+//! The Charts view: the partition's CPU, memory and GPU use and its queue over the last hour,
+//! day or week (h/l pick), read from the `[history]` database.
+
+use super::{App, View};
+use crate::history::ClusterSample;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    symbols,
+    text::Span,
+    widgets::{Axis as ChartAxis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Wrap},
+    Frame,
+};
+
+/// Points per chart line; each averages the samples of an equal slice of the window.
+const CHART_POINTS: i64 = 240;
+
+/// Show the legend unless it would cover over a third of the chart's width or all of its height.
+const LEGEND: (Constraint, Constraint) = (Constraint::Ratio(1, 3), Constraint::Ratio(1, 1));
+
+/// How far back the charts go.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(super) enum ChartWindow {
+    #[default]
+    Hour,
+    Day,
+    Week,
+}
+
+impl ChartWindow {
+    const ALL: [ChartWindow; 3] = [ChartWindow::Hour, ChartWindow::Day, ChartWindow::Week];
+
+    fn duration(self) -> chrono::Duration {
+        match self {
+            ChartWindow::Hour => chrono::Duration::hours(1),
+            ChartWindow::Day => chrono::Duration::days(1),
+            ChartWindow::Week => chrono::Duration::days(7),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ChartWindow::Hour => "1h",
+            ChartWindow::Day => "24h",
+            ChartWindow::Week => "7d",
+        }
+    }
+
+    /// How the time axis is labelled: a week needs the day.
+    fn time_format(self) -> &'static str {
+        match self {
+            ChartWindow::Week => "%a %H:%M",
+            _ => "%H:%M",
+        }
+    }
+
+    /// The next longer (or shorter) window, stopping at either end.
+    pub(super) fn step(self, longer: bool) -> Self {
+        let index = Self::ALL.iter().position(|window| *window == self).unwrap_or(0);
+        match longer {
+            true => Self::ALL[(index + 1).min(Self::ALL.len() - 1)],
+            false => Self::ALL[index.saturating_sub(1)],
+        }
+    }
+}
+
+impl App {
+    /// Pick a longer or shorter chart window and read its samples.
+    pub(super) fn step_chart_window(&mut self, longer: bool) {
+        self.chart_window = self.chart_window.step(longer);
+        self.load_chart_samples();
+    }
+
+    /// Read the partition's samples of the chart window again, if the Charts view is shown.
+    pub(super) fn load_chart_samples(&mut self) {
+        if self.view != View::Charts {
+            return;
+        }
+        let Some(ref history) = self.history else {
+            return;
+        };
+        match history.cluster_samples(&self.current_partition, self.now(), self.chart_window.duration(), CHART_POINTS) {
+            Ok(samples) => self.chart_samples = samples,
+            Err(e) => self.error_message = Some(format!("Failed to read history: {:#}", e)),
+        }
+    }
+
+    pub(super) fn render_charts(&self, f: &mut Frame, area: Rect) {
+        if self.history.is_none() {
+            let hint = Paragraph::new("No history is kept. Set enabled = true under [history] in config.toml \
+                                       to store the usage at every refresh and chart it here.")
+                .style(Style::default().fg(self.theme.muted))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title("Charts"));
+            f.render_widget(hint, area);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+
+        let now = self.now();
+        let window = self.chart_window.duration();
+        let since = (now - window).timestamp();
+        let span = window.num_seconds() as f64;
+        let fmt = self.chart_window.time_format();
+        let time_labels: Vec<Span> = [now - window, now - window / 2, now]
+            .iter()
+            .map(|time| Span::raw(self.cluster_tz.format(time, fmt)))
+            .collect();
+        let points = |value: fn(&ClusterSample) -> Option<f64>| -> Vec<(f64, f64)> {
+            self.chart_samples
+                .iter()
+                .filter_map(|sample| Some(((sample.time - since) as f64, value(sample)?)))
+                .collect()
+        };
+        let line = |name: &'static str, color: Color, data| {
+            Dataset::default()
+                .name(name)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(data)
+        };
+        let muted = Style::default().fg(self.theme.muted);
+        let windows: Vec<&str> = ChartWindow::ALL.iter().map(|w| w.label()).collect();
+        let empty = if self.chart_samples.is_empty() { ", no samples yet" } else { "" };
+
+        let cpu = points(|sample| sample.cpu);
+        let mem = points(|sample| sample.mem);
+        let gpu = points(|sample| sample.gpu);
+        let mut datasets = vec![line("CPU", self.theme.cpu_gauge, &cpu), line("Memory", self.theme.mem_gauge, &mem)];
+        if !gpu.is_empty() {
+            datasets.push(line("GPU", self.theme.gpu_gauge, &gpu));
+        }
+        let usage = Chart::new(datasets)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Utilization of {}, last {}{} (h/l: {})",
+                self.current_partition,
+                self.chart_window.label(),
+                empty,
+                windows.join(" "),
+            )))
+            .hidden_legend_constraints(LEGEND)
+            .x_axis(ChartAxis::default().style(muted).bounds([0.0, span]).labels(time_labels.clone()))
+            .y_axis(
+                ChartAxis::default()
+                    .style(muted)
+                    .bounds([0.0, 100.0])
+                    .labels(["0%", "50%", "100%"].into_iter().map(Span::raw).collect()),
+            );
+        f.render_widget(usage, chunks[0]);
+
+        let pending = points(|sample| Some(sample.pending_jobs));
+        let running = points(|sample| Some(sample.running_jobs));
+        let top = pending.iter().chain(&running).map(|(_, jobs)| *jobs).fold(0.0, f64::max).ceil().max(1.0);
+        let queue = Chart::new(vec![line("Pending", self.theme.warning, &pending), line("Running", self.theme.good, &running)])
+            .block(Block::default().borders(Borders::ALL).title("Jobs"))
+            .hidden_legend_constraints(LEGEND)
+            .x_axis(ChartAxis::default().style(muted).bounds([0.0, span]).labels(time_labels))
+            .y_axis(
+                ChartAxis::default()
+                    .style(muted)
+                    .bounds([0.0, top])
+                    .labels(["0".to_string(), format!("{}", (top / 2.0).round()), format!("{}", top)]
+                        .into_iter()
+                        .map(Span::raw)
+                        .collect()),
+            );
+        f.render_widget(queue, chunks[1]);
+    }
+}
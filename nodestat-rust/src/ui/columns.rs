@@ -0,0 +1,215 @@
+//! The columns of the node table, chosen and ordered with `--columns` or `columns` in config.toml.
+
+use super::SortKey;
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Node,
+    /// Only in the multi-cluster view
+    Cluster,
+    /// Only when several partitions are shown
+    Partition,
+    Cpu,
+    Memory,
+    AvailCpu,
+    AvailMem,
+    /// Only on partitions with GPUs
+    Gpu,
+    Load,
+    State,
+    Jobs,
+    Health,
+    /// The shortest remaining walltime of the jobs on the node
+    Eta,
+    Features,
+    Reason,
+    /// Added while a what-if request is active; not selectable
+    WhatIf,
+    /// Added while a fit request is active; not selectable
+    Fit,
+}
+
+/// The table without a `columns` setting; Cluster and Partition are added after Node when relevant.
+pub const DEFAULT: [Column; 10] = [
+    Column::Node,
+    Column::Cpu,
+    Column::Memory,
+    Column::AvailCpu,
+    Column::AvailMem,
+    Column::Gpu,
+    Column::Load,
+    Column::State,
+    Column::Jobs,
+    Column::Health,
+];
+
+const ALL: [Column; 15] = [
+    Column::Node,
+    Column::Cluster,
+    Column::Partition,
+    Column::Cpu,
+    Column::Memory,
+    Column::AvailCpu,
+    Column::AvailMem,
+    Column::Gpu,
+    Column::Load,
+    Column::State,
+    Column::Jobs,
+    Column::Health,
+    Column::Eta,
+    Column::Features,
+    Column::Reason,
+];
+
+impl Column {
+    /// The name used in `--columns`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Node => "node",
+            Column::Cluster => "cluster",
+            Column::Partition => "partition",
+            Column::Cpu => "cpu",
+            Column::Memory => "memory",
+            Column::AvailCpu => "avail-cpu",
+            Column::AvailMem => "avail-mem",
+            Column::Gpu => "gpu",
+            Column::Load => "load",
+            Column::State => "state",
+            Column::Jobs => "jobs",
+            Column::Health => "health",
+            Column::Eta => "eta",
+            Column::Features => "features",
+            Column::Reason => "reason",
+            Column::WhatIf => "what-if",
+            Column::Fit => "fit",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Node => "Node",
+            Column::Cluster => "Cluster",
+            Column::Partition => "Partition",
+            Column::Cpu => "CPU",
+            Column::Memory => "Memory",
+            Column::AvailCpu => "Avail CPU",
+            Column::AvailMem => "Avail Mem",
+            Column::Gpu => "GPU",
+            Column::Load => "Load",
+            Column::State => "State",
+            Column::Jobs => "Jobs",
+            Column::Health => "Health",
+            Column::Eta => "Free in",
+            Column::Features => "Features",
+            Column::Reason => "Reason",
+            Column::WhatIf => "What-if",
+            Column::Fit => "Fits",
+        }
+    }
+
+    /// The title in a narrow table.
+    pub fn short_title(self) -> &'static str {
+        match self {
+            Column::Cluster => "Clus",
+            Column::Partition => "Part",
+            Column::Memory => "Mem",
+            Column::AvailCpu => "FreeC",
+            Column::AvailMem => "FreeMem",
+            Column::Health => "Hlth",
+            Column::Eta => "Free",
+            Column::Features => "Feat",
+            other => other.title(),
+        }
+    }
+
+    /// Characters the column needs to stay readable in a narrow table, where the bars are
+    /// [`super::COMPACT_BAR_CELLS`] wide.
+    pub fn min_width(self) -> u16 {
+        match self {
+            Column::Node => 10,
+            Column::Cluster => 8,
+            Column::Partition => 9,
+            Column::Cpu => 16,
+            Column::Memory => 18,
+            Column::AvailCpu => 5,
+            Column::AvailMem => 7,
+            Column::Gpu | Column::Load => 5,
+            Column::State => 10,
+            Column::Jobs | Column::Health => 4,
+            Column::Eta => 6,
+            Column::Features => 10,
+            Column::Reason => 14,
+            Column::WhatIf => 9,
+            Column::Fit => 8,
+        }
+    }
+
+    /// Whether the column holds a usage bar, which is narrower in a narrow table.
+    pub fn has_bar(self) -> bool {
+        matches!(self, Column::Cpu | Column::Memory)
+    }
+
+    /// Share of the table width, before scaling to the columns shown.
+    pub fn weight(self) -> u16 {
+        match self {
+            Column::Node => 12,
+            Column::Cluster => 10,
+            Column::Partition => 8,
+            Column::Cpu | Column::Memory => 19,
+            Column::AvailCpu | Column::AvailMem => 7,
+            Column::Gpu | Column::Health => 6,
+            Column::Load => 5,
+            Column::State => 8,
+            Column::Jobs => 4,
+            Column::Eta => 7,
+            Column::Features => 12,
+            Column::Reason => 16,
+            Column::WhatIf => 9,
+            Column::Fit => 8,
+        }
+    }
+
+    pub(super) fn sort_key(self) -> Option<SortKey> {
+        match self {
+            Column::Node => Some(SortKey::Name),
+            Column::AvailCpu => Some(SortKey::AvailCores),
+            Column::AvailMem => Some(SortKey::AvailMem),
+            Column::State => Some(SortKey::State),
+            Column::Jobs => Some(SortKey::Jobs),
+            Column::Load => Some(SortKey::Load),
+            Column::Health => Some(SortKey::Health),
+            Column::Eta => Some(SortKey::Eta),
+            _ => None,
+        }
+    }
+
+    /// `gpus` and `mem` are accepted for `gpu` and `memory`; case doesn't matter.
+    fn parse(name: &str) -> Result<Self> {
+        let name = name.trim().to_lowercase().replace('_', "-");
+        let name = match name.as_str() {
+            "gpus" => "gpu",
+            "mem" => "memory",
+            other => other,
+        };
+        ALL.into_iter().find(|column| column.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = ALL.iter().map(|column| column.name()).collect();
+            anyhow!("Unknown column '{}' (available: {})", name, names.join(", "))
+        })
+    }
+
+    /// A `--columns` list, in the order given.
+    pub fn parse_list(names: &[String]) -> Result<Vec<Self>> {
+        let mut columns = Vec::new();
+        for name in names.iter().filter(|name| !name.trim().is_empty()) {
+            let column = Self::parse(name)?;
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        if columns.is_empty() {
+            return Err(anyhow!("--columns needs at least one column"));
+        }
+        Ok(columns)
+    }
+}
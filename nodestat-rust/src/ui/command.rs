@@ -0,0 +1,130 @@
+//! The `:` command line: partitions, filters, sorting and views by name, for driving the app
+//! without remembering every key, with Tab completion.
+
+use super::{SortKey, View};
+use anyhow::{anyhow, Result};
+
+/// Commands in completion order; any unique prefix works, so `:p gpu` switches partitions.
+pub const NAMES: [&str; 9] = ["partition", "filter", "sort", "user", "refresh", "view", "notify", "dump", "quit"];
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Partition(String),
+    /// A [`crate::filter::NodeFilter`] expression; empty clears it
+    Filter(String),
+    /// The key, and the direction if one was given
+    Sort(SortKey, Option<bool>),
+    /// Only nodes running this user's jobs; `None` shows all again
+    User(Option<String>),
+    Refresh(u64),
+    View(View),
+    /// A [`crate::availability::AvailabilityWatch`]; empty drops them all
+    Notify(String),
+    /// Write a snapshot, to this file or a timestamped one, its usernames replaced if asked
+    Dump { anonymize: bool, path: Option<String> },
+    Quit,
+}
+
+/// The command line while it is open.
+#[derive(Default)]
+pub struct CommandLine {
+    pub input: String,
+    /// What the last Tab could complete to, when it wasn't one thing
+    pub candidates: Vec<String>,
+}
+
+/// The command a (possibly abbreviated) name stands for.
+fn resolve(name: &str) -> Result<&'static str> {
+    let name = name.to_lowercase();
+    let matches: Vec<&str> = NAMES.iter().copied().filter(|command| command.starts_with(&name)).collect();
+    match matches[..] {
+        [command] => Ok(command),
+        [] => Err(anyhow!("Unknown command '{}' (commands: {})", name, NAMES.join(", "))),
+        _ => Err(anyhow!("'{}' could be {}", name, matches.join(", "))),
+    }
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim().trim_start_matches(':');
+        let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Commands: {}", NAMES.join(", ")));
+        }
+
+        let required = || if argument.is_empty() { Err(anyhow!(":{} needs an argument", resolve(name).unwrap_or(name))) } else { Ok(argument) };
+        Ok(match resolve(name)? {
+            "partition" => Command::Partition(required()?.to_string()),
+            "filter" => Command::Filter(argument.to_string()),
+            "sort" => {
+                let mut words = required()?.split_whitespace();
+                let key = words.next().unwrap_or_default();
+                let key = SortKey::parse(key).ok_or_else(|| {
+                    let names: Vec<&str> = SortKey::ALL.iter().map(|key| key.name()).collect();
+                    anyhow!("Unknown sort key '{}' (keys: {})", key, names.join(", "))
+                })?;
+                let descending = match words.next() {
+                    None => None,
+                    Some("asc" | "ascending") => Some(false),
+                    Some("desc" | "descending") => Some(true),
+                    Some(other) => return Err(anyhow!("'{}' is not asc or desc", other)),
+                };
+                Command::Sort(key, descending)
+            },
+            "user" => Command::User((!argument.is_empty()).then(|| argument.to_string())),
+            "refresh" => {
+                let secs = required()?.trim_end_matches('s');
+                Command::Refresh(secs.parse().map_err(|_| anyhow!("'{}' is not a number of seconds", argument))?)
+            },
+            "view" => {
+                let view = required()?;
+                Command::View(View::parse(view).ok_or_else(|| {
+                    let names: Vec<&str> = View::ALL.iter().map(|view| view.name()).collect();
+                    anyhow!("Unknown view '{}' (views: {})", view, names.join(", "))
+                })?)
+            },
+            "notify" => Command::Notify(argument.to_string()),
+            "dump" => {
+                let (anonymize, path) = match argument.split_once(char::is_whitespace).unwrap_or((argument, "")) {
+                    ("anonymize" | "anon", path) => (true, path.trim()),
+                    _ => (false, argument),
+                };
+                Command::Dump { anonymize, path: (!path.is_empty()).then(|| path.to_string()) }
+            },
+            _ => Command::Quit,
+        })
+    }
+}
+
+/// Complete the word being typed: the command name, else its argument among `arguments(command)`.
+/// Returns the new line, and the candidates when they have nothing more in common.
+pub fn complete(line: &str, arguments: impl Fn(&str) -> Vec<String>) -> (String, Vec<String>) {
+    let (head, word, candidates) = match line.rfind(' ') {
+        None => (String::new(), line, NAMES.iter().map(|name| format!("{} ", name)).collect()),
+        Some(space) => {
+            let name = line.split_whitespace().next().unwrap_or_default();
+            let Ok(command) = resolve(name) else {
+                return (line.to_string(), Vec::new());
+            };
+            (line[..=space].to_string(), &line[space + 1..], arguments(command))
+        },
+    };
+
+    let matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&word.to_lowercase()))
+        .collect();
+    match &matches[..] {
+        [] => (line.to_string(), Vec::new()),
+        [only] => (head + only, Vec::new()),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.chars().count(), |len, candidate| {
+                first.chars().zip(candidate.chars()).take_while(|(a, b)| a == b).count().min(len)
+            });
+            let prefix: String = first.chars().take(common).collect();
+            let prefix = if prefix.len() > word.len() { prefix } else { word.to_string() };
+            (head + &prefix, matches.iter().map(|m| m.trim_end().to_string()).collect())
+        },
+    }
+}
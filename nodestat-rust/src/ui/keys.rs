@@ -0,0 +1,212 @@
+//! Remapped keys from `[keys]` in config.toml (`quit = "Q"`, `refresh = "F5"`). A pressed key is
+//! translated to the built-in key of its action before it's handled, so the rest of the UI only
+//! knows the defaults.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Every action that can be remapped, with its built-in key. Actions sharing a key work in
+/// different views.
+const ACTIONS: [(&str, &str); 45] = [
+    ("refresh", "r"),
+    ("refresh_slower", "+"),
+    ("refresh_faster", "-"),
+    ("next_view", "tab"),
+    ("previous_view", "backtab"),
+    ("up", "k"),
+    ("down", "j"),
+    ("left", "h"),
+    ("right", "l"),
+    ("details", "enter"),
+    ("search", "/"),
+    ("command", ":"),
+    ("palette", "ctrl-p"),
+    ("partitions", "p"),
+    ("sort", "s"),
+    ("reverse_sort", "S"),
+    ("features", "f"),
+    ("mark", "v"),
+    ("group", "g"),
+    // Array tasks in the queue, racks while grouped
+    ("expand", "x"),
+    ("copy", "y"),
+    ("ssh", "o"),
+    ("watch", "w"),
+    ("watch_selected", "W"),
+    ("notify", "N"),
+    ("reservations", "R"),
+    ("licenses", "L"),
+    ("fairshare", "F"),
+    ("changes", "a"),
+    ("alerts", "A"),
+    ("what_if", "i"),
+    ("fit", "c"),
+    ("fit_only", "C"),
+    ("hold", "H"),
+    // Also resumes nodes with --admin
+    ("release", "U"),
+    ("cancel", "d"),
+    ("edit_reason", "e"),
+    ("drain", "D"),
+    ("zoom", "z"),
+    // --replay
+    ("play_pause", "P"),
+    ("step_back", "["),
+    ("step_forward", "]"),
+    ("jump_back", "{"),
+    ("jump_forward", "}"),
+    ("quit", "q"),
+];
+
+/// Keys with a fixed meaning: closing things, the arrows and paging, partitions. An F key bound
+/// to an action no longer toggles its cluster.
+fn is_reserved(key: Key) -> bool {
+    matches!(
+        key.code,
+        KeyCode::Esc | KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right
+            | KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End
+            | KeyCode::Char(' ') | KeyCode::Char('1'..='9')
+    )
+}
+
+/// A key with the modifiers that matter here; Shift is part of the character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn of(event: KeyEvent) -> Self {
+        Self { code: event.code, modifiers: event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT) }
+    }
+
+    /// `x`, `X`, `F5`, `enter`, `tab`, `ctrl-x`, `alt-x` and the like; names are case-insensitive.
+    fn parse(text: &str) -> Result<Self> {
+        let lower = text.to_lowercase();
+        for (prefix, modifier) in [("ctrl-", KeyModifiers::CONTROL), ("alt-", KeyModifiers::ALT)] {
+            if lower.starts_with(prefix) && text.len() > prefix.len() {
+                let key = Self::parse(&text[prefix.len()..]).map_err(|_| anyhow!("Unknown key '{}'", text))?;
+                // Terminals report Ctrl-P as ctrl and a lowercase p
+                let code = match key.code {
+                    KeyCode::Char(c) if modifier == KeyModifiers::CONTROL => KeyCode::Char(c.to_ascii_lowercase()),
+                    code => code,
+                };
+                return Ok(Self { code, modifiers: key.modifiers | modifier });
+            }
+        }
+        let mut chars = text.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match lower.as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" | "shift-tab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "esc" | "escape" => KeyCode::Esc,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                other => match other.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(anyhow!("Unknown key '{}'", text)),
+                },
+            },
+        };
+        Ok(Self { code, modifiers: KeyModifiers::NONE })
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            other => write!(f, "{}", format!("{:?}", other).to_lowercase()),
+        }
+    }
+}
+
+/// The pressed keys that stand for other ones.
+#[derive(Debug, Default)]
+pub struct Keymap {
+    /// Pressed key → the built-in key of its action
+    translated: HashMap<Key, Key>,
+    /// Built-in keys whose actions all moved elsewhere, which then do nothing
+    unbound: HashSet<Key>,
+}
+
+impl Keymap {
+    /// Check `[keys]` (action → key) and build the translation: unknown actions or keys,
+    /// reserved keys and two actions on one key are errors.
+    pub fn new(keys: &BTreeMap<String, String>) -> Result<Self> {
+        let mut bound: BTreeMap<&str, (Key, Key)> = BTreeMap::new();
+        for (action, default) in ACTIONS {
+            let default = Key::parse(default)?;
+            bound.insert(action, (default, default));
+        }
+        for (action, key) in keys {
+            let names: Vec<&str> = ACTIONS.iter().map(|(name, _)| *name).collect();
+            let (_, binding) = bound
+                .iter_mut()
+                .find(|(name, _)| **name == action.as_str())
+                .ok_or_else(|| anyhow!("[keys]: unknown action '{}' (actions: {})", action, names.join(", ")))?;
+            let key = Key::parse(key).map_err(|e| anyhow!("[keys] {}: {}", action, e))?;
+            if is_reserved(key) {
+                return Err(anyhow!("[keys] {}: '{}' is reserved", action, key));
+            }
+            binding.1 = key;
+        }
+
+        let mut keymap = Self::default();
+        let mut owners: HashMap<Key, (&str, Key)> = HashMap::new();
+        for (action, (default, key)) in &bound {
+            match owners.get(key) {
+                Some((other, other_default)) if other_default != default => {
+                    return Err(anyhow!("[keys]: '{}' is bound to both {} and {}", key, other, action));
+                },
+                _ => owners.insert(*key, (action, *default)),
+            };
+            if key != default {
+                keymap.translated.insert(*key, *default);
+                keymap.unbound.insert(*default);
+            }
+        }
+        // A built-in key that still has an action of its own keeps working
+        keymap.unbound.retain(|default| !owners.contains_key(default));
+        Ok(keymap)
+    }
+
+    /// The built-in key for a pressed one.
+    pub fn translate(&self, event: KeyEvent) -> KeyEvent {
+        let key = Key::of(event);
+        match self.translated.get(&key) {
+            Some(default) => KeyEvent::new(default.code, default.modifiers),
+            None if self.unbound.contains(&key) => KeyEvent::new(KeyCode::Null, KeyModifiers::NONE),
+            None => event,
+        }
+    }
+
+    /// The key now bound to the action built in on `code`, if it was remapped.
+    pub fn remapped(&self, code: KeyCode) -> Option<String> {
+        let default = Key { code, modifiers: KeyModifiers::NONE };
+        self.translated.iter().find(|(_, d)| **d == default).map(|(key, _)| key.to_string())
+    }
+}
@@ -0,0 +1,4561 @@
+use crate::alerts::{self, Alerts, Firing, Observation, Target};
+use crate::arrays::{self, ArrayJob, QueueEntry};
+use crate::availability::AvailabilityWatch;
+use crate::backoff::{FetchHealth, HealthLevel};
+use crate::changes::{self, ChangeTracker};
+use crate::clipboard::{self, Copied};
+use crate::clock::{self, ClusterTz};
+use crate::desktop;
+use crate::filter::{self, NodeFilter};
+use crate::health::{self, NodeHistory};
+use crate::history::{ClusterSample, HistoryDb};
+use crate::models::*;
+use crate::placement::{self, Fit, Placement, ResourceRequest};
+use crate::recent::RecentPartitions;
+use crate::recording::{self, Recorder, Replay};
+use crate::session::SessionState;
+use crate::schedulers::{shell_quote, Scheduler, Timeouts};
+use crate::search::NodeSearch;
+use crate::topology::{self, Rack, NO_PREFIX, NO_RACK};
+use crate::users::{self, UserSort, UserUsage};
+use crate::utilization::{self, Sample, UtilizationHistory};
+use crate::waits::{self, WaitStats};
+use crate::watchlist::Watchlist;
+use crate::webhook::{Notice, Webhook};
+use charts::ChartWindow;
+use command::{Command, CommandLine};
+use palette::{Action, Entry, Palette};
+use refresh::{Change, Finished, OtherPartition, Refresher, Snapshot};
+use std::time::{Duration, Instant};
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Sparkline, Table, TableState, Tabs, Wrap,
+    },
+    Frame, Terminal,
+};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// Nodes in a reservation starting within this window are flagged in the table.
+const RESERVATION_WARNING_HOURS: i64 = 24;
+
+/// Width of the usage bars, and of the node table's bars when the terminal is too narrow for them.
+const BAR_CELLS: usize = 20;
+const COMPACT_BAR_CELLS: usize = 10;
+
+/// Width of the utilization sparklines beside the gauges, which only show on terminals at
+/// least `SPARKLINE_MIN_WIDTH` wide, and of the trend label after them.
+const SPARKLINE_WIDTH: u16 = utilization::MAX_SAMPLES as u16;
+const SPARKLINE_LABEL_WIDTH: u16 = 8;
+const SPARKLINE_MIN_WIDTH: u16 = 100;
+
+/// CPU efficiency (percent of the allocated core-time) below which a job is flagged as
+/// wasting its allocation.
+const LOW_CPU_EFFICIENCY: u64 = 30;
+
+/// The shortest refresh interval, however it's set, so a forgotten NodeStat can't keep the
+/// scheduler busy; `+` and `-` step through `REFRESH_STEPS`.
+pub const MIN_REFRESH_SECS: u64 = 5;
+const REFRESH_STEPS: [u64; 8] = [5, 10, 15, 30, 60, 120, 300, 600];
+
+/// Frames of the spinner shown while a background refresh is under way, one per 100ms.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long each frame of a `--replay` shows while playing.
+const REPLAY_STEP: Duration = Duration::from_secs(1);
+/// Frames skipped by `{` and `}` in a replay.
+const REPLAY_JUMP: isize = 10;
+
+/// How long the queue wait times are kept before asking for them again: a week of accounting
+/// takes a while and hardly moves between refreshes.
+const JOB_WAITS_EVERY: Duration = Duration::from_secs(600);
+
+/// Below this the layout can't be drawn sensibly, so a warning is shown instead.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 20;
+
+/// Order of the node table; `s` cycles, `S` flips the direction, clicking a header sorts by it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortKey {
+    /// Nodes that can take jobs first, biggest first
+    Availability,
+    Health,
+    Name,
+    AvailCores,
+    AvailMem,
+    State,
+    Jobs,
+    Load,
+    /// Soonest to free up first; nodes without jobs last
+    Eta,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 9] = [
+        SortKey::Availability,
+        SortKey::Health,
+        SortKey::Name,
+        SortKey::AvailCores,
+        SortKey::AvailMem,
+        SortKey::State,
+        SortKey::Jobs,
+        SortKey::Load,
+        SortKey::Eta,
+    ];
+
+    fn next(self) -> Self {
+        match self {
+            SortKey::Availability => SortKey::Health,
+            SortKey::Health => SortKey::Name,
+            SortKey::Name => SortKey::AvailCores,
+            SortKey::AvailCores => SortKey::AvailMem,
+            SortKey::AvailMem => SortKey::State,
+            SortKey::State => SortKey::Jobs,
+            SortKey::Jobs => SortKey::Load,
+            SortKey::Load => SortKey::Eta,
+            SortKey::Eta => SortKey::Availability,
+        }
+    }
+
+    /// The name used by `:sort`.
+    fn name(self) -> &'static str {
+        match self {
+            SortKey::Availability => "availability",
+            SortKey::Health => "health",
+            SortKey::Name => "name",
+            SortKey::AvailCores => "availcores",
+            SortKey::AvailMem => "availmem",
+            SortKey::State => "state",
+            SortKey::Jobs => "jobs",
+            SortKey::Load => "load",
+            SortKey::Eta => "eta",
+        }
+    }
+
+    /// A [`SortKey::name`], ignoring case, dashes and underscores.
+    fn parse(name: &str) -> Option<Self> {
+        let name = name.to_lowercase().replace(['-', '_'], "");
+        Self::ALL.into_iter().find(|key| key.name() == name)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Availability => "availability",
+            SortKey::Health => "health",
+            SortKey::Name => "name",
+            SortKey::AvailCores => "available cores",
+            SortKey::AvailMem => "available memory",
+            SortKey::State => "state",
+            SortKey::Jobs => "job count",
+            SortKey::Load => "load",
+            SortKey::Eta => "time until free",
+        }
+    }
+
+    /// Counts read best biggest-first; names, states and health scores (worst first) don't.
+    fn descending_by_default(self) -> bool {
+        matches!(self, SortKey::AvailCores | SortKey::AvailMem | SortKey::Jobs | SortKey::Load)
+    }
+}
+
+/// What the main table shows; Tab and Shift-Tab cycle, Alt-1..7 jump to one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    Nodes,
+    /// Running jobs of the partition
+    Jobs,
+    /// Pending jobs of the partition with the reason they wait
+    Queue,
+    /// Running and pending jobs totalled per user
+    Users,
+    /// The current user's running jobs with their time and CPU efficiency
+    MyJobs,
+    /// The running jobs of each node as bars from their start to their time limit
+    Timeline,
+    /// Usage and queue depth over time from the `[history]` database
+    Charts,
+}
+
+impl View {
+    const ALL: [View; 7] = [View::Nodes, View::Jobs, View::Queue, View::Users, View::MyJobs, View::Timeline, View::Charts];
+
+    fn title(self) -> &'static str {
+        match self {
+            View::Nodes => "Nodes",
+            View::Jobs => "Jobs",
+            View::Queue => "Queue",
+            View::Users => "Users",
+            View::MyJobs => "My Jobs",
+            View::Timeline => "Timeline",
+            View::Charts => "Charts",
+        }
+    }
+
+    /// The name used by `:view`.
+    fn name(self) -> &'static str {
+        match self {
+            View::Nodes => "nodes",
+            View::Jobs => "jobs",
+            View::Queue => "queue",
+            View::Users => "users",
+            View::MyJobs => "myjobs",
+            View::Timeline => "timeline",
+            View::Charts => "charts",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        let name = name.to_lowercase().replace(['-', '_', ' '], "");
+        Self::ALL.into_iter().find(|view| view.name() == name)
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|view| *view == self).unwrap_or(0)
+    }
+
+    /// The next (or previous) tab, wrapping around.
+    fn cycle(self, forward: bool) -> Self {
+        let len = Self::ALL.len();
+        Self::ALL[if forward { (self.index() + 1) % len } else { (self.index() + len - 1) % len }]
+    }
+
+    /// The queue and users views need the pending jobs, which aren't fetched otherwise.
+    fn needs_pending_jobs(self) -> bool {
+        matches!(self, View::Queue | View::Users)
+    }
+}
+
+/// How the nodes view is grouped; `g` cycles through them and back to a flat table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Grouping {
+    /// By `[topology] rack_pattern`, else the scheduler's topology
+    Racks,
+    /// By `[topology] prefix_pattern`, e.g. hardware generations named `c6420-`, `amd-`
+    Prefix,
+}
+
+/// One line of the nodes view.
+enum NodeRow<'a> {
+    /// A rack header with the combined usage of its nodes
+    Rack(Rack<'a>),
+    Node(&'a Node),
+}
+
+/// One line of the queue view.
+enum QueueRow<'a> {
+    Job(&'a Job),
+    /// A job array collapsed into one row
+    Array(ArrayJob<'a>),
+    /// A task of an expanded array, listed under it
+    Task(&'a Job),
+}
+
+/// One line of the timeline view: a running job on a node, or a node without any.
+enum TimelineRow<'a> {
+    /// `first` for the node's first job, which carries its name
+    Job { node: &'a Node, job: &'a Job, first: bool },
+    Free(&'a Node),
+}
+
+/// What a table row shows, to find the selected one again after a refresh or re-sort moved it.
+#[derive(Debug, Clone, PartialEq)]
+enum RowKey {
+    /// Cluster and name
+    Node(Option<String>, String),
+    Rack(String),
+    /// Cluster and ID
+    Job(Option<String>, String),
+    Array(Option<String>, String),
+    User(String),
+}
+
+/// A table's selected row, and how far it was below the top of the table.
+struct Anchor {
+    view: View,
+    key: RowKey,
+    index: usize,
+    screen_row: usize,
+}
+
+/// Identifies a fairshare row across refreshes: cluster, account and user.
+type FairshareKey = (Option<String>, String, Option<String>);
+
+fn fairshare_key(entry: &Fairshare) -> FairshareKey {
+    (entry.cluster.clone(), entry.account.clone(), entry.user.clone())
+}
+
+/// What a text prompt's input will be used for once submitted.
+enum PromptKind {
+    NodeReason { node_ids: Vec<String> },
+    DrainReason { node_ids: Vec<String> },
+    WatchJob,
+    Notify,
+    WhatIf,
+    Fit,
+    FeatureFilter,
+}
+
+struct Prompt {
+    kind: PromptKind,
+    input: String,
+}
+
+impl Prompt {
+    fn title(&self) -> String {
+        match &self.kind {
+            PromptKind::NodeReason { node_ids } if node_ids.len() == 1 => {
+                format!("Reason for {}", node_ids[0])
+            }
+            PromptKind::NodeReason { node_ids } => format!("Reason for {} nodes", node_ids.len()),
+            PromptKind::DrainReason { node_ids } if node_ids.len() == 1 => {
+                format!("Drain {}: reason", node_ids[0])
+            }
+            PromptKind::DrainReason { node_ids } => format!("Drain {} nodes: reason", node_ids.len()),
+            PromptKind::WatchJob => "Watch/unwatch job ID".to_string(),
+            PromptKind::Notify => "Notify when, e.g. 8c 32g in gpu or node c001 idle (empty: none)".to_string(),
+            PromptKind::WhatIf => "What-if request, e.g. 2n 16c 64g 4h".to_string(),
+            PromptKind::Fit => "Where does it fit now, e.g. 16c 64g 1gpu (empty: off)".to_string(),
+            PromptKind::FeatureFilter => "Only nodes with features, e.g. avx512,a100 (empty: all)".to_string(),
+        }
+    }
+}
+
+/// An action on the cluster waiting for y/n in a confirmation dialog.
+enum Confirm {
+    CancelJob { id: String, name: String, cluster: Option<String> },
+    ResumeNodes { node_ids: Vec<String> },
+}
+
+impl Confirm {
+    fn question(&self) -> String {
+        match self {
+            Confirm::CancelJob { id, name, .. } => format!("Cancel job {} ({})?", id, name),
+            Confirm::ResumeNodes { node_ids } if node_ids.len() == 1 => format!("Resume {}?", node_ids[0]),
+            Confirm::ResumeNodes { node_ids } => format!("Resume {} nodes?", node_ids.len()),
+        }
+    }
+}
+
+/// A `--replay` being watched: `P` plays and pauses, `[` `]` step, `{` `}` jump.
+struct Playback {
+    replay: Arc<Replay>,
+    playing: bool,
+    last_step: Instant,
+}
+
+pub struct App {
+    scheduler: Arc<dyn Scheduler>,
+    current_partition: String,
+    partitions: Vec<String>,
+    /// Limits of the partitions, if the scheduler reports them
+    partition_limits: Vec<Partition>,
+    recent_partitions: RecentPartitions,
+    /// Selection in the `p` partition picker while it is open
+    partition_picker: Option<TableState>,
+    nodes: Vec<Node>,
+    jobs: Vec<Job>,
+    user_jobs: Vec<Job>,
+    pending_jobs: Vec<Job>,
+    /// Jobs that started over the last [`waits::WINDOW`], for the wait times per partition
+    job_waits: Vec<JobWait>,
+    job_waits_fetched: Option<Instant>,
+    current_user: String,
+    stats: ClusterStats,
+    table_state: TableState,
+    jobs_state: TableState,
+    queue_state: TableState,
+    users_state: TableState,
+    my_jobs_state: TableState,
+    timeline_state: TableState,
+    /// Never selects anything; the Charts view has no rows
+    charts_state: TableState,
+    chart_window: ChartWindow,
+    /// The partition's history over `chart_window`, while the Charts view is shown
+    chart_samples: Vec<ClusterSample>,
+    user_sort: UserSort,
+    user_sort_descending: bool,
+    /// Like `sort_headers`, for the Users view
+    user_sort_headers: (u16, Vec<(u16, u16, UserSort)>),
+    /// Arrays listed task by task in the queue view
+    expanded_arrays: HashSet<String>,
+    view: View,
+    /// Group the nodes view by rack or name prefix
+    grouping: Option<Grouping>,
+    /// Racks (or prefix groups) listed node by node while grouped
+    expanded_racks: HashSet<String>,
+    /// From `[topology] rack_pattern`; wins over `switches`
+    rack_pattern: Option<Regex>,
+    /// From `[topology] prefix_pattern`, else [`topology::DEFAULT_PREFIX_PATTERN`]
+    prefix_pattern: Regex,
+    /// Leaf switch of each node, if the scheduler reports its topology
+    switches: HashMap<String, String>,
+    refresh_interval: Duration,
+    /// How long a refresh waits for the nodes, jobs or your jobs: the longest `[timeouts]` allow
+    call_timeout: Option<Duration>,
+    /// `ssh_command` from config.toml, with `{node}` unreplaced
+    ssh_command: String,
+    /// Keys remapped in `[keys]`
+    keymap: Keymap,
+    /// A command to run with the terminal handed over, set by `o` and run by the main loop
+    shell_command: Option<String>,
+    fetch_health: FetchHealth,
+    /// The background refresh task, once the UI loop started it
+    refresher: Option<Refresher>,
+    /// Counts the refreshes started, to drop a background one overtaken by another
+    refresh_generation: u64,
+    should_quit: bool,
+    error_message: Option<String>,
+    status_message: Option<String>,
+    admin: bool,
+    marked_nodes: HashSet<String>,
+    prompt: Option<Prompt>,
+    confirm: Option<Confirm>,
+    cluster_tz: ClusterTz,
+    clock_skew: Option<chrono::Duration>,
+    /// The controller's clock at the last refresh, if it reports one
+    server_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// When the daemon polled what is shown, with `--connect`
+    polled_at: Option<chrono::DateTime<chrono::Utc>>,
+    watchlist: Watchlist,
+    reservations: Vec<Reservation>,
+    show_reservations: bool,
+    licenses: Vec<License>,
+    show_licenses: bool,
+    /// The user's `sshare` rows, only fetched while shown
+    fairshare: Vec<Fairshare>,
+    /// Direction each row's fairshare factor last moved in
+    fairshare_trends: HashMap<FairshareKey, std::cmp::Ordering>,
+    show_fairshare: bool,
+    what_if: Option<Placement>,
+    /// Where the `c` request fits right now
+    fit: Option<Fit>,
+    /// Hide the nodes without room for the fit request
+    fit_only: bool,
+    /// Show only nodes having all of these features
+    feature_filter: Vec<String>,
+    /// The `/` search bar's input while it is open
+    search_input: Option<String>,
+    search: Option<NodeSearch>,
+    /// The `:` command line while it is open
+    command_line: Option<CommandLine>,
+    /// Set with `:filter` or `:user`
+    node_filter: Option<NodeFilter>,
+    /// The Ctrl-P command palette while it is open
+    palette: Option<Palette>,
+    show_node_detail: bool,
+    show_job_detail: bool,
+    theme: Theme,
+    columns: Vec<Column>,
+    /// Columns scrolled out of a narrow node table, after the first
+    column_offset: usize,
+    /// Columns hidden left and right of the narrow node table at the last draw
+    scrolled_columns: (usize, usize),
+    /// Rows of the main table that fit on screen, for PageUp/PageDown
+    page_rows: usize,
+    sort_key: SortKey,
+    sort_descending: bool,
+    /// Row and `(x, width, key)` of the node table's sortable headers as last drawn
+    sort_headers: (u16, Vec<(u16, u16, SortKey)>),
+    node_history: NodeHistory,
+    /// [`placement::time_until_free`] of the fetched jobs
+    etas: HashMap<(Option<String>, String), chrono::Duration>,
+    changes: ChangeTracker,
+    /// Show the feed of recent node changes
+    show_changes: bool,
+    /// The table fills the terminal, like a zoomed tmux pane
+    zoomed: bool,
+    /// Watches not yet met; each fires once
+    availability_watches: Vec<AvailabilityWatch>,
+    /// The `[[alerts]]` rules, checked at every refresh
+    alerts: Alerts,
+    show_alerts: bool,
+    /// The last notification, shown until dismissed with Esc
+    banner: Option<String>,
+    /// From `[notifications]` in config.toml
+    bell: bool,
+    desktop_notifications: bool,
+    job_end_notifications: bool,
+    /// Where the ends of your jobs are also sent
+    job_end_webhooks: Vec<Webhook>,
+    utilization: UtilizationHistory,
+    /// Highest power draw seen per partition, the scale of the power gauge
+    peak_watts: HashMap<String, u64>,
+    /// Where `--record` appends every refresh
+    recorder: Option<Recorder>,
+    /// The `[history]` database, when enabled
+    history: Option<HistoryDb>,
+    playback: Option<Playback>,
+    // Fixed "current" time so rendering is reproducible in tests, and a replay shows its frame's
+    frozen_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl App {
+    pub async fn new(
+        scheduler: Box<dyn Scheduler>,
+        partition: Option<String>,
+        admin: bool,
+        cluster_tz: ClusterTz,
+        timeouts: &Timeouts,
+    ) -> Result<Self> {
+        let current_user = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+
+        // Discover partitions for the number-key bindings (don't fail on error), with their
+        // limits where the scheduler reports them
+        let partition_limits = scheduler.get_partitions().await.unwrap_or_default();
+        let (partitions, discovery_error) = if partition_limits.is_empty() {
+            match scheduler.list_partitions().await {
+                Ok(partitions) => (partitions, None),
+                Err(e) => (Vec::new(), Some(format!("Failed to list partitions: {}", e))),
+            }
+        } else {
+            let mut names: Vec<String> = Vec::new();
+            for partition in &partition_limits {
+                if !names.contains(&partition.name) {
+                    names.push(partition.name.clone());
+                }
+            }
+            (names, None)
+        };
+        // The network topology only changes with hardware, so it's fetched once
+        let switches = scheduler.get_topology().await.unwrap_or_default();
+        let partition = partition
+            .or_else(|| partitions.first().cloned())
+            .unwrap_or_default();
+        
+        let mut app = App {
+            scheduler: Arc::from(scheduler),
+            current_partition: partition,
+            partitions,
+            partition_limits,
+            recent_partitions: RecentPartitions::load(),
+            partition_picker: None,
+            nodes: Vec::new(),
+            jobs: Vec::new(), 
+            user_jobs: Vec::new(),
+            pending_jobs: Vec::new(),
+            job_waits: Vec::new(),
+            job_waits_fetched: None,
+            current_user,
+            stats: ClusterStats {
+                total_nodes: 0,
+                avail_nodes: 0,
+                total_cores: 0,
+                used_cores: 0,
+                avail_cores: 0,
+                total_memory: 0,
+                used_memory: 0,
+                avail_memory: 0,
+                gpu_profiles: Vec::new(),
+                current_watts: 0,
+                consumed_joules: 0,
+                power_nodes: 0,
+            },
+            table_state: TableState::default(),
+            jobs_state: TableState::default(),
+            queue_state: TableState::default(),
+            users_state: TableState::default(),
+            my_jobs_state: TableState::default(),
+            timeline_state: TableState::default(),
+            charts_state: TableState::default(),
+            chart_window: ChartWindow::default(),
+            chart_samples: Vec::new(),
+            user_sort: UserSort::Cores,
+            user_sort_descending: true,
+            user_sort_headers: (0, Vec::new()),
+            expanded_arrays: HashSet::new(),
+            view: View::Nodes,
+            grouping: None,
+            expanded_racks: HashSet::new(),
+            rack_pattern: None,
+            prefix_pattern: Regex::new(topology::DEFAULT_PREFIX_PATTERN).expect("valid default pattern"),
+            switches,
+            refresh_interval: Duration::from_secs(30),
+            call_timeout: timeouts.longest(),
+            ssh_command: "ssh {node}".to_string(),
+            keymap: Keymap::default(),
+            shell_command: None,
+            fetch_health: FetchHealth::default(),
+            refresher: None,
+            refresh_generation: 0,
+            should_quit: false,
+            error_message: None,
+            status_message: None,
+            admin,
+            marked_nodes: HashSet::new(),
+            prompt: None,
+            confirm: None,
+            cluster_tz,
+            clock_skew: None,
+            server_time: None,
+            polled_at: None,
+            watchlist: Watchlist::load(),
+            reservations: Vec::new(),
+            show_reservations: false,
+            licenses: Vec::new(),
+            show_licenses: false,
+            fairshare: Vec::new(),
+            fairshare_trends: HashMap::new(),
+            show_fairshare: false,
+            what_if: None,
+            fit: None,
+            fit_only: false,
+            feature_filter: Vec::new(),
+            search_input: None,
+            command_line: None,
+            node_filter: None,
+            palette: None,
+            search: None,
+            show_node_detail: false,
+            show_job_detail: false,
+            theme: Theme::default(),
+            columns: columns::DEFAULT.to_vec(),
+            column_offset: 0,
+            scrolled_columns: (0, 0),
+            page_rows: 10,
+            sort_key: SortKey::Availability,
+            sort_descending: false,
+            sort_headers: (0, Vec::new()),
+            node_history: NodeHistory::default(),
+            etas: HashMap::new(),
+            changes: ChangeTracker::default(),
+            show_changes: false,
+            zoomed: false,
+            availability_watches: Vec::new(),
+            alerts: Alerts::default(),
+            show_alerts: false,
+            banner: None,
+            bell: true,
+            desktop_notifications: false,
+            job_end_notifications: true,
+            job_end_webhooks: Vec::new(),
+            utilization: UtilizationHistory::default(),
+            peak_watts: HashMap::new(),
+            recorder: None,
+            history: None,
+            playback: None,
+            frozen_time: None,
+        };
+
+        app.fetch_data().await;
+        if app.error_message.is_none() {
+            app.error_message = discovery_error;
+        }
+        Ok(app)
+    }
+
+    pub fn set_refresh_interval(&mut self, interval: Duration) {
+        self.refresh_interval = interval.max(Duration::from_secs(MIN_REFRESH_SECS));
+    }
+
+    /// The next step of [`REFRESH_STEPS`] up (`longer`) or down from the current interval.
+    fn step_refresh_interval(&mut self, longer: bool) {
+        let secs = self.refresh_interval.as_secs();
+        let next = if longer {
+            REFRESH_STEPS.iter().find(|step| **step > secs).copied()
+        } else {
+            REFRESH_STEPS.iter().rev().find(|step| **step < secs).copied()
+        };
+        let secs = next.unwrap_or(secs);
+        self.set_refresh_interval(Duration::from_secs(secs));
+        self.status_message = Some(format!("Refreshing every {}s", self.refresh_interval.as_secs()));
+    }
+
+    fn toggle_playback(&mut self) {
+        let Some(ref mut playback) = self.playback else {
+            return;
+        };
+        playback.playing = !playback.playing;
+        playback.last_step = Instant::now();
+        self.status_message = Some(if playback.playing { "Playing" } else { "Paused" }.to_string());
+    }
+
+    /// Show the frame `delta` away in the replay; playing stops at the end.
+    async fn seek_replay(&mut self, delta: isize) {
+        let Some(ref mut playback) = self.playback else {
+            return;
+        };
+        playback.last_step = Instant::now();
+        if !playback.replay.seek(delta) {
+            let end = playback.playing;
+            playback.playing = false;
+            if end {
+                self.status_message = Some("End of the recording".to_string());
+            }
+            return;
+        }
+        self.frozen_time = Some(playback.replay.frame().time);
+        self.fetch_data().await;
+    }
+
+    fn record_history(&mut self) {
+        let now = self.now();
+        let Some(ref mut history) = self.history else {
+            return;
+        };
+        if let Err(e) = history.record(now, &self.current_partition, &self.stats, &self.jobs, self.pending_jobs.len(), &self.nodes) {
+            self.error_message = Some(format!("Failed to save history: {:#}", e));
+        }
+    }
+
+    /// Append what was just fetched to the `--record` file; `error` is why the nodes weren't.
+    fn record_frame(&mut self, error: Option<String>) {
+        if self.recorder.is_none() {
+            return;
+        }
+        let frame = self.frame(error);
+        if let Some(Err(e)) = self.recorder.as_mut().map(|recorder| recorder.record(&frame)) {
+            self.error_message = Some(format!("Failed to record: {:#}", e));
+        }
+    }
+
+    /// Everything last fetched, as recorded and dumped.
+    fn frame(&self, error: Option<String>) -> recording::Frame {
+        recording::Frame {
+            time: self.now(),
+            partition: self.current_partition.clone(),
+            partitions: self.partitions.clone(),
+            nodes: self.nodes.clone(),
+            jobs: self.jobs.clone(),
+            pending_jobs: self.pending_jobs.clone(),
+            user_jobs: self.user_jobs.clone(),
+            reservations: self.reservations.clone(),
+            licenses: self.licenses.clone(),
+            server_time: self.server_time,
+            error,
+        }
+    }
+
+    /// What went wrong in the last refresh, if anything.
+    pub fn error(&self) -> Option<&str> {
+        self.error_message.as_deref()
+    }
+
+    pub fn set_ssh_command(&mut self, command: String) {
+        self.ssh_command = command;
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<Column>) {
+        self.columns = columns;
+    }
+
+    /// Append every refresh from now on, starting with the one already shown.
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+        let error = self.error_message.clone().filter(|_| self.nodes.is_empty());
+        self.record_frame(error);
+    }
+
+    /// Check `alerts` at every refresh from now on.
+    pub fn set_alerts(&mut self, alerts: Alerts) {
+        self.alerts = alerts;
+    }
+
+    /// Store every refresh's usage in `history` from now on.
+    pub fn set_history(&mut self, history: HistoryDb) {
+        self.history = Some(history);
+    }
+
+    /// Show `replay` (which the scheduler answers from) at its current frame, paused.
+    pub async fn set_replay(&mut self, replay: Arc<Replay>) {
+        self.frozen_time = Some(replay.frame().time);
+        self.playback = Some(Playback { replay, playing: false, last_step: Instant::now() });
+        self.fetch_data().await;
+    }
+
+    /// The partition, sort and filters to restore next time; the caller adds columns and theme.
+    pub fn session_state(&self) -> SessionState {
+        SessionState {
+            partition: Some(self.current_partition.clone()),
+            sort: Some((self.sort_key.name().to_string(), self.sort_descending)),
+            filter: self.node_filter.as_ref().map(|node_filter| node_filter.source().to_string()),
+            features: self.feature_filter.clone(),
+            ..SessionState::default()
+        }
+    }
+
+    /// Sort and filter as in the saved session, skipping what no longer parses.
+    pub async fn restore_session(&mut self, state: &SessionState) {
+        if let Some((key, descending)) = state.sort.as_ref().and_then(|(key, descending)| Some((SortKey::parse(key)?, *descending))) {
+            self.sort_key = key;
+            self.sort_descending = descending;
+            self.sort_nodes();
+        }
+        if let Some(node_filter) = state.filter.as_deref().and_then(|source| NodeFilter::parse(source).ok().flatten()) {
+            self.node_filter = Some(node_filter);
+        }
+        if !state.features.is_empty() {
+            self.feature_filter = state.features.clone();
+            self.fetch_data().await;
+        }
+    }
+
+    /// Show only the nodes matching `node_filter`, as after `:filter`.
+    pub fn set_filter(&mut self, node_filter: NodeFilter) {
+        self.node_filter = Some(node_filter);
+    }
+
+    pub fn set_rack_pattern(&mut self, pattern: Option<Regex>) {
+        self.rack_pattern = pattern;
+    }
+
+    pub fn set_prefix_pattern(&mut self, pattern: Regex) {
+        self.prefix_pattern = pattern;
+    }
+
+    pub fn set_notifications(&mut self, bell: bool, desktop: bool, job_end: bool) {
+        self.bell = bell;
+        self.desktop_notifications = desktop;
+        self.job_end_notifications = job_end;
+    }
+
+    /// Send the ends of your jobs to `webhooks` as well.
+    pub fn set_job_end_webhooks(&mut self, webhooks: Vec<Webhook>) {
+        self.job_end_webhooks = webhooks;
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        // Setup terminal
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+
+        let result = self.run_app(&mut terminal).await;
+
+        // Restore terminal
+        disable_raw_mode()?;
+        execute!(
+            terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+        terminal.show_cursor()?;
+
+        result
+    }
+
+    async fn run_app<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        self.refresher = Some(Refresher::spawn(self.scheduler.clone()));
+        loop {
+            terminal.draw(|f| self.ui(f))?;
+
+            // Handle input
+            if event::poll(Duration::from_millis(100))? {
+                match event::read()? {
+                    Event::Key(key) => self.handle_key(key).await,
+                    Event::Mouse(mouse) => self.handle_mouse(mouse),
+                    _ => {},
+                }
+            }
+
+            if let Some(command) = self.shell_command.take() {
+                self.run_shell(terminal, &command)?;
+            }
+
+            // Auto refresh, backing off while the scheduler keeps failing; a replay moves on instead
+            match self.playback {
+                Some(ref playback) if playback.playing && playback.last_step.elapsed() >= REPLAY_STEP => {
+                    self.seek_replay(1).await;
+                },
+                Some(_) => {},
+                None if self.fetch_health.is_due(self.refresh_interval) => self.refresh().await,
+                None => {},
+            }
+            self.receive_refresh().await;
+
+            if self.should_quit {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Leave the TUI while `command` runs in the terminal, then restore it.
+    fn run_shell<B: Backend>(&mut self, terminal: &mut Terminal<B>, command: &str) -> Result<()> {
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+        terminal.show_cursor()?;
+
+        let status = std::process::Command::new("sh").arg("-c").arg(command).status();
+
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        terminal.clear()?;
+
+        match status {
+            Ok(status) if status.success() => {},
+            Ok(status) => self.status_message = Some(format!("'{}' exited with {}", command, status)),
+            Err(e) => self.error_message = Some(format!("Failed to run '{}': {}", command, e)),
+        }
+        Ok(())
+    }
+
+    async fn handle_key(&mut self, mut key: KeyEvent) {
+        // A palette entry acts as its built-in key would, or runs its command
+        let from_palette = self.palette.is_some();
+        if from_palette {
+            match self.handle_palette_key(key) {
+                Some(Action::Key(code)) => key = KeyEvent::new(code, KeyModifiers::NONE),
+                Some(Action::Command(line)) => {
+                    self.run_command(&line).await;
+                    return;
+                },
+                None => return,
+            }
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key).await;
+            return;
+        }
+        if self.confirm.is_some() {
+            self.handle_confirm_key(key).await;
+            return;
+        }
+        if self.search_input.is_some() {
+            self.handle_search_key(key);
+            return;
+        }
+        if self.command_line.is_some() {
+            self.handle_command_key(key).await;
+            return;
+        }
+        if self.partition_picker.is_some() {
+            self.handle_picker_key(key).await;
+            return;
+        }
+        if !from_palette {
+            key = self.keymap.translate(key);
+        }
+
+        match key.code {
+            // A refresh stuck on the controller is stopped first; the next Esc closes what's open
+            KeyCode::Esc if self.refresher.as_ref().and_then(Refresher::started).is_some() => {
+                if let Some(ref mut refresher) = self.refresher {
+                    refresher.cancel();
+                }
+                // Try again at the next interval rather than right away
+                self.fetch_health.record_cancelled();
+                self.status_message = Some("Refresh cancelled".to_string());
+            },
+            KeyCode::Enter if self.view == View::Nodes && self.selected_node().is_some() => {
+                self.show_node_detail = !self.show_node_detail;
+            },
+            KeyCode::Esc if self.show_node_detail => {
+                self.show_node_detail = false;
+            },
+            KeyCode::Enter if self.view == View::Queue && self.selected_pending_job().is_some() => {
+                self.show_job_detail = !self.show_job_detail;
+            },
+            KeyCode::Enter if matches!(self.view, View::Jobs | View::MyJobs | View::Timeline) && self.selected_job().is_some() => {
+                self.show_job_detail = !self.show_job_detail;
+            },
+            KeyCode::Esc if self.show_job_detail => {
+                self.show_job_detail = false;
+            },
+            KeyCode::Esc if self.banner.is_some() => {
+                self.banner = None;
+            },
+            KeyCode::Esc if self.search.is_some() => {
+                self.search = None;
+                self.table_state.select(None);
+            },
+            KeyCode::Char(':') => {
+                self.command_line = Some(CommandLine::default());
+            },
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let mut palette = Palette::default();
+                palette.state.select(Some(0));
+                self.palette = Some(palette);
+            },
+            KeyCode::Char('/') if self.view == View::Nodes => {
+                self.search_input = Some(self.search.as_ref().map(|s| s.pattern().to_string()).unwrap_or_default());
+            },
+            KeyCode::Char('d') | KeyCode::Delete if self.view == View::MyJobs => {
+                if let Some(job) = self.selected_job() {
+                    self.confirm = Some(Confirm::CancelJob {
+                        id: job.id.clone(),
+                        name: job.name.clone(),
+                        cluster: job.cluster.clone(),
+                    });
+                }
+            },
+            KeyCode::Char('H') if self.view == View::Queue => {
+                self.hold_selected(true).await;
+            },
+            KeyCode::Char('U') if self.view == View::Queue => {
+                self.hold_selected(false).await;
+            },
+            KeyCode::Char('y') => {
+                self.yank();
+            },
+            KeyCode::Char('o') if self.view == View::Nodes => {
+                if let Some(node) = self.selected_node() {
+                    self.shell_command = Some(self.ssh_command.replace("{node}", &shell_quote(&node.id)));
+                }
+            },
+            KeyCode::Char('x') if self.view == View::Queue => {
+                self.toggle_array();
+            },
+            KeyCode::Char('x') if self.grouping.is_some() => {
+                self.toggle_rack();
+            },
+            KeyCode::Char('g') if self.view == View::Nodes => {
+                self.grouping = match self.grouping {
+                    None => Some(Grouping::Racks),
+                    Some(Grouping::Racks) => Some(Grouping::Prefix),
+                    Some(Grouping::Prefix) => None,
+                };
+                self.table_state.select(None);
+                self.show_node_detail = false;
+                self.status_message = match self.grouping {
+                    Some(Grouping::Racks) if self.switches.is_empty() && self.rack_pattern.is_none() => {
+                        Some("No rack topology; set [topology] rack_pattern in config.toml".to_string())
+                    },
+                    Some(Grouping::Prefix) => Some(format!("Grouped by name prefix ({})", self.prefix_pattern)),
+                    _ => None,
+                };
+            },
+            KeyCode::Char('z') => {
+                self.zoomed = !self.zoomed;
+            },
+            KeyCode::Char('P') if self.playback.is_some() => self.toggle_playback(),
+            KeyCode::Char('[') if self.playback.is_some() => self.seek_replay(-1).await,
+            KeyCode::Char(']') if self.playback.is_some() => self.seek_replay(1).await,
+            KeyCode::Char('{') if self.playback.is_some() => self.seek_replay(-REPLAY_JUMP).await,
+            KeyCode::Char('}') if self.playback.is_some() => self.seek_replay(REPLAY_JUMP).await,
+            KeyCode::Char('+') => self.step_refresh_interval(true),
+            KeyCode::Char('-') => self.step_refresh_interval(false),
+            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Char('r') | KeyCode::Char(' ') => {
+                self.refresh().await;
+            },
+            KeyCode::F(n @ 1..=9) => {
+                self.toggle_cluster(n as usize - 1).await;
+            },
+            // Plain digits pick partitions
+            KeyCode::Char(c @ '1'..='7') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.set_view(View::ALL[c as usize - '1' as usize]).await;
+            },
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if let Some(partition) = self.partitions.get(index).cloned() {
+                    self.switch_partition(partition).await;
+                }
+            },
+            KeyCode::Char('p') if !self.partitions.is_empty() => {
+                let current = self.picker_partitions().iter().position(|p| *p == self.current_partition);
+                self.partition_picker = Some(TableState::default().with_selected(Some(current.unwrap_or(0))));
+                if self.job_waits_stale() {
+                    self.reload(None).await;
+                }
+            },
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.next_row();
+            },
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.previous_row();
+            },
+            KeyCode::PageDown => {
+                let index = self.selected_row() + self.page_rows;
+                self.select_row(index);
+            },
+            KeyCode::PageUp => {
+                let index = self.selected_row().saturating_sub(self.page_rows);
+                self.select_row(index);
+            },
+            KeyCode::Home => self.select_row(0),
+            KeyCode::End => self.select_row(usize::MAX),
+            KeyCode::Left | KeyCode::Char('h') if self.view == View::Nodes => {
+                self.column_offset = self.column_offset.saturating_sub(1);
+            },
+            KeyCode::Right | KeyCode::Char('l') if self.view == View::Nodes && self.scrolled_columns.1 > 0 => {
+                self.column_offset += 1;
+            },
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Right | KeyCode::Char('l') if self.view == View::Charts => {
+                self.step_chart_window(matches!(key.code, KeyCode::Right | KeyCode::Char('l')));
+            },
+            KeyCode::Tab => {
+                self.set_view(self.view.cycle(true)).await;
+            },
+            KeyCode::BackTab => {
+                self.set_view(self.view.cycle(false)).await;
+            },
+            KeyCode::Char('v') => {
+                self.toggle_mark();
+            },
+            KeyCode::Char('e') if self.admin => {
+                self.open_reason_prompt();
+            },
+            KeyCode::Char('D') if self.admin && self.view == View::Nodes => {
+                self.open_drain_prompt();
+            },
+            KeyCode::Char('U') if self.admin && self.view == View::Nodes => {
+                self.confirm_resume();
+            },
+            KeyCode::Char('s') if self.view == View::Users => {
+                self.set_user_sort(self.user_sort.next());
+            },
+            KeyCode::Char('S') if self.view == View::Users => {
+                self.set_user_sort(self.user_sort);
+            },
+            KeyCode::Char('s') => {
+                self.set_sort(self.sort_key.next());
+            },
+            KeyCode::Char('S') => {
+                self.set_sort(self.sort_key);
+            },
+            KeyCode::Char('i') => {
+                let input = self.what_if.as_ref().map(|p| p.request.to_string()).unwrap_or_default();
+                self.prompt = Some(Prompt { kind: PromptKind::WhatIf, input });
+            },
+            KeyCode::Esc if self.what_if.is_some() => {
+                self.what_if = None;
+                self.status_message = None;
+            },
+            KeyCode::Char('c') => {
+                let input = self.fit.as_ref().map(|fit| fit.request.to_string()).unwrap_or_default();
+                self.prompt = Some(Prompt { kind: PromptKind::Fit, input });
+            },
+            KeyCode::Char('C') if self.fit.is_some() => {
+                self.fit_only = !self.fit_only;
+                self.table_state.select(None);
+            },
+            KeyCode::Esc if self.fit.is_some() => {
+                self.fit = None;
+                self.fit_only = false;
+                self.status_message = None;
+            },
+            KeyCode::Char('w') => {
+                self.prompt = Some(Prompt { kind: PromptKind::WatchJob, input: String::new() });
+            },
+            KeyCode::Char('N') => {
+                self.prompt = Some(Prompt { kind: PromptKind::Notify, input: String::new() });
+            },
+            KeyCode::Char('W') => match self.selected_job_id() {
+                Some(job_id) => self.toggle_watch(&job_id).await,
+                None => self.status_message = Some("No job selected".to_string()),
+            },
+            KeyCode::Char('R') => {
+                self.show_reservations = !self.show_reservations;
+                if self.show_reservations && self.reservations.is_empty() {
+                    self.status_message = Some("No active or upcoming reservations".to_string());
+                }
+            },
+            KeyCode::Char('L') => {
+                self.show_licenses = !self.show_licenses;
+                if self.show_licenses && self.licenses.is_empty() {
+                    self.status_message = Some("No licenses configured".to_string());
+                }
+            },
+            KeyCode::Char('a') => {
+                self.show_changes = !self.show_changes;
+            },
+            KeyCode::Char('A') => {
+                self.show_alerts = !self.show_alerts;
+            },
+            KeyCode::Char('F') => {
+                self.show_fairshare = !self.show_fairshare;
+                if self.show_fairshare {
+                    self.reload(None).await;
+                }
+            },
+            KeyCode::Char('f') => {
+                let input = self.feature_filter.join(",");
+                self.prompt = Some(Prompt { kind: PromptKind::FeatureFilter, input });
+            },
+            _ => {},
+        }
+    }
+
+    async fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            },
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => prompt.input.clear(),
+            KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => {},
+            KeyCode::Char(c) => prompt.input.push(c),
+            KeyCode::Enter => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.submit_prompt(prompt).await;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        match mouse.kind {
+            // A click on a node table header sorts by that column
+            MouseEventKind::Down(_) if self.view == View::Nodes && mouse.row == self.sort_headers.0 => {
+                let column = self.sort_headers.1.iter().find(|(x, width, _)| (*x..x + width).contains(&mouse.column));
+                if let Some(&(_, _, key)) = column {
+                    self.set_sort(key);
+                }
+            },
+            MouseEventKind::Down(_) if self.view == View::Users && mouse.row == self.user_sort_headers.0 => {
+                let column = self.user_sort_headers.1.iter().find(|(x, width, _)| (*x..x + width).contains(&mouse.column));
+                if let Some(&(_, _, key)) = column {
+                    self.set_user_sort(key);
+                }
+            },
+            // Handle mouse clicks for table selection
+            MouseEventKind::Down(_) => {
+                let len = self.row_count();
+                let offset = self.view_state().offset();
+                if let Some(index) = (mouse.row as usize).checked_sub(6).map(|i| i + offset).filter(|i| *i < len) {
+                    self.view_state().select(Some(index));
+                }
+            },
+            MouseEventKind::ScrollDown => {
+                self.next_row();
+            },
+            MouseEventKind::ScrollUp => {
+                self.previous_row();
+            },
+            _ => {},
+        }
+    }
+
+    /// Keys while the partition picker is open.
+    async fn handle_picker_key(&mut self, key: KeyEvent) {
+        let len = self.picker_partitions().len();
+        let Some(picker) = self.partition_picker.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('p') | KeyCode::Char('q') => self.partition_picker = None,
+            KeyCode::Down | KeyCode::Char('j') => step_selection(picker, len, true),
+            KeyCode::Up | KeyCode::Char('k') => step_selection(picker, len, false),
+            KeyCode::Enter => {
+                let index = picker.selected().unwrap_or(0);
+                let partition = self.picker_partitions().get(index).map(|p| p.to_string());
+                self.partition_picker = None;
+                if let Some(partition) = partition {
+                    self.switch_partition(partition).await;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// The picker's list: recently used partitions first, then the rest in discovery order.
+    fn picker_partitions(&self) -> Vec<&str> {
+        let recent = self.recent_partitions.names().iter().filter(|name| self.partitions.contains(name));
+        let rest = self.partitions.iter().filter(|name| !self.recent_partitions.names().contains(name));
+        recent.chain(rest).map(String::as_str).collect()
+    }
+
+    async fn switch_partition(&mut self, partition: String) {
+        // Only the picker's ordering depends on it, so a failed save isn't worth an error
+        let _ = self.recent_partitions.record(&partition);
+        self.current_partition = partition;
+        self.reload(None).await;
+    }
+
+    /// Keys while the search bar is open: the table narrows with every keystroke.
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.search_input.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => {
+                self.search_input = None;
+                self.search = None;
+            },
+            KeyCode::Enter => {
+                self.search_input = None;
+                return;
+            },
+            KeyCode::Backspace => {
+                input.pop();
+            },
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => input.clear(),
+            KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => return,
+            KeyCode::Char(c) => input.push(c),
+            _ => return,
+        }
+        if let Some(ref input) = self.search_input {
+            self.search = NodeSearch::new(input);
+        }
+        let first = (!self.node_rows().is_empty()).then_some(0);
+        self.table_state.select(first);
+    }
+
+    /// Keys while the `:` command line is open.
+    async fn handle_command_key(&mut self, key: KeyEvent) {
+        let Some(line) = self.command_line.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.command_line = None,
+            KeyCode::Backspace if line.input.is_empty() => self.command_line = None,
+            KeyCode::Backspace => {
+                line.input.pop();
+            },
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => line.input.clear(),
+            KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => {},
+            KeyCode::Char(c) => line.input.push(c),
+            KeyCode::Tab => {
+                let input = line.input.clone();
+                let (input, candidates) = command::complete(&input, |command| self.command_arguments(command));
+                self.command_line = Some(CommandLine { input, candidates });
+            },
+            KeyCode::Enter => {
+                if let Some(line) = self.command_line.take() {
+                    self.run_command(&line.input).await;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn palette_entries(&self) -> Vec<Entry> {
+        let sort_keys: Vec<(&str, &str)> = SortKey::ALL.iter().map(|key| (key.name(), key.label())).collect();
+        let mut entries = palette::entries(self.view, self.admin, &self.partitions, &sort_keys);
+        for entry in &mut entries {
+            if let Some(key) = match entry.action {
+                Action::Key(code) => self.keymap.remapped(code),
+                Action::Command(_) => None,
+            } {
+                entry.hint = key;
+            }
+        }
+        entries
+    }
+
+    /// Keys while the palette is open; the action of the entry chosen with Enter.
+    fn handle_palette_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let entries = self.palette_entries();
+        let palette = self.palette.as_mut()?;
+        let len = palette.matches(&entries).len();
+
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Down => step_selection(&mut palette.state, len, true),
+            KeyCode::Up => step_selection(&mut palette.state, len, false),
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => step_selection(&mut palette.state, len, true),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => step_selection(&mut palette.state, len, false),
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                palette.input.clear();
+                palette.state.select(Some(0));
+            },
+            KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => {},
+            KeyCode::Char(c) => {
+                palette.input.push(c);
+                palette.state.select(Some(0));
+            },
+            KeyCode::Backspace => {
+                palette.input.pop();
+                palette.state.select(Some(0));
+            },
+            KeyCode::Enter => {
+                let chosen = palette.matches(&entries).get(palette.state.selected().unwrap_or(0)).map(|entry| entry.action.clone());
+                self.palette = None;
+                return chosen;
+            },
+            _ => {},
+        }
+        None
+    }
+
+    /// What Tab offers after a command's name.
+    fn command_arguments(&self, command: &str) -> Vec<String> {
+        match command {
+            "partition" => self.partitions.iter().cloned().chain(["all".to_string()]).collect(),
+            "filter" => filter::FIELDS.iter().map(|field| format!("{}=", field)).collect(),
+            "sort" => SortKey::ALL.iter().map(|key| key.name().to_string()).collect(),
+            "user" => {
+                let mut users: Vec<String> = self.jobs.iter().map(|job| job.user.clone()).collect();
+                users.sort();
+                users.dedup();
+                users
+            },
+            "view" => View::ALL.iter().map(|view| view.name().to_string()).collect(),
+            "dump" => vec!["anonymize".to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    async fn run_command(&mut self, line: &str) {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(e) => {
+                self.error_message = Some(e.to_string());
+                return;
+            }
+        };
+
+        match command {
+            Command::Partition(name) => {
+                if name != "all" && !self.partitions.is_empty() && !self.partitions.contains(&name) {
+                    self.error_message = Some(format!("Unknown partition '{}'", name));
+                    return;
+                }
+                self.switch_partition(name).await;
+            },
+            Command::Filter(expression) => match NodeFilter::parse(&expression) {
+                Ok(node_filter) => self.set_node_filter(node_filter),
+                Err(e) => self.error_message = Some(format!("Invalid filter: {}", e)),
+            },
+            Command::User(user) => {
+                self.set_node_filter(NodeFilter::with_user(self.node_filter.as_ref(), user.as_deref()));
+            },
+            Command::Sort(key, descending) => {
+                self.sort_by(key, descending.unwrap_or(key.descending_by_default()));
+            },
+            Command::Refresh(secs) => {
+                self.set_refresh_interval(Duration::from_secs(secs));
+                self.status_message = Some(format!("Refreshing every {}s", self.refresh_interval.as_secs()));
+            },
+            Command::View(view) => self.set_view(view).await,
+            Command::Notify(condition) => self.add_availability_watch(&condition).await,
+            Command::Dump { anonymize, path } => self.dump_snapshot(anonymize, path),
+            Command::Quit => self.should_quit = true,
+        }
+    }
+
+    /// Write what's shown to `path`, by default `nodestat-<time>.json` in the working directory,
+    /// for attaching to a bug report.
+    fn dump_snapshot(&mut self, anonymize: bool, path: Option<String>) {
+        let path = path.unwrap_or_else(|| format!("nodestat-{}.json", self.now().format("%Y%m%d-%H%M%S")));
+        let written = self.export_json(anonymize).and_then(|json| {
+            std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path))
+        });
+        match written {
+            Ok(()) => {
+                let anonymized = if anonymize { " with usernames replaced" } else { "" };
+                self.status_message = Some(format!("Snapshot written to {}{}", path, anonymized));
+            },
+            Err(e) => self.error_message = Some(format!("{:#}", e)),
+        }
+    }
+
+    fn set_node_filter(&mut self, node_filter: Option<NodeFilter>) {
+        self.node_filter = node_filter;
+        self.table_state.select(None);
+        self.status_message = Some(match self.node_filter {
+            Some(ref node_filter) => format!("Filter {}: {} node(s)", node_filter.source(), self.visible_nodes().len()),
+            None => "Filter cleared".to_string(),
+        });
+    }
+
+    async fn handle_confirm_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                if let Some(confirm) = self.confirm.take() {
+                    self.run_confirmed(confirm).await;
+                }
+            },
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.confirm = None;
+            },
+            _ => {},
+        }
+    }
+
+    async fn run_confirmed(&mut self, confirm: Confirm) {
+        let change = match confirm {
+            Confirm::CancelJob { id, cluster, .. } => Change::CancelJob { id, cluster },
+            Confirm::ResumeNodes { node_ids } => Change::ResumeNodes { node_ids },
+        };
+        self.reload(Some(change)).await;
+    }
+
+    async fn submit_prompt(&mut self, prompt: Prompt) {
+        match prompt.kind {
+            PromptKind::NodeReason { node_ids } => {
+                let reason = prompt.input.trim();
+                if reason.is_empty() {
+                    self.status_message = Some("Reason not changed: empty input".to_string());
+                    return;
+                }
+
+                self.reload(Some(Change::UpdateReason { node_ids, reason: reason.to_string() })).await;
+            },
+            PromptKind::DrainReason { node_ids } => {
+                let reason = prompt.input.trim();
+                if reason.is_empty() {
+                    self.status_message = Some("Not drained: a reason is required".to_string());
+                    return;
+                }
+
+                self.reload(Some(Change::DrainNodes { node_ids, reason: reason.to_string() })).await;
+            },
+            PromptKind::WatchJob => {
+                let job_id = prompt.input.trim();
+                if !job_id.is_empty() {
+                    self.toggle_watch(job_id).await;
+                }
+            },
+            PromptKind::Notify => self.add_availability_watch(&prompt.input).await,
+            PromptKind::WhatIf => match ResourceRequest::parse(&prompt.input) {
+                Ok(request) => {
+                    let result = placement::simulate(&request, &self.nodes, &self.jobs, &self.partition_limits);
+                    self.status_message = Some(result.summary());
+                    self.what_if = Some(result);
+                },
+                Err(e) => {
+                    self.error_message = Some(format!("Invalid what-if request: {}", e));
+                }
+            },
+            PromptKind::Fit if prompt.input.trim().is_empty() => {
+                self.fit = None;
+                self.fit_only = false;
+            },
+            PromptKind::Fit => match ResourceRequest::parse(&prompt.input) {
+                Ok(request) => {
+                    let fit = placement::find_fits(&request, &self.nodes, &self.selected_partitions());
+                    self.status_message = Some(fit.summary());
+                    self.fit = Some(fit);
+                    self.table_state.select(None);
+                },
+                Err(e) => {
+                    self.error_message = Some(format!("Invalid fit request: {}", e));
+                }
+            },
+            PromptKind::FeatureFilter => {
+                self.feature_filter = prompt.input
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|f| !f.is_empty())
+                    .map(str::to_lowercase)
+                    .collect();
+                self.table_state.select(None);
+                self.reload(None).await;
+            },
+        }
+    }
+
+    /// Pin the job to the watchlist, or unpin it if it is there already.
+    async fn toggle_watch(&mut self, job_id: &str) {
+        match self.watchlist.toggle(job_id) {
+            Ok(true) => {
+                self.reload(None).await;
+                self.status_message = Some(format!("Watching job {}", job_id));
+            },
+            Ok(false) => {
+                self.status_message = Some(format!("Stopped watching job {}", job_id));
+            },
+            Err(e) => {
+                self.error_message = Some(format!("Failed to save watchlist: {}", e));
+            }
+        }
+    }
+
+    /// Watch for `input` (see [`AvailabilityWatch::parse`]); empty drops every watch.
+    async fn add_availability_watch(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            self.availability_watches.clear();
+            self.status_message = Some("Notifications cleared".to_string());
+            return;
+        }
+        match AvailabilityWatch::parse(input) {
+            Ok(watch) => {
+                // It may hold already; on another partition, once its nodes are fetched
+                let elsewhere = watch.partition().is_some_and(|partition| partition != self.current_partition);
+                self.availability_watches.push(watch);
+                self.check_availability_watches(&HashMap::new());
+                if elsewhere {
+                    self.reload(None).await;
+                }
+                if let Some(watch) = self.availability_watches.last() {
+                    self.status_message = Some(format!("Will notify when {}", watch.source()));
+                }
+            },
+            Err(e) => self.error_message = Some(format!("Invalid watch: {}", e)),
+        }
+    }
+
+    /// Notify about the watches now met and drop them; watches on other partitions are checked
+    /// against their nodes in `others`, and wait for the next refresh if they aren't there.
+    fn check_availability_watches(&mut self, others: &HashMap<String, Vec<Node>>) {
+        let mut messages = Vec::new();
+        for watch in std::mem::take(&mut self.availability_watches) {
+            let nodes = match watch.partition().filter(|partition| *partition != self.current_partition) {
+                None => &self.nodes,
+                Some(partition) => match others.get(partition) {
+                    Some(nodes) => nodes,
+                    None => {
+                        self.availability_watches.push(watch);
+                        continue;
+                    },
+                },
+            };
+            match watch.check(nodes) {
+                Some(message) => messages.push(message),
+                None => self.availability_watches.push(watch),
+            }
+        }
+        if !messages.is_empty() {
+            self.notify(messages.join(" | "));
+        }
+    }
+
+    /// Check the `[[alerts]]` rules against the partition shown and the `others` they name, and
+    /// deliver the ones that fire.
+    async fn check_alerts(&mut self, pending_fetched: bool, others: Vec<OtherPartition>) {
+        if self.alerts.is_empty() {
+            return;
+        }
+        let now = self.now();
+        let current = self.current_partition.clone();
+        let pending = pending_fetched.then_some(self.pending_jobs.len());
+        let observation = Observation::new(&self.stats, &self.nodes, &self.jobs, pending);
+        let mut firings = self.alerts.check(&current, true, &observation, now);
+
+        for other in others {
+            let stats = ClusterStats::of(&other.nodes);
+            let observation = Observation::new(&stats, &other.nodes, &other.jobs, other.pending);
+            firings.extend(self.alerts.check(&other.partition, false, &observation, now));
+        }
+
+        for firing in firings {
+            self.deliver_alert(&firing).await;
+        }
+    }
+
+    /// The banner always; the bell, a desktop notification and webhooks as the rule's `notify`
+    /// or `[notifications]` ask.
+    async fn deliver_alert(&mut self, firing: &Firing) {
+        if self.bell || firing.targets.contains(&Target::Bell) {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        if self.desktop_notifications || firing.targets.contains(&Target::Desktop) {
+            let _ = desktop::notify("NodeStat alert", &firing.message);
+        }
+        for target in &firing.targets {
+            if let Target::Webhook(webhook) = target {
+                if let Err(e) = webhook.send(&Notice::Alert(firing)).await {
+                    self.error_message = Some(format!("{:#}", e));
+                }
+            }
+        }
+        self.banner = Some(format!("🚨 {}", firing.message));
+    }
+
+    fn update_watchlist(&mut self, jobs: Vec<Job>) {
+        let transitions = self.watchlist.update(jobs);
+        if !transitions.is_empty() {
+            self.notify(transitions.join(" | "));
+        }
+    }
+
+    /// Tell the user about their jobs that were running at the last refresh and no longer are,
+    /// with the final state if `finals` (as the scheduler still knows them) have it.
+    async fn announce_ended_jobs(&mut self, previous: Vec<Job>, finals: Vec<Job>) {
+        let ended: Vec<Job> = previous
+            .into_iter()
+            .filter(|job| !self.user_jobs.iter().any(|running| running.id == job.id))
+            .collect();
+        if ended.is_empty() {
+            return;
+        }
+
+        let time = self.now();
+        let mut messages = Vec::new();
+        for job in &ended {
+            let state = finals.iter().find(|f| f.id == job.id).map(|f| &f.state);
+            // Missing from one listing but not over; the next refresh tells
+            if matches!(state, Some(JobState::Running | JobState::Pending | JobState::Suspended)) {
+                continue;
+            }
+            let notice = Notice::JobEnded { job, state, time };
+            for webhook in &self.job_end_webhooks {
+                if let Err(e) = webhook.send(&notice).await {
+                    self.error_message = Some(format!("{:#}", e));
+                }
+            }
+            messages.push(notice.message());
+        }
+        if messages.is_empty() {
+            return;
+        }
+
+        let message = messages.join(" | ");
+        if self.job_end_notifications && !self.desktop_notifications {
+            let _ = desktop::notify("NodeStat", &message);
+        }
+        self.notify(message);
+    }
+
+    /// Show a message in the status line and ring the terminal bell.
+    async fn toggle_cluster(&mut self, index: usize) {
+        let Some(cluster) = self.scheduler.clusters().into_iter().nth(index) else {
+            return;
+        };
+        self.scheduler.set_cluster_enabled(&cluster.name, !cluster.enabled);
+        self.reload(None).await;
+    }
+
+    /// Show a banner until Esc, and ring the bell or send a desktop notification as configured.
+    fn notify(&mut self, message: String) {
+        if self.bell {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        if self.desktop_notifications {
+            let _ = desktop::notify("NodeStat", &message);
+        }
+        self.banner = Some(format!("🔔 {}", message));
+    }
+
+    async fn set_view(&mut self, view: View) {
+        self.view = view;
+        self.show_node_detail = false;
+        self.show_job_detail = false;
+        if view.needs_pending_jobs() || (view == View::Queue && self.job_waits_stale()) {
+            self.reload(None).await;
+        }
+        self.load_chart_samples();
+    }
+
+    /// Rows in the current view's table.
+    fn row_count(&self) -> usize {
+        match self.view {
+            View::Nodes => self.node_rows().len(),
+            View::Jobs => self.jobs.len(),
+            View::Queue => self.queue_rows().len(),
+            View::Users => self.user_usage().len(),
+            View::MyJobs => self.user_jobs.len(),
+            View::Timeline => self.timeline_rows().len(),
+            View::Charts => 0,
+        }
+    }
+
+    /// Selection of the current view's table.
+    fn view_state(&mut self) -> &mut TableState {
+        self.state_of(self.view)
+    }
+
+    fn state_of(&mut self, view: View) -> &mut TableState {
+        match view {
+            View::Nodes => &mut self.table_state,
+            View::Jobs => &mut self.jobs_state,
+            View::Queue => &mut self.queue_state,
+            View::Users => &mut self.users_state,
+            View::MyJobs => &mut self.my_jobs_state,
+            View::Timeline => &mut self.timeline_state,
+            View::Charts => &mut self.charts_state,
+        }
+    }
+
+    /// The rows of `view`'s table, by what they show.
+    fn row_keys(&self, view: View) -> Vec<RowKey> {
+        let job_key = |job: &Job| RowKey::Job(job.cluster.clone(), job.id.clone());
+        match view {
+            View::Nodes => self.node_rows().iter().map(|row| match row {
+                NodeRow::Rack(rack) => RowKey::Rack(rack.name.clone()),
+                NodeRow::Node(node) => RowKey::Node(node.cluster.clone(), node.id.clone()),
+            }).collect(),
+            View::Jobs => self.jobs.iter().map(job_key).collect(),
+            View::Queue => self.queue_rows().iter().map(|row| match row {
+                QueueRow::Job(job) | QueueRow::Task(job) => job_key(job),
+                QueueRow::Array(array) => RowKey::Array(array.cluster.clone(), array.id.clone()),
+            }).collect(),
+            View::Users => self.user_usage().into_iter().map(|usage| RowKey::User(usage.user)).collect(),
+            View::MyJobs => self.user_jobs.iter().map(job_key).collect(),
+            View::Timeline => self.timeline_rows().iter().map(|row| match row {
+                TimelineRow::Job { job, .. } => job_key(job),
+                TimelineRow::Free(node) => RowKey::Node(node.cluster.clone(), node.id.clone()),
+            }).collect(),
+            View::Charts => Vec::new(),
+        }
+    }
+
+    /// The selected row of every table, to put the selection back with [`App::restore_selection`]
+    /// once the rows have changed.
+    fn selection_anchors(&mut self) -> Vec<Anchor> {
+        let mut anchors = Vec::new();
+        for view in View::ALL {
+            let state = self.state_of(view);
+            let (Some(index), offset) = (state.selected(), state.offset()) else {
+                continue;
+            };
+            if let Some(key) = self.row_keys(view).into_iter().nth(index) {
+                anchors.push(Anchor { view, key, index, screen_row: index.saturating_sub(offset) });
+            }
+        }
+        anchors
+    }
+
+    /// Select the rows the anchors were on, at the same height on screen; a row that is gone
+    /// leaves the selection where it was.
+    fn restore_selection(&mut self, anchors: Vec<Anchor>) {
+        for anchor in anchors {
+            let keys = self.row_keys(anchor.view);
+            let index = match keys.iter().position(|key| *key == anchor.key) {
+                Some(index) => Some(index),
+                None => (!keys.is_empty()).then(|| anchor.index.min(keys.len() - 1)),
+            };
+            let state = self.state_of(anchor.view);
+            state.select(index);
+            *state.offset_mut() = index.map_or(0, |index| index.saturating_sub(anchor.screen_row));
+        }
+    }
+
+    fn user_usage(&self) -> Vec<UserUsage> {
+        let mut usage = users::aggregate(&self.jobs, &self.pending_jobs);
+        users::sort(&mut usage, self.user_sort, self.user_sort_descending);
+        usage
+    }
+
+    fn set_user_sort(&mut self, key: UserSort) {
+        self.user_sort_descending = if key == self.user_sort { !self.user_sort_descending } else { key.descending_by_default() };
+        self.user_sort = key;
+        let direction = if self.user_sort_descending { "descending" } else { "ascending" };
+        self.status_message = Some(format!("Users sorted by {}, {}", key.title().to_lowercase(), direction));
+    }
+
+    /// The node of the selected row; `None` on a rack header.
+    fn selected_node(&self) -> Option<&Node> {
+        let index = self.table_state.selected()?;
+        match self.node_rows().into_iter().nth(index)? {
+            NodeRow::Node(node) => Some(node),
+            NodeRow::Rack(_) => None,
+        }
+    }
+
+    /// Nodes matching the search, all of them without one, less those without room for the
+    /// fit request when only those are shown.
+    fn visible_nodes(&self) -> Vec<&Node> {
+        let mut nodes = match self.search {
+            Some(ref search) => search.filter(&self.nodes),
+            None => self.nodes.iter().collect(),
+        };
+        if let Some(fit) = self.fit.as_ref().filter(|_| self.fit_only) {
+            nodes.retain(|node| fit.slots(&node.id) > 0);
+        }
+        if let Some(ref node_filter) = self.node_filter {
+            nodes = node_filter.filter(nodes);
+        }
+        nodes
+    }
+
+    /// The rack or prefix group of a node while grouped.
+    fn group_of(&self, node: &Node) -> Option<String> {
+        match self.grouping? {
+            Grouping::Racks => topology::rack_of(node, self.rack_pattern.as_ref(), &self.switches),
+            Grouping::Prefix => topology::prefix_of(node, &self.prefix_pattern),
+        }
+    }
+
+    /// Name of the group of nodes [`App::group_of`] places nowhere.
+    fn leftover_group(&self) -> &'static str {
+        match self.grouping {
+            Some(Grouping::Prefix) => NO_PREFIX,
+            _ => NO_RACK,
+        }
+    }
+
+    /// Nodes view rows: every visible node, or when grouped the groups with the nodes of expanded ones.
+    fn node_rows(&self) -> Vec<NodeRow<'_>> {
+        if self.grouping.is_none() {
+            return self.visible_nodes().into_iter().map(NodeRow::Node).collect();
+        }
+        let mut rows = Vec::new();
+        for rack in topology::group(self.visible_nodes(), |node| self.group_of(node), self.leftover_group()) {
+            let nodes = if self.expanded_racks.contains(&rack.name) { rack.nodes.clone() } else { Vec::new() };
+            rows.push(NodeRow::Rack(rack));
+            rows.extend(nodes.into_iter().map(NodeRow::Node));
+        }
+        rows
+    }
+
+    /// Expand or collapse the group of the selected row, keeping the group selected.
+    fn toggle_rack(&mut self) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        let rows = self.node_rows();
+        let rack_name = match rows.get(index) {
+            Some(NodeRow::Rack(rack)) => rack.name.clone(),
+            Some(NodeRow::Node(node)) => self.group_of(node).unwrap_or_else(|| self.leftover_group().to_string()),
+            None => return,
+        };
+        let rack_row = rows.iter().position(|row| matches!(row, NodeRow::Rack(rack) if rack.name == rack_name));
+        drop(rows);
+
+        if !self.expanded_racks.remove(&rack_name) {
+            self.expanded_racks.insert(rack_name);
+        }
+        self.table_state.select(rack_row);
+    }
+
+    /// Queue view rows: pending jobs in queue order, arrays collapsed unless expanded.
+    fn queue_rows(&self) -> Vec<QueueRow<'_>> {
+        let mut rows = Vec::new();
+        for entry in arrays::group(&self.pending_jobs, &self.jobs) {
+            match entry {
+                QueueEntry::Job(job) => rows.push(QueueRow::Job(job)),
+                QueueEntry::Array(array) => {
+                    let tasks = if self.expanded_arrays.contains(&array.id) { array.tasks.clone() } else { Vec::new() };
+                    rows.push(QueueRow::Array(array));
+                    rows.extend(tasks.into_iter().map(QueueRow::Task));
+                },
+            }
+        }
+        rows
+    }
+
+    /// The running jobs of the shown nodes, in table order, each node's first to end first.
+    fn timeline_rows(&self) -> Vec<TimelineRow<'_>> {
+        let mut rows = Vec::new();
+        for node in self.visible_nodes() {
+            let mut jobs: Vec<&Job> = self
+                .jobs
+                .iter()
+                .filter(|job| job.state == JobState::Running && job.cluster == node.cluster && job.node_list.contains(&node.id))
+                .collect();
+            if jobs.is_empty() {
+                rows.push(TimelineRow::Free(node));
+                continue;
+            }
+            jobs.sort_by_key(|job| job.time_limit - job.elapsed);
+            rows.extend(jobs.into_iter().enumerate().map(|(i, job)| TimelineRow::Job { node, job, first: i == 0 }));
+        }
+        rows
+    }
+
+    /// The running job of the selected Jobs, My Jobs or Timeline view row.
+    fn selected_job(&self) -> Option<&Job> {
+        match self.view {
+            View::MyJobs => self.user_jobs.get(self.my_jobs_state.selected()?),
+            View::Timeline => match self.timeline_rows().into_iter().nth(self.timeline_state.selected()?)? {
+                TimelineRow::Job { job, .. } => Some(job),
+                TimelineRow::Free(_) => None,
+            },
+            _ => self.jobs.get(self.jobs_state.selected()?),
+        }
+    }
+
+    /// The pending job of the selected queue row; an array stands for its first pending task.
+    fn selected_pending_job(&self) -> Option<&Job> {
+        let index = self.queue_state.selected()?;
+        match self.queue_rows().into_iter().nth(index)? {
+            QueueRow::Job(job) | QueueRow::Task(job) => Some(job),
+            QueueRow::Array(array) => Some(array.first_pending()),
+        }
+    }
+
+    /// What a hold or release of the selected queue row acts on: the job, or the whole array;
+    /// with the job (or first pending task) it stands for.
+    fn selected_queue_target(&self) -> Option<(String, &Job)> {
+        let index = self.queue_state.selected()?;
+        match self.queue_rows().into_iter().nth(index)? {
+            QueueRow::Job(job) | QueueRow::Task(job) => Some((job.id.clone(), job)),
+            QueueRow::Array(array) => {
+                let job = array.first_pending();
+                Some((arrays::array_id(&job.id).unwrap_or_else(|| array.id.clone()), job))
+            },
+        }
+    }
+
+    /// The ID of the selected job, or of the selected queue row's job or array.
+    fn selected_job_id(&self) -> Option<String> {
+        match self.view {
+            View::Jobs | View::MyJobs | View::Timeline => self.selected_job().map(|job| job.id.clone()),
+            View::Queue => self.selected_queue_target().map(|(id, _)| id),
+            View::Nodes | View::Users | View::Charts => None,
+        }
+    }
+
+    /// The selected node's name or job's ID, for `y`.
+    fn yank_text(&self) -> Option<String> {
+        match self.view {
+            View::Nodes => self.selected_node().map(|node| node.id.clone()),
+            _ => self.selected_job_id(),
+        }
+    }
+
+    fn yank(&mut self) {
+        let Some(text) = self.yank_text() else {
+            return;
+        };
+        match clipboard::copy(&text) {
+            Ok(Copied::Command(_)) => self.status_message = Some(format!("Copied {}", text)),
+            Ok(Copied::Osc52) => self.status_message = Some(format!("Copied {} (through the terminal)", text)),
+            Err(e) => self.error_message = Some(format!("Failed to copy {}: {}", text, e)),
+        }
+    }
+
+    /// Hold (or release) the selected pending job of the user's.
+    async fn hold_selected(&mut self, hold: bool) {
+        let Some((id, job)) = self.selected_queue_target() else {
+            return;
+        };
+        if job.user != self.current_user {
+            self.error_message = Some(format!("Job {} belongs to {}; only your own jobs can be held or released", id, job.user));
+            return;
+        }
+
+        let cluster = job.cluster.clone();
+        self.reload(Some(Change::HoldJob { id, cluster, hold })).await;
+    }
+
+    /// Expand or collapse the array of the selected queue row, keeping the array selected.
+    fn toggle_array(&mut self) {
+        let Some(index) = self.queue_state.selected() else {
+            return;
+        };
+        let rows = self.queue_rows();
+        let array_id = match rows.get(index) {
+            Some(QueueRow::Array(array)) => array.id.clone(),
+            Some(QueueRow::Task(job)) => match arrays::split_id(&job.id) {
+                Some((base, _)) => base.to_string(),
+                None => return,
+            },
+            _ => return,
+        };
+        let array_row = rows.iter().position(|row| matches!(row, QueueRow::Array(array) if array.id == array_id));
+        drop(rows);
+
+        if !self.expanded_arrays.remove(&array_id) {
+            self.expanded_arrays.insert(array_id);
+        }
+        self.queue_state.select(array_row);
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(id) = self.selected_node().map(|n| n.id.clone()) {
+            if !self.marked_nodes.remove(&id) {
+                self.marked_nodes.insert(id);
+            }
+        }
+    }
+
+    /// Target the marked nodes if there are any, otherwise the selected node.
+    /// The nodes an admin action applies to: the marked ones, else the selected one.
+    fn target_nodes(&self) -> Vec<&Node> {
+        if self.marked_nodes.is_empty() {
+            self.selected_node().into_iter().collect()
+        } else {
+            self.nodes.iter().filter(|n| self.marked_nodes.contains(&n.id)).collect()
+        }
+    }
+
+    fn open_drain_prompt(&mut self) {
+        let node_ids: Vec<String> = self.target_nodes()
+            .into_iter()
+            .filter(|n| !n.is_drained())
+            .map(|n| n.id.clone())
+            .collect();
+        if node_ids.is_empty() {
+            self.status_message = Some("No nodes in service selected".to_string());
+            return;
+        }
+
+        self.prompt = Some(Prompt {
+            kind: PromptKind::DrainReason { node_ids },
+            input: String::new(),
+        });
+    }
+
+    fn confirm_resume(&mut self) {
+        let node_ids: Vec<String> = self.target_nodes()
+            .into_iter()
+            .filter(|n| n.is_drained())
+            .map(|n| n.id.clone())
+            .collect();
+        if node_ids.is_empty() {
+            self.status_message = Some("No drained/down nodes selected".to_string());
+            return;
+        }
+
+        self.confirm = Some(Confirm::ResumeNodes { node_ids });
+    }
+
+    fn open_reason_prompt(&mut self) {
+        let targets = self.target_nodes();
+
+        let node_ids: Vec<String> = targets
+            .iter()
+            .filter(|n| n.is_drained())
+            .map(|n| n.id.clone())
+            .collect();
+
+        if node_ids.is_empty() {
+            self.status_message = Some("No drained/down nodes selected".to_string());
+            return;
+        }
+
+        // Pre-fill with the current reason when editing a single node
+        let input = match targets.as_slice() {
+            [node] => node.reason.clone().unwrap_or_default(),
+            _ => String::new(),
+        };
+
+        self.prompt = Some(Prompt {
+            kind: PromptKind::NodeReason { node_ids },
+            input,
+        });
+    }
+
+    /// Fetch everything and show it, waiting for the scheduler.
+    async fn fetch_data(&mut self) {
+        let request = self.refresh_request();
+        let snapshot = refresh::fetch(&*self.scheduler, &request).await;
+        self.show_snapshot(snapshot).await;
+    }
+
+    /// Refresh in the background once the UI loop runs, so drawing and keys go on meanwhile;
+    /// before that, right away. Does nothing while a background refresh is under way.
+    async fn refresh(&mut self) {
+        match self.refresher {
+            None => return self.fetch_data().await,
+            Some(ref refresher) if refresher.started().is_some() => return,
+            Some(_) => {},
+        }
+        let request = self.refresh_request();
+        if let Some(ref mut refresher) = self.refresher {
+            refresher.start(request);
+        }
+    }
+
+    /// Fetch again for what's shown now, after making `change` if there is one: in
+    /// the background once the UI loop runs, in place of a refresh under way; before that,
+    /// right away.
+    async fn reload(&mut self, change: Option<Change>) {
+        let mut request = self.refresh_request();
+        request.change = change;
+        match self.refresher {
+            Some(ref mut refresher) => refresher.restart(request),
+            None => {
+                let finished = refresh::run(&*self.scheduler, request, &tokio::sync::Notify::new()).await;
+                self.show_finished(finished).await;
+            },
+        }
+    }
+
+    /// Show the background refreshes done since, and what became of their changes.
+    async fn receive_refresh(&mut self) {
+        while let Some(finished) = self.refresher.as_mut().and_then(Refresher::try_recv) {
+            self.show_finished(finished).await;
+        }
+    }
+
+    /// Show `finished`'s snapshot, unless a later refresh overtook it, then how its changes went.
+    async fn show_finished(&mut self, finished: Finished) {
+        if let Some(snapshot) = finished.snapshot.filter(|_| finished.generation == self.refresh_generation) {
+            self.show_snapshot(snapshot).await;
+        }
+        for (change, result) in finished.changes {
+            match result {
+                Ok(message) => {
+                    match change {
+                        Change::CancelJob { .. } => self.show_job_detail = false,
+                        Change::ResumeNodes { .. } | Change::UpdateReason { .. } | Change::DrainNodes { .. } => {
+                            self.marked_nodes.clear();
+                        },
+                        Change::HoldJob { .. } => {},
+                    }
+                    self.status_message = Some(message);
+                },
+                Err(message) => self.error_message = Some(message),
+            }
+        }
+    }
+
+    /// What to fetch for the partitions, views and panels shown.
+    fn refresh_request(&mut self) -> refresh::Request {
+        self.refresh_generation += 1;
+        let current = self.current_partition.clone();
+        // The history and the alerts count the queue whichever view is shown
+        let pending_jobs = self.view.needs_pending_jobs()
+            || self.history.is_some()
+            || self.alerts.needs_pending_jobs(&current, true);
+        let job_waits_since = match self.view == View::Queue || self.partition_picker.is_some() {
+            true => self.job_waits_due(),
+            false => None,
+        };
+        let mut watch_partitions: Vec<String> = Vec::new();
+        for partition in self.availability_watches.iter().filter_map(AvailabilityWatch::partition) {
+            if partition != current && !watch_partitions.iter().any(|p| p == partition) {
+                watch_partitions.push(partition.to_string());
+            }
+        }
+        refresh::Request {
+            generation: self.refresh_generation,
+            partitions: self.selected_partitions(),
+            user: self.current_user.clone(),
+            user_jobs: self.user_jobs.iter().map(|job| job.id.clone()).collect(),
+            pending_jobs,
+            job_waits_since,
+            fairshare: self.show_fairshare,
+            watchlist: self.watchlist.ids().to_vec(),
+            alert_partitions: self
+                .alerts
+                .other_partitions(&current)
+                .into_iter()
+                .map(|partition| {
+                    let pending = self.alerts.needs_pending_jobs(&partition, false);
+                    (partition, pending)
+                })
+                .collect(),
+            watch_partitions,
+            call_timeout: self.call_timeout,
+            change: None,
+        }
+    }
+
+    async fn show_snapshot(&mut self, snapshot: Snapshot) {
+        let anchors = self.selection_anchors();
+        self.apply_snapshot(snapshot).await;
+        self.restore_selection(anchors);
+    }
+
+    async fn apply_snapshot(&mut self, snapshot: Snapshot) {
+        self.error_message = None;
+        self.status_message = None;
+        let mut errors = Vec::new();
+        let mut nodes_error = None;
+
+        let nodes_fetched = match snapshot.nodes {
+            Ok(mut nodes) => {
+                nodes.retain(|node| self.has_features(node));
+                self.node_history.record(&nodes);
+                self.stats = ClusterStats::of(&nodes);
+                self.utilization.record(&self.current_partition, &self.stats);
+                let peak = self.peak_watts.entry(self.current_partition.clone()).or_default();
+                *peak = (*peak).max(self.stats.current_watts);
+                self.nodes = nodes;
+                true
+            },
+            Err(e) => {
+                self.error_message = Some(format!("Failed to get nodes: {:#}", e));
+                errors.push(format!("nodes: {:#}", e));
+                nodes_error = Some(format!("{:#}", e));
+                false
+            }
+        };
+        
+        // Jobs (keep the previous ones on error, but count it against the connection health)
+        match snapshot.jobs {
+            Ok(jobs) => {
+                self.jobs = jobs;
+                self.etas = placement::time_until_free(&self.jobs);
+            },
+            Err(e) => errors.push(format!("jobs: {:#}", e)),
+        }
+
+        // Most schedulers don't list a node's jobs, so they come from the job list
+        if nodes_fetched {
+            link_jobs(&mut self.nodes, &self.jobs);
+            self.changes.record(&self.nodes, self.now());
+            self.check_availability_watches(&snapshot.watch_nodes);
+        }
+        self.sort_nodes();
+        
+        // User jobs (don't fail on error)
+        if let Some(user_jobs) = snapshot.user_jobs {
+            let previous = std::mem::replace(&mut self.user_jobs, user_jobs);
+            self.announce_ended_jobs(previous, snapshot.ended_jobs).await;
+        }
+
+        let pending_fetched = snapshot.pending_jobs.is_some();
+        if let Some(pending_jobs) = snapshot.pending_jobs {
+            self.set_pending_jobs(pending_jobs);
+        }
+        if nodes_fetched {
+            self.record_history();
+            self.load_chart_samples();
+            self.check_alerts(pending_fetched, snapshot.alert_partitions).await;
+        }
+        if let Some((since, job_waits)) = snapshot.job_waits {
+            self.set_job_waits(since, job_waits);
+        }
+        if let Some(fairshare) = snapshot.fairshare {
+            self.set_fairshare(fairshare);
+        }
+
+        // Re-run the what-if simulation against the fresh data
+        if let Some(request) = self.what_if.as_ref().map(|p| p.request.clone()) {
+            let result = placement::simulate(&request, &self.nodes, &self.jobs, &self.partition_limits);
+            self.status_message = Some(result.summary());
+            self.what_if = Some(result);
+        }
+        if let Some(request) = self.fit.as_ref().map(|fit| fit.request.clone()) {
+            self.fit = Some(placement::find_fits(&request, &self.nodes, &self.selected_partitions()));
+        }
+
+        if let Some(jobs) = snapshot.watchlist {
+            self.update_watchlist(jobs);
+        }
+
+        if let Some(mut reservations) = snapshot.reservations {
+            let now = self.now();
+            reservations.retain(|r| r.end_time > now);
+            reservations.sort_by_key(|r| r.start_time);
+            self.reservations = reservations;
+        }
+        if let Some(licenses) = snapshot.licenses {
+            self.licenses = licenses;
+        }
+
+        self.polled_at = snapshot.polled_at;
+
+        // Clock skew
+        self.server_time = snapshot.server_time;
+        let submit_times: Vec<_> = self.jobs.iter()
+            .chain(self.user_jobs.iter())
+            .map(|job| job.submit_time)
+            .collect();
+        self.clock_skew = clock::detect_skew(self.server_time, &submit_times, self.now());
+        
+        if errors.is_empty() {
+            self.fetch_health.record_success();
+        } else {
+            self.fetch_health.record_failure(errors.join("; "));
+        }
+        self.record_frame(nodes_error);
+    }
+
+    /// The partitions shown: `--partition all`, a comma-separated list, or just one.
+    fn selected_partitions(&self) -> Vec<String> {
+        if self.current_partition == "all" && !self.partitions.is_empty() {
+            return self.partitions.clone();
+        }
+        self.current_partition
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn multi_partition(&self) -> bool {
+        self.selected_partitions().len() > 1
+    }
+
+    fn set_pending_jobs(&mut self, pending_jobs: Result<Vec<Job>>) {
+        match pending_jobs {
+            Ok(jobs) => {
+                let anchors = self.selection_anchors();
+                self.pending_jobs = jobs;
+                self.restore_selection(anchors);
+            },
+            Err(e) => {
+                self.error_message = Some(format!("Failed to get pending jobs: {:#}", e));
+            }
+        }
+    }
+
+    /// The wait times are only fetched for the queue view and the partition picker, and not
+    /// more often than `JOB_WAITS_EVERY`. Without the scheduler's accounting they come from
+    /// `[history]`, if it's kept.
+    fn job_waits_stale(&self) -> bool {
+        self.job_waits_fetched.is_none_or(|fetched| fetched.elapsed() >= JOB_WAITS_EVERY)
+    }
+
+    /// Where the wait times should start, if it's time to fetch them again.
+    fn job_waits_due(&mut self) -> Option<chrono::DateTime<chrono::Utc>> {
+        if !self.job_waits_stale() {
+            return None;
+        }
+        self.job_waits_fetched = Some(Instant::now());
+        Some(self.now() - waits::WINDOW)
+    }
+
+    fn set_job_waits(&mut self, since: chrono::DateTime<chrono::Utc>, job_waits: Result<Vec<JobWait>>) {
+        self.job_waits = match (job_waits, &self.history) {
+            (Ok(waits), _) => waits,
+            (Err(_), Some(history)) => history.job_waits(since).unwrap_or_else(|e| {
+                self.error_message = Some(format!("Failed to read history: {:#}", e));
+                Vec::new()
+            }),
+            (Err(_), None) => Vec::new(),
+        };
+    }
+
+    /// Fairshare is only fetched while its panel is open. A row's trend is kept until its
+    /// factor moves again, since Slurm only recalculates it every few minutes.
+    fn set_fairshare(&mut self, fairshare: Result<Vec<Fairshare>>) {
+        match fairshare {
+            Ok(fairshare) => {
+                for entry in &fairshare {
+                    let key = fairshare_key(entry);
+                    let previous = self.fairshare.iter().find(|f| fairshare_key(f) == key).and_then(|f| f.fairshare);
+                    let trend = previous.zip(entry.fairshare).and_then(|(before, now)| now.partial_cmp(&before));
+                    if let Some(trend) = trend.filter(|t| t.is_ne()) {
+                        self.fairshare_trends.insert(key, trend);
+                    }
+                }
+                self.fairshare = fairshare;
+                if self.fairshare.is_empty() {
+                    self.status_message = Some(format!("No fairshare associations for {}", self.current_user));
+                }
+            },
+            Err(e) => {
+                self.show_fairshare = false;
+                self.error_message = Some(format!("Failed to get fairshare: {:#}", e));
+            }
+        }
+    }
+
+    fn has_features(&self, node: &Node) -> bool {
+        self.feature_filter
+            .iter()
+            .all(|wanted| node.features.iter().any(|f| f.eq_ignore_ascii_case(wanted)))
+    }
+
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.frozen_time.unwrap_or_else(chrono::Utc::now)
+    }
+
+    /// The reservation the node is in now, or enters within the warning window.
+    fn node_reservation(&self, node: &Node) -> Option<&Reservation> {
+        let horizon = self.now() + chrono::Duration::hours(RESERVATION_WARNING_HOURS);
+        self.reservations.iter().find(|r| {
+            r.start_time <= horizon && r.cluster == node.cluster && r.nodes.contains(&node.id)
+        })
+    }
+
+    fn health_score(&self, node: &Node) -> u8 {
+        health::score(node, self.node_history.flaps(&node.id))
+    }
+
+    /// Sort by `key`: in its usual direction when it's new, the other way round when it's current.
+    fn set_sort(&mut self, key: SortKey) {
+        let descending = if key == self.sort_key { !self.sort_descending } else { key.descending_by_default() };
+        self.sort_by(key, descending);
+    }
+
+    fn sort_by(&mut self, key: SortKey, descending: bool) {
+        self.sort_descending = descending;
+        self.sort_key = key;
+        let anchors = self.selection_anchors();
+        self.sort_nodes();
+        self.restore_selection(anchors);
+        let direction = if self.sort_descending { "descending" } else { "ascending" };
+        self.status_message = Some(format!("Sorted by {}, {}", self.sort_key.label(), direction));
+    }
+
+    /// Order the nodes by the chosen key, again after every refresh; ties go by name.
+    fn sort_nodes(&mut self) {
+        let history = &self.node_history;
+        let etas = &self.etas;
+        let eta = |node: &Node| etas.get(&(node.cluster.clone(), node.id.clone())).copied();
+        let (key, descending) = (self.sort_key, self.sort_descending);
+        self.nodes.sort_by(|a, b| {
+            let order = match key {
+                // Available nodes first, the biggest (cores, then memory) first among them
+                SortKey::Availability => match (a.is_available(), b.is_available()) {
+                    (true, true) => (b.available_cores(), b.available_mem()).cmp(&(a.available_cores(), a.available_mem())),
+                    (false, false) => state_rank(a).cmp(&state_rank(b)),
+                    (a_available, b_available) => b_available.cmp(&a_available),
+                },
+                SortKey::Health => health::score(a, history.flaps(&a.id)).cmp(&health::score(b, history.flaps(&b.id))),
+                SortKey::Name => std::cmp::Ordering::Equal,
+                SortKey::AvailCores => a.available_cores().cmp(&b.available_cores()),
+                SortKey::AvailMem => a.available_mem().cmp(&b.available_mem()),
+                SortKey::State => state_rank(a).cmp(&state_rank(b)).then_with(|| a.state.to_string().cmp(&b.state.to_string())),
+                SortKey::Jobs => a.jobs.len().cmp(&b.jobs.len()),
+                SortKey::Load => a.cpu_load.unwrap_or(0.0).total_cmp(&b.cpu_load.unwrap_or(0.0)),
+                SortKey::Eta => (eta(a).is_none(), eta(a)).cmp(&(eta(b).is_none(), eta(b))),
+            };
+            let order = order.then_with(|| topology::natural_key(&a.id).cmp(&topology::natural_key(&b.id)));
+            if descending { order.reverse() } else { order }
+        });
+    }
+
+    fn next_row(&mut self) {
+        let len = self.row_count();
+        step_selection(self.view_state(), len, true);
+    }
+
+    fn previous_row(&mut self) {
+        let len = self.row_count();
+        step_selection(self.view_state(), len, false);
+    }
+
+    /// Select row `index` of the current view, or the last row past the end.
+    fn select_row(&mut self, index: usize) {
+        let len = self.row_count();
+        if len > 0 {
+            self.view_state().select(Some(index.min(len - 1)));
+        }
+    }
+
+    fn selected_row(&mut self) -> usize {
+        self.view_state().selected().unwrap_or(0)
+    }
+
+    fn user_has_jobs_on_node(&self, node: &Node) -> bool {
+        self.user_jobs.iter().any(|job| {
+            job.state == JobState::Running
+                && job.cluster == node.cluster
+                && job.node_list.contains(&node.id)
+        })
+    }
+
+    fn ui(&mut self, f: &mut Frame) {
+        let size = f.size();
+        if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+            let warning = Paragraph::new(vec![
+                Line::from(Span::styled("Terminal too small", Style::default().fg(self.theme.warning).add_modifier(Modifier::BOLD))),
+                Line::from(format!("{}x{}, need at least {}x{}", size.width, size.height, MIN_WIDTH, MIN_HEIGHT)),
+                Line::from(Span::styled("q: quit", Style::default().fg(self.theme.muted))),
+            ])
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+            f.render_widget(warning, centered_rect(100, 3, size));
+            return;
+        }
+
+        // Zoomed, the table takes everything but the header and help lines
+        let unzoomed = |height: u16| if self.zoomed { 0 } else { height };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(unzoomed(1)), // Title
+                Constraint::Length(unzoomed(1)), // Spacing
+                Constraint::Length(1), // Header
+                Constraint::Length(unzoomed(self.stats_height())), // Stats
+                Constraint::Length(unzoomed(1)), // Tabs
+                Constraint::Min(10),   // Table
+                Constraint::Length(unzoomed(self.watchlist_height())), // Watchlist
+                Constraint::Length(unzoomed(self.reservations_height())), // Reservations
+                Constraint::Length(unzoomed(self.licenses_height())), // Licenses
+                Constraint::Length(unzoomed(self.fairshare_height())), // Fairshare
+                Constraint::Length(unzoomed(self.changes_height())), // Recent changes
+                Constraint::Length(unzoomed(self.alerts_height())), // Alerts
+                Constraint::Length(unzoomed(1)), // Jobs
+                Constraint::Length(1), // Help
+            ])
+            .split(f.size());
+        // Borders, header and the gap below it
+        self.page_rows = usize::from(chunks[5].height.saturating_sub(4)).max(1);
+
+        // Title
+        let title = Paragraph::new("🖥️  NodeStat - Cluster Monitor")
+            .style(Style::default().fg(self.theme.title).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center);
+        f.render_widget(title, chunks[0]);
+
+        // Connection health and the refresh under way, then the error, status or header
+        let mut spans = self.health_indicator();
+        if let Some(started) = self.refresher.as_ref().and_then(Refresher::started) {
+            let frame = SPINNER[(started.elapsed().as_millis() / 100) as usize % SPINNER.len()];
+            spans.push(Span::styled(format!("{} Refreshing… (Esc: cancel)  ", frame), Style::default().fg(self.theme.muted)));
+        }
+        if let Some(ref error) = self.error_message {
+            spans.push(Span::styled(format!("Error: {}", error), Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD)));
+            f.render_widget(Paragraph::new(Line::from(spans)), chunks[2]);
+        } else if let Some(ref banner) = self.banner {
+            spans.push(Span::styled(banner.as_str(), Style::default().fg(self.theme.warning).add_modifier(Modifier::BOLD | Modifier::REVERSED)));
+            spans.push(Span::styled("  (Esc: dismiss)", Style::default().fg(self.theme.muted)));
+            f.render_widget(Paragraph::new(Line::from(spans)), chunks[2]);
+        } else if let Some(ref status) = self.status_message {
+            spans.push(Span::styled(status.as_str(), Style::default().fg(self.theme.good)));
+            f.render_widget(Paragraph::new(Line::from(spans)), chunks[2]);
+        } else {
+            // Header
+            let last_update = match self.fetch_health.since_success() {
+                Some(elapsed) => format!("{}s ago", elapsed.as_secs()),
+                None => "never".to_string(),
+            };
+            spans.push(Span::styled(format!("Partition: {}", self.current_partition), Style::default().fg(self.theme.label)));
+            if let Some(partition) = self.partition_limits.iter().find(|p| p.name == self.current_partition) {
+                let style = if partition.is_up() { Style::default().fg(self.theme.muted) } else { Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD) };
+                spans.push(Span::styled(format!(" ({})", format_partition_limits(partition)), style));
+            }
+            let header = match self.playback {
+                Some(ref playback) if playback.replay.len() == 1 => format!("    Snapshot taken: {}",
+                                                                          self.cluster_tz.format(&self.now(), "%Y-%m-%d %H:%M:%S %Z")),
+                Some(ref playback) => format!("    Replay: {}/{} {}    Recorded: {}",
+                                              playback.replay.position() + 1,
+                                              playback.replay.len(),
+                                              if playback.playing { "▶" } else { "paused" },
+                                              self.cluster_tz.format(&self.now(), "%Y-%m-%d %H:%M:%S %Z")),
+                None if self.polled_at.is_some() => format!("    Cluster time: {}", self.cluster_tz.format(&self.now(), "%H:%M:%S %Z")),
+                None => format!("    Last update: {} (every {}s)    Cluster time: {}",
+                                last_update,
+                                self.refresh_interval.as_secs(),
+                                self.cluster_tz.format(&self.now(), "%H:%M:%S %Z")),
+            };
+            // Data from the daemon or the rate limit's cache is as old as when it was fetched,
+            // however recently it was asked for
+            if let Some(polled_at) = self.polled_at.filter(|_| self.playback.is_none()) {
+                let age = (self.now() - polled_at).to_std().unwrap_or_default();
+                let style = match age > self.refresh_interval * 2 {
+                    true => Style::default().fg(self.theme.warning).add_modifier(Modifier::BOLD),
+                    false => Style::default().fg(self.theme.special).add_modifier(Modifier::BOLD),
+                };
+                spans.push(Span::styled(format!("    Cached: {}s old", age.as_secs()), style));
+            }
+            spans.push(Span::styled(header, Style::default().fg(self.theme.label)));
+            if !self.feature_filter.is_empty() {
+                spans.push(Span::styled(
+                    format!("    Features: {}", self.feature_filter.join(",")),
+                    Style::default().fg(self.theme.special).add_modifier(Modifier::BOLD),
+                ));
+            }
+            if let Some(ref node_filter) = self.node_filter {
+                spans.push(Span::styled(
+                    format!("    Filter: {}", node_filter.source()),
+                    Style::default().fg(self.theme.special).add_modifier(Modifier::BOLD),
+                ));
+            }
+            if !self.availability_watches.is_empty() {
+                let watches: Vec<&str> = self.availability_watches.iter().map(AvailabilityWatch::source).collect();
+                spans.push(Span::styled(
+                    format!("    Notify when: {}", watches.join(", ")),
+                    Style::default().fg(self.theme.special).add_modifier(Modifier::BOLD),
+                ));
+            }
+            if let Some(ref fit) = self.fit {
+                let (text, color) = match fit.by_partition() {
+                    Some(partitions) => (format!("    Fits {}: {}", fit.request, partitions), self.theme.good),
+                    None => (format!("    Fits {}: nowhere now", fit.request), self.theme.warning),
+                };
+                spans.push(Span::styled(text, Style::default().fg(color).add_modifier(Modifier::BOLD)));
+            }
+            if self.fetch_health.level() != HealthLevel::Ok {
+                spans.push(Span::styled(
+                    format!("    Retrying in {}s: {}",
+                            self.fetch_health.next_attempt_in(self.refresh_interval).as_secs(),
+                            self.fetch_health.last_error().unwrap_or_default()),
+                    Style::default().fg(self.theme.warning),
+                ));
+            }
+            if let Some(skew) = self.clock_skew {
+                spans.push(Span::styled(
+                    format!("    ⚠ Clock skew {} (times may be off)", clock::format_skew(&skew)),
+                    Style::default().fg(self.theme.warning).add_modifier(Modifier::BOLD),
+                ));
+            }
+            f.render_widget(Paragraph::new(Line::from(spans)), chunks[2]);
+        }
+
+        // Stats
+        if !self.zoomed {
+            self.render_stats(f, chunks[3]);
+        }
+
+        // Tabs
+        let tabs = Tabs::new(View::ALL.iter().map(|view| view.title()).collect())
+            .select(self.view.index())
+            .style(Style::default().fg(self.theme.muted))
+            .highlight_style(Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD));
+        f.render_widget(tabs, chunks[4]);
+
+        // Table
+        match self.view {
+            View::Nodes if self.show_node_detail => {
+                let split = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .split(chunks[5]);
+                self.render_table(f, split[0]);
+                self.render_node_detail(f, split[1]);
+            },
+            View::Nodes => self.render_table(f, chunks[5]),
+            View::Jobs => self.render_jobs(f, chunks[5]),
+            View::Queue => self.render_queue(f, chunks[5]),
+            View::Users => self.render_users(f, chunks[5]),
+            View::MyJobs => self.render_my_jobs(f, chunks[5]),
+            View::Timeline => self.render_timeline(f, chunks[5]),
+            View::Charts => self.render_charts(f, chunks[5]),
+        }
+
+        // Watchlist
+        if !self.watchlist.is_empty() {
+            self.render_watchlist(f, chunks[6]);
+        }
+
+        // Reservations
+        if self.show_reservations && !self.reservations.is_empty() {
+            self.render_reservations(f, chunks[7]);
+        }
+
+        // Licenses
+        if self.show_licenses && !self.licenses.is_empty() {
+            self.render_licenses(f, chunks[8]);
+        }
+
+        // Fairshare
+        if self.show_fairshare && !self.fairshare.is_empty() {
+            self.render_fairshare(f, chunks[9]);
+        }
+
+        // Recent changes
+        if self.show_changes {
+            self.render_changes(f, chunks[10]);
+        }
+
+        // Alerts
+        if self.show_alerts {
+            self.render_alerts(f, chunks[11]);
+        }
+
+        // Jobs summary
+        let mut jobs_summary = format!("Jobs: {} running ({} yours)", 
+                                  self.jobs.len(), 
+                                  self.user_jobs.len());
+        if self.view.needs_pending_jobs() {
+            let pending: u32 = self.pending_jobs.iter().map(|job| arrays::task_count(&job.id)).sum();
+            jobs_summary.push_str(&format!(", {} pending", pending));
+        }
+        let jobs_widget = Paragraph::new(jobs_summary)
+            .style(Style::default().fg(self.theme.accent));
+        f.render_widget(jobs_widget, chunks[12]);
+
+        // Help
+        let clusters = self.scheduler.clusters();
+        let mut help_text: String = if clusters.is_empty() {
+            self.partitions
+                .iter()
+                .take(9)
+                .enumerate()
+                .map(|(i, p)| format!("{}: {} | ", i + 1, p))
+                .collect()
+        } else {
+            clusters
+                .iter()
+                .take(9)
+                .enumerate()
+                .map(|(i, c)| format!("F{}: {}{} | ", i + 1, if c.enabled { "✓" } else { "✗" }, c.name))
+                .collect()
+        };
+        if self.zoomed {
+            help_text.insert_str(0, "z: unzoom | ");
+        }
+        help_text.push_str("tab/shift-tab: views | enter: details | y: copy | /: search | :: command | ctrl-p: palette | p: partitions | r: refresh | +/-: refresh interval | s/S: sort | f: features | v: mark | w/W: watch job/selected job | N: notify when | R: reservations | L: licenses | F: fairshare | a: changes | A: alerts | i: what-if | c/C: fit | g/x: group by rack/prefix | z: zoom | q: quit | mouse: click/scroll");
+        if self.admin {
+            help_text.push_str(" | e: edit reason | D/U: drain/resume");
+        }
+        match self.view {
+            View::Nodes => help_text.push_str(" | o: ssh"),
+            View::Queue => help_text.push_str(" | H/U: hold/release job"),
+            View::MyJobs => help_text.push_str(" | d: cancel job"),
+            _ => {},
+        }
+        let help = match (&self.command_line, &self.search_input, &self.search) {
+            (Some(line), _, _) => {
+                let hint = if line.candidates.is_empty() {
+                    "  (Tab: complete, Enter: run, Esc: cancel)".to_string()
+                } else {
+                    format!("  {}", line.candidates.join("  "))
+                };
+                Paragraph::new(Line::from(vec![
+                    Span::styled(format!(":{}█", line.input), self.theme.heading()),
+                    Span::styled(hint, Style::default().fg(self.theme.muted)),
+                ]))
+            },
+            (None, Some(input), _) => Paragraph::new(Line::from(vec![
+                Span::styled(format!("/{}█", input), self.theme.heading()),
+                Span::styled(format!("  {} (Enter: keep, Esc: clear)", self.search_matches()), Style::default().fg(self.theme.muted)),
+            ])),
+            (None, None, Some(search)) => Paragraph::new(Line::from(vec![
+                Span::styled(format!("/{}: {} | ", search.pattern(), self.search_matches()), Style::default().fg(self.theme.special)),
+                Span::styled(help_text, Style::default().fg(self.theme.muted)),
+            ])),
+            (None, None, None) => Paragraph::new(help_text).style(Style::default().fg(self.theme.muted)),
+        };
+        f.render_widget(help, chunks[13]);
+
+        if self.show_job_detail {
+            match self.view {
+                View::Jobs | View::MyJobs | View::Timeline => self.render_job_detail(f),
+                _ => self.render_pending_job_detail(f),
+            }
+        }
+
+        if self.partition_picker.is_some() {
+            self.render_partition_picker(f);
+        }
+
+        if self.palette.is_some() {
+            self.render_palette(f);
+        }
+
+        if self.prompt.is_some() {
+            self.render_prompt(f);
+        }
+
+        if self.confirm.is_some() {
+            self.render_confirm(f);
+        }
+    }
+
+    fn render_partition_picker(&mut self, f: &mut Frame) {
+        let recent = self.recent_partitions.names();
+        let rows: Vec<Row> = self.picker_partitions().into_iter().map(|name| {
+            let limits = match self.partition_limits.iter().find(|p| p.name == name) {
+                Some(partition) => format_partition_limits(partition),
+                None => String::new(),
+            };
+            let style = if name == self.current_partition {
+                Style::default().fg(self.theme.label).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let mut cells = vec![
+                Cell::from(name.to_string()),
+                Cell::from(if recent.iter().any(|r| r == name) { "recent" } else { "" }).style(Style::default().fg(self.theme.muted)),
+                Cell::from(limits),
+            ];
+            if let Some(stats) = WaitStats::of(&self.job_waits, name) {
+                cells.push(Cell::from(format!("wait {} (p90 {})", placement::format_wait(&stats.median), placement::format_wait(&stats.p90))).style(Style::default().fg(self.theme.muted)));
+            }
+            Row::new(cells).style(style)
+        }).collect();
+
+        // Wider with the wait times of the last week, where the scheduler or [history] has them
+        let (percent_x, widths) = match self.job_waits.is_empty() {
+            true => (60, vec![Constraint::Percentage(20), Constraint::Percentage(10), Constraint::Percentage(65)]),
+            false => (80, vec![Constraint::Percentage(15), Constraint::Percentage(8), Constraint::Percentage(50), Constraint::Percentage(27)]),
+        };
+        let area = centered_rect(percent_x, rows.len() as u16 + 2, f.size());
+        let table = Table::new(rows, widths)
+            .block(Block::default().borders(Borders::ALL).title("Partitions (Enter: switch, Esc: close)"))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+        f.render_widget(Clear, area);
+        if let Some(picker) = self.partition_picker.as_mut() {
+            f.render_stateful_widget(table, area, picker);
+        }
+    }
+
+    fn render_palette(&mut self, f: &mut Frame) {
+        let entries = self.palette_entries();
+        let Some(palette) = self.palette.as_mut() else {
+            return;
+        };
+
+        let matches = palette.matches(&entries);
+        let rows: Vec<Row> = matches
+            .iter()
+            .map(|entry| Row::new(vec![
+                Cell::from(entry.label.clone()),
+                Cell::from(entry.hint.clone()).style(Style::default().fg(self.theme.muted)),
+            ]))
+            .collect();
+
+        let area = centered_rect(60, (rows.len() as u16).clamp(1, 16) + 3, f.size());
+        let block = Block::default().borders(Borders::ALL).title("Commands (Enter: run, Esc: close)");
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+
+        let input = Paragraph::new(format!("> {}█", palette.input)).style(self.theme.heading());
+        f.render_widget(input, Rect { height: 1, ..inner });
+        let list = Rect { y: inner.y + 1, height: inner.height.saturating_sub(1), ..inner };
+        let table = Table::new(rows, [Constraint::Percentage(70), Constraint::Percentage(30)])
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(table, list, &mut palette.state);
+    }
+
+    /// `5 of 812 nodes` for the search footer.
+    fn search_matches(&self) -> String {
+        format!("{} of {} nodes", self.visible_nodes().len(), self.nodes.len())
+    }
+
+    /// Green/yellow/red dot for the health of the scheduler connection; failures are spelled out.
+    fn health_indicator(&self) -> Vec<Span<'static>> {
+        let (color, label) = match self.fetch_health.level() {
+            HealthLevel::Ok => (self.theme.good, None),
+            HealthLevel::Degraded => (self.theme.warning, Some(format!("stale ({} failed) ", self.fetch_health.failures()))),
+            HealthLevel::Failing => (self.theme.error, Some(format!("failing ({} failed) ", self.fetch_health.failures()))),
+        };
+        let mut spans = vec![Span::styled("● ", Style::default().fg(color))];
+        if let Some(label) = label {
+            spans.push(Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)));
+        }
+        spans
+    }
+
+    fn render_prompt(&self, f: &mut Frame) {
+        let Some(ref prompt) = self.prompt else {
+            return;
+        };
+
+        let area = centered_rect(60, 3, f.size());
+        let input = Paragraph::new(format!("{}█", prompt.input))
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{} (Enter: apply, Esc: cancel)", prompt.title())))
+            .style(Style::default().fg(self.theme.header));
+        f.render_widget(Clear, area);
+        f.render_widget(input, area);
+    }
+
+    fn render_confirm(&self, f: &mut Frame) {
+        let Some(ref confirm) = self.confirm else {
+            return;
+        };
+
+        let area = centered_rect(40, 4, f.size());
+        let dialog = Paragraph::new(vec![
+            Line::from(Span::styled(confirm.question(), Style::default().fg(self.theme.header).add_modifier(Modifier::BOLD))),
+            Line::from(Span::styled("y: yes, n/Esc: no", Style::default().fg(self.theme.muted))),
+        ])
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.theme.error))
+                .title("Confirm"));
+        f.render_widget(Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn watchlist_height(&self) -> u16 {
+        if self.watchlist.is_empty() {
+            0
+        } else {
+            // Borders + header + one row per job, capped so the node table keeps its space
+            (self.watchlist.ids().len() as u16 + 3).min(10)
+        }
+    }
+
+    fn render_watchlist(&self, f: &mut Frame, area: Rect) {
+        let header_cells = ["Job", "Name", "User", "State", "Elapsed / Limit", "GPUs", "Node"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1);
+
+        let rows = self.watchlist.ids().iter().map(|id| match self.watchlist.job(id) {
+            Some(job) => {
+                let state_style = self.theme.job_state(&job.state);
+                Row::new(vec![
+                    Cell::from(job.id.clone()),
+                    Cell::from(job.name.clone()),
+                    Cell::from(job.user.clone()),
+                    Cell::from(self.theme.job_label(&job.state)).style(state_style),
+                    Cell::from(format!("{} / {}", format_duration(&job.elapsed), format_duration(&job.time_limit))),
+                    Cell::from(format_gpu_request(job)),
+                    Cell::from(job.node_list.join(",")),
+                ])
+            },
+            None => Row::new(vec![
+                Cell::from(id.clone()),
+                Cell::from("not found").style(Style::default().fg(self.theme.muted)),
+            ]),
+        });
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(14),
+            Constraint::Percentage(18),
+            Constraint::Percentage(11),
+            Constraint::Percentage(8),
+            Constraint::Percentage(18),
+            Constraint::Percentage(8),
+            Constraint::Percentage(23),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title("Watchlist"));
+
+        f.render_widget(table, area);
+    }
+
+    /// A percentage history at the right end of a gauge row, then its trend and how far back it goes.
+    fn render_sparkline(&self, f: &mut Frame, row: Rect, values: &[u64], span: Duration, color: Color) {
+        let trend = match (values.first(), values.last()) {
+            (Some(first), Some(last)) if *last > first + 5 => "↗",
+            (Some(first), Some(last)) if last + 5 < *first => "↘",
+            _ => "→",
+        };
+        let minutes = span.as_secs() / 60;
+        let span = match minutes {
+            0 => "<1m".to_string(),
+            1..=59 => format!("{}m", minutes),
+            _ => format!("{}h{:02}m", minutes / 60, minutes % 60),
+        };
+
+        let x = row.x + row.width - SPARKLINE_WIDTH - SPARKLINE_LABEL_WIDTH;
+        let sparkline = Sparkline::default().data(values).max(100).style(Style::default().fg(color));
+        f.render_widget(sparkline, Rect { x, width: SPARKLINE_WIDTH, ..row });
+        let label = Paragraph::new(format!(" {} {}", trend, span)).style(Style::default().fg(self.theme.muted));
+        f.render_widget(label, Rect { x: x + SPARKLINE_WIDTH, width: SPARKLINE_LABEL_WIDTH, ..row });
+    }
+
+    fn stats_height(&self) -> u16 {
+        // The GPU and power gauges only show when the partition has GPUs or reports power
+        let mut height = 5;
+        if self.stats.total_gpus() > 0 {
+            height += 1;
+        }
+        if self.stats.power_nodes > 0 {
+            height += 1;
+        }
+        if self.multi_partition() {
+            height += 1;
+        }
+        height
+    }
+
+    fn reservations_height(&self) -> u16 {
+        if self.show_reservations && !self.reservations.is_empty() {
+            (self.reservations.len() as u16 + 3).min(10)
+        } else {
+            0
+        }
+    }
+
+    fn render_reservations(&self, f: &mut Frame, area: Rect) {
+        let header_cells = ["Reservation", "When", "Start", "End", "Nodes", "Flags", "Users"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1);
+
+        let now = self.now();
+        let rows = self.reservations.iter().map(|r| {
+            let when = if r.is_active(now) {
+                Cell::from(format!("active, {} left", placement::format_wait(&(r.end_time - now))))
+                    .style(Style::default().fg(self.theme.error))
+            } else {
+                Cell::from(format!("in {}", placement::format_wait(&(r.start_time - now))))
+                    .style(Style::default().fg(self.theme.warning))
+            };
+            let name = match r.cluster {
+                Some(ref cluster) => format!("{} ({})", r.name, cluster),
+                None => r.name.clone(),
+            };
+            Row::new(vec![
+                Cell::from(name).style(if r.is_maintenance() { Style::default().fg(self.theme.special) } else { Style::default() }),
+                when,
+                Cell::from(self.cluster_tz.format(&r.start_time, "%m-%d %H:%M")),
+                Cell::from(self.cluster_tz.format(&r.end_time, "%m-%d %H:%M")),
+                Cell::from(format!("{}: {}", r.nodes.len(), r.nodes.join(","))),
+                Cell::from(r.flags.join(",")),
+                Cell::from(r.users.join(",")),
+            ])
+        });
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(10),
+            Constraint::Percentage(10),
+            Constraint::Percentage(25),
+            Constraint::Percentage(12),
+            Constraint::Percentage(13),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title("Reservations (R: hide)"));
+
+        f.render_widget(table, area);
+    }
+
+    fn licenses_height(&self) -> u16 {
+        if self.show_licenses && !self.licenses.is_empty() {
+            (self.licenses.len() as u16 + 3).min(10)
+        } else {
+            0
+        }
+    }
+
+    fn render_licenses(&self, f: &mut Frame, area: Rect) {
+        let header_cells = ["License", "In use", "Free", "Reserved"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1);
+
+        let rows = self.licenses.iter().map(|license| {
+            let name = match license.cluster {
+                Some(ref cluster) => format!("{} ({})", license.name, cluster),
+                None => license.name.clone(),
+            };
+            // Jobs needing an exhausted license pend with reason "Licenses"
+            let free_style = match license.free() {
+                0 => Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD),
+                free if free * 10 < license.total => Style::default().fg(self.theme.warning),
+                _ => Style::default().fg(self.theme.good),
+            };
+            Row::new(vec![
+                Cell::from(name),
+                Cell::from(self.create_progress_bar(license.used.into(), license.total.into())),
+                Cell::from(license.free().to_string()).style(free_style),
+                Cell::from(license.reserved.to_string()),
+            ])
+        });
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+            Constraint::Percentage(15),
+            Constraint::Percentage(20),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title("Licenses (L: hide)"));
+
+        f.render_widget(table, area);
+    }
+
+    fn changes_height(&self) -> u16 {
+        if self.show_changes {
+            (self.changes.feed().len().max(1) as u16 + 2).min(8)
+        } else {
+            0
+        }
+    }
+
+    fn render_changes(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title("Recent changes (a: hide)");
+        if self.changes.feed().is_empty() {
+            let empty = Paragraph::new("No changes since startup").style(Style::default().fg(self.theme.muted)).block(block);
+            f.render_widget(empty, area);
+            return;
+        }
+
+        let rows = self.changes.feed().iter().map(|change| {
+            let node = match change.cluster {
+                Some(ref cluster) => format!("{} ({})", change.node, cluster),
+                None => change.node.clone(),
+            };
+            Row::new(vec![
+                Cell::from(self.cluster_tz.format(&change.at, "%H:%M:%S")).style(Style::default().fg(self.theme.muted)),
+                Cell::from(node),
+                Cell::from(change.description.clone()),
+            ])
+        });
+        let table = Table::new(rows, [Constraint::Percentage(10), Constraint::Percentage(20), Constraint::Percentage(70)])
+            .block(block);
+        f.render_widget(table, area);
+    }
+
+    fn alerts_height(&self) -> u16 {
+        if self.show_alerts {
+            (self.alerts.iter().count().max(1) as u16 + 3).min(10)
+        } else {
+            0
+        }
+    }
+
+    /// Each rule with its value at the last refresh and whether it holds, fires or is cooling down.
+    fn render_alerts(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title("Alerts (A: hide)");
+        if self.alerts.is_empty() {
+            let empty = Paragraph::new("No [[alerts]] rules in config.toml").style(Style::default().fg(self.theme.muted)).block(block);
+            f.render_widget(empty, area);
+            return;
+        }
+
+        let header = Row::new(["Alert", "Rule", "Now", "State"].iter().map(|h| Cell::from(*h).style(self.theme.heading())));
+        let now = self.now();
+        let rows = self.alerts.iter().map(|alert| {
+            let value = alert.value().map_or("-".to_string(), alerts::format_value);
+            let state = match (alert.since(), alert.last_fired()) {
+                (Some(since), _) if alert.is_firing(now) => Cell::from(format!("FIRING for {}", placement::format_wait(&(now - since))))
+                    .style(Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD)),
+                (Some(since), _) => Cell::from(format!("holding for {}", placement::format_wait(&(now - since))))
+                    .style(Style::default().fg(self.theme.warning)),
+                (None, Some(fired)) => Cell::from(format!("ok, fired {} ago", placement::format_wait(&(now - fired))))
+                    .style(Style::default().fg(self.theme.muted)),
+                (None, None) => Cell::from("ok").style(Style::default().fg(self.theme.good)),
+            };
+            Row::new(vec![Cell::from(alert.name().to_string()), Cell::from(alert.rule().to_string()), Cell::from(value), state])
+        });
+        let table = Table::new(
+            rows,
+            [Constraint::Percentage(25), Constraint::Percentage(40), Constraint::Percentage(10), Constraint::Percentage(25)],
+        )
+        .header(header)
+        .block(block);
+        f.render_widget(table, area);
+    }
+
+    fn fairshare_height(&self) -> u16 {
+        if self.show_fairshare && !self.fairshare.is_empty() {
+            (self.fairshare.len() as u16 + 3).min(10)
+        } else {
+            0
+        }
+    }
+
+    fn render_fairshare(&self, f: &mut Frame, area: Rect) {
+        let header_cells = ["Account", "User", "Shares", "Usage", "Effective usage", "Fairshare"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1);
+
+        let rows = self.fairshare.iter().map(|entry| {
+            let account = match entry.cluster {
+                Some(ref cluster) => format!("{} ({})", entry.account, cluster),
+                None => entry.account.clone(),
+            };
+            let (user, user_style) = match entry.user {
+                Some(ref user) => (user.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                None => ("(account)".to_string(), Style::default().fg(self.theme.muted)),
+            };
+            // Using more than the shares (effective usage above them) drags the factor below 0.5
+            let usage_style = if entry.effective_usage > entry.norm_shares {
+                Style::default().fg(self.theme.warning)
+            } else {
+                Style::default()
+            };
+            let fairshare = match entry.fairshare {
+                Some(factor) => {
+                    let trend = match self.fairshare_trends.get(&fairshare_key(entry)) {
+                        Some(std::cmp::Ordering::Greater) => " ↑",
+                        Some(std::cmp::Ordering::Less) => " ↓",
+                        _ => "",
+                    };
+                    let style = match factor {
+                        f if f < 0.25 => Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD),
+                        f if f < 0.5 => Style::default().fg(self.theme.warning),
+                        _ => Style::default().fg(self.theme.good),
+                    };
+                    Cell::from(format!("{:.3}{}", factor, trend)).style(style)
+                },
+                None => Cell::from("-").style(Style::default().fg(self.theme.muted)),
+            };
+            Row::new(vec![
+                Cell::from(account),
+                Cell::from(user).style(user_style),
+                Cell::from(format!("{:.1}%", entry.norm_shares * 100.0)),
+                Cell::from(format!("{} CPU-h", entry.raw_usage / 3600)),
+                Cell::from(format!("{:.1}%", entry.effective_usage * 100.0)).style(usage_style),
+                fairshare,
+            ])
+        });
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(20),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(20),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title("Fairshare (F: hide)"));
+
+        f.render_widget(table, area);
+    }
+
+    fn render_jobs(&mut self, f: &mut Frame, area: Rect) {
+        let header_cells = ["Job", "User", "Name", "Nodes", "CPUs", "Memory", "GPUs", "Elapsed", "Limit", "Used"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+        let rows: Vec<Row> = self.jobs.iter().map(|job| {
+            let mine = job.user == self.current_user;
+            let id = match job.cluster {
+                Some(ref cluster) => format!("{} ({})", job.id, cluster),
+                None => job.id.clone(),
+            };
+            // Share of the time limit used up; jobs near it are about to be killed
+            let used = match job.time_limit.num_seconds() {
+                limit if limit > 0 => {
+                    let percent = job.elapsed.num_seconds() * 100 / limit;
+                    let style = match percent {
+                        90.. => Style::default().fg(self.theme.error),
+                        75..=89 => Style::default().fg(self.theme.warning),
+                        _ => Style::default(),
+                    };
+                    Cell::from(format!("{}%", percent)).style(style)
+                },
+                _ => Cell::from("-"),
+            };
+
+            Row::new(vec![
+                Cell::from(if mine { format!("★ {}", id) } else { id }),
+                Cell::from(job.user.clone()),
+                Cell::from(job.name.clone()),
+                Cell::from(job.node_list.join(",")),
+                Cell::from(job.req_cpus.to_string()),
+                Cell::from(format_bytes(job.req_mem)),
+                Cell::from(format_gpu_request(job)),
+                Cell::from(format_duration(&job.elapsed)),
+                Cell::from(format_duration(&job.time_limit)),
+                used,
+            ])
+            .style(if mine { Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD) } else { Style::default() })
+        }).collect();
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(12),
+            Constraint::Percentage(8),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(5),
+            Constraint::Percentage(7),
+            Constraint::Percentage(7),
+            Constraint::Percentage(9),
+            Constraint::Percentage(9),
+            Constraint::Percentage(5),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(format!("Jobs: {} running", self.jobs.len())))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut self.jobs_state);
+    }
+
+    /// Bold red below [`LOW_CPU_EFFICIENCY`].
+    fn efficiency_style(&self, efficiency: u64) -> Style {
+        if efficiency < LOW_CPU_EFFICIENCY {
+            Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        }
+    }
+
+    fn render_my_jobs(&mut self, f: &mut Frame, area: Rect) {
+        let header_cells = ["Job", "Name", "State", "Partition", "Nodes", "CPUs", "Memory", "Elapsed / Limit", "Progress", "CPU Eff"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+        let rows: Vec<Row> = self.user_jobs.iter().map(|job| {
+            let id = match job.cluster {
+                Some(ref cluster) => format!("{} ({})", job.id, cluster),
+                None => job.id.clone(),
+            };
+            let progress = match job.time_limit.num_seconds() {
+                limit if limit > 0 => {
+                    let ratio = job.elapsed.num_seconds() as f64 / limit as f64;
+                    let style = match ratio {
+                        r if r >= 0.9 => Style::default().fg(self.theme.error),
+                        r if r >= 0.75 => Style::default().fg(self.theme.warning),
+                        _ => Style::default(),
+                    };
+                    Cell::from(format!("{} {}%", progress_bar(ratio, COMPACT_BAR_CELLS), (ratio * 100.0) as u64)).style(style)
+                },
+                _ => Cell::from("-"),
+            };
+            // Unknown until the scheduler accounts some CPU time, which Slurm does per step
+            let efficiency = match job.cpu_efficiency() {
+                Some(efficiency) => Cell::from(format!("{}%", efficiency)).style(self.efficiency_style(efficiency)),
+                None => Cell::from("-").style(Style::default().fg(self.theme.muted)),
+            };
+
+            Row::new(vec![
+                Cell::from(id),
+                Cell::from(job.name.clone()),
+                Cell::from(self.theme.job_label(&job.state)).style(self.theme.job_state(&job.state)),
+                Cell::from(job.partition.clone()),
+                Cell::from(job.node_list.join(",")),
+                Cell::from(job.req_cpus.to_string()),
+                Cell::from(format_bytes(job.req_mem)),
+                Cell::from(format!("{} / {}", format_duration(&job.elapsed), format_duration(&job.time_limit))),
+                progress,
+                efficiency,
+            ])
+        }).collect();
+
+        let low = self.user_jobs.iter()
+            .filter(|job| job.cpu_efficiency().is_some_and(|efficiency| efficiency < LOW_CPU_EFFICIENCY))
+            .count();
+        let mut title = format!("My Jobs: {} running as {}", self.user_jobs.len(), self.current_user);
+        if low > 0 {
+            title.push_str(&format!(", {} below {}% CPU efficiency", low, LOW_CPU_EFFICIENCY));
+        }
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(9),
+            Constraint::Percentage(12),
+            Constraint::Percentage(8),
+            Constraint::Percentage(8),
+            Constraint::Percentage(11),
+            Constraint::Percentage(5),
+            Constraint::Percentage(7),
+            Constraint::Percentage(16),
+            Constraint::Percentage(12),
+            Constraint::Percentage(12),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut self.my_jobs_state);
+    }
+
+    fn render_timeline(&mut self, f: &mut Frame, area: Rect) {
+        let timeline_rows = self.timeline_rows();
+        let running: Vec<&Job> = timeline_rows
+            .iter()
+            .filter_map(|row| match row {
+                TimelineRow::Job { job, .. } => Some(*job),
+                TimelineRow::Free(_) => None,
+            })
+            .collect();
+
+        // Back to the earliest start and ahead to the latest time limit, so every bar fits
+        let past = running.iter().map(|job| job.elapsed).max().unwrap_or_else(chrono::Duration::zero);
+        let ahead = running.iter().map(|job| job.time_limit - job.elapsed).max().unwrap_or_else(chrono::Duration::zero);
+        let widths = [12, 10, 10, 9];
+        let fixed = widths.iter().sum::<u16>() + widths.len() as u16 + 2 + 3;
+        let axis = Axis::new(past, ahead, area.width.saturating_sub(fixed).max(10) as usize);
+
+        let header = Row::new(vec![
+            Cell::from("Node"),
+            Cell::from("Job"),
+            Cell::from("User"),
+            Cell::from(axis.labels()),
+            Cell::from("Ends in"),
+        ])
+        .style(self.theme.heading())
+        .height(1)
+        .bottom_margin(1);
+
+        let muted = Style::default().fg(self.theme.muted);
+        let rows: Vec<Row> = timeline_rows.iter().map(|row| match row {
+            TimelineRow::Job { node, job, first } => {
+                let mine = job.user == self.current_user;
+                let remaining = (job.time_limit - job.elapsed).max(chrono::Duration::zero());
+                let percent = (job.elapsed.num_seconds() * 100).checked_div(job.time_limit.num_seconds()).unwrap_or(0);
+                let elapsed_style = match percent {
+                    90.. => Style::default().fg(self.theme.error),
+                    75..=89 => Style::default().fg(self.theme.warning),
+                    _ => Style::default().fg(self.theme.good),
+                };
+                Row::new(vec![
+                    Cell::from(if *first { node.id.clone() } else { String::new() }),
+                    Cell::from(if mine { format!("★ {}", job.id) } else { job.id.clone() }),
+                    Cell::from(job.user.clone()),
+                    Cell::from(axis.bar(job.elapsed, remaining, elapsed_style, muted)),
+                    Cell::from(placement::format_wait(&remaining)),
+                ])
+                .style(if mine { Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD) } else { Style::default() })
+            },
+            TimelineRow::Free(node) => Row::new(vec![
+                Cell::from(node.id.clone()),
+                Cell::from(Span::styled(self.theme.node_label(node), self.theme.node_state(node))),
+                Cell::from(""),
+                Cell::from(axis.bar(chrono::Duration::zero(), chrono::Duration::zero(), muted, muted)),
+                Cell::from(""),
+            ]),
+        }).collect();
+
+        let title = format!(
+            "Timeline: {} running jobs on {} nodes (█ elapsed, ░ left until the time limit)",
+            running.len(),
+            timeline_rows.iter().filter(|row| matches!(row, TimelineRow::Job { first: true, .. })).count(),
+        );
+        let table = Table::new(rows, [
+            Constraint::Length(widths[0]),
+            Constraint::Length(widths[1]),
+            Constraint::Length(widths[2]),
+            Constraint::Length(axis.cells as u16),
+            Constraint::Length(widths[3]),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut self.timeline_state);
+    }
+
+    fn render_users(&mut self, f: &mut Frame, area: Rect) {
+        let header_cells = UserSort::ALL.iter().map(|key| {
+            let title = if *key == self.user_sort {
+                format!("{}{}", if self.user_sort_descending { "▼" } else { "▲" }, key.title())
+            } else {
+                key.title().to_string()
+            };
+            Cell::from(title).style(self.theme.heading())
+        });
+        let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+        let usage = self.user_usage();
+        let rows: Vec<Row> = usage.iter().map(|usage| {
+            let mine = usage.user == self.current_user;
+            let cores = match self.stats.total_cores {
+                0 => usage.cores.to_string(),
+                total => format!("{} ({}%)", usage.cores, usage.cores as u64 * 100 / total as u64),
+            };
+            Row::new(vec![
+                Cell::from(if mine { format!("★ {}", usage.user) } else { usage.user.clone() }),
+                Cell::from(usage.running.to_string()),
+                Cell::from(cores),
+                Cell::from(format_bytes(usage.mem)),
+                Cell::from(usage.gpus.to_string()),
+                Cell::from(usage.nodes.to_string()),
+                Cell::from(usage.pending.to_string()),
+                Cell::from(usage.oldest.as_ref().map(format_duration).unwrap_or_else(|| "-".to_string())),
+            ])
+            .style(if mine { Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD) } else { Style::default() })
+        }).collect();
+
+        let widths = [
+            Constraint::Percentage(18),
+            Constraint::Percentage(10),
+            Constraint::Percentage(14),
+            Constraint::Percentage(12),
+            Constraint::Percentage(8),
+            Constraint::Percentage(8),
+            Constraint::Percentage(9),
+            Constraint::Percentage(13),
+        ];
+        let (header_y, header_columns) = header_columns(area, &widths, self.users_state.selected().is_some());
+        self.user_sort_headers = (header_y, header_columns.into_iter().zip(UserSort::ALL).map(|((x, width), key)| (x, width, key)).collect());
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(format!("Users: {} with jobs", usage.len())))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut self.users_state);
+    }
+
+    fn render_queue(&mut self, f: &mut Frame, area: Rect) {
+        let header_cells = ["#", "Job", "User", "Name", "Reason", "Nodes", "CPUs", "Memory", "GPUs", "Limit", "Priority", "Starts", "Waiting"]
+            .iter()
+            .map(|h| Cell::from(*h).style(self.theme.heading()));
+        let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+        let now = self.now();
+        let queue_rows = self.queue_rows();
+        let mut position = 0;
+        let mut positions = Vec::new();
+        let mut has_arrays = false;
+        let rows: Vec<Row> = queue_rows.iter().map(|row| {
+            let (job, id, reason) = match row {
+                QueueRow::Job(job) => (*job, job.id.clone(), None),
+                QueueRow::Array(array) => {
+                    has_arrays = true;
+                    let arrow = if self.expanded_arrays.contains(&array.id) { "▾" } else { "▸" };
+                    let reason = Cell::from(array.summary()).style(Style::default().fg(self.theme.info));
+                    (array.first_pending(), format!("{} {}", arrow, array.id), Some(reason))
+                },
+                QueueRow::Task(job) => (*job, format!("  {}", job.id), None),
+            };
+            let reason = reason.unwrap_or_else(|| match job.state {
+                JobState::Running => Cell::from("running").style(Style::default().fg(self.theme.good)),
+                _ => {
+                    let reason = job.reason.as_deref().unwrap_or("-");
+                    let reason_style = match reason {
+                        // Waiting its turn
+                        "Resources" | "Priority" => Style::default().fg(self.theme.warning),
+                        // Waiting by design
+                        "Dependency" | "BeginTime" | "-" => Style::default().fg(self.theme.label),
+                        // Blocked by a limit, hold or unavailable nodes
+                        _ => Style::default().fg(self.theme.error),
+                    };
+                    Cell::from(reason.to_string()).style(reason_style)
+                },
+            });
+            let mine = job.user == self.current_user;
+            let id = match job.cluster {
+                Some(ref cluster) => format!("{} ({})", id, cluster),
+                None => id,
+            };
+            // Tasks of an expanded array share its position
+            let number = match row {
+                QueueRow::Task(_) => String::new(),
+                _ => {
+                    position += 1;
+                    if mine {
+                        positions.push(format!("#{}", position));
+                    }
+                    position.to_string()
+                },
+            };
+
+            Row::new(vec![
+                Cell::from(number),
+                Cell::from(if mine { format!("★ {}", id) } else { id }),
+                Cell::from(job.user.clone()),
+                Cell::from(job.name.clone()),
+                reason,
+                Cell::from(job.req_nodes.to_string()),
+                Cell::from(job.req_cpus.to_string()),
+                Cell::from(format_bytes(job.req_mem)),
+                Cell::from(format_gpu_request(job)),
+                Cell::from(format_duration(&job.time_limit)),
+                Cell::from(job.priority.as_ref().map_or("-".to_string(), |p| p.total.to_string())),
+                Cell::from(format_est_start(job.est_start, now)),
+                Cell::from(placement::format_wait(&(now - job.submit_time))),
+            ])
+            .style(if mine { Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD) } else { Style::default() })
+        }).collect();
+
+        let pending: u32 = self.pending_jobs.iter().map(|job| arrays::task_count(&job.id)).sum();
+        let mut title = format!("Queue: {} pending", pending);
+        if !positions.is_empty() {
+            title.push_str(&format!(", yours at {}", positions.join(", ")));
+        }
+        if let Some(stats) = WaitStats::of(&self.job_waits, &self.current_partition) {
+            title.push_str(&format!(" · waits over {}d: {} ({} jobs)", waits::WINDOW.num_days(), stats.summary(), stats.jobs));
+        }
+        if has_arrays {
+            title.push_str(" (x: expand array)");
+        }
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(3),
+            Constraint::Percentage(11),
+            Constraint::Percentage(6),
+            Constraint::Percentage(6),
+            Constraint::Percentage(17),
+            Constraint::Percentage(4),
+            Constraint::Percentage(4),
+            Constraint::Percentage(5),
+            Constraint::Percentage(7),
+            Constraint::Percentage(8),
+            Constraint::Percentage(6),
+            Constraint::Percentage(7),
+            Constraint::Percentage(7),
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut self.queue_state);
+    }
+
+    /// Why the selected pending job waits: reason, priority breakdown and estimated start.
+    fn render_pending_job_detail(&self, f: &mut Frame) {
+        let Some(job) = self.selected_pending_job() else {
+            return;
+        };
+
+        let label = Style::default().fg(self.theme.label);
+        let now = self.now();
+        let entries = arrays::group(&self.pending_jobs, &self.jobs);
+        let same = |j: &Job| j.id == job.id && j.cluster == job.cluster;
+        let position = entries
+            .iter()
+            .position(|entry| match entry {
+                QueueEntry::Job(j) => same(j),
+                QueueEntry::Array(array) => array.tasks.iter().any(|j| same(j)),
+            })
+            .unwrap_or(0) + 1;
+        let mut lines = vec![
+            Line::from(vec![Span::styled("User:       ", label), Span::raw(job.user.clone())]),
+            Line::from(vec![
+                Span::styled("Position:   ", label),
+                Span::raw(format!("#{} of {}", position, entries.len())),
+            ]),
+            Line::from(vec![Span::styled("Reason:     ", label), Span::raw(job.reason.clone().unwrap_or_else(|| "-".to_string()))]),
+        ];
+        if let Some(QueueEntry::Array(array)) = entries.get(position - 1) {
+            lines.push(Line::from(vec![
+                Span::styled("Array:      ", label),
+                Span::raw(format!("{}, {}", array.id, array.summary())),
+            ]));
+        }
+        lines.extend([
+            Line::from(vec![
+                Span::styled("Est. start: ", label),
+                Span::raw(match job.est_start {
+                    Some(start) => format!("{} ({})", self.cluster_tz.format(&start, "%m-%d %H:%M"), format_est_start(Some(start), now)),
+                    None => "not planned yet".to_string(),
+                }),
+            ]),
+        ]);
+
+        if let Some(ref priority) = job.priority {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("Priority    ", label.add_modifier(Modifier::BOLD)),
+                Span::raw(priority.total.to_string()),
+            ]));
+            for (name, value) in priority.factors() {
+                lines.push(Line::from(format!(
+                    "  {:<10} {}",
+                    name,
+                    self.create_progress_bar(value, priority.total),
+                )));
+            }
+        }
+
+        let area = centered_rect(60, lines.len() as u16 + 2, f.size());
+        let popup = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(format!("Job {} (Enter/Esc: close)", job.id)));
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+
+    /// Everything about the selected running job: where it runs, what it asked for and how
+    /// far into its time limit it is.
+    fn render_job_detail(&self, f: &mut Frame) {
+        let Some(job) = self.selected_job() else {
+            return;
+        };
+
+        let label = Style::default().fg(self.theme.label);
+        let now = self.now();
+        let mut lines = Vec::new();
+        if let Some(ref cluster) = job.cluster {
+            lines.push(Line::from(vec![Span::styled("Cluster:    ", label), Span::raw(cluster.clone())]));
+        }
+        lines.extend([
+            Line::from(vec![Span::styled("User:       ", label), Span::raw(job.user.clone())]),
+            Line::from(vec![Span::styled("Name:       ", label), Span::raw(job.name.clone())]),
+            Line::from(vec![Span::styled("State:      ", label), Span::styled(self.theme.job_label(&job.state), self.theme.job_state(&job.state))]),
+            Line::from(vec![Span::styled("Partition:  ", label), Span::raw(job.partition.clone())]),
+            Line::from(vec![Span::styled("Nodes:      ", label), Span::raw(job.node_list.join(","))]),
+            Line::from(vec![
+                Span::styled("Requested:  ", label),
+                Span::raw(format!(
+                    "{} node{}, {} CPUs, {}, GPUs {}",
+                    job.req_nodes,
+                    if job.req_nodes == 1 { "" } else { "s" },
+                    job.req_cpus,
+                    format_bytes(job.req_mem),
+                    format_gpu_request(job),
+                )),
+            ]),
+        ]);
+
+        if let Some(efficiency) = job.cpu_efficiency() {
+            lines.push(Line::from(vec![
+                Span::styled("CPU time:   ", label),
+                Span::raw(format!(
+                    "{} of {} core-time ",
+                    format_duration(&job.cpu_time),
+                    format_duration(&job.core_time()),
+                )),
+                Span::styled(format!("({}%)", efficiency), self.efficiency_style(efficiency)),
+            ]));
+        }
+
+        let time = match job.time_limit.num_seconds() {
+            limit if limit > 0 => {
+                let ratio = job.elapsed.num_seconds() as f64 / limit as f64;
+                let style = match ratio {
+                    r if r >= 0.9 => Style::default().fg(self.theme.error),
+                    r if r >= 0.75 => Style::default().fg(self.theme.warning),
+                    _ => Style::default(),
+                };
+                vec![
+                    Span::styled(progress_bar(ratio, BAR_CELLS), style),
+                    Span::raw(format!(
+                        " {} of {}, {} left",
+                        format_duration(&job.elapsed),
+                        format_duration(&job.time_limit),
+                        format_duration(&(job.time_limit - job.elapsed)),
+                    )),
+                ]
+            },
+            _ => vec![Span::raw(format!("{} elapsed, no time limit", format_duration(&job.elapsed)))],
+        };
+        lines.push(Line::from([vec![Span::styled("Time:       ", label)], time].concat()));
+        lines.extend([
+            Line::from(vec![
+                Span::styled("Submitted:  ", label),
+                Span::raw(format!(
+                    "{} ({} ago)",
+                    self.cluster_tz.format(&job.submit_time, "%m-%d %H:%M"),
+                    placement::format_wait(&(now - job.submit_time)),
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled("Work dir:   ", label),
+                Span::raw(job.work_dir.clone().unwrap_or_else(|| "-".to_string())),
+            ]),
+        ]);
+
+        let area = centered_rect(70, lines.len() as u16 + 2, f.size());
+        let popup = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(format!("Job {} (Enter/Esc: close)", job.id)));
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+
+    fn render_stats(&self, f: &mut Frame, area: Rect) {
+        let cpu_ratio = if self.stats.total_cores > 0 {
+            self.stats.used_cores as f64 / self.stats.total_cores as f64
+        } else {
+            0.0
+        };
+
+        let mem_ratio = if self.stats.total_memory > 0 {
+            self.stats.used_memory as f64 / self.stats.total_memory as f64
+        } else {
+            0.0
+        };
+
+        let stats_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); self.stats_height() as usize])
+            .split(area);
+
+        // Sparklines of the recent samples beside the gauges, once there is a trend to show
+        let (samples, span) = self.utilization.samples(&self.current_partition);
+        let sparklines = samples.len() >= 2 && area.width >= SPARKLINE_MIN_WIDTH;
+        let gauge_area = |row: usize| -> Rect {
+            if sparklines {
+                Rect { width: stats_layout[row].width - SPARKLINE_WIDTH - SPARKLINE_LABEL_WIDTH - 1, ..stats_layout[row] }
+            } else {
+                stats_layout[row]
+            }
+        };
+        let sparkline = |f: &mut Frame, row: usize, values: Vec<u64>, color: Color| {
+            if sparklines {
+                self.render_sparkline(f, stats_layout[row], &values, span, color);
+            }
+        };
+
+        // CPU gauge
+        let cpu_gauge = Gauge::default()
+            .block(Block::default().borders(Borders::NONE))
+            .gauge_style(Style::default().fg(self.theme.cpu_gauge))
+            .percent((cpu_ratio * 100.0) as u16)
+            .label(format!("CPU  {}/{}", self.stats.used_cores, self.stats.total_cores));
+        f.render_widget(cpu_gauge, gauge_area(0));
+        sparkline(f, 0, samples.iter().map(|s| s.cpu).collect(), self.theme.cpu_gauge);
+
+        // Memory gauge  
+        let mem_gauge = Gauge::default()
+            .block(Block::default().borders(Borders::NONE))
+            .gauge_style(Style::default().fg(self.theme.mem_gauge))
+            .percent((mem_ratio * 100.0) as u16)
+            .label(format!("MEM  {}GB/{}GB", self.stats.used_memory / GB, self.stats.total_memory / GB));
+        f.render_widget(mem_gauge, gauge_area(1));
+        sparkline(f, 1, samples.iter().map(|s| s.mem).collect(), self.theme.mem_gauge);
+
+        // GPU gauge, only on partitions with GPUs
+        let mut row = 2;
+        let total_gpus = self.stats.total_gpus();
+        if total_gpus > 0 {
+            let used_gpus = self.stats.used_gpus();
+            let gpu_gauge = Gauge::default()
+                .block(Block::default().borders(Borders::NONE))
+                .gauge_style(Style::default().fg(self.theme.gpu_gauge))
+                .percent((used_gpus as f64 / total_gpus as f64 * 100.0) as u16)
+                .label(format!("GPU  {}/{}", used_gpus, total_gpus));
+            f.render_widget(gpu_gauge, gauge_area(row));
+            sparkline(f, row, samples.iter().filter_map(|s: &Sample| s.gpu).collect(), self.theme.gpu_gauge);
+            row += 1;
+        }
+
+        // Power gauge, against the highest draw seen this session
+        if self.stats.power_nodes > 0 {
+            let peak = self.peak_watts.get(&self.current_partition).copied().unwrap_or(0).max(1);
+            let mut label = format!("PWR  {} (peak {}), {} since boot",
+                                    format_watts(self.stats.current_watts),
+                                    format_watts(peak),
+                                    format_joules(self.stats.consumed_joules));
+            if self.stats.power_nodes < self.stats.total_nodes {
+                label.push_str(&format!(", {}/{} nodes reporting", self.stats.power_nodes, self.stats.total_nodes));
+            }
+            let power_gauge = Gauge::default()
+                .block(Block::default().borders(Borders::NONE))
+                .gauge_style(Style::default().fg(self.theme.power_gauge))
+                .percent((self.stats.current_watts as f64 / peak as f64 * 100.0).min(100.0) as u16)
+                .label(label);
+            f.render_widget(power_gauge, gauge_area(row));
+            row += 1;
+        }
+
+        // Node summary
+        let node_summary = Paragraph::new(format!("Nodes: {} total, {} available", 
+                                                 self.stats.total_nodes, 
+                                                 self.stats.avail_nodes));
+        f.render_widget(node_summary, stats_layout[row]);
+
+        // GPU availability per model / MIG profile
+        if !self.stats.gpu_profiles.is_empty() {
+            let mut spans = vec![Span::raw("GPUs free: ")];
+            for (i, profile) in self.stats.gpu_profiles.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" · "));
+                }
+                let style = if profile.available() == 0 {
+                    Style::default().fg(self.theme.error)
+                } else {
+                    Style::default().fg(self.theme.good)
+                };
+                let label = if profile.is_mig() { format!("{} (MIG)", profile.model) } else { profile.model.clone() };
+                spans.push(Span::raw(format!("{} ", label)));
+                spans.push(Span::styled(format!("{}/{}", profile.available(), profile.total), style));
+            }
+            f.render_widget(Paragraph::new(Line::from(spans)), stats_layout[row + 1]);
+        }
+
+        // Per-cluster summary in a multi-cluster view
+        let clusters = self.scheduler.clusters();
+        if !clusters.is_empty() {
+            let mut spans = vec![Span::raw("Clusters: ")];
+            for (i, cluster) in clusters.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" · "));
+                }
+                spans.push(Span::raw(format!("{} ", cluster.name)));
+                if !cluster.enabled {
+                    spans.push(Span::styled("off", Style::default().fg(self.theme.muted)));
+                } else if let Some(ref error) = cluster.error {
+                    spans.push(Span::styled(format!("error: {}", error), Style::default().fg(self.theme.error)));
+                } else {
+                    let nodes: Vec<Node> = self.nodes
+                        .iter()
+                        .filter(|node| node.cluster.as_deref().is_some_and(|c| {
+                            c == cluster.name || c.strip_prefix(cluster.name.as_str()).is_some_and(|sub| sub.starts_with('/'))
+                        }))
+                        .cloned()
+                        .collect();
+                    let stats = ClusterStats::of(&nodes);
+                    spans.push(Span::styled(
+                        format!("{}/{} nodes, {} cores free", stats.avail_nodes, stats.total_nodes, stats.avail_cores),
+                        Style::default().fg(if stats.avail_nodes > 0 { self.theme.good } else { self.theme.warning }),
+                    ));
+                }
+            }
+            f.render_widget(Paragraph::new(Line::from(spans)), stats_layout[row + 2]);
+        }
+
+        // Per-partition subtotals when several are shown
+        if self.multi_partition() {
+            let mut spans = vec![Span::raw("Partitions: ")];
+            for (i, partition) in self.selected_partitions().iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" · "));
+                }
+                let nodes: Vec<Node> = self.nodes.iter().filter(|node| node.partitions.contains(partition)).cloned().collect();
+                let stats = ClusterStats::of(&nodes);
+                spans.push(Span::raw(format!("{} ", partition)));
+                spans.push(Span::styled(
+                    format!("{}/{} nodes, {} cores, {} free", stats.avail_nodes, stats.total_nodes, stats.avail_cores, format_bytes(stats.avail_memory)),
+                    Style::default().fg(if stats.avail_nodes > 0 { self.theme.good } else { self.theme.warning }),
+                ));
+            }
+            f.render_widget(Paragraph::new(Line::from(spans)), stats_layout[row + 3]);
+        }
+    }
+
+    /// Drawer beside the node table with everything known about the selected node and the
+    /// jobs running on it; follows the selection.
+    fn render_node_detail(&self, f: &mut Frame, area: Rect) {
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+
+        let label = Style::default().fg(self.theme.label);
+        let mut lines = Vec::new();
+        if let Some(ref cluster) = node.cluster {
+            lines.push(Line::from(vec![Span::styled("Cluster:    ", label), Span::raw(cluster.clone())]));
+        }
+        lines.extend([
+            Line::from(vec![Span::styled("State:      ", label), Span::styled(if node.state_flags.is_empty() {
+                format!("{}{}", self.theme.node_symbol(node), node.state)
+            } else {
+                format!("{}{} ({})", self.theme.node_symbol(node), node.state, node.state_flags.join(", "))
+            }, self.theme.node_state(node))]),
+            Line::from(vec![Span::styled("Reason:     ", label), Span::raw(node.reason.clone().unwrap_or_else(|| "-".to_string()))]),
+            Line::from(vec![Span::styled("Partitions: ", label), Span::raw(node.partitions.join(", "))]),
+            Line::from(vec![
+                Span::styled("Features:   ", label),
+                Span::raw(if node.features.is_empty() { "-".to_string() } else { node.features.join(", ") }),
+            ]),
+            Line::from(vec![
+                Span::styled("Gres:       ", label),
+                Span::raw(if node.gpus.is_empty() {
+                    "-".to_string()
+                } else {
+                    node.gpus
+                        .iter()
+                        .map(|gpu| format!("gpu:{}:{} ({} used)", gpu.model, gpu.total, gpu.used))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }),
+            ]),
+            Line::from(vec![Span::styled("OS:         ", label), Span::raw(node.os.clone().unwrap_or_else(|| "-".to_string()))]),
+            Line::from(vec![
+                Span::styled("CPU:        ", label),
+                Span::raw(format!("{}/{} used, load {}", node.used_cores, node.total_cores,
+                    node.cpu_load.map(|l| format!("{:.2}", l)).unwrap_or_else(|| "n/a".to_string()))),
+            ]),
+            Line::from(vec![
+                Span::styled("Memory:     ", label),
+                Span::raw(format!("{} / {} used", format_bytes(node.used_mem), format_bytes(node.total_mem))),
+            ]),
+            Line::from(vec![Span::styled("Health:     ", label), Span::raw(self.health_score(node).to_string())]),
+        ]);
+        if let Some(watts) = node.current_watts {
+            let mut power = format_watts(watts.into());
+            if let Some(joules) = node.consumed_joules {
+                power.push_str(&format!(", {} since boot", format_joules(joules)));
+            }
+            lines.push(Line::from(vec![Span::styled("Power:      ", label), Span::raw(power)]));
+        }
+        if let Some(eta) = self.etas.get(&(node.cluster.clone(), node.id.clone())) {
+            lines.push(Line::from(vec![
+                Span::styled("Free in:    ", label),
+                Span::raw(format!("{} (first job to hit its time limit)", placement::format_wait(eta))),
+            ]));
+        }
+        if let Some(reservation) = self.node_reservation(node) {
+            let now = self.now();
+            let when = if reservation.is_active(now) {
+                format!("active until {}", self.cluster_tz.format(&reservation.end_time, "%m-%d %H:%M"))
+            } else {
+                format!("starts in {}", placement::format_wait(&(reservation.start_time - now)))
+            };
+            lines.push(Line::from(vec![
+                Span::styled("Reserved:   ", label),
+                Span::raw(format!("{} ({})", reservation.name, when)),
+            ]));
+        }
+
+        // Running jobs come from the job list; ids the node reports but the list lacks
+        // (other partitions, say) are shown without an owner
+        let jobs: Vec<&Job> = self
+            .jobs
+            .iter()
+            .filter(|job| job.cluster == node.cluster && job.node_list.contains(&node.id))
+            .collect();
+        let unknown: Vec<&String> = node.jobs.iter().filter(|id| !jobs.iter().any(|job| &job.id == *id)).collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Jobs ({})", jobs.len() + unknown.len()),
+            label.add_modifier(Modifier::BOLD),
+        )));
+        for job in jobs {
+            let mine = job.user == self.current_user;
+            let left = format_duration(&(job.time_limit - job.elapsed));
+            let line = format!("  {:<10} {:<10} {:>4} CPUs  {} left", job.id, job.user, job.req_cpus, left);
+            lines.push(if mine {
+                Line::styled(format!("★{}", &line[1..]), Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD))
+            } else {
+                Line::from(line)
+            });
+        }
+        for id in unknown {
+            lines.push(Line::from(format!("  {:<10} ?", id)));
+        }
+
+        let drawer = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(format!("{} (Enter/Esc: close)", node.id)));
+        f.render_widget(drawer, area);
+    }
+
+    /// The configured columns, without the ones that would be empty here, and with Cluster and
+    /// Partition after Node when several clusters or partitions are shown.
+    fn visible_columns(&self) -> Vec<Column> {
+        let show_cluster = self.nodes.iter().any(|node| node.cluster.is_some());
+        let show_gpus = self.nodes.iter().any(|node| !node.gpus.is_empty());
+        let show_partition = self.multi_partition();
+
+        let mut columns: Vec<Column> = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|column| match column {
+                Column::Cluster => show_cluster,
+                Column::Partition => show_partition,
+                Column::Gpu => show_gpus,
+                _ => true,
+            })
+            .collect();
+        let after_node = columns.iter().position(|c| *c == Column::Node).map_or(0, |i| i + 1);
+        if show_partition && !self.columns.contains(&Column::Partition) {
+            columns.insert(after_node, Column::Partition);
+        }
+        if show_cluster && !self.columns.contains(&Column::Cluster) {
+            columns.insert(after_node, Column::Cluster);
+        }
+        if self.what_if.is_some() {
+            columns.push(Column::WhatIf);
+        }
+        if self.fit.is_some() {
+            columns.push(Column::Fit);
+        }
+        columns
+    }
+
+    /// The columns that fit in a table `width` characters wide: all of them when the full-size
+    /// bars fit, else the first column and as many as fit after it from `column_offset` on
+    /// (narrow bars). Also returns whether the table is narrow and the columns scrolled out on
+    /// each side.
+    fn fit_columns(&mut self, columns: &[Column], width: u16) -> (Vec<Column>, bool, (usize, usize)) {
+        let bar_extra = (BAR_CELLS - COMPACT_BAR_CELLS) as u16;
+        let full_width: u16 = columns.iter().map(|c| c.min_width() + 1 + if c.has_bar() { bar_extra } else { 0 }).sum();
+        let Some((first, rest)) = columns.split_first().filter(|_| full_width > width) else {
+            return (columns.to_vec(), false, (0, 0));
+        };
+
+        let room = width.saturating_sub(first.min_width() + 1);
+        let fits = |from: usize| {
+            let mut used = 0;
+            rest[from..].iter().take_while(|c| {
+                used += c.min_width() + 1;
+                used <= room
+            }).count()
+        };
+        let mut offset = self.column_offset.min(rest.len().saturating_sub(1));
+        // After a resize, don't leave columns scrolled out on the left that would fit
+        while offset > 0 && fits(offset - 1) == rest.len() - offset + 1 {
+            offset -= 1;
+        }
+        self.column_offset = offset;
+
+        let shown = fits(offset);
+        let mut fitted = vec![*first];
+        fitted.extend_from_slice(&rest[offset..offset + shown]);
+        (fitted, true, (offset, rest.len() - offset - shown))
+    }
+
+    fn render_table(&mut self, f: &mut Frame, area: Rect) {
+        let visible = self.visible_columns();
+        // Inside the borders, after the highlight symbol
+        let (columns, compact, scrolled) = self.fit_columns(&visible, area.width.saturating_sub(5));
+        self.scrolled_columns = scrolled;
+        let selected_partitions = self.selected_partitions();
+        let sort_keys: Vec<Option<SortKey>> = columns.iter().map(|column| column.sort_key()).collect();
+        let header_cells = columns.iter().zip(&sort_keys).map(|(column, key)| {
+            let title = if compact { column.short_title() } else { column.title() };
+            let title = match key {
+                Some(key) if *key == self.sort_key => format!("{}{}", if self.sort_descending { "▼" } else { "▲" }, title),
+                _ => title.to_string(),
+            };
+            Cell::from(title).style(self.theme.heading())
+        });
+        let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+        // Only the rows in view are built, which matters with thousands of nodes
+        let node_rows = self.node_rows();
+        let total_rows = node_rows.len();
+        let window = viewport(&self.table_state, total_rows, area.height.saturating_sub(4).into());
+        let rows: Vec<Row> = node_rows[window.clone()].iter().map(|row| {
+            let node = match row {
+                NodeRow::Rack(rack) => return self.rack_row(rack, &columns, compact),
+                NodeRow::Node(node) => *node,
+            };
+            Row::new(columns.iter().map(|column| self.node_cell(*column, node, &selected_partitions, compact)))
+        }).collect();
+        drop(node_rows);
+        *self.table_state.offset_mut() = window.start;
+
+        let widths: Vec<Constraint> = if compact {
+            columns.iter().map(|column| Constraint::Length(column.min_width())).collect()
+        } else {
+            // Each column's share of the 93% left after the highlight symbol and the column gaps
+            let weights: Vec<u16> = columns.iter().map(|column| column.weight()).collect();
+            scale_to(&weights, 93).into_iter().map(Constraint::Percentage).collect()
+        };
+
+        let (header_y, header_columns) = header_columns(area, &widths, self.table_state.selected().is_some());
+        self.sort_headers = (
+            header_y,
+            header_columns.into_iter().zip(sort_keys).filter_map(|((x, width), key)| Some((x, width, key?))).collect(),
+        );
+
+        let mut title = match self.fit {
+            Some(ref fit) if self.fit_only => format!("Nodes with room for {}", fit.request),
+            _ => "Nodes".to_string(),
+        };
+        if window.len() < total_rows {
+            title.push_str(&format!(" {}-{} of {}", window.start + 1, window.end, total_rows));
+        }
+        let title = match scrolled {
+            (0, 0) => title,
+            (left, right) => {
+                title.push(' ');
+                if left > 0 {
+                    title.push_str(&format!("◂ {} ", left));
+                }
+                if right > 0 {
+                    title.push_str(&format!("{} ▸ ", right));
+                }
+                title + "(h/l: columns)"
+            },
+        };
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight())
+            .highlight_symbol(">> ");
+
+        let mut window_state = TableState::default().with_selected(self.table_state.selected().map(|i| i - window.start));
+        f.render_stateful_widget(table, area, &mut window_state);
+    }
+
+    /// A node table cell, reversed for a while after its value changed.
+    fn node_cell(&self, column: Column, node: &Node, selected_partitions: &[String], compact: bool) -> Cell<'static> {
+        let field = match column {
+            Column::State => Some(changes::Field::State),
+            Column::Cpu | Column::AvailCpu => Some(changes::Field::Cores),
+            Column::Memory | Column::AvailMem => Some(changes::Field::Memory),
+            Column::Gpu => Some(changes::Field::Gpus),
+            Column::Jobs => Some(changes::Field::Jobs),
+            Column::Reason => Some(changes::Field::Reason),
+            _ => None,
+        };
+        let cell = self.node_cell_content(column, node, selected_partitions, compact);
+        match field {
+            Some(field) if self.changes.is_changed(node, field) => cell.reversed(),
+            _ => cell,
+        }
+    }
+
+    fn node_cell_content(&self, column: Column, node: &Node, selected_partitions: &[String], compact: bool) -> Cell<'static> {
+        let muted = Style::default().fg(self.theme.muted);
+        let bar_cells = if compact { COMPACT_BAR_CELLS } else { BAR_CELLS };
+        match column {
+            Column::Node => {
+                let user_has_jobs = self.user_has_jobs_on_node(node);
+                let mut node_name = if user_has_jobs {
+                    format!("★ {}", node.id)
+                } else {
+                    node.id.clone()
+                };
+                let reserved = self.node_reservation(node).is_some();
+                if reserved {
+                    node_name = format!("⚑ {}", node_name);
+                }
+                if self.marked_nodes.contains(&node.id) {
+                    node_name = format!("✓ {}", node_name);
+                }
+                if self.grouping.is_some() {
+                    node_name = format!("  {}", node_name);
+                }
+                Cell::from(node_name).style(if user_has_jobs {
+                    Style::default().fg(self.theme.accent).add_modifier(Modifier::BOLD)
+                } else if reserved {
+                    Style::default().fg(self.theme.special)
+                } else {
+                    Style::default()
+                })
+            },
+            Column::Cluster => Cell::from(node.cluster.clone().unwrap_or_default()),
+            Column::Partition => {
+                let partitions: Vec<&str> = node.partitions.iter().filter(|p| selected_partitions.contains(p)).map(String::as_str).collect();
+                Cell::from(partitions.join(","))
+            },
+            Column::Cpu => Cell::from(usage_bar(node.used_cores.into(), node.total_cores.into(), bar_cells)),
+            Column::Memory => Cell::from(usage_bar(node.used_mem_gb(), node.total_mem_gb(), bar_cells)),
+            Column::AvailCpu => Cell::from(node.available_cores().to_string()),
+            Column::AvailMem => Cell::from(format_bytes(node.available_mem())),
+            Column::Gpu => match node.total_gpus() {
+                0 => Cell::from("-").style(muted),
+                total => Cell::from(format!("{}/{}", node.used_gpus(), total)).style(
+                    if node.available_gpus() > 0 { Style::default().fg(self.theme.good) } else { Style::default().fg(self.theme.error) }
+                ),
+            },
+            Column::Load => match node.cpu_load {
+                Some(load) if node.is_overloaded() => Cell::from(format!("{:.1}!", load))
+                    .style(Style::default().fg(self.theme.error).add_modifier(Modifier::BOLD)),
+                Some(load) => Cell::from(format!("{:.1}", load)),
+                None => Cell::from("-").style(muted),
+            },
+            Column::State => Cell::from(self.theme.node_label(node)).style(self.theme.node_state(node)),
+            Column::Jobs => Cell::from(node.jobs.len().to_string()),
+            Column::Eta => match self.etas.get(&(node.cluster.clone(), node.id.clone())) {
+                Some(eta) => Cell::from(placement::format_wait(eta)),
+                None => Cell::from("-").style(muted),
+            },
+            Column::Health => {
+                let health_score = self.health_score(node);
+                Cell::from(health_score.to_string()).style(self.theme.health(health_score))
+            },
+            Column::Features if node.features.is_empty() => Cell::from("-").style(muted),
+            Column::Features => Cell::from(node.features.join(",")),
+            Column::Reason => match node.reason {
+                Some(ref reason) => Cell::from(reason.clone()),
+                None => Cell::from("-").style(muted),
+            },
+            Column::WhatIf => match self.what_if.as_ref().and_then(|what_if| what_if.node_eta.get(&node.id)) {
+                Some(eta) if eta.is_zero() => Cell::from("fits now")
+                    .style(Style::default().fg(self.theme.good).add_modifier(Modifier::BOLD)),
+                Some(eta) => Cell::from(format!("in {}", placement::format_wait(eta)))
+                    .style(Style::default().fg(self.theme.warning)),
+                None => Cell::from("-").style(muted),
+            },
+            Column::Fit => match self.fit.as_ref().map_or(0, |fit| fit.slots(&node.id)) {
+                0 => Cell::from("-").style(muted),
+                slots => Cell::from(format!("{} slot{}", slots, if slots == 1 { "" } else { "s" }))
+                    .style(Style::default().fg(self.theme.good).add_modifier(Modifier::BOLD)),
+            },
+        }
+    }
+
+    /// Header row of a rack: its nodes' combined usage, the count of nodes out of service
+    /// and the worst health score.
+    fn rack_row(&self, rack: &Rack, columns: &[Column], compact: bool) -> Row<'static> {
+        let arrow = if self.expanded_racks.contains(&rack.name) { "▾" } else { "▸" };
+        let (avail_cores, avail_mem) = rack.available();
+        let bar_cells = if compact { COMPACT_BAR_CELLS } else { BAR_CELLS };
+
+        Row::new(columns.iter().map(|column| match column {
+            Column::Node => Cell::from(format!("{} {} ({})", arrow, rack.name, rack.nodes.len()))
+                .style(Style::default().fg(self.theme.label).add_modifier(Modifier::BOLD)),
+            Column::Cpu => Cell::from(usage_bar(rack.used_cores().into(), rack.total_cores().into(), bar_cells)),
+            Column::Memory => Cell::from(usage_bar(rack.used_mem() / GB, rack.total_mem() / GB, bar_cells)),
+            Column::AvailCpu => Cell::from(avail_cores.to_string()),
+            Column::AvailMem => Cell::from(format_bytes(avail_mem)),
+            Column::Gpu => match rack.total_gpus() {
+                0 => Cell::from("-").style(Style::default().fg(self.theme.muted)),
+                total => Cell::from(format!("{}/{}", rack.used_gpus(), total)),
+            },
+            Column::Load => match rack.nodes.iter().filter(|node| node.is_overloaded()).count() {
+                0 => Cell::from(""),
+                n => Cell::from(format!("{} high", n)).style(Style::default().fg(self.theme.error)),
+            },
+            Column::State => match rack.down() {
+                0 => Cell::from("all up").style(Style::default().fg(self.theme.good)),
+                n => Cell::from(format!("{} down", n)).style(Style::default().fg(self.theme.error)),
+            },
+            Column::Jobs => Cell::from(rack.jobs().to_string()),
+            Column::Eta => match rack.nodes.iter().filter_map(|node| self.etas.get(&(node.cluster.clone(), node.id.clone()))).min() {
+                Some(eta) => Cell::from(placement::format_wait(eta)),
+                None => Cell::from("-").style(Style::default().fg(self.theme.muted)),
+            },
+            Column::Health => {
+                let health_score = rack.nodes.iter().map(|node| self.health_score(node)).min().unwrap_or(100);
+                Cell::from(health_score.to_string()).style(self.theme.health(health_score))
+            },
+            Column::WhatIf => {
+                let fits = match self.what_if {
+                    Some(ref what_if) => rack.nodes.iter().filter(|node| what_if.node_eta.get(&node.id).is_some_and(|eta| eta.is_zero())).count(),
+                    None => 0,
+                };
+                match fits {
+                    0 => Cell::from("-").style(Style::default().fg(self.theme.muted)),
+                    n => Cell::from(format!("{} fit now", n)).style(Style::default().fg(self.theme.good)),
+                }
+            },
+            Column::Fit => match self.fit.as_ref().map_or(0, |fit| rack.nodes.iter().map(|node| fit.slots(&node.id)).sum()) {
+                0 => Cell::from("-").style(Style::default().fg(self.theme.muted)),
+                slots => Cell::from(format!("{} slots", slots)).style(Style::default().fg(self.theme.good)),
+            },
+            Column::Cluster | Column::Partition | Column::Features | Column::Reason => Cell::from(""),
+        }))
+    }
+
+    fn create_progress_bar(&self, used: u64, total: u64) -> String {
+        usage_bar(used, total, BAR_CELLS)
+    }
+}
+
+/// A bar of `cells` followed by `used/total`.
+fn usage_bar(used: u64, total: u64, cells: usize) -> String {
+    if total == 0 {
+        return format!("{} 0/0", "░".repeat(cells));
+    }
+
+    format!("{} {}/{}", progress_bar(used as f64 / total as f64, cells), used, total)
+}
+
+/// `weights` scaled to add up to `total`, giving the rounding leftovers to the largest remainders.
+fn scale_to(weights: &[u16], total: u16) -> Vec<u16> {
+    let sum: u32 = weights.iter().map(|&w| u32::from(w)).sum::<u32>().max(1);
+    let exact: Vec<u32> = weights.iter().map(|&w| u32::from(w) * u32::from(total)).collect();
+    let mut scaled: Vec<u16> = exact.iter().map(|e| (e / sum) as u16).collect();
+    let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+    by_remainder.sort_by_key(|&i| std::cmp::Reverse(exact[i] % sum));
+    let leftover = total.saturating_sub(scaled.iter().sum());
+    for &i in by_remainder.iter().take(leftover as usize) {
+        scaled[i] += 1;
+    }
+    scaled
+}
+
+/// A bar of `cells` filled to `ratio` (clamped to 0..=1).
+fn progress_bar(ratio: f64, cells: usize) -> String {
+    let filled_length = (ratio.clamp(0.0, 1.0) * cells as f64) as usize;
+    format!("{}{}", "█".repeat(filled_length), "░".repeat(cells - filled_length))
+}
+
+/// The time axis of the timeline view: `cells` columns from `past` ago to `ahead` from now.
+struct Axis {
+    cells: usize,
+    /// Seconds per column
+    scale: f64,
+    /// The column of the present
+    now: usize,
+    past: chrono::Duration,
+    ahead: chrono::Duration,
+}
+
+impl Axis {
+    fn new(past: chrono::Duration, ahead: chrono::Duration, cells: usize) -> Self {
+        let span = (past + ahead).num_seconds().max(1) as f64;
+        let scale = span / cells as f64;
+        let now = ((past.num_seconds() as f64 / scale) as usize).min(cells - 1);
+        Self { cells, scale, now, past, ahead }
+    }
+
+    fn column(&self, offset: chrono::Duration) -> usize {
+        ((offset.num_seconds() as f64 / self.scale).round() as usize).min(self.cells)
+    }
+
+    /// `-6h00m`, `now` and `+10h00m` at the left edge, the present and the right edge.
+    fn labels(&self) -> String {
+        let mut labels = vec![' '; self.cells];
+        let mut put = |at: usize, text: &str| {
+            for (i, c) in text.chars().enumerate() {
+                if let Some(cell) = labels.get_mut(at + i) {
+                    *cell = c;
+                }
+            }
+        };
+        let ahead = format!("+{}", placement::format_wait(&self.ahead));
+        put(self.cells.saturating_sub(ahead.chars().count()), &ahead);
+        if self.past > chrono::Duration::zero() {
+            put(0, &format!("-{}", placement::format_wait(&self.past)));
+        }
+        put(self.now, "│now");
+        labels.into_iter().collect()
+    }
+
+    /// A job that started `elapsed` ago and hits its limit `remaining` from now, with a tick at
+    /// the present where the bar leaves it blank.
+    fn bar(&self, elapsed: chrono::Duration, remaining: chrono::Duration, elapsed_style: Style, remaining_style: Style) -> Line<'static> {
+        let start = self.now.saturating_sub(self.column(elapsed));
+        let end = if remaining > chrono::Duration::zero() {
+            (self.now + self.column(remaining).max(1)).min(self.cells)
+        } else {
+            self.now
+        };
+        let mut spans = vec![Span::raw(" ".repeat(start)), Span::styled("█".repeat(self.now - start), elapsed_style)];
+        if end > self.now {
+            spans.push(Span::styled("░".repeat(end - self.now), remaining_style));
+        } else {
+            spans.push(Span::styled("┊", remaining_style));
+        }
+        spans.push(Span::raw(" ".repeat(self.cells.saturating_sub(end.max(self.now + 1)))));
+        Line::from(spans)
+    }
+}
+
+/// `max 3-00:00:00, 25 nodes, 4 GB/CPU default, 16 GB max`, leading with the state unless it's up.
+fn format_partition_limits(partition: &Partition) -> String {
+    let mut parts = Vec::new();
+    if !partition.is_up() {
+        parts.push(partition.state.clone());
+    }
+    parts.push(match partition.max_time {
+        Some(ref max_time) => format!("max {}", format_duration(max_time)),
+        None => "no time limit".to_string(),
+    });
+    if partition.total_nodes > 0 {
+        parts.push(format!("{} nodes", partition.total_nodes));
+    }
+    match (partition.default_mem_per_cpu, partition.max_mem_per_cpu) {
+        (Some(default), Some(max)) => parts.push(format!("{}/CPU default, {} max", format_bytes(default), format_bytes(max))),
+        (Some(default), None) => parts.push(format!("{}/CPU default", format_bytes(default))),
+        (None, Some(max)) => parts.push(format!("max {}/CPU", format_bytes(max))),
+        (None, None) => {}
+    }
+    if !partition.allow_accounts.is_empty() {
+        parts.push(format!("accounts: {}", partition.allow_accounts.join(",")));
+    }
+    parts.join(", ")
+}
+
+/// Nodes that can take jobs, then partly used, full, finishing jobs, and out of service.
+fn state_rank(node: &Node) -> u8 {
+    if node.is_available() {
+        return 0;
+    }
+    match node.state {
+        NodeState::Running | NodeState::Mixed => 1,
+        NodeState::Busy | NodeState::Allocated => 2,
+        NodeState::Completing => 3,
+        _ => 4,
+    }
+}
+
+/// Where a bordered Table in `area` puts its header row and each column (after the highlight
+/// symbol, one space apart), so a click on a header can sort by it.
+fn header_columns(area: Rect, widths: &[Constraint], selected: bool) -> (u16, Vec<(u16, u16)>) {
+    let mut constraints = vec![Constraint::Length(if selected { 3 } else { 0 })];
+    for width in widths {
+        constraints.extend([*width, Constraint::Length(1)]);
+    }
+    let header_area = Rect { x: area.x + 1, y: area.y + 1, width: area.width.saturating_sub(2), height: 1 };
+    let columns = Layout::default().direction(Direction::Horizontal).constraints(constraints).split(header_area);
+    (header_area.y, columns.iter().skip(1).step_by(2).map(|column| (column.x, column.width)).collect())
+}
+
+/// The rows of a `len`-row table shown in `height` lines: from the state's offset, scrolled
+/// just enough to keep the selection in view.
+fn viewport(state: &TableState, len: usize, height: usize) -> std::ops::Range<usize> {
+    let height = height.max(1);
+    let mut offset = state.offset().min(len.saturating_sub(height));
+    if let Some(selected) = state.selected().filter(|i| *i < len) {
+        offset = offset.min(selected).max((selected + 1).saturating_sub(height));
+    }
+    offset..len.min(offset + height)
+}
+
+/// Move a table selection one row down (or up), wrapping around.
+fn step_selection(state: &mut TableState, len: usize, forward: bool) {
+    if len == 0 {
+        return;
+    }
+    let i = match state.selected() {
+        Some(i) if forward => (i + 1) % len,
+        Some(i) => (i + len - 1) % len,
+        None => 0,
+    };
+    state.select(Some(i));
+}
+
+/// `in 3h20m` for a planned start, `now` once it's due, `-` if not planned.
+fn format_est_start(est_start: Option<chrono::DateTime<chrono::Utc>>, now: chrono::DateTime<chrono::Utc>) -> String {
+    match est_start {
+        Some(start) if start > now => format!("in {}", placement::format_wait(&(start - now))),
+        Some(_) => "now".to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// `2 (a100)`, `2`, or `-` for jobs without GPUs.
+fn format_gpu_request(job: &Job) -> String {
+    match (job.req_gpus, &job.gpu_type) {
+        (0, _) => "-".to_string(),
+        (count, Some(model)) => format!("{} ({})", count, model),
+        (count, None) => count.to_string(),
+    }
+}
+
+/// A rectangle `percent_x` wide and `height` rows tall, centered in `area`.
+fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height: height.min(area.height),
+    }
+}
+
+mod charts;
+mod columns;
+mod command;
+mod keys;
+mod palette;
+mod plain;
+mod refresh;
+#[cfg(test)]
+mod tests;
+mod theme;
+
+pub use columns::Column;
+pub use keys::Keymap;
+pub use theme::Theme;
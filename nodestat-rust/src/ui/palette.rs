@@ -0,0 +1,136 @@
+//! The Ctrl-P command palette: every action by name, narrowed by a fuzzy search, so features
+//! stay discoverable without learning their keys.
+
+use super::View;
+use crossterm::event::KeyCode;
+use ratatui::widgets::TableState;
+
+/// What choosing an entry does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// The same as pressing the key
+    Key(KeyCode),
+    /// A `:` command line
+    Command(String),
+}
+
+/// Where a keyed action works.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    Anywhere,
+    In(View),
+    /// The nodes view with `--admin`
+    AdminNodes,
+}
+
+/// The actions bound to keys, with the key shown next to them.
+const KEYED: [(&str, &str, KeyCode, Scope); 33] = [
+    ("Refresh now", "r", KeyCode::Char('r'), Scope::Anywhere),
+    ("Refresh less often", "+", KeyCode::Char('+'), Scope::Anywhere),
+    ("Refresh more often", "-", KeyCode::Char('-'), Scope::Anywhere),
+    ("Next view", "tab", KeyCode::Tab, Scope::Anywhere),
+    ("Search nodes", "/", KeyCode::Char('/'), Scope::In(View::Nodes)),
+    ("Command line", ":", KeyCode::Char(':'), Scope::Anywhere),
+    ("Pick a partition", "p", KeyCode::Char('p'), Scope::Anywhere),
+    ("Next sort key", "s", KeyCode::Char('s'), Scope::Anywhere),
+    ("Reverse sort", "S", KeyCode::Char('S'), Scope::Anywhere),
+    ("Filter by features", "f", KeyCode::Char('f'), Scope::Anywhere),
+    ("Mark node", "v", KeyCode::Char('v'), Scope::In(View::Nodes)),
+    ("Group by rack or name prefix", "g", KeyCode::Char('g'), Scope::In(View::Nodes)),
+    ("Copy name or ID", "y", KeyCode::Char('y'), Scope::Anywhere),
+    ("SSH into node", "o", KeyCode::Char('o'), Scope::In(View::Nodes)),
+    ("Watch or unwatch a job", "w", KeyCode::Char('w'), Scope::Anywhere),
+    ("Watch or unwatch the selected job", "W", KeyCode::Char('W'), Scope::Anywhere),
+    ("Notify when capacity frees up", "N", KeyCode::Char('N'), Scope::Anywhere),
+    ("Toggle reservations", "R", KeyCode::Char('R'), Scope::Anywhere),
+    ("Toggle licenses", "L", KeyCode::Char('L'), Scope::Anywhere),
+    ("Toggle fairshare", "F", KeyCode::Char('F'), Scope::Anywhere),
+    ("Toggle recent changes", "a", KeyCode::Char('a'), Scope::Anywhere),
+    ("Toggle alerts", "A", KeyCode::Char('A'), Scope::Anywhere),
+    ("What-if placement", "i", KeyCode::Char('i'), Scope::Anywhere),
+    ("Find where a request fits", "c", KeyCode::Char('c'), Scope::Anywhere),
+    ("Only nodes with room for the fit", "C", KeyCode::Char('C'), Scope::Anywhere),
+    ("Hold job", "H", KeyCode::Char('H'), Scope::In(View::Queue)),
+    ("Release job", "U", KeyCode::Char('U'), Scope::In(View::Queue)),
+    ("Cancel job", "d", KeyCode::Char('d'), Scope::In(View::MyJobs)),
+    ("Edit node reason", "e", KeyCode::Char('e'), Scope::AdminNodes),
+    ("Drain nodes", "D", KeyCode::Char('D'), Scope::AdminNodes),
+    ("Resume nodes", "U", KeyCode::Char('U'), Scope::AdminNodes),
+    ("Zoom the table to the whole terminal", "z", KeyCode::Char('z'), Scope::Anywhere),
+    ("Quit", "q", KeyCode::Char('q'), Scope::Anywhere),
+];
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub label: String,
+    /// The key or command that does the same
+    pub hint: String,
+    pub action: Action,
+}
+
+/// The entries available in `view`: the keyed actions that work there, then views, sort keys
+/// and `partitions` as commands.
+pub fn entries(view: View, admin: bool, partitions: &[String], sort_keys: &[(&str, &str)]) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = KEYED
+        .iter()
+        .filter(|(.., scope)| match scope {
+            Scope::Anywhere => true,
+            Scope::In(only) => *only == view,
+            Scope::AdminNodes => admin && view == View::Nodes,
+        })
+        .map(|(label, key, code, _)| Entry { label: label.to_string(), hint: key.to_string(), action: Action::Key(*code) })
+        .collect();
+
+    let command = |label: String, line: String| Entry { label, hint: format!(":{}", line), action: Action::Command(line) };
+    entries.extend(View::ALL.iter().map(|view| command(format!("Go to {}", view.title()), format!("view {}", view.name()))));
+    entries.extend(sort_keys.iter().map(|(name, label)| command(format!("Sort by {}", label), format!("sort {}", name))));
+    entries.extend(partitions.iter().map(|name| command(format!("Switch to partition {}", name), format!("partition {}", name))));
+    entries.push(command("Dump a snapshot for a bug report".to_string(), "dump".to_string()));
+    entries.push(command("Dump a snapshot without usernames".to_string(), "dump anonymize".to_string()));
+    entries
+}
+
+/// How well `query` matches `text` as a case-insensitive subsequence, higher is better; `None`
+/// if it doesn't. Runs of consecutive characters and matches at word starts score more.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|c| *c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    // Shorter labels win ties, so "Quit" beats "Toggle reservations" for "q"
+    Some(score * 100 - text.len() as i32)
+}
+
+/// The palette while it is open.
+#[derive(Default)]
+pub struct Palette {
+    pub input: String,
+    pub state: TableState,
+}
+
+impl Palette {
+    /// `entries` matching the input, best first.
+    pub fn matches<'a>(&self, entries: &'a [Entry]) -> Vec<&'a Entry> {
+        if self.input.trim().is_empty() {
+            return entries.iter().collect();
+        }
+        let mut scored: Vec<(i32, &Entry)> = entries
+            .iter()
+            .filter_map(|entry| Some((fuzzy_score(&self.input, &entry.label)?, entry)))
+            .collect();
+        scored.sort_by_key(|(score, _)| -score);
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
@@ -0,0 +1,320 @@
+//! The one-shot subcommands (`nodes`, `jobs`, `queue`, `stats`, `report`, `export`, `check`):
+//! aligned plain text, `--format` lines, Markdown, InfluxDB line protocol or JSON, for scripts,
+//! tickets and terminals that can't show the TUI.
+
+use super::{refresh, App, Column};
+use crate::arrays;
+use crate::influx::Point;
+use crate::models::{format_bytes, format_duration, ClusterStats, Job, Node, GB};
+use crate::placement;
+use crate::recording::Frame;
+use crate::template::{self, Template};
+use crate::users;
+use anyhow::Result;
+use serde::Serialize;
+
+/// The `jobs` table without `--format`, as template fields.
+const JOB_COLUMNS: [&str; 9] = ["id", "user", "name", "state", "cpus", "mem", "elapsed", "time_limit", "nodes"];
+
+/// The `queue` table without `--format`, in the scheduler's order.
+const QUEUE_COLUMNS: [&str; 7] = ["id", "user", "name", "cpus", "mem", "priority", "reason"];
+
+/// How many users `report` lists, biggest core count first.
+const REPORT_USERS: usize = 10;
+
+/// What `export` and `:dump` write: a frame `--from-snapshot` and `--replay` can show, and the
+/// stats over it.
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    #[serde(flatten)]
+    frame: Frame,
+    stats: &'a ClusterStats,
+}
+
+/// `used/total (percent)`, the percent left out when there is no total.
+fn usage(used: u64, total: u64, unit: &str) -> String {
+    if total == 0 {
+        return format!("{}{}/{}{}", used, unit, total, unit);
+    }
+    format!("{}{}/{}{} ({}%)", used, unit, total, unit, used * 100 / total)
+}
+
+/// Rows with each column padded to its widest value.
+fn aligned(rows: Vec<Vec<String>>) -> String {
+    let columns = rows.first().map_or(0, Vec::len);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| rows.iter().map(|row| row[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+            cells.join("  ").trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+/// A Markdown table, the first row as its header; `|` in a cell is escaped so it stays one cell.
+fn markdown_table(rows: Vec<Vec<String>>) -> String {
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let Some((header, rows)) = rows.split_first() else {
+        return String::new();
+    };
+    let mut table = line(header) + &format!("|{}\n", "---|".repeat(header.len()));
+    for row in rows {
+        table += &line(row);
+    }
+    table
+}
+
+impl App {
+    /// The summary over the node table, or `format` filled in from the stats.
+    pub fn stats_output(&self, format: Option<&Template>) -> String {
+        let stats = &self.stats;
+        if let Some(format) = format {
+            return format.render(|name| template::stats_field(stats, &self.current_partition, name)) + "\n";
+        }
+        let mut lines = vec![
+            format!("Partition: {}    Nodes: {}/{} available", self.current_partition, stats.avail_nodes, stats.total_nodes),
+            format!("CPU  {}", usage(stats.used_cores.into(), stats.total_cores.into(), "")),
+            format!("MEM  {}", usage(stats.used_memory / GB, stats.total_memory / GB, "GB")),
+        ];
+        if stats.total_gpus() > 0 {
+            lines.push(format!("GPU  {}", usage(stats.used_gpus().into(), stats.total_gpus().into(), "")));
+        }
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+
+    /// The stats and the node table, or `format` filled in for every node shown.
+    pub fn nodes_output(&self, format: Option<&Template>) -> String {
+        if let Some(format) = format {
+            return self.visible_nodes().into_iter().map(|node| format.render(|name| match name {
+                "health" => self.health_score(node).to_string(),
+                "eta" => self.etas.get(&(node.cluster.clone(), node.id.clone())).map_or("-".to_string(), placement::format_wait),
+                _ => template::node_field(node, name),
+            }) + "\n").collect();
+        }
+
+        let columns = self.visible_columns();
+        let selected_partitions = self.selected_partitions();
+        let mut table = vec![columns.iter().map(|column| column.title().to_string()).collect::<Vec<_>>()];
+        for node in self.visible_nodes() {
+            table.push(columns.iter().map(|column| self.plain_cell(*column, node, &selected_partitions)).collect());
+        }
+        format!("{}\n{}", self.stats_output(None), aligned(table))
+    }
+
+    /// The running jobs, or with `pending` the queue, of `user` if given: a table, or `format`
+    /// filled in for each.
+    pub fn jobs_output(&self, format: Option<&Template>, pending: bool, user: Option<&str>) -> String {
+        let jobs = if pending { &self.pending_jobs } else { &self.jobs };
+        let jobs = jobs.iter().filter(|job| user.is_none_or(|user| job.user == user));
+        if let Some(format) = format {
+            return jobs.map(|job| format.render(|name| template::job_field(job, name)) + "\n").collect();
+        }
+
+        let fields: &[&str] = if pending { &QUEUE_COLUMNS } else { &JOB_COLUMNS };
+        let mut table = vec![fields.iter().map(|field| field.to_uppercase()).collect::<Vec<_>>()];
+        table.extend(jobs.map(|job| fields.iter().map(|field| template::job_field(job, field)).collect()));
+        aligned(table)
+    }
+
+    /// A `nodestat_partition` point per partition shown and, with `nodes`, a `nodestat_node` point
+    /// per node shown, in InfluxDB line protocol.
+    pub fn influx_output(&self, nodes: bool) -> String {
+        let now = self.now();
+        let mut lines = String::new();
+        for partition in self.selected_partitions() {
+            let members: Vec<Node> = self.nodes.iter().filter(|node| node.partitions.contains(&partition)).cloned().collect();
+            let stats = match self.multi_partition() {
+                true => ClusterStats::of(&members),
+                false => self.stats.clone(),
+            };
+            // An array's pending tasks count one by one, as in the TUI
+            let count = |jobs: &[Job]| jobs.iter().filter(|job| job.partition == partition).map(|job| i64::from(arrays::task_count(&job.id))).sum::<i64>();
+            let mut point = Point::new("nodestat_partition")
+                .tag("partition", &partition)
+                .int("total_nodes", stats.total_nodes)
+                .int("avail_nodes", stats.avail_nodes)
+                .int("total_cores", stats.total_cores)
+                .int("used_cores", stats.used_cores)
+                .int("total_mem", stats.total_memory as i64)
+                .int("used_mem", stats.used_memory as i64)
+                .int("total_gpus", stats.total_gpus())
+                .int("used_gpus", stats.used_gpus())
+                .int("running_jobs", count(&self.jobs))
+                .int("pending_jobs", count(&self.pending_jobs));
+            if stats.power_nodes > 0 {
+                point = point.int("watts", stats.current_watts as i64);
+            }
+            lines += &point.line(now);
+        }
+        if !nodes {
+            return lines;
+        }
+
+        let selected_partitions = self.selected_partitions();
+        for node in self.visible_nodes() {
+            let partitions: Vec<&str> = node.partitions.iter().filter(|p| selected_partitions.contains(p)).map(String::as_str).collect();
+            let mut point = Point::new("nodestat_node")
+                .tag("cluster", node.cluster.as_deref().unwrap_or(""))
+                .tag("node", &node.id)
+                .tag("partition", &partitions.join(","))
+                .tag("state", &node.state_label())
+                .int("total_cores", node.total_cores)
+                .int("used_cores", node.used_cores)
+                .int("total_mem", node.total_mem as i64)
+                .int("used_mem", node.used_mem as i64)
+                .int("total_gpus", node.total_gpus())
+                .int("used_gpus", node.used_gpus())
+                .int("jobs", node.jobs.len() as i64);
+            if let Some(load) = node.cpu_load {
+                point = point.float("load", load);
+            }
+            if let Some(watts) = node.current_watts {
+                point = point.int("watts", watts);
+            }
+            lines += &point.line(now);
+        }
+        lines
+    }
+
+    /// A summary to paste into a ticket: the stats, the top users and the nodes that are down or
+    /// draining with their reasons; as aligned text, or GitHub/GitLab Markdown with `markdown`.
+    pub fn report_output(&self, markdown: bool) -> String {
+        let stats = &self.stats;
+        let pending: u32 = self.pending_jobs.iter().map(|job| arrays::task_count(&job.id)).sum();
+        let resource = |name: &str, used: u64, total: u64, show: fn(u64) -> String| {
+            let percent = (used * 100).checked_div(total).map_or("-".to_string(), |percent| format!("{}%", percent));
+            vec![name.to_string(), show(used), show(total), percent]
+        };
+        let mut resources = vec![
+            ["Resource", "Used", "Total", "Use"].map(String::from).to_vec(),
+            resource("CPU", stats.used_cores.into(), stats.total_cores.into(), |n| n.to_string()),
+            resource("Memory", stats.used_memory, stats.total_memory, format_bytes),
+        ];
+        if stats.total_gpus() > 0 {
+            resources.push(resource("GPU", stats.used_gpus().into(), stats.total_gpus().into(), |n| n.to_string()));
+        }
+
+        let mut top_users = vec![["User", "Running", "Cores", "Memory", "GPUs", "Pending", "Oldest"].map(String::from).to_vec()];
+        top_users.extend(users::aggregate(&self.jobs, &self.pending_jobs).into_iter().take(REPORT_USERS).map(|usage| {
+            vec![
+                usage.user,
+                usage.running.to_string(),
+                usage.cores.to_string(),
+                format_bytes(usage.mem),
+                usage.gpus.to_string(),
+                usage.pending.to_string(),
+                usage.oldest.as_ref().map_or("-".to_string(), format_duration),
+            ]
+        }));
+
+        let mut down = vec![["Node", "State", "Reason"].map(String::from).to_vec()];
+        down.extend(self.nodes.iter().filter(|node| !node.state.is_up() || node.has_flag("DRAIN")).map(|node| {
+            vec![node.id.clone(), node.state_label(), node.reason.clone().unwrap_or_else(|| "-".to_string())]
+        }));
+
+        let time = self.cluster_tz.format(&self.now(), "%Y-%m-%d %H:%M %Z");
+        let nodes = format!("{}/{} available", stats.avail_nodes, stats.total_nodes);
+        let jobs = format!("{} running, {} pending", self.jobs.len(), pending);
+        let down_title = format!("Down and draining nodes ({})", down.len() - 1);
+        if !markdown {
+            let section = |title: &str, table: Vec<Vec<String>>| match table.len() {
+                1 => format!("\n{}: none\n", title),
+                _ => format!("\n{}\n{}", title, aligned(table)),
+            };
+            return format!("Partition {} at {}\nNodes: {}    Jobs: {}\n\n", self.current_partition, time, nodes, jobs)
+                + &aligned(resources)
+                + &section("Top users", top_users)
+                + &section(&down_title, down);
+        }
+        let section = |title: &str, table: Vec<Vec<String>>| match table.len() {
+            1 => format!("\n### {}\n\nNone.\n", title),
+            _ => format!("\n### {}\n\n{}", title, markdown_table(table)),
+        };
+        format!("## Partition `{}` at {}\n\n**Nodes:** {} · **Jobs:** {}\n\n", self.current_partition, time, nodes, jobs)
+            + &markdown_table(resources)
+            + &section("Top users", top_users)
+            + &section(&down_title, down)
+    }
+
+    /// Everything fetched, as pretty-printed JSON; `anonymize` replaces the usernames.
+    pub fn export_json(&self, anonymize: bool) -> Result<String> {
+        let mut frame = self.frame(self.error_message.clone().filter(|_| self.nodes.is_empty()));
+        if anonymize {
+            frame.anonymize();
+        }
+        Ok(serde_json::to_string_pretty(&Snapshot { frame, stats: &self.stats })? + "\n")
+    }
+
+    /// A monitoring-plugin line (`OK - ... | perfdata`) and its exit status: 0 if the partition
+    /// has no more than `max_down` nodes down or draining and at least `min_free` nodes with
+    /// free cores, else 1.
+    pub fn check(&self, max_down: Option<u32>, min_free: Option<u32>) -> (i32, String) {
+        let down = self.nodes.iter().filter(|node| !node.state.is_up() || node.has_flag("DRAIN")).count() as u32;
+        let free = self.stats.avail_nodes;
+        let mut problems = Vec::new();
+        if let Some(max_down) = max_down.filter(|max_down| down > *max_down) {
+            problems.push(format!("{} nodes down or draining (max {})", down, max_down));
+        }
+        if let Some(min_free) = min_free.filter(|min_free| free < *min_free) {
+            problems.push(format!("{} nodes with free cores (min {})", free, min_free));
+        }
+        let perfdata = format!(
+            "nodes={} down={} free={} cores_used={} cores_total={}",
+            self.stats.total_nodes, down, free, self.stats.used_cores, self.stats.total_cores,
+        );
+        if problems.is_empty() {
+            let summary = format!("{} nodes, {} down or draining, {} with free cores", self.stats.total_nodes, down, free);
+            return (0, format!("OK - {}: {} | {}", self.current_partition, summary, perfdata));
+        }
+        (1, format!("WARNING - {}: {} | {}", self.current_partition, problems.join(", "), perfdata))
+    }
+
+    /// Fetch the queue, which the TUI only does while a view shows it.
+    pub async fn load_queue(&mut self) {
+        let pending_jobs = refresh::fetch_jobs(&*self.scheduler, &self.selected_partitions(), true).await;
+        self.set_pending_jobs(pending_jobs);
+    }
+
+    /// A node table cell without the bars, marks and colors.
+    fn plain_cell(&self, column: Column, node: &Node, selected_partitions: &[String]) -> String {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        match column {
+            Column::Node => node.id.clone(),
+            Column::Cluster => node.cluster.clone().unwrap_or_default(),
+            Column::Partition => {
+                let partitions: Vec<&str> = node.partitions.iter().filter(|p| selected_partitions.contains(p)).map(String::as_str).collect();
+                partitions.join(",")
+            },
+            Column::Cpu => format!("{}/{}", node.used_cores, node.total_cores),
+            Column::Memory => format!("{}/{}", node.used_mem_gb(), node.total_mem_gb()),
+            Column::AvailCpu => node.available_cores().to_string(),
+            Column::AvailMem => format_bytes(node.available_mem()),
+            Column::Gpu => match node.total_gpus() {
+                0 => "-".to_string(),
+                total => format!("{}/{}", node.used_gpus(), total),
+            },
+            Column::Load => or_dash(node.cpu_load.map(|load| {
+                format!("{:.1}{}", load, if node.is_overloaded() { "!" } else { "" })
+            })),
+            Column::State => node.state_label(),
+            Column::Jobs => node.jobs.len().to_string(),
+            Column::Health => self.health_score(node).to_string(),
+            Column::Eta => or_dash(self.etas.get(&(node.cluster.clone(), node.id.clone())).map(placement::format_wait)),
+            Column::Features if node.features.is_empty() => "-".to_string(),
+            Column::Features => node.features.join(","),
+            Column::Reason => or_dash(node.reason.clone()),
+            Column::WhatIf => or_dash(self.what_if.as_ref().and_then(|what_if| what_if.node_eta.get(&node.id)).map(|eta| {
+                if eta.is_zero() { "fits now".to_string() } else { format!("in {}", placement::format_wait(eta)) }
+            })),
+            Column::Fit => match self.fit.as_ref().map_or(0, |fit| fit.slots(&node.id)) {
+                0 => "-".to_string(),
+                slots => format!("{} slot{}", slots, if slots == 1 { "" } else { "s" }),
+            },
+        }
+    }
+}
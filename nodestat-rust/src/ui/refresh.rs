@@ -0,0 +1,360 @@
+//! The scheduler calls of a refresh, made by a task of their own so that drawing and keys go
+//! on while a slow `sacct` runs: the UI loop sends a [`Request`] built from what it shows and
+//! gets the [`Snapshot`] back over a channel once every call is done. Changes made from the UI
+//! (draining, cancelling a job, ...) go the same way, each followed by the refresh showing it.
+
+use crate::models::*;
+use crate::schedulers::Scheduler;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
+
+/// What a refresh asks the scheduler for, as the app stood when it started.
+pub(super) struct Request {
+    /// Counts the refreshes; a snapshot overtaken by a later one is dropped
+    pub generation: u64,
+    /// Every partition shown
+    pub partitions: Vec<String>,
+    pub user: String,
+    /// The user's jobs running at the last refresh, to tell which ended since
+    pub user_jobs: Vec<String>,
+    pub pending_jobs: bool,
+    /// Only set when the wait times are due
+    pub job_waits_since: Option<DateTime<Utc>>,
+    pub fairshare: bool,
+    pub watchlist: Vec<String>,
+    /// Other partitions the alert rules check, with whether they count pending jobs
+    pub alert_partitions: Vec<(String, bool)>,
+    /// Other partitions with availability watches
+    pub watch_partitions: Vec<String>,
+    /// Give up on each call after this long; its commands are killed with it
+    pub call_timeout: Option<Duration>,
+    /// A change to make first
+    pub change: Option<Change>,
+}
+
+/// A change to the cluster, made before the refresh that then shows it.
+pub(super) enum Change {
+    CancelJob { id: String, cluster: Option<String> },
+    ResumeNodes { node_ids: Vec<String> },
+    UpdateReason { node_ids: Vec<String>, reason: String },
+    DrainNodes { node_ids: Vec<String>, reason: String },
+    HoldJob { id: String, cluster: Option<String>, hold: bool },
+}
+
+impl Change {
+    /// Make the change; what to tell the user, either way.
+    async fn run(&self, scheduler: &dyn Scheduler, limit: Option<Duration>) -> std::result::Result<String, String> {
+        match self {
+            Change::CancelJob { id, cluster } => timed(limit, scheduler.cancel_job(id, cluster.as_deref()))
+                .await
+                .map(|()| format!("Cancelled job {}", id))
+                .map_err(|e| format!("Failed to cancel job {}: {}", id, e)),
+            Change::ResumeNodes { node_ids } => timed(limit, scheduler.resume_nodes(node_ids))
+                .await
+                .map(|()| format!("Resumed {} node(s)", node_ids.len()))
+                .map_err(|e| format!("Failed to resume nodes: {}", e)),
+            Change::UpdateReason { node_ids, reason } => timed(limit, scheduler.update_node_reason(node_ids, reason))
+                .await
+                .map(|()| format!("Updated reason on {} node(s)", node_ids.len()))
+                .map_err(|e| format!("Failed to update reason: {}", e)),
+            Change::DrainNodes { node_ids, reason } => timed(limit, scheduler.drain_nodes(node_ids, reason))
+                .await
+                .map(|()| format!("Draining {} node(s)", node_ids.len()))
+                .map_err(|e| format!("Failed to drain nodes: {}", e)),
+            Change::HoldJob { id, cluster, hold: true } => timed(limit, scheduler.hold_job(id, cluster.as_deref()))
+                .await
+                .map(|()| format!("Held job {}", id))
+                .map_err(|e| format!("Failed to hold job {}: {}", id, e)),
+            Change::HoldJob { id, cluster, hold: false } => timed(limit, scheduler.release_job(id, cluster.as_deref()))
+                .await
+                .map(|()| format!("Released job {}", id))
+                .map_err(|e| format!("Failed to release job {}: {}", id, e)),
+        }
+    }
+}
+
+/// What became of a [`Request`].
+pub(super) struct Finished {
+    pub generation: u64,
+    /// The changes made since the last refresh shown, and what to tell the user about each
+    pub changes: Vec<(Change, std::result::Result<String, String>)>,
+    /// `None` when the refresh was cancelled or overtaken
+    pub snapshot: Option<Snapshot>,
+}
+
+/// Another partition's nodes and jobs, for its alert rules.
+pub(super) struct OtherPartition {
+    pub partition: String,
+    pub nodes: Vec<Node>,
+    pub jobs: Vec<Job>,
+    pub pending: Option<usize>,
+}
+
+/// Everything a [`Request`] fetched. Only the nodes and jobs count as the refresh failing;
+/// the rest is `None` when it wasn't asked for or couldn't be had.
+pub(super) struct Snapshot {
+    pub nodes: Result<Vec<Node>>,
+    pub jobs: Result<Vec<Job>>,
+    pub user_jobs: Option<Vec<Job>>,
+    /// The user's jobs that ended since the last refresh, in their final state where known
+    pub ended_jobs: Vec<Job>,
+    pub pending_jobs: Option<Result<Vec<Job>>>,
+    pub job_waits: Option<(DateTime<Utc>, Result<Vec<JobWait>>)>,
+    pub fairshare: Option<Result<Vec<Fairshare>>>,
+    pub watchlist: Option<Vec<Job>>,
+    /// The alert rules' other partitions that could be fetched
+    pub alert_partitions: Vec<OtherPartition>,
+    pub watch_nodes: HashMap<String, Vec<Node>>,
+    pub reservations: Option<Vec<Reservation>>,
+    pub licenses: Option<Vec<License>>,
+    pub polled_at: Option<DateTime<Utc>>,
+    pub server_time: Option<DateTime<Utc>>,
+}
+
+/// Make `request`'s change, if it has one, then the scheduler calls it asks for unless
+/// `cancel` is notified first; a change is always made in full.
+pub(super) async fn run(scheduler: &dyn Scheduler, mut request: Request, cancel: &Notify) -> Finished {
+    let mut changes = Vec::new();
+    if let Some(change) = request.change.take() {
+        let result = change.run(scheduler, request.call_timeout).await;
+        changes.push((change, result));
+    }
+    // Dropped where it stands when cancelled, killing its commands
+    let snapshot = tokio::select! {
+        snapshot = fetch(scheduler, &request) => Some(snapshot),
+        _ = cancel.notified() => None,
+    };
+    Finished { generation: request.generation, changes, snapshot }
+}
+
+/// Make the scheduler calls `request` asks for.
+pub(super) async fn fetch(scheduler: &dyn Scheduler, request: &Request) -> Snapshot {
+    let limit = request.call_timeout;
+    // Each is a command or more (an `sacct` can take seconds), so they run side by side
+    let (nodes, jobs, user_jobs) = tokio::join!(
+        timed(limit, fetch_nodes(scheduler, &request.partitions)),
+        timed(limit, fetch_jobs(scheduler, &request.partitions, false)),
+        timed(limit, scheduler.get_user_jobs(&request.user)),
+    );
+    let user_jobs = user_jobs.ok();
+    let ended: Vec<String> = match user_jobs {
+        Some(ref running) => request
+            .user_jobs
+            .iter()
+            .filter(|id| !running.iter().any(|job| job.id == **id))
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    // Don't fail on error; the jobs have then just "ended"
+    let ended_jobs = match ended.is_empty() {
+        true => Vec::new(),
+        false => timed(limit, scheduler.get_jobs_by_id(&ended)).await.unwrap_or_default(),
+    };
+
+    let pending_jobs = match request.pending_jobs {
+        true => Some(timed(limit, fetch_jobs(scheduler, &request.partitions, true)).await),
+        false => None,
+    };
+    let job_waits = match request.job_waits_since {
+        Some(since) => Some((since, timed(limit, scheduler.get_job_waits(since)).await)),
+        None => None,
+    };
+    let fairshare = match request.fairshare {
+        true => Some(timed(limit, scheduler.get_fairshare(&request.user)).await),
+        false => None,
+    };
+    // Don't fail on error; the panel keeps the last known states
+    let watchlist = match request.watchlist.is_empty() {
+        true => None,
+        false => timed(limit, scheduler.get_jobs_by_id(&request.watchlist)).await.ok(),
+    };
+
+    // A partition that fails to fetch is checked again at the next refresh
+    let mut alert_partitions = Vec::new();
+    for (partition, pending) in &request.alert_partitions {
+        let Ok(nodes) = timed(limit, scheduler.get_nodes(partition)).await else {
+            continue;
+        };
+        let jobs = timed(limit, scheduler.get_jobs(partition)).await.unwrap_or_default();
+        let pending = match pending {
+            true => timed(limit, scheduler.get_pending_jobs(partition)).await.ok().map(|jobs| jobs.len()),
+            false => None,
+        };
+        alert_partitions.push(OtherPartition { partition: partition.clone(), nodes, jobs, pending });
+    }
+
+    Snapshot {
+        nodes,
+        jobs,
+        user_jobs,
+        ended_jobs,
+        pending_jobs,
+        job_waits,
+        fairshare,
+        watchlist,
+        alert_partitions,
+        watch_nodes: watch_nodes(scheduler, &request.watch_partitions, limit).await,
+        // Not every scheduler has reservations or licenses, or knows its own time
+        reservations: timed(limit, scheduler.get_reservations()).await.ok(),
+        licenses: timed(limit, scheduler.get_licenses()).await.ok(),
+        polled_at: timed(limit, scheduler.get_polled_at()).await.ok().flatten(),
+        server_time: timed(limit, scheduler.get_server_time()).await.ok().flatten(),
+    }
+}
+
+/// `call`, unless it takes longer than `limit`.
+async fn timed<T>(limit: Option<Duration>, call: impl Future<Output = Result<T>>) -> Result<T> {
+    let Some(limit) = limit else {
+        return call.await;
+    };
+    match tokio::time::timeout(limit, call).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow!("timed out after {}s", limit.as_secs())),
+    }
+}
+
+/// Nodes of every partition in `partitions`, each once even when it is in several of them.
+pub(super) async fn fetch_nodes(scheduler: &dyn Scheduler, partitions: &[String]) -> Result<Vec<Node>> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut seen: HashMap<(Option<String>, String), usize> = HashMap::new();
+    for partition in partitions {
+        for node in scheduler.get_nodes(partition).await? {
+            match seen.get(&(node.cluster.clone(), node.id.clone())) {
+                // Schedulers that only report the queried partition list each one separately
+                Some(&index) => {
+                    let existing = &mut nodes[index];
+                    for name in node.partitions {
+                        if !existing.partitions.contains(&name) {
+                            existing.partitions.push(name);
+                        }
+                    }
+                },
+                None => {
+                    seen.insert((node.cluster.clone(), node.id.clone()), nodes.len());
+                    nodes.push(node);
+                },
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+/// Running (or pending) jobs of every partition in `partitions`.
+pub(super) async fn fetch_jobs(scheduler: &dyn Scheduler, partitions: &[String], pending: bool) -> Result<Vec<Job>> {
+    let mut jobs: Vec<Job> = Vec::new();
+    let mut seen = HashSet::new();
+    for partition in partitions {
+        let partition_jobs = if pending {
+            scheduler.get_pending_jobs(partition).await?
+        } else {
+            scheduler.get_jobs(partition).await?
+        };
+        for job in partition_jobs {
+            if seen.insert((job.cluster.clone(), job.id.clone())) {
+                jobs.push(job);
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+/// The nodes of each partition in `partitions`, for the availability watches on them.
+pub(super) async fn watch_nodes(
+    scheduler: &dyn Scheduler,
+    partitions: &[String],
+    limit: Option<Duration>,
+) -> HashMap<String, Vec<Node>> {
+    let mut nodes = HashMap::new();
+    for partition in partitions {
+        // Don't fail on error; the watch waits for the next refresh
+        nodes.insert(partition.clone(), timed(limit, scheduler.get_nodes(partition)).await.unwrap_or_default());
+    }
+    nodes
+}
+
+/// The task making the refreshes, and the channels to and from it.
+pub(super) struct Refresher {
+    requests: mpsc::UnboundedSender<(Request, Arc<Notify>)>,
+    finished: mpsc::Receiver<Finished>,
+    running: Option<Running>,
+    /// Changes made by refreshes that were overtaken, to show with the one that overtook them
+    made: Vec<(Change, std::result::Result<String, String>)>,
+}
+
+/// The refresh under way.
+struct Running {
+    generation: u64,
+    started: Instant,
+    cancel: Arc<Notify>,
+}
+
+impl Refresher {
+    /// Start the task; it ends with the `Refresher`.
+    pub fn spawn(scheduler: Arc<dyn Scheduler>) -> Self {
+        let (requests, mut incoming) = mpsc::unbounded_channel::<(Request, Arc<Notify>)>();
+        let (outgoing, finished) = mpsc::channel(1);
+        tokio::spawn(async move {
+            while let Some((request, cancel)) = incoming.recv().await {
+                let finished = run(&*scheduler, request, &cancel).await;
+                if outgoing.send(finished).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Self { requests, finished, running: None, made: Vec::new() }
+    }
+
+    /// When the refresh under way started, if one is.
+    pub fn started(&self) -> Option<Instant> {
+        self.running.as_ref().map(|running| running.started)
+    }
+
+    /// Start `request`, unless a refresh is under way.
+    pub fn start(&mut self, request: Request) {
+        if self.running.is_none() {
+            self.restart(request);
+        }
+    }
+
+    /// Start `request` in place of the refresh under way, whose fetch is dropped; a change it
+    /// makes is still made.
+    pub fn restart(&mut self, request: Request) {
+        self.cancel();
+        let (generation, cancel) = (request.generation, Arc::new(Notify::new()));
+        if self.requests.send((request, cancel.clone())).is_ok() {
+            self.running = Some(Running { generation, started: Instant::now(), cancel });
+        }
+    }
+
+    /// Stop the refresh under way, if there is one.
+    pub fn cancel(&mut self) -> bool {
+        let Some(running) = self.running.take() else {
+            return false;
+        };
+        running.cancel.notify_one();
+        true
+    }
+
+    /// The refresh under way, once it's done. The snapshot of one that was cancelled or
+    /// overtaken is dropped, but not the change it made: that comes with the refresh under
+    /// way, or right away once there's none.
+    pub fn try_recv(&mut self) -> Option<Finished> {
+        while let Ok(Finished { generation, changes, snapshot }) = self.finished.try_recv() {
+            self.made.extend(changes);
+            if self.running.as_ref().is_some_and(|running| running.generation == generation) {
+                self.running = None;
+                return Some(Finished { generation, changes: std::mem::take(&mut self.made), snapshot });
+            }
+            if self.running.is_none() && !self.made.is_empty() {
+                return Some(Finished { generation, changes: std::mem::take(&mut self.made), snapshot: None });
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,1857 @@
+//! Golden-file rendering tests.
+//!
+//! Each test renders a screen into a `TestBackend` and compares the text content with
+//! `tests/golden/<name>.txt`. Run with `UPDATE_GOLDEN=1 cargo test` to regenerate the
+//! files after an intentional layout change, and review the diff before committing.
+
+use super::*;
+use crate::clock::ClusterTz;
+use crate::template::{self, Template};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::backend::TestBackend;
+use ratatui::style::Color;
+use std::path::PathBuf;
+use std::sync::Once;
+
+const WIDTH: u16 = 140;
+const HEIGHT: u16 = 40;
+
+fn frozen_now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+}
+
+/// Deterministic scheduler returning a fixed snapshot.
+struct FixtureScheduler {
+    fail: bool,
+}
+
+fn node(id: &str, state: NodeState, cores: (u32, u32), mem_mb: (u32, u32)) -> Node {
+    Node {
+        id: id.to_string(),
+        state,
+        total_cores: cores.1,
+        used_cores: cores.0,
+        total_mem: mem_mb.1 as u64 * MB,
+        used_mem: mem_mb.0 as u64 * MB,
+        partitions: vec!["batch".to_string()],
+        jobs: Vec::new(),
+        job_users: Vec::new(),
+        reason: None,
+        cpu_load: Some(cores.0 as f64),
+        current_watts: Some(200 + cores.0 * 5),
+        consumed_joules: Some(500 * 3_600_000),
+        os: Some("Linux 5.14.0-362.el9.x86_64".to_string()),
+        gpus: Vec::new(),
+        features: vec!["avx512".to_string()],
+        state_flags: Vec::new(),
+        cluster: None,
+    }
+}
+
+fn job(id: &str, user: &str, node_id: &str, cpus: u32, remaining_hours: i64) -> Job {
+    Job {
+        id: id.to_string(),
+        user: user.to_string(),
+        name: format!("run_{}", id),
+        state: JobState::Running,
+        node_list: vec![node_id.to_string()],
+        partition: "batch".to_string(),
+        req_nodes: 1,
+        req_cpus: cpus,
+        req_mem: 64 * GB,
+        req_gpus: 0,
+        gpu_type: None,
+        reason: None,
+        priority: None,
+        est_start: None,
+        time_limit: chrono::Duration::hours(24),
+        elapsed: chrono::Duration::hours(24 - remaining_hours),
+        cpu_time: chrono::Duration::hours(cpus as i64),
+        submit_time: frozen_now() - chrono::Duration::hours(24),
+        work_dir: Some(format!("/home/{}/{}", user, id)),
+        cluster: None,
+    }
+}
+
+#[async_trait]
+impl Scheduler for FixtureScheduler {
+    async fn get_nodes(&self, partition: &str) -> anyhow::Result<Vec<Node>> {
+        if self.fail {
+            return Err(anyhow!("scontrol command failed: slurm_load_node error: Unable to contact slurm controller"));
+        }
+
+        let mut drained = node("c005", NodeState::Drained, (0, 64), (0, 256000));
+        drained.reason = Some("NHC: check_fs_mount /scratch".to_string());
+        let mut down = node("c006", NodeState::Down, (0, 64), (0, 256000));
+        // Unreachable: no power reading
+        down.current_watts = None;
+        down.consumed_joules = None;
+        down.reason = Some("Not responding".to_string());
+        let mut draining = node("c003", NodeState::Allocated, (64, 64), (200000, 256000));
+        draining.state_flags = vec!["DRAIN".to_string()];
+        draining.reason = Some("kernel update".to_string());
+        let mut overloaded = node("c004", NodeState::Running, (8, 64), (240000, 256000));
+        overloaded.cpu_load = Some(70.0);
+        let mut gpu = node("g001", NodeState::Running, (16, 64), (64000, 512000));
+        gpu.gpus = vec![
+            GpuGres { model: "a100".to_string(), total: 2, used: 1 },
+            GpuGres { model: "a100_3g.20gb".to_string(), total: 4, used: 3 },
+        ];
+        gpu.features.push("a100".to_string());
+        gpu.partitions.push("gpu".to_string());
+
+        let nodes = vec![
+            node("c001", NodeState::Idle, (0, 64), (4000, 256000)),
+            node("c002", NodeState::Mixed, (32, 64), (128000, 256000)),
+            draining,
+            overloaded,
+            gpu,
+            drained,
+            down,
+        ];
+        Ok(nodes.into_iter().filter(|node| node.partitions.iter().any(|p| p == partition)).collect())
+    }
+
+    async fn get_jobs(&self, _partition: &str) -> anyhow::Result<Vec<Job>> {
+        Ok(vec![
+            job("1001", "alice", "c002", 32, 3),
+            job("1002", "bob", "c003", 48, 1),
+            job("1003", "tester", "c003", 16, 6),
+            job("1004", "carol", "c004", 8, 10),
+            Job { name: "sweep".to_string(), ..job("1014_1", "dave", "c002", 8, 12) },
+            Job { name: "sweep".to_string(), ..job("1014_2", "dave", "c002", 8, 12) },
+        ])
+    }
+
+    async fn get_user_jobs(&self, _user: &str) -> anyhow::Result<Vec<Job>> {
+        Ok(vec![job("1003", "tester", "c003", 16, 6)])
+    }
+
+    async fn get_jobs_by_id(&self, job_ids: &[String]) -> anyhow::Result<Vec<Job>> {
+        // 1005 ran out of time; other finished jobs have aged out of accounting
+        let timed_out = Job { state: JobState::Timeout, ..job("1005", "tester", "c001", 4, 0) };
+        Ok(job_ids.iter().filter(|id| *id == "1005").map(|_| timed_out.clone()).collect())
+    }
+
+    async fn cancel_job(&self, job_id: &str, _cluster: Option<&str>) -> anyhow::Result<()> {
+        match job_id {
+            "1003" => Ok(()),
+            _ => Err(anyhow!("Invalid job id specified")),
+        }
+    }
+
+    async fn hold_job(&self, job_id: &str, _cluster: Option<&str>) -> anyhow::Result<()> {
+        match job_id {
+            "1011" => Ok(()),
+            _ => Err(anyhow!("Access/permission denied")),
+        }
+    }
+
+    async fn release_job(&self, job_id: &str, cluster: Option<&str>) -> anyhow::Result<()> {
+        self.hold_job(job_id, cluster).await
+    }
+
+    async fn drain_nodes(&self, _node_ids: &[String], _reason: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn resume_nodes(&self, _node_ids: &[String]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn get_pending_jobs(&self, _partition: &str) -> anyhow::Result<Vec<Job>> {
+        let pending = |id: &str, user: &str, cpus: u32, waiting_hours: i64, fairshare: u64, reason: &str| {
+            let mut job = job(id, user, "", cpus, 0);
+            job.state = JobState::Pending;
+            job.node_list = Vec::new();
+            job.elapsed = chrono::Duration::zero();
+            job.submit_time = frozen_now() - chrono::Duration::hours(waiting_hours);
+            job.reason = Some(reason.to_string());
+            let age = waiting_hours as u64 * 100;
+            job.priority = Some(JobPriority {
+                total: age + fairshare + 2000,
+                age,
+                fairshare,
+                partition: 2000,
+                ..JobPriority::default()
+            });
+            job
+        };
+        let mut gpu_job = pending("1012", "carol", 16, 1, 4000, "QOSMaxJobsPerUserLimit");
+        gpu_job.req_gpus = 2;
+        gpu_job.gpu_type = Some("a100".to_string());
+
+        let mut array_rest = pending("1014_[3-10%2]", "dave", 8, 3, 5000, "JobArrayTaskLimit");
+        array_rest.name = "sweep".to_string();
+        let mut next = pending("1010", "bob", 128, 20, 5000, "Resources");
+        next.est_start = Some(frozen_now() + chrono::Duration::minutes(95));
+
+        Ok(vec![
+            next,
+            pending("1011", "tester", 64, 5, 6000, "Priority"),
+            array_rest,
+            gpu_job,
+            pending("1013", "alice", 8, 2, 1000, "Dependency"),
+        ])
+    }
+
+    async fn list_partitions(&self) -> anyhow::Result<Vec<String>> {
+        Ok(vec!["batch".to_string(), "gpu".to_string()])
+    }
+
+    async fn get_partitions(&self) -> anyhow::Result<Vec<Partition>> {
+        let partition = |name: &str, total_nodes| Partition {
+            name: name.to_string(),
+            state: "UP".to_string(),
+            is_default: name == "batch",
+            max_time: Some(chrono::Duration::days(3)),
+            default_mem_per_cpu: Some(4 * GB),
+            max_mem_per_cpu: Some(16 * GB),
+            total_nodes,
+            allow_accounts: Vec::new(),
+            deny_accounts: Vec::new(),
+            cluster: None,
+        };
+        Ok(vec![partition("batch", 7), partition("gpu", 1)])
+    }
+
+    async fn get_licenses(&self) -> anyhow::Result<Vec<License>> {
+        let license = |name: &str, total, used, reserved| License {
+            name: name.to_string(),
+            total,
+            used,
+            reserved,
+            cluster: None,
+        };
+        Ok(vec![license("matlab", 10, 10, 0), license("ansys", 20, 6, 4)])
+    }
+
+    async fn get_fairshare(&self, user: &str) -> anyhow::Result<Vec<Fairshare>> {
+        let entry = |account: &str, user: Option<&str>, norm_shares, raw_usage, effective_usage, fairshare| Fairshare {
+            account: account.to_string(),
+            user: user.map(str::to_string),
+            norm_shares,
+            raw_usage,
+            effective_usage,
+            fairshare,
+            cluster: None,
+        };
+        Ok(vec![
+            entry("physics", None, 0.25, 36_000_000, 0.4, None),
+            entry("physics", Some(user), 0.1, 18_000_000, 0.5, Some(0.21)),
+            entry("chem", Some(user), 0.5, 360_000, 0.05, Some(0.87)),
+        ])
+    }
+
+    async fn get_topology(&self) -> anyhow::Result<HashMap<String, String>> {
+        // g001 is left out to show the catch-all group
+        Ok(["c001", "c002", "c003", "c004", "c005", "c006"]
+            .into_iter()
+            .map(|id| (id.to_string(), if id < "c005" { "r01" } else { "r02" }.to_string()))
+            .collect())
+    }
+
+    async fn get_reservations(&self) -> anyhow::Result<Vec<Reservation>> {
+        Ok(vec![
+            Reservation {
+                name: "maint_fs".to_string(),
+                start_time: frozen_now() + chrono::Duration::hours(2),
+                end_time: frozen_now() + chrono::Duration::hours(8),
+                nodes: vec!["c001".to_string(), "c002".to_string()],
+                users: vec!["root".to_string()],
+                flags: vec!["MAINT".to_string()],
+                cluster: None,
+            },
+            Reservation {
+                name: "ml_lab".to_string(),
+                start_time: frozen_now() - chrono::Duration::hours(1),
+                end_time: frozen_now() + chrono::Duration::days(3),
+                nodes: vec!["g001".to_string()],
+                users: vec!["carol".to_string()],
+                flags: Vec::new(),
+                cluster: None,
+            },
+        ])
+    }
+}
+
+async fn fixture_app(fail: bool, admin: bool) -> App {
+    // Keep the watchlist (and any other persisted state) away from the real config dir
+    static ISOLATE: Once = Once::new();
+    ISOLATE.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("nodestat-golden-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", dir);
+    });
+
+    let scheduler = Box::new(FixtureScheduler { fail });
+    let mut app = App::new(scheduler, Some("batch".to_string()), admin, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    app.frozen_time = Some(frozen_now());
+    app.current_user = "tester".to_string();
+    // Fetch again so time-dependent data (reservations) is judged against the frozen clock
+    app.fetch_data().await;
+    app
+}
+
+async fn press(app: &mut App, code: KeyCode) {
+    app.handle_key(KeyEvent::new(code, KeyModifiers::NONE)).await;
+}
+
+/// Nodes, Jobs, then Queue.
+async fn open_queue(app: &mut App) {
+    press(app, KeyCode::Tab).await;
+    press(app, KeyCode::Tab).await;
+}
+
+async fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        press(app, KeyCode::Char(c)).await;
+    }
+}
+
+fn render(app: &mut App) -> String {
+    render_sized(app, WIDTH, HEIGHT)
+}
+
+fn render_sized(app: &mut App, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| app.ui(f)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content
+        .chunks(buffer.area.width as usize)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.txt", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing golden file {} (run with UPDATE_GOLDEN=1)", path.display()));
+
+    if expected != actual {
+        let diff: Vec<String> = expected
+            .lines()
+            .zip(actual.lines())
+            .enumerate()
+            .filter(|(_, (e, a))| e != a)
+            .map(|(i, (e, a))| format!("line {}:\n  expected: {}\n  actual:   {}", i + 1, e, a))
+            .collect();
+        panic!("Rendering of '{}' differs from golden file:\n{}", name, diff.join("\n"));
+    }
+}
+
+#[tokio::test]
+async fn node_table() {
+    let mut app = fixture_app(false, false).await;
+    assert_golden("node_table", &render(&mut app));
+}
+
+#[tokio::test]
+async fn node_table_sorted_by_health() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('s')).await;
+    assert_golden("node_table_health", &render(&mut app));
+}
+
+#[tokio::test]
+async fn selection_survives_resort_and_refresh() {
+    let mut app = fixture_app(false, false).await;
+    let drained = app.nodes.iter().position(|n| n.id == "c005").unwrap();
+    app.table_state.select(Some(drained));
+    *app.table_state.offset_mut() = drained - 2;
+
+    // The row stays as far below the top as it was, unless it moved closer to the top than that
+    let mut screen_row = 2;
+    for key in [KeyCode::Char('s'), KeyCode::Char('s'), KeyCode::Char('r')] {
+        press(&mut app, key).await;
+        assert_eq!(app.selected_node().map(|n| n.id.as_str()), Some("c005"));
+        let index = app.table_state.selected().unwrap();
+        screen_row = screen_row.min(index);
+        assert_eq!(app.table_state.offset(), index - screen_row);
+    }
+    assert_eq!(app.sort_key, SortKey::Name);
+}
+
+#[tokio::test]
+async fn background_refresh() {
+    let mut app = fixture_app(false, false).await;
+    app.refresher = Some(Refresher::spawn(app.scheduler.clone()));
+    app.nodes.clear();
+    press(&mut app, KeyCode::Char('r')).await;
+    // The old data stays up until the task is done
+    assert!(app.nodes.is_empty());
+    assert!(render(&mut app).contains("Refreshing… (Esc: cancel)  Partition: batch"));
+    // One refresh at a time
+    let generation = app.refresh_generation;
+    press(&mut app, KeyCode::Char('r')).await;
+    assert_eq!(app.refresh_generation, generation);
+
+    while app.refresher.as_ref().and_then(Refresher::started).is_some() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        app.receive_refresh().await;
+    }
+    assert_eq!(app.nodes.len(), 7);
+    assert!(!render(&mut app).contains("Refreshing…"));
+
+    // A refresh overtaken by one made in the meantime is dropped
+    press(&mut app, KeyCode::Char('r')).await;
+    app.fetch_data().await;
+    app.nodes.clear();
+    while app.refresher.as_ref().and_then(Refresher::started).is_some() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        app.receive_refresh().await;
+    }
+    assert!(app.nodes.is_empty());
+}
+
+/// Takes 4s over each of the nodes, jobs and your jobs, and never lists the `stuck` partition's nodes.
+struct SlowScheduler;
+
+#[async_trait]
+impl Scheduler for SlowScheduler {
+    async fn get_nodes(&self, partition: &str) -> anyhow::Result<Vec<Node>> {
+        if partition == "stuck" {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        Ok(vec![node("c001", NodeState::Idle, (0, 64), (4000, 256000))])
+    }
+
+    async fn get_jobs(&self, _partition: &str) -> anyhow::Result<Vec<Job>> {
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        Ok(Vec::new())
+    }
+
+    async fn get_user_jobs(&self, _user: &str) -> anyhow::Result<Vec<Job>> {
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        Ok(Vec::new())
+    }
+
+    async fn list_partitions(&self) -> anyhow::Result<Vec<String>> {
+        Ok(vec!["batch".to_string(), "stuck".to_string()])
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn concurrent_fetch() {
+    let started = tokio::time::Instant::now();
+    let mut app = App::new(Box::new(SlowScheduler), Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    // Side by side, not 12s one after the other
+    assert_eq!(started.elapsed(), Duration::from_secs(4));
+    assert_eq!(app.nodes.len(), 1);
+
+    app.current_partition = "stuck".to_string();
+    app.fetch_data().await;
+    assert_eq!(app.error_message.as_deref(), Some("Failed to get nodes: timed out after 60s"));
+    assert_eq!(app.nodes.len(), 1);
+
+    // As long as the slowest command may take
+    let timeouts: Timeouts = toml::from_str("default = 30\nsacct = 90").unwrap();
+    app.call_timeout = timeouts.longest();
+    app.fetch_data().await;
+    assert_eq!(app.error_message.as_deref(), Some("Failed to get nodes: timed out after 90s"));
+}
+
+/// The fixture, with a license server that never answers.
+struct HungLicenses;
+
+#[async_trait]
+impl Scheduler for HungLicenses {
+    async fn get_nodes(&self, partition: &str) -> anyhow::Result<Vec<Node>> {
+        FixtureScheduler { fail: false }.get_nodes(partition).await
+    }
+
+    async fn get_jobs(&self, partition: &str) -> anyhow::Result<Vec<Job>> {
+        FixtureScheduler { fail: false }.get_jobs(partition).await
+    }
+
+    async fn get_user_jobs(&self, user: &str) -> anyhow::Result<Vec<Job>> {
+        FixtureScheduler { fail: false }.get_user_jobs(user).await
+    }
+
+    async fn list_partitions(&self) -> anyhow::Result<Vec<String>> {
+        FixtureScheduler { fail: false }.list_partitions().await
+    }
+
+    async fn get_licenses(&self) -> anyhow::Result<Vec<License>> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn every_call_timed() {
+    let started = tokio::time::Instant::now();
+    let app = App::new(Box::new(HungLicenses), Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    // The rest of the refresh is shown once the licenses are given up on
+    assert_eq!(started.elapsed(), Duration::from_secs(60));
+    assert_eq!(app.nodes.len(), 7);
+    assert!(app.licenses.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn cancel_refresh() {
+    let mut app = App::new(Box::new(SlowScheduler), Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    app.refresher = Some(Refresher::spawn(app.scheduler.clone()));
+    app.nodes.clear();
+    app.show_node_detail = true;
+    press(&mut app, KeyCode::Char('r')).await;
+    press(&mut app, KeyCode::Esc).await;
+    assert_eq!(app.status_message.as_deref(), Some("Refresh cancelled"));
+    assert!(app.refresher.as_ref().and_then(Refresher::started).is_none());
+    // Only the next Esc closes the node's details
+    assert!(app.show_node_detail);
+    press(&mut app, KeyCode::Esc).await;
+    assert!(!app.show_node_detail);
+    assert!(!app.fetch_health.is_due(app.refresh_interval));
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    app.receive_refresh().await;
+    assert!(app.nodes.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn changes_in_background() {
+    let mut app = App::new(Box::new(SlowScheduler), Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    app.refresher = Some(Refresher::spawn(app.scheduler.clone()));
+    let started = tokio::time::Instant::now();
+    app.current_partition = "stuck".to_string();
+    app.reload(None).await;
+    app.run_confirmed(Confirm::ResumeNodes { node_ids: vec!["c001".to_string()] }).await;
+    assert_eq!(started.elapsed(), Duration::ZERO);
+    assert!(app.refresher.as_ref().and_then(Refresher::started).is_some());
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    app.receive_refresh().await;
+    assert!(app.error_message.is_none());
+
+    // Overtaken, its change shows with the refresh that overtook it
+    app.current_partition = "batch".to_string();
+    app.reload(None).await;
+    while app.refresher.as_ref().and_then(Refresher::started).is_some() {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        app.receive_refresh().await;
+    }
+    assert_eq!(app.current_partition, "batch");
+    assert_eq!(app.nodes.len(), 1);
+    assert_eq!(app.error_message.as_deref(), Some("Failed to resume nodes: Resuming nodes is not supported by this scheduler"));
+
+    // Or on its own once cancelled
+    app.current_partition = "stuck".to_string();
+    app.reload(None).await;
+    app.run_confirmed(Confirm::ResumeNodes { node_ids: vec!["c001".to_string()] }).await;
+    app.refresher.as_mut().unwrap().cancel();
+    app.error_message = None;
+    while app.error_message.is_none() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        app.receive_refresh().await;
+    }
+    assert_eq!(app.error_message.as_deref(), Some("Failed to resume nodes: Resuming nodes is not supported by this scheduler"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn cached_answer_age() {
+    let rate_limit: crate::config::RateLimit = toml::from_str("min_interval = 5").unwrap();
+    let fixture = Box::new(FixtureScheduler { fail: false });
+    let scheduler = crate::schedulers::RateLimitedScheduler::new(fixture, Duration::from_secs(rate_limit.min_interval));
+    let mut app = App::new(Box::new(scheduler), Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    assert!(app.polled_at.is_none());
+
+    press(&mut app, KeyCode::Char('r')).await;
+    assert_eq!(app.nodes.len(), 7);
+    let polled_at = app.polled_at.expect("served from the cache");
+    app.frozen_time = Some(polled_at + chrono::Duration::seconds(3));
+    assert!(render(&mut app).contains("Cached: 3s old    Cluster time: "));
+}
+
+#[tokio::test]
+async fn changes_since_last_refresh() {
+    let mut app = fixture_app(false, false).await;
+    let mut nodes = app.nodes.clone();
+    let c002 = nodes.iter_mut().find(|n| n.id == "c002").unwrap();
+    c002.state = NodeState::Idle;
+    c002.used_cores = 0;
+    c002.used_mem = 4000 * MB;
+    let c006 = nodes.iter_mut().find(|n| n.id == "c006").unwrap();
+    c006.reason = None;
+    app.changes.record(&nodes, frozen_now());
+    app.nodes = nodes;
+
+    let c002 = app.nodes.iter().find(|n| n.id == "c002").unwrap();
+    assert!(app.changes.is_changed(c002, changes::Field::State));
+    assert!(app.changes.is_changed(c002, changes::Field::Cores));
+    assert!(!app.changes.is_changed(c002, changes::Field::Reason));
+
+    press(&mut app, KeyCode::Char('a')).await;
+    assert_golden("changes", &render(&mut app));
+}
+
+#[tokio::test]
+async fn sort_by_header_click() {
+    let mut app = fixture_app(false, false).await;
+    render(&mut app);
+    let (row, ref headers) = app.sort_headers;
+    let &(column, _, _) = headers.iter().find(|(_, _, key)| *key == SortKey::AvailMem).unwrap();
+    app.handle_mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: column + 1,
+        row,
+        modifiers: KeyModifiers::NONE,
+    });
+    // Most free memory first; S flips it
+    assert_eq!(app.nodes[0].id, "g001");
+    press(&mut app, KeyCode::Char('S')).await;
+    assert_golden("node_table_avail_mem", &render(&mut app));
+}
+
+#[tokio::test]
+async fn partition_picker() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('p')).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.current_partition, "gpu");
+
+    // The partition just used comes first next time
+    press(&mut app, KeyCode::Char('p')).await;
+    assert_golden("partition_picker", &render(&mut app));
+}
+
+#[tokio::test]
+async fn all_partitions() {
+    let mut app = fixture_app(false, false).await;
+    app.current_partition = "all".to_string();
+    app.fetch_data().await;
+    // g001 is in both partitions but listed once; the fixture's jobs come back for each
+    assert_eq!(app.nodes.len(), 7);
+    assert_eq!(app.jobs.len(), 6);
+    assert_golden("all_partitions", &render(&mut app));
+}
+
+#[tokio::test]
+async fn error_state() {
+    let mut app = fixture_app(true, false).await;
+    assert_golden("error_state", &render(&mut app));
+}
+
+#[tokio::test]
+async fn reason_prompt_popup() {
+    let mut app = fixture_app(false, true).await;
+    let drained = app.nodes.iter().position(|n| n.id == "c005").unwrap();
+    app.table_state.select(Some(drained));
+    press(&mut app, KeyCode::Char('e')).await;
+    assert_golden("reason_prompt", &render(&mut app));
+}
+
+#[tokio::test]
+async fn drain_and_resume_nodes() {
+    let mut app = fixture_app(false, true).await;
+    let select = |app: &mut App, id: &str| {
+        let row = app.nodes.iter().position(|n| n.id == id).unwrap();
+        app.table_state.select(Some(row));
+    };
+
+    select(&mut app, "c001");
+    press(&mut app, KeyCode::Char('D')).await;
+    assert_golden("drain_prompt", &render(&mut app));
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.status_message.as_deref(), Some("Not drained: a reason is required"));
+
+    press(&mut app, KeyCode::Char('D')).await;
+    type_text(&mut app, "bad DIMM").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.status_message.as_deref(), Some("Draining 1 node(s)"));
+
+    // Only drained or down nodes can be resumed, and only after confirming
+    press(&mut app, KeyCode::Char('U')).await;
+    assert_eq!(app.status_message.as_deref(), Some("No drained/down nodes selected"));
+    select(&mut app, "c005");
+    press(&mut app, KeyCode::Char('U')).await;
+    assert!(app.confirm.is_some());
+    press(&mut app, KeyCode::Char('y')).await;
+    assert_eq!(app.status_message.as_deref(), Some("Resumed 1 node(s)"));
+}
+
+#[tokio::test]
+async fn what_if_column() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('i')).await;
+    type_text(&mut app, "48c 64g").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_golden("what_if", &render(&mut app));
+}
+
+#[tokio::test]
+async fn what_if_gpus_and_time_limit() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('i')).await;
+    type_text(&mut app, "1c 2gpu 4h").await;
+    press(&mut app, KeyCode::Enter).await;
+    let what_if = app.what_if.as_ref().unwrap();
+    assert_eq!(what_if.node_eta.keys().collect::<Vec<_>>(), ["g001"]);
+    assert_eq!(app.status_message.as_deref(), Some("What-if 1n 1c 0g 2gpu 4h: fits now (1 node(s) available)"));
+
+    // More GPUs than are free wait for the jobs holding them
+    app.jobs.push(Job { req_gpus: 4, ..job("1020", "erin", "g001", 16, 5) });
+    let request = ResourceRequest::parse("1c 3gpu 4h").unwrap();
+    let what_if = placement::simulate(&request, &app.nodes, &app.jobs, &app.partition_limits);
+    assert_eq!(what_if.start_in, Some(chrono::Duration::hours(5)));
+
+    // More than the node has, or longer than the partitions allow, never fits
+    for input in ["1c 7gpu", "1c 1gpu 4d"] {
+        let request = ResourceRequest::parse(input).unwrap();
+        let what_if = placement::simulate(&request, &app.nodes, &app.jobs, &app.partition_limits);
+        assert!(what_if.summary().ends_with("no node configuration fits this request"), "{}", input);
+    }
+}
+
+#[tokio::test]
+async fn fit_finder() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('c')).await;
+    type_text(&mut app, "8c 32g").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_golden("fit", &render(&mut app));
+
+    // C keeps only the nodes with room
+    press(&mut app, KeyCode::Char('C')).await;
+    let fit = app.fit.as_ref().unwrap();
+    assert_eq!(app.visible_nodes().len(), fit.node_slots.len());
+    assert!(app.visible_nodes().iter().all(|node| fit.slots(&node.id) > 0));
+    assert_golden("fit_only", &render(&mut app));
+
+    // GPUs count too, and Esc clears it all
+    press(&mut app, KeyCode::Char('c')).await;
+    app.handle_key(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL)).await;
+    type_text(&mut app, "1c 1gpu").await;
+    press(&mut app, KeyCode::Enter).await;
+    let fit = app.fit.as_ref().unwrap();
+    assert!(fit.node_slots.keys().all(|id| app.nodes.iter().any(|node| node.id == *id && node.available_gpus() > 0)));
+    press(&mut app, KeyCode::Esc).await;
+    assert!(app.fit.is_none() && !app.fit_only);
+}
+
+#[tokio::test]
+async fn feature_filter() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('f')).await;
+    type_text(&mut app, "A100").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_golden("feature_filter", &render(&mut app));
+}
+
+#[tokio::test]
+async fn reservations_panel() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('R')).await;
+    assert_golden("reservations", &render(&mut app));
+}
+
+#[tokio::test]
+async fn licenses_panel() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('L')).await;
+    assert_golden("licenses", &render(&mut app));
+}
+
+#[tokio::test]
+async fn fairshare_panel() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('F')).await;
+    // Pretend the last refresh saw different factors, so both trends show
+    app.fairshare[1].fairshare = Some(0.25);
+    app.fairshare[2].fairshare = Some(0.8);
+    app.fetch_data().await;
+    assert_golden("fairshare", &render(&mut app));
+}
+
+#[tokio::test]
+async fn rack_grouping() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('g')).await;
+    press(&mut app, KeyCode::Down).await;
+    press(&mut app, KeyCode::Char('x')).await;
+    assert_golden("rack_grouping", &render(&mut app));
+}
+
+#[tokio::test]
+async fn prefix_grouping() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('g')).await;
+    press(&mut app, KeyCode::Char('g')).await;
+    assert_eq!(app.status_message.as_deref(), Some("Grouped by name prefix (^([A-Za-z]+))"));
+    // The c nodes collapse into one header; g001 gets its own
+    press(&mut app, KeyCode::Down).await;
+    press(&mut app, KeyCode::Down).await;
+    press(&mut app, KeyCode::Char('x')).await;
+    assert_golden("prefix_grouping", &render(&mut app));
+
+    press(&mut app, KeyCode::Char('g')).await;
+    assert_eq!(app.row_count(), 7);
+}
+
+#[tokio::test]
+async fn jobs_view() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Tab).await;
+    assert_golden("jobs", &render(&mut app));
+}
+
+#[tokio::test]
+async fn job_detail_popup() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Tab).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_golden("job_detail", &render(&mut app));
+}
+
+#[tokio::test]
+async fn users_view() {
+    let mut app = fixture_app(false, false).await;
+    app.handle_key(KeyEvent::new(KeyCode::Char('4'), KeyModifiers::ALT)).await;
+    assert_golden("users", &render(&mut app));
+}
+
+#[tokio::test]
+async fn timeline_view() {
+    let mut app = fixture_app(false, false).await;
+    app.handle_key(KeyEvent::new(KeyCode::Char('6'), KeyModifiers::ALT)).await;
+    assert_golden("timeline", &render(&mut app));
+
+    // c004 leads the table, then c002 whose rows end with the array tasks; Enter opens a job
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    assert_eq!(app.selected_job().map(|job| job.id.as_str()), Some("1004"));
+    press(&mut app, KeyCode::Enter).await;
+    assert!(app.show_job_detail);
+}
+
+#[tokio::test]
+async fn users_sorted_by_oldest_job() {
+    let mut app = fixture_app(false, false).await;
+    app.handle_key(KeyEvent::new(KeyCode::Char('4'), KeyModifiers::ALT)).await;
+    render(&mut app);
+    let (row, ref headers) = app.user_sort_headers;
+    let &(column, _, _) = headers.iter().find(|(_, _, key)| *key == UserSort::Oldest).unwrap();
+    app.handle_mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: column + 1,
+        row,
+        modifiers: KeyModifiers::NONE,
+    });
+    assert_golden("users_oldest", &render(&mut app));
+
+    // S flips it, s moves on to the next column
+    press(&mut app, KeyCode::Char('S')).await;
+    assert!(!app.user_sort_descending);
+    press(&mut app, KeyCode::Char('s')).await;
+    assert_eq!(app.user_sort, UserSort::User);
+}
+
+#[tokio::test]
+async fn my_jobs_efficiency() {
+    let mut app = fixture_app(false, false).await;
+    // The last tab: 16 CPU-hours over 18h on 16 CPUs is 5%, flagged
+    app.handle_key(KeyEvent::new(KeyCode::Char('5'), KeyModifiers::ALT)).await;
+    assert_golden("my_jobs", &render(&mut app));
+
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert!(app.show_job_detail);
+    assert_eq!(app.selected_job().map(|job| job.id.as_str()), Some("1003"));
+}
+
+#[tokio::test]
+async fn cancel_own_job() {
+    let mut app = fixture_app(false, false).await;
+    app.handle_key(KeyEvent::new(KeyCode::Char('5'), KeyModifiers::ALT)).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('d')).await;
+    assert_golden("cancel_confirm", &render(&mut app));
+
+    // Anything but y leaves the job alone
+    press(&mut app, KeyCode::Char('j')).await;
+    assert!(app.confirm.is_some());
+    press(&mut app, KeyCode::Esc).await;
+    assert!(app.confirm.is_none() && app.status_message.is_none());
+
+    press(&mut app, KeyCode::Char('d')).await;
+    press(&mut app, KeyCode::Char('y')).await;
+    assert!(app.confirm.is_none());
+    assert_eq!(app.status_message.as_deref(), Some("Cancelled job 1003"));
+}
+
+#[tokio::test]
+async fn hold_and_release_own_job() {
+    let mut app = fixture_app(false, false).await;
+    open_queue(&mut app).await;
+
+    // bob's job is left alone
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('H')).await;
+    assert!(app.error_message.as_deref().is_some_and(|e| e.contains("belongs to bob")));
+
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('H')).await;
+    assert_eq!(app.status_message.as_deref(), Some("Held job 1011"));
+    press(&mut app, KeyCode::Char('U')).await;
+    assert_eq!(app.status_message.as_deref(), Some("Released job 1011"));
+
+    assert_eq!(arrays::array_id("1014_[3-10%2]").as_deref(), Some("1014"));
+    assert_eq!(arrays::array_id("77[4].pbs01").as_deref(), Some("77[].pbs01"));
+    assert_eq!(arrays::array_id("1011"), None);
+}
+
+#[tokio::test]
+async fn queue_view() {
+    let mut app = fixture_app(false, false).await;
+    open_queue(&mut app).await;
+    assert_golden("queue", &render(&mut app));
+}
+
+#[tokio::test]
+async fn queue_array_expanded() {
+    let mut app = fixture_app(false, false).await;
+    open_queue(&mut app).await;
+    for _ in 0..3 {
+        press(&mut app, KeyCode::Char('j')).await;
+    }
+    press(&mut app, KeyCode::Char('x')).await;
+    assert_golden("queue_array", &render(&mut app));
+}
+
+#[tokio::test]
+async fn pending_job_detail_popup() {
+    let mut app = fixture_app(false, false).await;
+    open_queue(&mut app).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_golden("pending_job_detail", &render(&mut app));
+}
+
+#[tokio::test]
+async fn node_detail_popup() {
+    let mut app = fixture_app(false, false).await;
+    let gpu_node = app.nodes.iter().position(|n| n.id == "g001").unwrap();
+    app.table_state.select(Some(gpu_node));
+    press(&mut app, KeyCode::Enter).await;
+    assert_golden("node_detail", &render(&mut app));
+}
+
+#[tokio::test]
+async fn search_narrows_nodes() {
+    let mut app = fixture_app(false, false).await;
+    // A regex on the node name, or any node running one of bob's jobs
+    press(&mut app, KeyCode::Char('/')).await;
+    type_text(&mut app, "c00[56]|BOB").await;
+    assert_golden("search_typing", &render(&mut app));
+
+    press(&mut app, KeyCode::Enter).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    assert_golden("search_applied", &render(&mut app));
+}
+
+#[tokio::test]
+async fn ssh_into_selected_node() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('o')).await;
+    assert_eq!(app.shell_command, None);
+
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('o')).await;
+    assert_eq!(app.shell_command.as_deref(), Some("ssh c001"));
+
+    app.shell_command = None;
+    app.set_ssh_command("ssh -t login01 ssh {node}".to_string());
+    press(&mut app, KeyCode::Char('o')).await;
+    assert_eq!(app.shell_command.as_deref(), Some("ssh -t login01 ssh c001"));
+    assert_eq!(shell_quote("node 1"), "'node 1'");
+}
+
+#[tokio::test]
+async fn yank_selection() {
+    let mut app = fixture_app(false, false).await;
+    assert_eq!(app.yank_text(), None);
+    press(&mut app, KeyCode::Char('j')).await;
+    assert_eq!(app.yank_text().as_deref(), Some("c001"));
+
+    // A collapsed array row yields the ID that addresses the whole array
+    open_queue(&mut app).await;
+    press(&mut app, KeyCode::End).await;
+    let (id, _) = app.selected_queue_target().unwrap();
+    assert_eq!(app.yank_text(), Some(id));
+
+    assert_eq!(crate::clipboard::osc52("c001"), "\x1b]52;c;YzAwMQ==\x07");
+}
+
+#[tokio::test]
+async fn watch_selected_job() {
+    let mut app = fixture_app(false, false).await;
+    // Nodes aren't jobs; W leaves the (shared) watchlist file alone
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('W')).await;
+    assert_eq!(app.status_message.as_deref(), Some("No job selected"));
+
+    // Any job can be pinned from the job views, not just the user's own
+    press(&mut app, KeyCode::Tab).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    assert_eq!(app.selected_job_id().as_deref(), Some("1002"));
+    app.handle_key(KeyEvent::new(KeyCode::Char('6'), KeyModifiers::ALT)).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    assert_eq!(app.selected_job_id().as_deref(), Some("1004"));
+}
+
+#[tokio::test]
+async fn command_line() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "p").await;
+    press(&mut app, KeyCode::Tab).await;
+    type_text(&mut app, "g").await;
+    press(&mut app, KeyCode::Tab).await;
+    assert_eq!(app.command_line.as_ref().unwrap().input, "partition gpu");
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.current_partition, "gpu");
+
+    for line in ["p batch", "sort avail-mem asc", "refresh 10", "filter state=idle"] {
+        press(&mut app, KeyCode::Char(':')).await;
+        type_text(&mut app, line).await;
+        press(&mut app, KeyCode::Enter).await;
+    }
+    assert_eq!(app.current_partition, "batch");
+    assert_eq!((app.sort_key, app.sort_descending), (SortKey::AvailMem, false));
+    assert_eq!(app.refresh_interval, Duration::from_secs(10));
+    let ids: Vec<&str> = app.visible_nodes().iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(ids, ["c001"]);
+
+    // :user adds to the filter and replaces an earlier user
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "u bob").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.node_filter.as_ref().unwrap().source(), "state=idle user=bob");
+    assert!(app.visible_nodes().is_empty());
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "filter state=draining").await;
+    press(&mut app, KeyCode::Enter).await;
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "user bob").await;
+    press(&mut app, KeyCode::Enter).await;
+    let ids: Vec<&str> = app.visible_nodes().iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(ids, ["c003"]);
+
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "sort avail").await;
+    press(&mut app, KeyCode::Tab).await;
+    assert_golden("command_line", &render(&mut app));
+
+    press(&mut app, KeyCode::Esc).await;
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "frobnicate").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert!(app.error_message.as_deref().is_some_and(|e| e.starts_with("Unknown command 'frobnicate'")));
+}
+
+#[tokio::test]
+async fn filter_expressions() {
+    let mut app = fixture_app(false, false).await;
+    let mut matching = |expression: &str| {
+        app.set_filter(NodeFilter::parse(expression).unwrap().unwrap());
+        app.visible_nodes().iter().map(|n| n.id.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(matching("state==idle || avail_mem_gb>=400"), ["c001", "g001"]);
+    assert_eq!(matching("features~A1 && !(load>10)"), Vec::<String>::new());
+    assert_eq!(matching("state!=down state!~^drain jobs>=2"), ["c002"]);
+    assert_eq!(matching("reason~'check_fs|responding'"), ["c005", "c006"]);
+    assert_eq!(matching("(user=dave || user=bob) && avail_cores<10"), ["c003"]);
+
+    let error = |expression: &str| NodeFilter::parse(expression).unwrap_err().to_string();
+    assert_eq!(error("load~high"), "load is a number; use ==, !=, <, <=, > or >=");
+    assert_eq!(error("state>idle"), "state is text; use ==, !=, ~ or !~");
+    assert_eq!(error("avail_mem_gb>=lots"), "avail_mem_gb is a number, not 'lots'");
+    assert_eq!(error("(state=idle"), "Missing ')'");
+    assert_eq!(error("state=idle & jobs=0"), "Use && and || to combine terms (quote a value with '&' in it)");
+    assert!(error("cores>4").starts_with("Unknown field 'cores'"));
+}
+
+#[tokio::test]
+async fn refresh_interval_steps() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('+')).await;
+    assert_eq!(app.refresh_interval, Duration::from_secs(60));
+    press(&mut app, KeyCode::Char('-')).await;
+    press(&mut app, KeyCode::Char('-')).await;
+    assert_eq!(app.status_message.as_deref(), Some("Refreshing every 15s"));
+
+    // Never below the floor, however it's asked for
+    for _ in 0..4 {
+        press(&mut app, KeyCode::Char('-')).await;
+    }
+    assert_eq!(app.refresh_interval, Duration::from_secs(MIN_REFRESH_SECS));
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "refresh 1").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.status_message.as_deref(), Some("Refreshing every 5s"));
+}
+
+#[tokio::test]
+async fn availability_notifications() {
+    let mut app = fixture_app(false, false).await;
+    for line in ["notify node c005 idle", "notify 2n 60c", "notify node g001 idle in gpu"] {
+        press(&mut app, KeyCode::Char(':')).await;
+        type_text(&mut app, line).await;
+        press(&mut app, KeyCode::Enter).await;
+    }
+    // c001 is the only node with 60 free cores, and c005 stays drained
+    assert_eq!(app.availability_watches.len(), 3);
+    assert!(app.banner.is_none());
+
+    // A watch that already holds fires at once and is dropped
+    press(&mut app, KeyCode::Char('N')).await;
+    type_text(&mut app, "60c 200g").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.banner.as_deref(), Some("🔔 60c 200g is free: c001"));
+    assert_eq!(app.availability_watches.len(), 3);
+    assert_golden("notify_banner", &render(&mut app));
+
+    press(&mut app, KeyCode::Esc).await;
+    assert!(app.banner.is_none());
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "notify node").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.error_message.as_deref(), Some("Invalid watch: Which node?"));
+}
+
+#[tokio::test]
+async fn own_job_ended() {
+    let mut app = fixture_app(false, false).await;
+    app.set_notifications(false, false, false);
+    // Running at the last refresh, gone from this one
+    app.user_jobs.push(job("1005", "tester", "c001", 4, 1));
+    app.user_jobs.push(job("1006", "tester", "c001", 4, 1));
+    app.fetch_data().await;
+    assert_eq!(app.banner.as_deref(), Some("🔔 Job 1005 (run_1005) ended: TO | Job 1006 (run_1006) ended"));
+
+    // 1003 is still running
+    app.banner = None;
+    app.fetch_data().await;
+    assert!(app.banner.is_none());
+}
+
+/// A local HTTP server answering 200 to `requests` POSTs, returning their bodies.
+async fn webhook_receiver(requests: usize) -> (String, tokio::task::JoinHandle<Vec<serde_json::Value>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let bodies = tokio::spawn(async move {
+        let mut bodies = Vec::new();
+        for _ in 0..requests {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            // Headers, then as much body as they announce
+            let body = loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).into_owned();
+                let Some((headers, body)) = text.split_once("\r\n\r\n") else { continue };
+                let length = headers
+                    .lines()
+                    .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|n| n.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break body.to_string();
+                }
+            };
+            stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n").await.unwrap();
+            bodies.push(serde_json::from_str(&body).unwrap());
+        }
+        bodies
+    });
+    (url, bodies)
+}
+
+#[tokio::test]
+async fn job_end_webhooks() {
+    let (url, bodies) = webhook_receiver(3).await;
+    let mut app = fixture_app(false, false).await;
+    app.set_notifications(false, false, false);
+    let hooks = [format!("slack:{}", url), format!("webhook:{}", url)];
+    app.set_job_end_webhooks(crate::webhook::Webhook::parse_list(&hooks).unwrap());
+    app.user_jobs.push(job("1005", "tester", "c001", 4, 1));
+    app.fetch_data().await;
+    assert!(app.error_message.is_none());
+
+    let config: crate::config::Config = toml::from_str(&format!(
+        "[[alerts]]\nname = 'Busy'\nwhen = 'running_jobs >= 6'\nnotify = ['mattermost:{}']",
+        url
+    ))
+    .unwrap();
+    app.set_alerts(crate::alerts::Alerts::new(&config.alerts).unwrap());
+    app.fetch_data().await;
+
+    let bodies = bodies.await.unwrap();
+    assert_eq!(bodies[0]["text"], ":checkered_flag: Job 1005 (run_1005) ended: TO");
+    assert_eq!(bodies[1]["event"], "job_end");
+    assert_eq!(bodies[1]["job"], "1005");
+    assert_eq!(bodies[1]["state"], "Timeout");
+    assert_eq!(bodies[2]["text"], ":rotating_light: Busy: running_jobs &gt;= 6 in batch (now 6)");
+
+    let error = crate::webhook::Webhook::parse_list(&["teams:https://example.com".to_string()]).unwrap_err();
+    assert_eq!(error.to_string(), "Unknown webhook 'teams:https://example.com' (use webhook:, slack: or mattermost:<url>)");
+}
+
+#[tokio::test]
+async fn one_shot_output() {
+    let mut app = fixture_app(false, false).await;
+    app.load_queue().await;
+    let output = [
+        app.nodes_output(None),
+        app.jobs_output(None, false, None),
+        app.jobs_output(None, true, Some("tester")),
+        app.check(Some(2), None).1,
+    ];
+    assert_golden("one_shot", &output.join("\n"));
+    assert_eq!(app.check(None, Some(4)).0, 0);
+    assert_eq!(app.check(None, Some(5)).0, 1);
+}
+
+#[tokio::test]
+async fn report_output() {
+    let mut app = fixture_app(false, false).await;
+    app.load_queue().await;
+    assert_golden("report", &app.report_output(false));
+    assert_golden("report_markdown", &app.report_output(true));
+}
+
+#[tokio::test]
+async fn influx_output() {
+    let mut app = fixture_app(false, false).await;
+    app.load_queue().await;
+    let output = app.influx_output(true);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert_eq!(
+        lines[0],
+        "nodestat_partition,partition=batch total_nodes=7i,avail_nodes=4i,total_cores=448i,used_cores=120i,\
+         total_mem=2048000000000i,used_mem=636000000000i,total_gpus=6i,used_gpus=4i,running_jobs=6i,pending_jobs=12i,\
+         watts=1800i 1709294400000000000"
+    );
+    assert!(lines[1].starts_with("nodestat_node,node=c001,partition=batch,state=Idle total_cores=64i,used_cores=0i,"));
+    assert_eq!(app.influx_output(false), format!("{}\n", lines[0]));
+
+    let point = crate::influx::Point::new("a b").tag("reason", "not responding, x=1").tag("empty", "").float("f", 0.5);
+    assert_eq!(point.line(frozen_now()), "a\\ b,reason=not\\ responding\\,\\ x\\=1 f=0.5 1709294400000000000\n");
+}
+
+#[tokio::test]
+async fn format_template() {
+    let mut app = fixture_app(false, false).await;
+    let nodes = Template::parse(r"{id}\t{state}\t{avail_cores}/{total_cores} {{{users}}}", &template::NODE_FIELDS).unwrap();
+    let output = app.nodes_output(Some(&nodes));
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "c001\tIdle\t64/64 {-}");
+    assert_eq!(lines[3], "c002\tMixed\t32/64 {alice,dave}");
+    assert_eq!(lines.len(), 7);
+
+    app.set_filter(NodeFilter::parse("state=idle").unwrap().unwrap());
+    assert_eq!(app.nodes_output(Some(&nodes)), "c001\tIdle\t64/64 {-}\n");
+
+    let jobs = Template::parse("{id} {user} {nodes} {elapsed}", &template::JOB_FIELDS).unwrap();
+    assert_eq!(app.jobs_output(Some(&jobs), false, Some("carol")), "1004 carol c004 14:00:00\n");
+    let stats = Template::parse("{partition}: {used_cores}/{total_cores}", &template::STATS_FIELDS).unwrap();
+    assert_eq!(app.stats_output(Some(&stats)), "batch: 120/448\n");
+
+    let error = Template::parse("{id} {cores}", &template::NODE_FIELDS).unwrap_err().to_string();
+    assert!(error.starts_with("Unknown field '{cores}'"), "{}", error);
+    assert!(Template::parse("{id", &template::NODE_FIELDS).is_err());
+}
+
+#[tokio::test]
+async fn command_palette() {
+    let mut app = fixture_app(false, false).await;
+    let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+    app.handle_key(ctrl_p).await;
+    type_text(&mut app, "sort").await;
+    assert_golden("palette", &render(&mut app));
+
+    // Keyed entries act like their key, the rest run a command
+    press(&mut app, KeyCode::Esc).await;
+    app.handle_key(ctrl_p).await;
+    type_text(&mut app, "fairsh").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert!(app.palette.is_none() && app.show_fairshare);
+
+    app.handle_key(ctrl_p).await;
+    type_text(&mut app, "partgpu").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.current_partition, "gpu");
+
+    // Admin actions are only offered with --admin
+    app.handle_key(ctrl_p).await;
+    type_text(&mut app, "drain").await;
+    press(&mut app, KeyCode::Enter).await;
+    assert!(app.prompt.is_none());
+    assert!(palette::fuzzy_score("dn", "Drain nodes").is_some());
+    assert_eq!(palette::fuzzy_score("xq", "Drain nodes"), None);
+}
+
+#[tokio::test]
+async fn node_drawer_follows_selection() {
+    let mut app = fixture_app(false, false).await;
+    let c002 = app.nodes.iter().position(|n| n.id == "c002").unwrap();
+    app.table_state.select(Some(c002));
+    press(&mut app, KeyCode::Enter).await;
+    press(&mut app, KeyCode::Char('j')).await;
+    assert_golden("node_drawer_jobs", &render(&mut app));
+}
+
+#[tokio::test]
+async fn custom_columns() {
+    let mut app = fixture_app(false, false).await;
+    let names: Vec<String> = ["node", "State", "reason", "features", "gpus"].iter().map(|s| s.to_string()).collect();
+    app.set_columns(Column::parse_list(&names).unwrap());
+    assert_golden("custom_columns", &render(&mut app));
+
+    assert!(Column::parse_list(&["node".to_string(), "color".to_string()]).is_err());
+}
+
+#[tokio::test]
+async fn time_until_free() {
+    let mut app = fixture_app(false, false).await;
+    app.set_columns(vec![Column::Node, Column::State, Column::Jobs, Column::Eta]);
+    app.set_sort(SortKey::Eta);
+    assert_golden("eta_column", &render(&mut app));
+
+    // c003 runs jobs with 1h and 6h left; the earliest counts
+    let c003 = app.etas.get(&(None, "c003".to_string())).copied();
+    assert_eq!(c003, Some(chrono::Duration::hours(1)));
+    assert!(!app.etas.contains_key(&(None, "c001".to_string())));
+}
+
+#[tokio::test]
+async fn node_jobs_from_job_list() {
+    let mut app = fixture_app(false, false).await;
+    // The fixture nodes list no jobs of their own, so they come from the job list
+    let c003 = app.nodes.iter().find(|n| n.id == "c003").unwrap();
+    assert_eq!(c003.jobs, ["1002", "1003"]);
+    assert_eq!(c003.job_users, ["bob", "tester"]);
+    let c001 = app.nodes.iter().find(|n| n.id == "c001").unwrap();
+    assert!(c001.jobs.is_empty() && c001.job_users.is_empty());
+
+    app.set_sort(SortKey::Jobs);
+    assert_eq!(app.nodes[0].id, "c002");
+}
+
+#[tokio::test]
+async fn narrow_terminal() {
+    let mut app = fixture_app(false, false).await;
+    // An 80-column tmux pane: narrow bars, short titles, and the last columns scrolled out
+    assert_golden("narrow", &render_sized(&mut app, 80, 30));
+
+    press(&mut app, KeyCode::Char('l')).await;
+    press(&mut app, KeyCode::Char('l')).await;
+    assert_golden("narrow_scrolled", &render_sized(&mut app, 80, 30));
+
+    assert_golden("too_small", &render_sized(&mut app, 50, 12));
+}
+
+#[tokio::test]
+async fn zoomed_table() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char('a')).await;
+    press(&mut app, KeyCode::Char('z')).await;
+    assert_golden("zoomed", &render(&mut app));
+
+    // The same key restores the layout, recent changes and all
+    press(&mut app, KeyCode::Char('z')).await;
+    let restored = render(&mut app);
+    assert!(restored.contains("Recent changes") && restored.contains("CPU  120/448"));
+}
+
+#[tokio::test]
+async fn paging_large_cluster() {
+    let mut app = fixture_app(false, false).await;
+    let template = app.nodes.iter().find(|n| n.id == "c001").unwrap().clone();
+    app.nodes = (1..=4500).map(|i| Node { id: format!("n{:04}", i), ..template.clone() }).collect();
+    app.sort_nodes();
+
+    press(&mut app, KeyCode::End).await;
+    assert_golden("paging_end", &render(&mut app));
+
+    // A page up moves the selection one screen, and the window just far enough to show it
+    press(&mut app, KeyCode::PageUp).await;
+    let selected = 4500 - app.page_rows;
+    let rendered = render(&mut app);
+    assert!(rendered.contains(&format!(">> n{:04}", selected)), "{}", rendered);
+    assert!(rendered.contains(&format!("Nodes {}-{} of 4500", selected, selected + app.page_rows - 1)), "{}", rendered);
+
+    press(&mut app, KeyCode::Home).await;
+    assert!(render(&mut app).contains(">> n0001"));
+}
+
+#[tokio::test]
+async fn utilization_sparklines() {
+    let mut app = fixture_app(false, false).await;
+    // Rising CPU, falling memory and steady GPUs over the last six minutes
+    let now = std::time::Instant::now();
+    let partition = app.current_partition.clone();
+    for step in 1..=12u32 {
+        let mut stats = app.stats.clone();
+        stats.used_cores = stats.total_cores * step / 12;
+        stats.used_memory = stats.total_memory / u64::from(step);
+        let at = now - std::time::Duration::from_secs(u64::from(12 - step) * 30);
+        app.utilization.record_at(&partition, &stats, at);
+    }
+    assert_golden("sparklines", &render(&mut app));
+}
+
+#[tokio::test]
+async fn colorblind_symbols() {
+    let mut app = fixture_app(false, false).await;
+    app.set_theme(Theme::colorblind());
+    assert_golden("colorblind", &render(&mut app));
+}
+
+#[test]
+fn theme_from_config() {
+    let config: crate::config::Config = toml::from_str(
+        r##"
+        [themes.site]
+        base = "light"
+        accent = "#ff8700"
+        error = "LightRed"
+
+        [themes.symbols]
+        base = "dark"
+        symbols = true
+
+        [themes.typo]
+        acent = "red"
+        "##,
+    )
+    .unwrap();
+
+    let theme = Theme::resolve("site", &config.themes).unwrap();
+    assert_eq!(theme.accent, Color::Rgb(255, 135, 0));
+    assert_eq!(theme.error, Color::LightRed);
+    assert_eq!(theme.good, Theme::light().good);
+
+    assert_eq!(Theme::resolve("solarized", &config.themes).unwrap(), Theme::solarized());
+    assert!(Theme::resolve("symbols", &config.themes).unwrap().symbols);
+    assert!(!theme.symbols);
+    assert!(Theme::resolve("typo", &config.themes).is_err());
+    assert!(Theme::resolve("neon", &config.themes).is_err());
+}
+
+#[tokio::test]
+async fn custom_keys() {
+    let keys = |toml: &str| -> anyhow::Result<Keymap> {
+        let config: crate::config::Config = toml::from_str(toml).unwrap();
+        Keymap::new(&config.keys)
+    };
+    let err = |toml: &str| format!("{:#}", keys(toml).unwrap_err());
+
+    assert_eq!(err("[keys]\nquit = \"x\""), "[keys]: 'x' is bound to both expand and quit");
+    assert!(err("[keys]\nquit = \"Esc\"").contains("'esc' is reserved"));
+    assert!(err("[keys]\nexit = \"x\"").starts_with("[keys]: unknown action 'exit'"));
+    assert_eq!(err("[keys]\nquit = \"hyper-q\""), "[keys] quit: Unknown key 'hyper-q'");
+    // Swapping two keys is fine
+    assert!(keys("[keys]\nup = \"j\"\ndown = \"k\"").is_ok());
+
+    let mut app = fixture_app(false, false).await;
+    app.set_keymap(keys("[keys]\nquit = \"Q\"\nrefresh = \"F5\"").unwrap());
+    press(&mut app, KeyCode::Char('q')).await;
+    assert!(!app.should_quit);
+    let quit = app.palette_entries().into_iter().find(|entry| entry.label == "Quit").unwrap();
+    assert_eq!(quit.hint, "Q");
+    press(&mut app, KeyCode::Char('Q')).await;
+    assert!(app.should_quit);
+}
+
+#[tokio::test]
+async fn session_restore() {
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "sort name desc").await;
+    press(&mut app, KeyCode::Enter).await;
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, "filter avail_cores>0").await;
+    press(&mut app, KeyCode::Enter).await;
+    let mut state = app.session_state();
+    state.features = vec!["a100".to_string()];
+    state.theme = Some("light".to_string());
+    state.save("fixture@local").unwrap();
+    SessionState::default().save("other@local").unwrap();
+
+    let saved = SessionState::load("fixture@local");
+    assert_eq!(saved, state);
+    assert_eq!(saved.partition.as_deref(), Some("batch"));
+    assert_eq!(saved.sort, Some(("name".to_string(), true)));
+
+    let mut app = fixture_app(false, false).await;
+    app.restore_session(&saved).await;
+    assert_eq!((app.sort_key, app.sort_descending), (SortKey::Name, true));
+    assert_eq!(app.node_filter.as_ref().map(NodeFilter::source), Some("avail_cores>0"));
+    let ids: Vec<&str> = app.visible_nodes().iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(ids, ["g001"]);
+
+    // Whatever no longer parses is left at the default
+    let mut app = fixture_app(false, false).await;
+    app.restore_session(&SessionState { sort: Some(("speed".to_string(), true)), filter: Some("load>>".to_string()), ..saved }).await;
+    assert_eq!(app.sort_key, SortKey::Availability);
+    assert!(app.node_filter.is_none());
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let path = std::env::temp_dir().join(format!("nodestat-record-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut app = fixture_app(false, false).await;
+    app.set_recorder(Recorder::create(&path).unwrap());
+    app.frozen_time = Some(frozen_now() + chrono::Duration::minutes(1));
+    press(&mut app, KeyCode::Char('r')).await;
+    let recorded: Vec<String> = app.nodes.iter().map(|node| node.id.clone()).collect();
+
+    let replay = Arc::new(Replay::load(&path).unwrap());
+    assert_eq!(replay.len(), 2);
+    let scheduler = Box::new(crate::schedulers::ReplayScheduler::new(replay.clone()));
+    let mut app = App::new(scheduler, Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default())
+        .await
+        .unwrap();
+    app.current_user = "tester".to_string();
+    app.set_replay(replay.clone()).await;
+    let replayed: Vec<String> = app.nodes.iter().map(|node| node.id.clone()).collect();
+    assert_eq!(replayed, recorded);
+    assert_eq!(app.user_jobs.len(), 1);
+    assert_golden("replay", &render(&mut app));
+
+    press(&mut app, KeyCode::Char('}')).await;
+    assert_eq!((replay.position(), app.now()), (1, frozen_now() + chrono::Duration::minutes(1)));
+    press(&mut app, KeyCode::Char('P')).await;
+    press(&mut app, KeyCode::Char(']')).await;
+    assert!(!app.playback.as_ref().unwrap().playing);
+    assert_eq!(app.status_message.as_deref(), Some("End of the recording"));
+    press(&mut app, KeyCode::Char('[')).await;
+    assert_eq!(replay.position(), 0);
+
+    let _ = std::fs::remove_file(&path);
+    assert!(Replay::load(&path).is_err());
+}
+
+#[tokio::test]
+async fn snapshot_dump() {
+    let path = std::env::temp_dir().join(format!("nodestat-snapshot-{}.json", std::process::id()));
+    let mut app = fixture_app(false, false).await;
+    press(&mut app, KeyCode::Char(':')).await;
+    type_text(&mut app, &format!("dump anonymize {}", path.display())).await;
+    press(&mut app, KeyCode::Enter).await;
+    assert_eq!(app.status_message, Some(format!("Snapshot written to {} with usernames replaced", path.display())));
+
+    let snapshot = Replay::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(snapshot.len(), 1);
+    let frame = snapshot.frame();
+    let users: Vec<&str> = frame.jobs.iter().map(|job| job.user.as_str()).collect();
+    assert_eq!(users, ["user1", "user2", "user3", "user4", "user5", "user5"]);
+    assert_eq!(frame.user_jobs[0].user, "user3");
+    assert!(frame.nodes.iter().flat_map(|node| &node.job_users).all(|user| user.starts_with("user")));
+
+    // What the maintainer sees
+    let snapshot = Arc::new(snapshot);
+    let scheduler = Box::new(crate::schedulers::ReplayScheduler::new(snapshot.clone()));
+    let mut app = App::new(scheduler, None, false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default()).await.unwrap();
+    app.set_replay(snapshot).await;
+    assert_eq!(app.current_partition, "batch");
+    assert_eq!(app.nodes.len(), 7);
+    assert!(render(&mut app).contains("Snapshot taken: 2024-03-01 12:00:00 UTC"));
+}
+
+#[tokio::test]
+async fn history_samples() {
+    let dir = std::env::temp_dir().join(format!("nodestat-history-{}", std::process::id()));
+    let config: crate::config::Config = toml::from_str(&format!(
+        "[history]\nenabled = true\npath = '{}'\nretention_days = 1",
+        dir.join("history.db").display()
+    ))
+    .unwrap();
+    // Needs the sqlite3 command
+    let Ok(history) = crate::history::HistoryDb::open(&config.history) else {
+        return;
+    };
+    let mut app = fixture_app(false, false).await;
+    app.set_history(history);
+    press(&mut app, KeyCode::Char('r')).await;
+    app.frozen_time = Some(frozen_now() + chrono::Duration::days(2));
+    press(&mut app, KeyCode::Char('r')).await;
+    assert!(app.error_message.is_none());
+    // A load that isn't a number is stored as none
+    let mut history = app.history.take().unwrap();
+    let mut nodes = app.nodes.clone();
+    nodes.retain(|node| node.id == "c001");
+    nodes[0].cpu_load = Some(f64::NAN);
+    let later = frozen_now() + chrono::Duration::days(2) + chrono::Duration::minutes(1);
+    history.record(later, "gpu", &app.stats, &[], 0, &nodes).unwrap();
+    // Once its writes are done
+    drop(history);
+
+    let query = |sql: &str| {
+        let output = std::process::Command::new("sqlite3").arg(dir.join("history.db")).arg(sql).output().unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    // The first refresh is past the retention by the second
+    assert_eq!(
+        query("SELECT partition, used_cores, total_cores, running_jobs, pending_jobs FROM cluster_samples WHERE partition = 'batch'"),
+        "batch|120|448|6|5\n",
+    );
+    assert_eq!(query("SELECT count(*) FROM node_samples"), "8\n");
+    assert_eq!(query(&format!("SELECT node, load IS NULL FROM node_samples WHERE time = {}", later.timestamp())), "c001|1\n");
+    assert_eq!(query("SELECT state, load, jobs FROM node_samples WHERE node = 'c003'"), "Draining|64.0|2\n");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn charts_view() {
+    let mut app = fixture_app(false, false).await;
+    app.handle_key(KeyEvent::new(KeyCode::Char('7'), KeyModifiers::ALT)).await;
+    assert!(render(&mut app).contains("Set enabled = true under [history]"));
+
+    let dir = std::env::temp_dir().join(format!("nodestat-charts-{}", std::process::id()));
+    let config: crate::config::Config = toml::from_str(&format!(
+        "[history]\nenabled = true\npath = '{}'",
+        dir.join("history.db").display()
+    ))
+    .unwrap();
+    // Needs the sqlite3 command
+    let Ok(history) = crate::history::HistoryDb::open(&config.history) else {
+        return;
+    };
+    app.set_history(history);
+    for minutes in [180, 40, 20, 0] {
+        app.frozen_time = Some(frozen_now() - chrono::Duration::minutes(minutes));
+        press(&mut app, KeyCode::Char('r')).await;
+    }
+    assert!(app.error_message.is_none());
+    // The refresh three hours ago is outside the last hour, not the last day
+    assert_eq!(app.chart_samples.len(), 3);
+    assert_golden("charts", &render(&mut app));
+    press(&mut app, KeyCode::Char('l')).await;
+    assert_eq!(app.chart_samples.len(), 4);
+    press(&mut app, KeyCode::Char('l')).await;
+    press(&mut app, KeyCode::Char('l')).await;
+    assert_eq!(app.chart_window, ChartWindow::Week);
+    press(&mut app, KeyCode::Char('h')).await;
+    assert_eq!(app.chart_window, ChartWindow::Day);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn queue_wait_times() {
+    let dir = std::env::temp_dir().join(format!("nodestat-waits-{}", std::process::id()));
+    let config: crate::config::Config = toml::from_str(&format!(
+        "[history]\nenabled = true\npath = '{}'",
+        dir.join("history.db").display()
+    ))
+    .unwrap();
+    // Needs the sqlite3 command
+    let Ok(history) = crate::history::HistoryDb::open(&config.history) else {
+        return;
+    };
+    let mut app = fixture_app(false, false).await;
+    // The fixture scheduler keeps no accounting, so the waits of the jobs seen running are used
+    app.set_history(history);
+    press(&mut app, KeyCode::Char('r')).await;
+    open_queue(&mut app).await;
+    // Submitted a day ago, running for 12h to 23h
+    assert!(render(&mut app).contains("Queue: 12 pending, yours at #2 · waits over 7d: median 6h00m, p90 12h00m (6 jobs)"));
+
+    // The picker lists them per partition; gpu had no jobs start
+    press(&mut app, KeyCode::Char('p')).await;
+    let picker = render(&mut app);
+    assert!(picker.lines().any(|line| line.contains("batch") && line.contains("wait 6h00m (p90 12h00m)")));
+    assert!(picker.lines().any(|line| line.contains("gpu") && !line.contains("wait")));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn alert_rules() {
+    let config: crate::config::Config = toml::from_str(
+        r#"
+        [[alerts]]
+        name = "Busy"
+        when = "running_jobs >= 6"
+        cooldown = "1h"
+
+        [[alerts]]
+        when = "partition:gpu avail_gpus >= 0 for 10m"
+        "#,
+    )
+    .unwrap();
+    let mut app = fixture_app(false, false).await;
+    app.set_alerts(crate::alerts::Alerts::new(&config.alerts).unwrap());
+    press(&mut app, KeyCode::Char('r')).await;
+    assert_eq!(app.banner.as_deref(), Some("🚨 Busy: running_jobs >= 6 in batch (now 6)"));
+
+    // The gpu rule holds but not for long enough yet, and Busy is cooling down
+    app.banner = None;
+    app.frozen_time = Some(frozen_now() + chrono::Duration::minutes(5));
+    press(&mut app, KeyCode::Char('r')).await;
+    assert!(app.banner.is_none());
+    press(&mut app, KeyCode::Char('A')).await;
+    let screen = render(&mut app);
+    assert!(screen.contains("Alerts (A: hide)"));
+    assert!(screen.lines().any(|line| line.contains("Busy") && line.contains("FIRING for 5m")));
+    assert!(screen.lines().any(|line| line.contains("partition:gpu") && line.contains("holding for 5m")));
+
+    app.frozen_time = Some(frozen_now() + chrono::Duration::minutes(10));
+    press(&mut app, KeyCode::Char('r')).await;
+    assert!(app.banner.as_deref().is_some_and(|banner| banner.starts_with("🚨 avail_gpus >= 0 for 10m in gpu (now ")));
+
+    let bad: crate::config::Config = toml::from_str("[[alerts]]\nwhen = \"idle_nodes > 3\"").unwrap();
+    let error = crate::alerts::Alerts::new(&bad.alerts).unwrap_err();
+    assert!(format!("{:#}", error).starts_with("[[alerts]] 'idle_nodes > 3': Unknown metric 'idle_nodes'"));
+}
+
+#[tokio::test]
+async fn http_api() {
+    let daemon = crate::daemon::Daemon::new(Box::new(FixtureScheduler { fail: false }), std::time::Duration::from_secs(30));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(crate::http::run(listener, std::sync::Arc::new(daemon), Some("s3cret".to_string())));
+    let client = reqwest::Client::new();
+    let get = |path: &str, token: &str| client.get(format!("{}{}", base, path)).bearer_auth(token).send();
+
+    let response = get("/stats", "wrong").await.unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    // batch is the default partition
+    let stats: ClusterStats = get("/stats", "s3cret").await.unwrap().json().await.unwrap();
+    let nodes: Vec<Node> = get("/nodes?partition=batch", "s3cret").await.unwrap().json().await.unwrap();
+    assert_eq!(stats.total_nodes as usize, nodes.len());
+    assert_eq!(stats.avail_cores, ClusterStats::of(&nodes).avail_cores);
+    let jobs: Vec<Job> = get("/jobs", "s3cret").await.unwrap().json().await.unwrap();
+    assert_eq!(jobs.len(), 6);
+
+    let partitions: serde_json::Value = get("/partitions", "s3cret").await.unwrap().json().await.unwrap();
+    assert_eq!(partitions[0]["name"], "batch");
+    assert_eq!(partitions[1]["name"], "gpu");
+    assert_eq!(partitions[0]["is_default"], true);
+
+    assert_eq!(crate::http::parse_listen(":8080").unwrap().to_string(), "0.0.0.0:8080");
+}
+
+#[tokio::test]
+async fn daemon_socket() {
+    let socket = std::env::temp_dir().join(format!("nodestat-daemon-{}", std::process::id())).join("nodestat.sock");
+    // Left behind by a daemon that is gone
+    std::fs::create_dir_all(socket.parent().unwrap()).unwrap();
+    std::fs::write(&socket, "").unwrap();
+    let scheduler = Box::new(FixtureScheduler { fail: false });
+    let alerts = crate::alerts::Alerts::new(&[]).unwrap();
+    let serve = crate::daemon::serve(scheduler, None, Some(socket.clone()), std::time::Duration::from_secs(30), alerts, None, None);
+    tokio::spawn(serve);
+    while std::fs::metadata(&socket).is_ok_and(|metadata| metadata.len() == 0 && metadata.is_file()) {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let remote = crate::schedulers::RemoteScheduler::new(&format!("unix:{}", socket.display())).unwrap();
+    let mut app = App::new(Box::new(remote), Some("batch".to_string()), false, ClusterTz::Named(chrono_tz::UTC), &Timeouts::default()).await.unwrap();
+    assert_eq!(app.nodes.len(), 7);
+    let polled_at = app.polled_at.expect("the daemon says when it polled");
+    app.frozen_time = Some(polled_at + chrono::Duration::seconds(90));
+    let screen = render(&mut app);
+    assert!(screen.contains("Cached: 90s old    Cluster time: "), "{}", screen);
+}
+
+/// A broker that accepts one MQTT client and passes on what it publishes as `(topic, payload, retain)`.
+async fn fake_broker() -> (String, tokio::sync::mpsc::UnboundedReceiver<(String, String, bool)>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        loop {
+            let Ok(kind) = stream.read_u8().await else { return };
+            let (mut length, mut shift) = (0usize, 0);
+            loop {
+                let byte = stream.read_u8().await.unwrap();
+                length |= usize::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).await.unwrap();
+            match kind >> 4 {
+                // CONNECT: accepted
+                1 => stream.write_all(&[0x20, 2, 0, 0]).await.unwrap(),
+                // PUBLISH: topic, packet ID at QoS 1 (acknowledged), payload
+                3 => {
+                    let topic_len = usize::from(u16::from_be_bytes([body[0], body[1]]));
+                    let topic = String::from_utf8(body[2..2 + topic_len].to_vec()).unwrap();
+                    let mut rest = &body[2 + topic_len..];
+                    if kind & 0x06 != 0 {
+                        stream.write_all(&[0x40, 2, rest[0], rest[1]]).await.unwrap();
+                        rest = &rest[2..];
+                    }
+                    let _ = sender.send((topic, String::from_utf8_lossy(rest).into_owned(), kind & 1 == 1));
+                },
+                // PINGREQ
+                12 => stream.write_all(&[0xd0, 0]).await.unwrap(),
+                _ => {},
+            }
+        }
+    });
+    (address, receiver)
+}
+
+#[tokio::test]
+async fn mqtt_publishing() {
+    let (broker, mut published) = fake_broker().await;
+    let daemon = crate::daemon::Daemon::new(Box::new(FixtureScheduler { fail: false }), std::time::Duration::from_secs(30));
+    let config: crate::config::Config = toml::from_str("[mqtt]\ntopic_prefix = 'lab'\npartitions = ['batch']").unwrap();
+    tokio::spawn(async move { crate::mqtt::publish(std::sync::Arc::new(daemon), &broker, config.mqtt).await });
+
+    let mut topics = std::collections::HashMap::new();
+    while !topics.contains_key("lab/batch/stats") || !topics.contains_key("lab/batch/nodes/c001") || !topics.contains_key("lab/status") {
+        let (topic, payload, retain) = tokio::time::timeout(std::time::Duration::from_secs(5), published.recv()).await.unwrap().unwrap();
+        assert!(retain, "{} isn't retained", topic);
+        topics.insert(topic, payload);
+    }
+    assert_eq!(topics["lab/status"], "online");
+    let stats: serde_json::Value = serde_json::from_str(&topics["lab/batch/stats"]).unwrap();
+    assert_eq!(stats["partition"], "batch");
+    assert_eq!(stats["running_jobs"], 6);
+    let node: Node = serde_json::from_str(&topics["lab/batch/nodes/c001"]).unwrap();
+    assert_eq!(node.id, "c001");
+}
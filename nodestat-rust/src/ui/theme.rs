@@ -0,0 +1,300 @@
+//! Colors of the TUI by role, so every widget follows the chosen theme.
+
+use crate::config::ThemeConfig;
+use crate::models::{JobState, Node, NodeState};
+use anyhow::{anyhow, Context, Result};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::BTreeMap;
+
+pub const BUILTIN: [&str; 4] = ["dark", "light", "solarized", "colorblind"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// The title bar
+    pub title: Color,
+    /// Table headers and text being typed
+    pub header: Color,
+    /// Labels of detail popups, the partition header, rack names
+    pub label: Color,
+    /// Hints, placeholders and things switched off
+    pub muted: Color,
+    /// Background of the selected row
+    pub selection: Color,
+    /// The user's own jobs, the active tab
+    pub accent: Color,
+    pub good: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Fully allocated nodes, jobs killed for memory
+    pub busy: Color,
+    /// Rebooting nodes, suspended jobs, job arrays
+    pub info: Color,
+    /// Filters, reservations and maintenance
+    pub special: Color,
+    pub preempted: Color,
+    pub node_fail: Color,
+    pub cpu_gauge: Color,
+    pub mem_gauge: Color,
+    pub gpu_gauge: Color,
+    pub power_gauge: Color,
+    /// Prefix node and job states with a symbol, so they can be told apart without color
+    pub symbols: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            title: Color::Magenta,
+            header: Color::White,
+            label: Color::Cyan,
+            muted: Color::Gray,
+            selection: Color::DarkGray,
+            accent: Color::Yellow,
+            good: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            busy: Color::LightRed,
+            info: Color::Blue,
+            special: Color::Magenta,
+            preempted: Color::LightBlue,
+            node_fail: Color::LightMagenta,
+            cpu_gauge: Color::Red,
+            mem_gauge: Color::Blue,
+            gpu_gauge: Color::Green,
+            power_gauge: Color::Yellow,
+            symbols: false,
+        }
+    }
+
+    /// Dark, saturated colors that stay readable on a white background.
+    pub fn light() -> Self {
+        Self {
+            title: Color::Rgb(0, 95, 175),
+            header: Color::Black,
+            label: Color::Rgb(0, 95, 135),
+            muted: Color::DarkGray,
+            selection: Color::Rgb(208, 208, 208),
+            accent: Color::Rgb(175, 95, 0),
+            good: Color::Rgb(0, 135, 0),
+            warning: Color::Rgb(175, 135, 0),
+            error: Color::Rgb(175, 0, 0),
+            busy: Color::Rgb(215, 95, 0),
+            info: Color::Rgb(0, 0, 175),
+            special: Color::Rgb(95, 0, 135),
+            preempted: Color::Rgb(0, 95, 215),
+            node_fail: Color::Rgb(135, 0, 95),
+            cpu_gauge: Color::Rgb(175, 0, 0),
+            mem_gauge: Color::Rgb(0, 0, 175),
+            gpu_gauge: Color::Rgb(0, 135, 0),
+            power_gauge: Color::Rgb(175, 135, 0),
+            symbols: false,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized palette, for a Solarized dark terminal.
+    pub fn solarized() -> Self {
+        Self {
+            title: Color::Rgb(211, 54, 130),
+            header: Color::Rgb(147, 161, 161),
+            label: Color::Rgb(42, 161, 152),
+            muted: Color::Rgb(88, 110, 117),
+            selection: Color::Rgb(7, 54, 66),
+            accent: Color::Rgb(181, 137, 0),
+            good: Color::Rgb(133, 153, 0),
+            warning: Color::Rgb(181, 137, 0),
+            error: Color::Rgb(220, 50, 47),
+            busy: Color::Rgb(203, 75, 22),
+            info: Color::Rgb(38, 139, 210),
+            special: Color::Rgb(108, 113, 196),
+            preempted: Color::Rgb(38, 139, 210),
+            node_fail: Color::Rgb(211, 54, 130),
+            cpu_gauge: Color::Rgb(220, 50, 47),
+            mem_gauge: Color::Rgb(38, 139, 210),
+            gpu_gauge: Color::Rgb(133, 153, 0),
+            power_gauge: Color::Rgb(181, 137, 0),
+            symbols: false,
+        }
+    }
+
+    /// The Okabe-Ito palette, which keeps blue/orange/vermillion distinct under deuteranopia and
+    /// protanopia (nothing relies on red vs green), plus state symbols.
+    pub fn colorblind() -> Self {
+        Self {
+            title: Color::Rgb(204, 121, 167),
+            header: Color::White,
+            label: Color::Rgb(0, 158, 115),
+            muted: Color::Gray,
+            selection: Color::DarkGray,
+            accent: Color::Rgb(240, 228, 66),
+            good: Color::Rgb(86, 180, 233),
+            warning: Color::Rgb(240, 228, 66),
+            error: Color::Rgb(213, 94, 0),
+            busy: Color::Rgb(230, 159, 0),
+            info: Color::Rgb(0, 114, 178),
+            special: Color::Rgb(204, 121, 167),
+            preempted: Color::Rgb(0, 114, 178),
+            node_fail: Color::Rgb(204, 121, 167),
+            cpu_gauge: Color::Rgb(213, 94, 0),
+            mem_gauge: Color::Rgb(0, 114, 178),
+            gpu_gauge: Color::Rgb(86, 180, 233),
+            power_gauge: Color::Rgb(240, 228, 66),
+            symbols: true,
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "solarized" => Some(Self::solarized()),
+            "colorblind" => Some(Self::colorblind()),
+            _ => None,
+        }
+    }
+
+    /// A theme from `[themes.<name>]` in config.toml, else a built-in one.
+    pub fn resolve(name: &str, themes: &BTreeMap<String, ThemeConfig>) -> Result<Self> {
+        let Some(config) = themes.get(name) else {
+            return Self::builtin(name).ok_or_else(|| {
+                anyhow!("Unknown theme '{}' (built-in: {}; or define [themes.{}] in config.toml)", name, BUILTIN.join(", "), name)
+            });
+        };
+
+        let base = config.base.as_deref().unwrap_or("dark");
+        let mut theme = Self::builtin(base)
+            .ok_or_else(|| anyhow!("[themes.{}]: unknown base '{}' (built-in: {})", name, base, BUILTIN.join(", ")))?;
+        for (role, value) in &config.colors {
+            let color = value
+                .parse()
+                .map_err(|_| anyhow!("'{}' is not a color name, #rrggbb or an ANSI index", value))
+                .with_context(|| format!("[themes.{}] {}", name, role))?;
+            *theme.role_mut(role).with_context(|| format!("[themes.{}]", name))? = color;
+        }
+        if let Some(symbols) = config.symbols {
+            theme.symbols = symbols;
+        }
+        Ok(theme)
+    }
+
+    fn role_mut(&mut self, role: &str) -> Result<&mut Color> {
+        Ok(match role {
+            "title" => &mut self.title,
+            "header" => &mut self.header,
+            "label" => &mut self.label,
+            "muted" => &mut self.muted,
+            "selection" => &mut self.selection,
+            "accent" => &mut self.accent,
+            "good" => &mut self.good,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "busy" => &mut self.busy,
+            "info" => &mut self.info,
+            "special" => &mut self.special,
+            "preempted" => &mut self.preempted,
+            "node_fail" => &mut self.node_fail,
+            "cpu_gauge" => &mut self.cpu_gauge,
+            "mem_gauge" => &mut self.mem_gauge,
+            "gpu_gauge" => &mut self.gpu_gauge,
+            "power_gauge" => &mut self.power_gauge,
+            _ => return Err(anyhow!("unknown color role '{}'", role)),
+        })
+    }
+
+    /// Bold table headers.
+    pub fn heading(&self) -> Style {
+        Style::default().fg(self.header).add_modifier(Modifier::BOLD)
+    }
+
+    /// The selected row of a table.
+    pub fn highlight(&self) -> Style {
+        Style::default().bg(self.selection).add_modifier(Modifier::BOLD)
+    }
+
+    /// Green from 80, yellow from 50, bold red below.
+    pub fn health(&self, score: u8) -> Style {
+        match score {
+            80.. => Style::default().fg(self.good),
+            50..=79 => Style::default().fg(self.warning),
+            _ => Style::default().fg(self.error).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn node_state(&self, node: &Node) -> Style {
+        match node.state {
+            _ if node.is_draining() => Style::default().fg(self.error),
+            NodeState::Idle => Style::default().fg(self.good),
+            NodeState::Running | NodeState::Mixed => Style::default().fg(self.warning),
+            NodeState::Busy | NodeState::Allocated => Style::default().fg(self.busy),
+            NodeState::Completing => Style::default().fg(self.label),
+            NodeState::Reboot => Style::default().fg(self.info),
+            NodeState::Maint | NodeState::Reserved => Style::default().fg(self.special),
+            NodeState::Down | NodeState::Offline | NodeState::PlannedDown => Style::default().fg(self.muted),
+            NodeState::Drained | NodeState::Fail => Style::default().fg(self.error),
+        }
+    }
+
+    /// `"○ "` and the like before a node's state when the theme has symbols, else empty.
+    pub fn node_symbol(&self, node: &Node) -> &'static str {
+        if !self.symbols {
+            return "";
+        }
+        match node.state {
+            _ if node.is_draining() => "⊘ ",
+            NodeState::Idle => "○ ",
+            NodeState::Running | NodeState::Mixed => "◐ ",
+            NodeState::Busy | NodeState::Allocated => "● ",
+            NodeState::Completing => "◔ ",
+            NodeState::Reboot => "↻ ",
+            NodeState::Maint | NodeState::Reserved => "◆ ",
+            NodeState::Down | NodeState::Offline | NodeState::PlannedDown => "✗ ",
+            NodeState::Drained | NodeState::Fail => "⊗ ",
+        }
+    }
+
+    /// [`Node::state_label`] after its symbol.
+    pub fn node_label(&self, node: &Node) -> String {
+        format!("{}{}", self.node_symbol(node), node.state_label())
+    }
+
+    /// A job's state code, after its symbol when the theme has them.
+    pub fn job_label(&self, state: &JobState) -> String {
+        if !self.symbols {
+            return state.to_string();
+        }
+        let symbol = match state {
+            JobState::Running => '▶',
+            JobState::Pending => '◷',
+            JobState::Completed => '✓',
+            JobState::Suspended => '‖',
+            JobState::Preempted => '↯',
+            JobState::Cancelled => '⊘',
+            JobState::Timeout => '◔',
+            JobState::OutOfMemory => '▲',
+            JobState::NodeFail => '⊗',
+            JobState::Failed => '✗',
+        };
+        format!("{} {}", symbol, state)
+    }
+
+    /// Each way a job can end gets its own color, so an OOM isn't mistaken for a timeout.
+    pub fn job_state(&self, state: &JobState) -> Style {
+        match state {
+            JobState::Running => Style::default().fg(self.good),
+            JobState::Pending => Style::default().fg(self.warning),
+            JobState::Completed => Style::default().fg(self.label),
+            JobState::Suspended => Style::default().fg(self.info),
+            JobState::Preempted => Style::default().fg(self.preempted),
+            JobState::Cancelled => Style::default().fg(self.muted),
+            JobState::Timeout => Style::default().fg(self.special),
+            JobState::OutOfMemory => Style::default().fg(self.busy).add_modifier(Modifier::BOLD),
+            JobState::NodeFail => Style::default().fg(self.node_fail),
+            JobState::Failed => Style::default().fg(self.error),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Per-user totals of the running and pending jobs, for the Users view.
+
+use crate::arrays;
+use crate::models::Job;
+use chrono::Duration;
+
+/// What one user has running and queued.
+pub struct UserUsage {
+    pub user: String,
+    pub running: u32,
+    pub cores: u32,
+    /// Requested memory of the running jobs, in bytes
+    pub mem: u64,
+    pub gpus: u32,
+    pub nodes: u32,
+    /// Pending tasks, counting each task of a compressed array entry
+    pub pending: u32,
+    /// How long the user's longest-running job has been running
+    pub oldest: Option<Duration>,
+}
+
+impl UserUsage {
+    fn new(user: &str) -> Self {
+        Self {
+            user: user.to_string(),
+            running: 0,
+            cores: 0,
+            mem: 0,
+            gpus: 0,
+            nodes: 0,
+            pending: 0,
+            oldest: None,
+        }
+    }
+}
+
+/// One entry per user with running or pending jobs, biggest core count first.
+pub fn aggregate(running: &[Job], pending: &[Job]) -> Vec<UserUsage> {
+    let mut users: Vec<UserUsage> = Vec::new();
+
+    for job in running {
+        let usage = usage_of(&mut users, &job.user);
+        usage.running += 1;
+        usage.cores += job.req_cpus;
+        usage.mem += job.req_mem;
+        usage.gpus += job.req_gpus;
+        usage.nodes += job.req_nodes;
+        usage.oldest = usage.oldest.max(Some(job.elapsed));
+    }
+    for job in pending {
+        usage_of(&mut users, &job.user).pending += arrays::task_count(&job.id);
+    }
+
+    sort(&mut users, UserSort::Cores, true);
+    users
+}
+
+/// Order of the Users view; `s` cycles, `S` flips the direction, clicking a header sorts by it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserSort {
+    User,
+    Running,
+    Cores,
+    Memory,
+    Gpus,
+    Nodes,
+    Pending,
+    Oldest,
+}
+
+impl UserSort {
+    /// In column order, as the header titles.
+    pub const ALL: [UserSort; 8] = [
+        UserSort::User,
+        UserSort::Running,
+        UserSort::Cores,
+        UserSort::Memory,
+        UserSort::Gpus,
+        UserSort::Nodes,
+        UserSort::Pending,
+        UserSort::Oldest,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|key| *key == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            UserSort::User => "User",
+            UserSort::Running => "Running",
+            UserSort::Cores => "Cores",
+            UserSort::Memory => "Memory",
+            UserSort::Gpus => "GPUs",
+            UserSort::Nodes => "Nodes",
+            UserSort::Pending => "Pending",
+            UserSort::Oldest => "Oldest",
+        }
+    }
+
+    /// Users by name; every total biggest first.
+    pub fn descending_by_default(self) -> bool {
+        self != UserSort::User
+    }
+}
+
+/// Ties go by user name.
+pub fn sort(users: &mut [UserUsage], key: UserSort, descending: bool) {
+    users.sort_by(|a, b| {
+        let order = match key {
+            UserSort::User => a.user.cmp(&b.user),
+            UserSort::Running => a.running.cmp(&b.running),
+            UserSort::Cores => a.cores.cmp(&b.cores),
+            UserSort::Memory => a.mem.cmp(&b.mem),
+            UserSort::Gpus => a.gpus.cmp(&b.gpus),
+            UserSort::Nodes => a.nodes.cmp(&b.nodes),
+            UserSort::Pending => a.pending.cmp(&b.pending),
+            UserSort::Oldest => a.oldest.cmp(&b.oldest),
+        };
+        let order = if descending { order.reverse() } else { order };
+        order.then_with(|| a.user.cmp(&b.user))
+    });
+}
+
+fn usage_of<'a>(users: &'a mut Vec<UserUsage>, user: &str) -> &'a mut UserUsage {
+    let index = match users.iter().position(|u| u.user == user) {
+        Some(index) => index,
+        None => {
+            users.push(UserUsage::new(user));
+            users.len() - 1
+        },
+    };
+    &mut users[index]
+}
@@ -0,0 +1,65 @@
+//! Recent CPU, memory and GPU utilization per partition, for the sparklines next to the gauges.
+
+use crate::models::ClusterStats;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Samples kept per partition, one per column of the sparkline; at the default 30s refresh,
+/// the last 20 minutes.
+pub const MAX_SAMPLES: usize = 40;
+
+/// A refresh this soon after the last sample replaces it, so hammering `r` doesn't push out
+/// the history.
+const MIN_SPACING: Duration = Duration::from_secs(5);
+
+/// Utilization at one refresh, in percent.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub cpu: u64,
+    pub mem: u64,
+    /// `None` on partitions without GPUs
+    pub gpu: Option<u64>,
+}
+
+impl Sample {
+    fn of(stats: &ClusterStats) -> Self {
+        let percent = |used: u64, total: u64| (used * 100).checked_div(total).unwrap_or(0);
+        let total_gpus = stats.total_gpus();
+        Self {
+            cpu: percent(stats.used_cores.into(), stats.total_cores.into()),
+            mem: percent(stats.used_memory, stats.total_memory),
+            gpu: (total_gpus > 0).then(|| percent(stats.used_gpus().into(), total_gpus.into())),
+        }
+    }
+}
+
+/// A ring buffer of samples per partition, so switching partitions doesn't mix them.
+#[derive(Default)]
+pub struct UtilizationHistory {
+    samples: HashMap<String, VecDeque<(Instant, Sample)>>,
+}
+
+impl UtilizationHistory {
+    pub fn record(&mut self, partition: &str, stats: &ClusterStats) {
+        self.record_at(partition, stats, Instant::now());
+    }
+
+    pub fn record_at(&mut self, partition: &str, stats: &ClusterStats, at: Instant) {
+        let samples = self.samples.entry(partition.to_string()).or_default();
+        if samples.back().is_some_and(|(last, _)| at.duration_since(*last) < MIN_SPACING) {
+            samples.pop_back();
+        } else if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back((at, Sample::of(stats)));
+    }
+
+    /// The samples of `partition`, oldest first, and how long ago the first was taken.
+    pub fn samples(&self, partition: &str) -> (Vec<Sample>, Duration) {
+        let Some(samples) = self.samples.get(partition) else {
+            return (Vec::new(), Duration::ZERO);
+        };
+        let span = samples.front().map(|(at, _)| at.elapsed()).unwrap_or_default();
+        (samples.iter().map(|(_, sample)| *sample).collect(), span)
+    }
+}
@@ -0,0 +1,41 @@
+//! Queue wait times per partition: the median and 90th percentile of how long the jobs that
+//! started in the last week waited, from the scheduler's accounting (Slurm `sacct`) or, where
+//! there is none, the jobs `[history]` saw start.
+
+use crate::models::JobWait;
+use crate::placement::format_wait;
+use chrono::Duration;
+
+/// How far back the wait times go.
+pub const WINDOW: Duration = Duration::days(7);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitStats {
+    pub jobs: usize,
+    pub median: Duration,
+    pub p90: Duration,
+}
+
+impl WaitStats {
+    /// The waits of the jobs that started in `partition` (any of a comma-separated list, every
+    /// one for `all`); `None` if none did.
+    pub fn of(waits: &[JobWait], partition: &str) -> Option<Self> {
+        let mut waits: Vec<Duration> = waits
+            .iter()
+            .filter(|wait| partition == "all" || partition.split(',').any(|name| name.trim() == wait.partition))
+            .map(|wait| wait.wait)
+            .collect();
+        if waits.is_empty() {
+            return None;
+        }
+        waits.sort();
+        // Nearest rank: the smallest wait at least `percent` of the jobs didn't exceed
+        let percentile = |percent: usize| waits[(waits.len() * percent).div_ceil(100) - 1];
+        Some(Self { jobs: waits.len(), median: percentile(50), p90: percentile(90) })
+    }
+
+    /// `median 12m, p90 2h05m`
+    pub fn summary(&self) -> String {
+        format!("median {}, p90 {}", format_wait(&self.median), format_wait(&self.p90))
+    }
+}
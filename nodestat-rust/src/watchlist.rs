@@ -0,0 +1,86 @@
+use crate::models::{Job, JobState};
+use crate::paths;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Job IDs the user asked to follow, persisted across sessions.
+pub struct Watchlist {
+    path: PathBuf,
+    ids: Vec<String>,
+    jobs: HashMap<String, Job>,
+}
+
+impl Watchlist {
+    pub fn load() -> Self {
+        let path = paths::config_dir().join("watchlist.json");
+        let ids = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            ids,
+            jobs: HashMap::new(),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.ids)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn job(&self, id: &str) -> Option<&Job> {
+        self.jobs.get(id)
+    }
+
+    /// Add the job if it isn't watched yet, otherwise remove it. Returns whether it is now watched.
+    pub fn toggle(&mut self, id: &str) -> Result<bool> {
+        let watched = if let Some(pos) = self.ids.iter().position(|i| i == id) {
+            self.ids.remove(pos);
+            self.jobs.remove(id);
+            false
+        } else {
+            self.ids.push(id.to_string());
+            true
+        };
+        self.save()?;
+        Ok(watched)
+    }
+
+    /// Store freshly fetched jobs and describe every state change since the last update.
+    pub fn update(&mut self, jobs: Vec<Job>) -> Vec<String> {
+        let mut transitions = Vec::new();
+        let previous: HashMap<String, JobState> = self.jobs
+            .iter()
+            .map(|(id, job)| (id.clone(), job.state.clone()))
+            .collect();
+
+        for job in jobs {
+            if !self.ids.contains(&job.id) {
+                continue;
+            }
+            if let Some(old_state) = previous.get(&job.id) {
+                if *old_state != job.state {
+                    transitions.push(format!("Job {} ({}): {} → {}", job.id, job.name, old_state, job.state));
+                }
+            }
+            self.jobs.insert(job.id.clone(), job);
+        }
+
+        transitions
+    }
+}
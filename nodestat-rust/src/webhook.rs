@@ -0,0 +1,126 @@
+//! Webhooks: alerts and the ends of your jobs POSTed to a URL, either as NodeStat's own JSON for
+//! on-call tools and scripts or as a Slack (or Mattermost, which takes the same) message.
+
+use crate::alerts::Firing;
+use crate::models::{Job, JobState};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Give up on a webhook that doesn't answer, rather than hold up the refresh.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a webhook's body looks like.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// `{"event", "message", "time", ...}` with the alert's or job's fields
+    Json,
+    /// `{"text", "username"}`, as Slack and Mattermost incoming webhooks take it
+    Slack,
+}
+
+/// A `webhook:<url>`, `slack:<url>` or `mattermost:<url>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Webhook {
+    pub url: String,
+    pub format: Format,
+}
+
+impl Webhook {
+    /// `None` if `text` doesn't name a webhook at all.
+    pub fn parse(text: &str) -> Option<Result<Self>> {
+        let (kind, url) = text.split_once(':')?;
+        let format = match kind {
+            "webhook" => Format::Json,
+            "slack" | "mattermost" => Format::Slack,
+            _ => return None,
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Some(Err(anyhow!("Expected an http(s) URL after '{}:', not '{}'", kind, url)));
+        }
+        Some(Ok(Self { url: url.to_string(), format }))
+    }
+
+    /// Every `text` as a webhook; anything else is an error.
+    pub fn parse_list(texts: &[String]) -> Result<Vec<Self>> {
+        texts
+            .iter()
+            .map(|text| {
+                Self::parse(text).unwrap_or_else(|| Err(anyhow!("Unknown webhook '{}' (use webhook:, slack: or mattermost:<url>)", text)))
+            })
+            .collect()
+    }
+
+    pub async fn send(&self, notice: &Notice<'_>) -> Result<()> {
+        let body = match self.format {
+            Format::Json => notice.json(),
+            Format::Slack => json!({ "text": notice.text(), "username": "NodeStat" }),
+        };
+        let response = reqwest::Client::new()
+            .post(&self.url)
+            .timeout(TIMEOUT)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach webhook {}", self.url))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Webhook {} answered {}", self.url, response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Something worth a webhook.
+pub enum Notice<'a> {
+    Alert(&'a Firing),
+    /// One of your jobs ended, in `state` if accounting still knows it
+    JobEnded { job: &'a Job, state: Option<&'a JobState>, time: DateTime<Utc> },
+}
+
+impl Notice<'_> {
+    /// The one line the banner also shows.
+    pub fn message(&self) -> String {
+        match self {
+            Notice::Alert(firing) => firing.message.clone(),
+            Notice::JobEnded { job, state: Some(state), .. } => format!("Job {} ({}) ended: {}", job.id, job.name, state),
+            Notice::JobEnded { job, state: None, .. } => format!("Job {} ({}) ended", job.id, job.name),
+        }
+    }
+
+    fn json(&self) -> Value {
+        match self {
+            Notice::Alert(firing) => json!({
+                "event": "alert",
+                "alert": firing.name,
+                "partition": firing.partition,
+                "message": self.message(),
+                "time": firing.time.to_rfc3339(),
+            }),
+            Notice::JobEnded { job, state, time } => json!({
+                "event": "job_end",
+                "job": job.id,
+                "name": job.name,
+                "user": job.user,
+                "partition": job.partition,
+                "cluster": job.cluster,
+                "state": state,
+                "message": self.message(),
+                "time": time.to_rfc3339(),
+            }),
+        }
+    }
+
+    /// The message in Slack's markup, which Mattermost shares.
+    fn text(&self) -> String {
+        match self {
+            Notice::Alert(_) => format!(":rotating_light: {}", slack_escape(&self.message())),
+            Notice::JobEnded { .. } => format!(":checkered_flag: {}", slack_escape(&self.message())),
+        }
+    }
+}
+
+/// Slack treats `&`, `<` and `>` as markup.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}